@@ -0,0 +1,78 @@
+//! Measures creating four sizes of the images in tests/assets copied `BATCH_COPIES` times
+//! (8 by default) one file after the other, compared to a batch that processes the files
+//! in parallel and a batch of one file in flight that only resizes the sizes in parallel.
+//! Run with `cargo bench --bench batch`.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+use thumbnailer::{create_thumbnails_from_path, BatchOptions, ThumbnailSize, ThumbnailerBuilder};
+
+const SIZES: [ThumbnailSize; 4] = [
+    ThumbnailSize::Icon,
+    ThumbnailSize::Small,
+    ThumbnailSize::Medium,
+    ThumbnailSize::Large,
+];
+
+const IMAGES: [&str; 6] = [
+    "test.png",
+    "test.jpg",
+    "test.webp",
+    "rgba16.png",
+    "display_p3.png",
+    "orientation_6.jpg",
+];
+
+/// Prints the duration of the function for the number of files
+fn measure<F: FnOnce()>(name: &str, files: usize, f: F) {
+    let started = Instant::now();
+    f();
+    let elapsed = started.elapsed();
+
+    println!(
+        "{:<13} {:>8.2?} for {} files, {:>6.2?} per file",
+        name,
+        elapsed,
+        files,
+        elapsed / files as u32
+    );
+}
+
+fn main() {
+    let copies: usize = std::env::var("BATCH_COPIES")
+        .ok()
+        .and_then(|copies| copies.parse().ok())
+        .unwrap_or(8);
+    let assets = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/assets");
+    let dir = tempfile::tempdir().unwrap();
+    let mut paths = Vec::new();
+    for copy in 0..copies {
+        for name in IMAGES.iter() {
+            let path = dir.path().join(format!("{}-{}", copy, name));
+            fs::copy(assets.join(name), &path).unwrap();
+            paths.push(path);
+        }
+    }
+    println!("{} threads", rayon::current_num_threads());
+
+    let builder = ThumbnailerBuilder::new().sizes(SIZES);
+    measure("sequential", paths.len(), || {
+        for path in &paths {
+            create_thumbnails_from_path(path, SIZES).unwrap();
+        }
+    });
+    measure("batch", paths.len(), || {
+        for (_, result) in builder.run_batch(paths.clone(), BatchOptions::default()) {
+            result.unwrap();
+        }
+    });
+    measure("one in flight", paths.len(), || {
+        let options = BatchOptions {
+            max_in_flight: Some(1),
+        };
+        for (_, result) in builder.run_batch(paths.clone(), options) {
+            result.unwrap();
+        }
+    });
+}
@@ -0,0 +1,95 @@
+//! Measures the peak memory and duration of creating four sizes of a 40 megapixel jpeg
+//! read through a buffered reader and from shared bytes, and of cropping it to cover
+//! the sizes. Run with `cargo bench`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::{BufReader, Cursor};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use thumbnailer::image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use thumbnailer::{
+    create_thumbnails, create_thumbnails_shared, ResizeMode, ThumbnailSize, ThumbnailerBuilder,
+};
+
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const SIZES: [ThumbnailSize; 4] = [
+    ThumbnailSize::Icon,
+    ThumbnailSize::Small,
+    ThumbnailSize::Medium,
+    ThumbnailSize::Large,
+];
+
+/// Prints the most memory the function had allocated at once and its duration
+fn measure<F: FnOnce()>(name: &str, f: F) {
+    let start = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(start, Ordering::SeqCst);
+    let started = Instant::now();
+    f();
+    let peak = PEAK.load(Ordering::SeqCst).saturating_sub(start);
+
+    println!(
+        "{:<8} peak {:>7.1} MiB in {:>6.2?}",
+        name,
+        peak as f64 / (1024.0 * 1024.0),
+        started.elapsed()
+    );
+}
+
+fn main() {
+    // a photo-like gradient with some noise so that the jpeg isn't tiny
+    let (width, height) = (7296, 5472);
+    let image = RgbImage::from_fn(width, height, |x, y| {
+        let noise = (x.wrapping_mul(2_654_435_761) ^ y.wrapping_mul(40_503)) % 32;
+        Rgb([
+            (x * 224 / width + noise) as u8,
+            (y * 224 / height + noise) as u8,
+            ((x + y) % 224 + noise) as u8,
+        ])
+    });
+    let mut jpeg = Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(image)
+        .write_to(&mut jpeg, ImageFormat::Jpeg)
+        .unwrap();
+    let bytes = jpeg.into_inner();
+    println!(
+        "{}x{} jpeg with {:.1} MiB",
+        width,
+        height,
+        bytes.len() as f64 / (1024.0 * 1024.0)
+    );
+
+    measure("buffered", || {
+        let reader = BufReader::new(Cursor::new(&bytes));
+        create_thumbnails(reader, mime::IMAGE_JPEG, SIZES).unwrap();
+    });
+    measure("shared", || {
+        create_thumbnails_shared(&bytes, mime::IMAGE_JPEG, SIZES).unwrap();
+    });
+    measure("cover", || {
+        ThumbnailerBuilder::new()
+            .sizes(SIZES)
+            .resize_mode(ResizeMode::Cover)
+            .run(Cursor::new(&bytes), mime::IMAGE_JPEG)
+            .unwrap();
+    });
+}
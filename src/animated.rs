@@ -0,0 +1,117 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::{ThumbnailSize, DEFAULT_WEBP_QUALITY};
+use image::codecs::gif::{GifEncoder, Repeat};
+#[cfg(not(feature = "libwebp"))]
+use image::error::ImageFormatHint;
+use image::metadata::LoopCount;
+use image::Frame;
+#[cfg(not(feature = "libwebp"))]
+use image::ImageFormat;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::Write;
+#[cfg(feature = "libwebp")]
+use std::time::Duration;
+#[cfg(feature = "libwebp")]
+use webp::{AnimEncoder, AnimFrame, WebPConfig};
+
+/// A thumbnail that keeps all frames of an animated source image
+#[derive(Clone)]
+pub struct AnimatedThumbnail {
+    pub(crate) frames: Vec<Frame>,
+    pub(crate) loop_count: LoopCount,
+    pub(crate) requested_size: ThumbnailSize,
+}
+
+impl AnimatedThumbnail {
+    /// Writes the frames as an animated gif with the original delays and loop count
+    pub fn write_gif<W: Write>(self, writer: &mut W) -> ThumbResult<()> {
+        let repeat = match self.loop_count {
+            LoopCount::Infinite => Repeat::Infinite,
+            LoopCount::Finite(n) => Repeat::Finite(u16::try_from(n.get()).unwrap_or(u16::MAX)),
+        };
+        let mut encoder = GifEncoder::new(writer);
+        encoder.set_repeat(repeat)?;
+        encoder.encode_frames(self.frames)?;
+
+        Ok(())
+    }
+
+    /// Writes the frames as a lossy animated webp with the default quality
+    pub fn write_webp<W: Write>(self, writer: &mut W) -> ThumbResult<()> {
+        self.write_webp_with_quality(writer, DEFAULT_WEBP_QUALITY)
+    }
+
+    /// Writes the frames as a lossy animated webp with the given quality (0-100)
+    /// and the original delays and loop count. Requires the `libwebp` feature and returns
+    /// [ThumbError::UnsupportedOutputFormat] without it.
+    #[cfg_attr(not(feature = "libwebp"), allow(unused_variables))]
+    pub fn write_webp_with_quality<W: Write>(self, writer: &mut W, quality: u8) -> ThumbResult<()> {
+        #[cfg(feature = "libwebp")]
+        return self.encode_webp(writer, quality);
+        #[cfg(not(feature = "libwebp"))]
+        Err(ThumbError::UnsupportedOutputFormat(ImageFormatHint::Exact(
+            ImageFormat::WebP,
+        )))
+    }
+
+    /// Encodes the frames with the animation encoder of libwebp
+    #[cfg(feature = "libwebp")]
+    fn encode_webp<W: Write>(self, writer: &mut W, quality: u8) -> ThumbResult<()> {
+        let (width, height) = self.size();
+        let mut config =
+            WebPConfig::new().map_err(|_| ThumbError::encode("webp", "invalid webp config"))?;
+        config.quality = quality.min(100) as f32;
+
+        let mut encoder = AnimEncoder::new(width, height, &config);
+        encoder.set_loop_count(match self.loop_count {
+            LoopCount::Infinite => 0,
+            LoopCount::Finite(n) => i32::try_from(n.get()).unwrap_or(i32::MAX),
+        });
+        let mut timestamp = Duration::ZERO;
+        for frame in &self.frames {
+            let buffer = frame.buffer();
+            encoder.add_frame(AnimFrame::from_rgba(
+                buffer.as_raw(),
+                buffer.width(),
+                buffer.height(),
+                timestamp.as_millis() as i32,
+            ));
+            timestamp += Duration::from(frame.delay());
+        }
+        let memory = encoder
+            .try_encode()
+            .map_err(|e| ThumbError::encode("webp", format!("{:?}", e)))?;
+        writer.write_all(&memory)?;
+
+        Ok(())
+    }
+
+    /// Returns the size of the frames as width, height
+    pub fn size(&self) -> (u32, u32) {
+        self.frames
+            .first()
+            .map(|frame| frame.buffer().dimensions())
+            .unwrap_or_default()
+    }
+
+    /// Returns the size that was requested when creating the thumbnail
+    pub fn requested_size(&self) -> ThumbnailSize {
+        self.requested_size
+    }
+
+    /// Returns the number of frames
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+}
+
+impl fmt::Debug for AnimatedThumbnail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnimatedThumbnail")
+            .field("size", &self.size())
+            .field("frame_count", &self.frame_count())
+            .field("requested_size", &self.requested_size)
+            .finish()
+    }
+}
@@ -0,0 +1,94 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::{Thumbnail, ThumbnailSize, ThumbnailerBuilder};
+use mime::Mime;
+use std::io::{self, Cursor};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::task;
+
+/// Creates thumbnails of the requested sizes like [crate::create_thumbnails] without blocking
+/// the async executor. The input is read asynchronously and the decoding and resizing
+/// happens on the blocking thread pool.
+pub async fn create_thumbnails_async<
+    R: AsyncRead + Unpin,
+    I: IntoIterator<Item = ThumbnailSize>,
+>(
+    reader: R,
+    mime: Mime,
+    sizes: I,
+) -> ThumbResult<Vec<Thumbnail>> {
+    ThumbnailerBuilder::new()
+        .sizes(sizes)
+        .copy_duplicates(true)
+        .run_async(reader, mime)
+        .await
+}
+
+impl ThumbnailerBuilder {
+    /// Creates thumbnails like [ThumbnailerBuilder::run] without blocking the async executor.
+    /// Dropping the returned future stops the background work before the next resize step.
+    pub async fn run_async<R: AsyncRead + Unpin>(
+        &self,
+        mut reader: R,
+        mime: Mime,
+    ) -> ThumbResult<Vec<Thumbnail>> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await?;
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let _guard = CancelOnDrop(cancelled.clone());
+        let builder = self.clone().cancel_token(cancelled);
+
+        spawn_blocking(move || builder.run(Cursor::new(buf), mime)).await
+    }
+}
+
+impl Thumbnail {
+    /// Writes the bytes of the image in a png format to an async writer
+    pub async fn write_png_async<W: AsyncWrite + Unpin>(self, writer: &mut W) -> ThumbResult<()> {
+        let buf = spawn_blocking(move || {
+            let mut buf = Cursor::new(Vec::new());
+            self.write_png(&mut buf)?;
+            Ok(buf.into_inner())
+        })
+        .await?;
+        writer.write_all(&buf).await?;
+
+        Ok(())
+    }
+
+    /// Writes the bytes of the image in a jpeg format to an async writer
+    pub async fn write_jpeg_async<W: AsyncWrite + Unpin>(self, writer: &mut W) -> ThumbResult<()> {
+        let buf = spawn_blocking(move || {
+            let mut buf = Cursor::new(Vec::new());
+            self.write_jpeg(&mut buf)?;
+            Ok(buf.into_inner())
+        })
+        .await?;
+        writer.write_all(&buf).await?;
+
+        Ok(())
+    }
+}
+
+/// Sets the flag when dropped so that background work can stop early
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+async fn spawn_blocking<T, F>(f: F) -> ThumbResult<T>
+where
+    F: FnOnce() -> ThumbResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    match task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => Err(ThumbError::Io(io::Error::other(e))),
+    }
+}
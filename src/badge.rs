@@ -0,0 +1,84 @@
+use crate::overlay::{apply_overlay, OverlayPosition};
+use crate::resize::resize_image;
+use crate::{FilterType, ResizeMode};
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+
+const PLAY_PNG: &[u8] = include_bytes!("../assets/play.png");
+const AUDIO_PNG: &[u8] = include_bytes!("../assets/audio.png");
+
+/// The size of the badge relative to the shorter edge of the thumbnail
+const BADGE_SCALE: f32 = 0.3;
+
+/// The size of the graphic relative to the size of the scrim behind it
+const GRAPHIC_SCALE: f32 = 0.6;
+
+/// The alpha of the dark circle behind the badge graphic
+const SCRIM_ALPHA: f32 = 0.45;
+
+lazy_static::lazy_static! {
+    static ref PLAY: DynamicImage = image::load_from_memory(PLAY_PNG).unwrap();
+    static ref AUDIO: DynamicImage = image::load_from_memory(AUDIO_PNG).unwrap();
+}
+
+/// A badge drawn onto the center of thumbnails to mark the kind of media
+#[derive(Clone, Debug)]
+pub enum Badge {
+    /// A play triangle for videos
+    Play,
+
+    /// A music note for audio files
+    Audio,
+
+    /// A custom graphic that is scaled to fit the badge
+    Custom(DynamicImage),
+}
+
+impl Badge {
+    fn graphic(&self) -> &DynamicImage {
+        match self {
+            Badge::Play => &PLAY,
+            Badge::Audio => &AUDIO,
+            Badge::Custom(image) => image,
+        }
+    }
+
+    /// Draws the badge centered on the image with a dark scrim behind it.
+    /// The badge is sized proportionally to the shorter edge of the image.
+    pub(crate) fn apply(&self, image: DynamicImage) -> DynamicImage {
+        let (width, height) = image.dimensions();
+        let size = (width.min(height) as f32 * BADGE_SCALE).round() as u32;
+        if size == 0 {
+            return image;
+        }
+        let image = apply_overlay(
+            image,
+            &DynamicImage::ImageRgba8(scrim(size)),
+            OverlayPosition::Center,
+            1.0,
+            0,
+        );
+
+        let graphic_size = ((size as f32 * GRAPHIC_SCALE).round() as u32).max(1);
+        let graphic = resize_image(
+            self.graphic(),
+            graphic_size,
+            graphic_size,
+            ResizeMode::Fit,
+            FilterType::Triangle,
+        );
+
+        apply_overlay(image, &graphic, OverlayPosition::Center, 1.0, 0)
+    }
+}
+
+/// Returns a translucent black circle with smooth edges
+fn scrim(size: u32) -> RgbaImage {
+    let radius = size as f32 / 2.0;
+
+    RgbaImage::from_fn(size, size, |x, y| {
+        let dx = x as f32 + 0.5 - radius;
+        let dy = y as f32 + 0.5 - radius;
+        let coverage = (radius - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0.0, 1.0);
+        Rgba([0, 0, 0, (coverage * SCRIM_ALPHA * 255.0).round() as u8])
+    })
+}
@@ -0,0 +1,110 @@
+use crate::error::ThumbResult;
+use crate::{Thumbnail, ThumbnailSize, ThumbnailerBuilder};
+use image::{ColorType, DynamicImage, ImageFormat};
+use mime::Mime;
+use std::io::{BufRead, Seek};
+use std::time::Duration;
+
+/// Information about the source that was collected while decoding it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SourceInfo {
+    /// The width of the decoded source image
+    pub width: u32,
+
+    /// The height of the decoded source image
+    pub height: u32,
+
+    /// The detected format of the source if it is an image format
+    pub format: Option<ImageFormat>,
+
+    /// The color type of the decoded source image
+    pub color_type: ColorType,
+
+    /// The duration of video sources if the container provides one
+    pub duration: Option<Duration>,
+
+    /// The number of pages of document sources
+    pub page_count: Option<usize>,
+
+    /// The horizontal and vertical resolution in dots per inch that png, jpeg and tiff
+    /// sources store in their metadata
+    pub dpi: Option<(f32, f32)>,
+}
+
+/// A decoded source image that thumbnails can be created from multiple times
+/// without decoding the source again
+///
+/// Example:
+/// ```
+/// use thumbnailer::{BaseImage, ThumbnailSize};
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let file = File::open("tests/assets/test.png").unwrap();
+/// let base = BaseImage::decode(BufReader::new(file), mime::IMAGE_PNG).unwrap();
+/// let small = base.thumbnails([ThumbnailSize::Small]).unwrap();
+/// let large = base.thumbnails([ThumbnailSize::Large]).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct BaseImage {
+    pub(crate) image: DynamicImage,
+    pub(crate) info: SourceInfo,
+    pub(crate) mime: Option<Mime>,
+}
+
+impl BaseImage {
+    pub(crate) fn new(image: DynamicImage, format: Option<ImageFormat>) -> Self {
+        let info = SourceInfo {
+            width: image.width(),
+            height: image.height(),
+            format,
+            color_type: image.color(),
+            duration: None,
+            page_count: None,
+            dpi: None,
+        };
+
+        Self {
+            image,
+            info,
+            mime: None,
+        }
+    }
+
+    /// Decodes the given reader providing the content as bytes and the mime
+    /// describing the contents type
+    pub fn decode<R: BufRead + Seek>(reader: R, mime: Mime) -> ThumbResult<Self> {
+        ThumbnailerBuilder::new().decode(reader, mime)
+    }
+
+    /// Creates thumbnails of the requested sizes in the same order as the sizes
+    pub fn thumbnails<I: IntoIterator<Item = ThumbnailSize>>(
+        &self,
+        sizes: I,
+    ) -> ThumbResult<Vec<Thumbnail>> {
+        ThumbnailerBuilder::new()
+            .sizes(sizes)
+            .copy_duplicates(true)
+            .run_base(self)
+    }
+
+    /// Returns the size of the decoded image as width, height
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.image.width(), self.image.height())
+    }
+
+    /// Returns the detected format of the source if it is an image format
+    pub fn format(&self) -> Option<ImageFormat> {
+        self.info.format
+    }
+
+    /// Returns the information about the source that was collected while decoding it
+    pub fn info(&self) -> SourceInfo {
+        self.info
+    }
+}
+
+/// Returns whether the mime describes a video or audio file
+pub(crate) fn is_media_mime(mime: Option<&Mime>) -> bool {
+    mime.is_some_and(|mime| mime.type_() == mime::VIDEO || mime.type_() == mime::AUDIO)
+}
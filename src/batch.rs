@@ -0,0 +1,122 @@
+use crate::builder::panic_message;
+use crate::error::{ThumbError, ThumbResult};
+use crate::formats::registry::ReadSeek;
+use crate::{open_path, Thumbnail, ThumbnailSize, ThumbnailerBuilder};
+use mime::Mime;
+use std::fmt::{self, Debug, Formatter};
+use std::io::{BufRead, Seek};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+/// An input of [ThumbnailerBuilder::run_batch]
+pub enum BatchInput {
+    /// A file whose media type is guessed from its extension and detected from its
+    /// content like [crate::create_thumbnails_from_path]
+    Path(PathBuf),
+
+    /// A reader with the media type of its content
+    Reader(Box<dyn ReadSeek + Send>, Mime),
+}
+
+impl BatchInput {
+    /// Creates an input from a reader and the media type of its content
+    pub fn reader<R: BufRead + Seek + Send + 'static>(reader: R, mime: Mime) -> Self {
+        Self::Reader(Box::new(reader), mime)
+    }
+}
+
+impl Debug for BatchInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchInput::Path(path) => f.debug_tuple("Path").field(path).finish(),
+            BatchInput::Reader(_, mime) => f.debug_tuple("Reader").field(mime).finish(),
+        }
+    }
+}
+
+impl From<PathBuf> for BatchInput {
+    fn from(path: PathBuf) -> Self {
+        Self::Path(path)
+    }
+}
+
+impl From<&Path> for BatchInput {
+    fn from(path: &Path) -> Self {
+        Self::Path(path.to_path_buf())
+    }
+}
+
+impl<R: BufRead + Seek + Send + 'static> From<(R, Mime)> for BatchInput {
+    fn from((reader, mime): (R, Mime)) -> Self {
+        Self::reader(reader, mime)
+    }
+}
+
+/// Options for processing many inputs with [ThumbnailerBuilder::run_batch]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchOptions {
+    /// The most inputs that are decoded and resized at the same time. Each of them holds
+    /// its decoded image in memory, so this bounds the memory of large batches.
+    /// Defaults to the number of threads of the thread pool.
+    pub max_in_flight: Option<usize>,
+}
+
+/// Creates thumbnails of the requested sizes for each of the inputs like
+/// [ThumbnailerBuilder::run_batch]. The results are returned in the same order as the
+/// inputs together with the index of their input.
+///
+/// Example:
+/// ```
+/// use std::path::PathBuf;
+/// use thumbnailer::{create_thumbnails_batch, BatchOptions, ThumbnailSize};
+///
+/// let inputs = [PathBuf::from("tests/assets/test.png"), PathBuf::from("missing.png")];
+/// let results = create_thumbnails_batch(inputs, [ThumbnailSize::Small], BatchOptions::default());
+/// assert!(results[0].1.is_ok());
+/// assert!(results[1].1.is_err());
+/// ```
+pub fn create_thumbnails_batch<I, T, S>(
+    inputs: I,
+    sizes: S,
+    options: BatchOptions,
+) -> Vec<(usize, ThumbResult<Vec<Thumbnail>>)>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<BatchInput>,
+    S: IntoIterator<Item = ThumbnailSize>,
+{
+    ThumbnailerBuilder::new()
+        .sizes(sizes)
+        .copy_duplicates(true)
+        .run_batch(inputs, options)
+}
+
+/// Creates the thumbnails of a single input of a batch. Panics are returned as decoding
+/// errors, so that they don't abort the other inputs.
+pub(crate) fn run_input(
+    builder: &ThumbnailerBuilder,
+    input: BatchInput,
+) -> ThumbResult<Vec<Thumbnail>> {
+    let (reader, mime, sniffing): (Box<dyn ReadSeek + Send>, Mime, bool) = match input {
+        BatchInput::Path(path) => {
+            let (reader, mime) = open_path(&path)?;
+            (Box::new(reader), mime, true)
+        }
+        BatchInput::Reader(reader, mime) => (reader, mime, false),
+    };
+    let format = mime.essence_str().to_string();
+
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        if sniffing {
+            builder.clone().allow_sniffing(true).run(reader, mime)
+        } else {
+            builder.run(reader, mime)
+        }
+    }))
+    .unwrap_or_else(|payload| {
+        Err(ThumbError::decode(
+            format,
+            format!("decoding panicked: {}", panic_message(payload.as_ref())),
+        ))
+    })
+}
@@ -0,0 +1,1444 @@
+use crate::base_image::is_media_mime;
+use crate::batch::{run_input, BatchInput, BatchOptions};
+use crate::error::{ThumbError, ThumbResult};
+#[cfg(feature = "ffmpeg")]
+use crate::formats::get_video_bases;
+use crate::formats::registry::FormatHandler;
+use crate::formats::{get_animation, get_base_image, supports_unseekable, DecodeOptions};
+use crate::mask::Shape;
+use crate::overlay::Overlay;
+use crate::progress::{ProgressCallback, Stage};
+use crate::resize::{fit_dimensions, pad_image, resize_image_with_crop, Sharpen};
+use crate::thumbnail_iter::ThumbnailIter;
+use crate::trace::{record_elapsed, trace_span, Start};
+use crate::transform::Transform;
+use crate::trim::{crop_to_content, trim_bounds, Bounds};
+use crate::utils::forward_reader::ForwardReader;
+use crate::{
+    AnimatedThumbnail, Badge, BaseImage, CropStrategy, FilterType, FlipDirection, GenerationStats,
+    OverlayPosition, Progress, ResizeMode, Rotation, SizeConstraint, SizeStats, SourceInfo,
+    Thumbnail, ThumbnailSize, TrimMode, VideoFrameSelector, DEFAULT_MATTE,
+};
+use image::{DynamicImage, Frame, GenericImageView, ImageFormat, Rgb, Rgba};
+use mime::Mime;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "parallel")]
+use rayon::ThreadPool;
+use std::io::{BufRead, Cursor, Read, Seek};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+#[cfg(feature = "parallel")]
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The default maximum length of the longer edge of thumbnails with a shortest edge constraint
+const DEFAULT_MAX_CONSTRAINED_DIMENSION: u32 = 4096;
+
+/// The results of the distinct sizes, the position of the result of each added size and
+/// the stats of the sizes if they are collected
+type ResizedBase = (Vec<ThumbResult<Thumbnail>>, Vec<usize>, Vec<SizeStats>);
+
+/// Builder for creating thumbnails with custom options
+///
+/// Example:
+/// ```
+/// use thumbnailer::{ThumbnailSize, ThumbnailerBuilder};
+/// use thumbnailer::FilterType;
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let file = File::open("tests/assets/test.png").unwrap();
+/// let reader = BufReader::new(file);
+/// let thumbnails = ThumbnailerBuilder::new()
+///     .size(ThumbnailSize::Medium)
+///     .filter(FilterType::Triangle)
+///     .preserve_aspect(false)
+///     .run(reader, mime::IMAGE_PNG)
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ThumbnailerBuilder {
+    sizes: Vec<ThumbnailSize>,
+    filter: FilterType,
+    preserve_aspect: bool,
+    resize_mode: Option<ResizeMode>,
+    crop: CropStrategy,
+    no_upscale: bool,
+    max_constrained_dimension: u32,
+    matte: Rgb<u8>,
+    sharpen: Option<Sharpen>,
+    transforms: Vec<Transform>,
+    overlay: Option<Overlay>,
+    badge: Option<Badge>,
+    force_badge: bool,
+    deterministic: bool,
+    max_frames: Option<usize>,
+    parallel: bool,
+    largest_first: bool,
+    progressive_downscale: bool,
+    deduplicate_sizes: bool,
+    copy_duplicates: bool,
+    collect_stats: bool,
+    #[cfg(feature = "parallel")]
+    thread_pool: Option<Arc<ThreadPool>>,
+    decode_options: DecodeOptions,
+    progress: Option<ProgressCallback>,
+    timeout: Option<Duration>,
+    trim: Option<TrimMode>,
+}
+
+impl Default for ThumbnailerBuilder {
+    fn default() -> Self {
+        Self {
+            sizes: Vec::new(),
+            filter: FilterType::Lanczos3,
+            preserve_aspect: true,
+            resize_mode: None,
+            crop: CropStrategy::default(),
+            no_upscale: false,
+            max_constrained_dimension: DEFAULT_MAX_CONSTRAINED_DIMENSION,
+            matte: DEFAULT_MATTE,
+            sharpen: None,
+            transforms: Vec::new(),
+            overlay: None,
+            badge: None,
+            force_badge: false,
+            deterministic: false,
+            max_frames: None,
+            parallel: true,
+            largest_first: true,
+            progressive_downscale: false,
+            deduplicate_sizes: true,
+            copy_duplicates: false,
+            collect_stats: false,
+            #[cfg(feature = "parallel")]
+            thread_pool: None,
+            decode_options: DecodeOptions::default(),
+            progress: None,
+            timeout: None,
+            trim: None,
+        }
+    }
+}
+
+impl ThumbnailerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a size or [SizeConstraint] to the thumbnails that are created
+    pub fn size<S: Into<ThumbnailSize>>(mut self, size: S) -> Self {
+        self.sizes.push(size.into());
+        self
+    }
+
+    /// Adds multiple sizes or [SizeConstraint]s to the thumbnails that are created
+    pub fn sizes<I, S>(mut self, sizes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<ThumbnailSize>,
+    {
+        self.sizes.extend(sizes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the filter used for resizing. Defaults to [FilterType::Lanczos3]
+    pub fn filter(mut self, filter: FilterType) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Sets whether the aspect ratio of the source should be preserved.
+    /// If disabled the image is stretched to the exact requested dimensions.
+    pub fn preserve_aspect(mut self, preserve_aspect: bool) -> Self {
+        self.preserve_aspect = preserve_aspect;
+        self
+    }
+
+    /// Sets how the image is fitted into the requested dimensions. Defaults to [ResizeMode::Fit]
+    pub fn resize_mode(mut self, mode: ResizeMode) -> Self {
+        self.resize_mode = Some(mode);
+        self
+    }
+
+    /// Sets which region of the image is kept when cropping with [ResizeMode::Cover].
+    /// Defaults to [CropStrategy::Center].
+    pub fn crop_strategy(mut self, strategy: CropStrategy) -> Self {
+        self.crop = strategy;
+        self
+    }
+
+    /// Sets whether images smaller than the requested size in both dimensions are returned
+    /// as they are instead of being upscaled. This applies to all resize modes.
+    pub fn no_upscale(mut self, no_upscale: bool) -> Self {
+        self.no_upscale = no_upscale;
+        self
+    }
+
+    /// Sets the maximum length of the longer edge of thumbnails with a
+    /// [SizeConstraint::ShortestEdge]. Sources with a more extreme aspect ratio are
+    /// cropped with the configured [CropStrategy]. Defaults to 4096.
+    pub fn max_constrained_dimension(mut self, max_dimension: u32) -> Self {
+        self.max_constrained_dimension = max_dimension;
+        self
+    }
+
+    /// Sets the background color that transparent areas are blended onto when writing
+    /// formats without an alpha channel like jpeg. Defaults to white.
+    pub fn matte(mut self, color: Rgb<u8>) -> Self {
+        self.matte = color;
+        self
+    }
+
+    /// Sharpens the thumbnails after resizing with an unsharp mask. The amount (e.g. 0.5)
+    /// is reduced for thumbnails larger than 256 pixels, the radius is the sigma of the blur
+    /// and differences below the threshold are not sharpened. Disabled by default.
+    pub fn sharpen(mut self, amount: f32, radius: f32, threshold: i32) -> Self {
+        self.sharpen = Some(Sharpen {
+            amount,
+            radius,
+            threshold,
+        });
+        self
+    }
+
+    /// Blurs all thumbnails after resizing like [crate::Thumbnail::blur].
+    /// Post-processing steps are applied in the order they were added.
+    pub fn blur(mut self, sigma: f32) -> Self {
+        self.transforms.push(Transform::Blur(sigma));
+        self
+    }
+
+    /// Pixelates all thumbnails after resizing like [crate::Thumbnail::pixelate].
+    /// Post-processing steps are applied in the order they were added.
+    pub fn pixelate(mut self, block_size: u32) -> Self {
+        self.transforms.push(Transform::Pixelate(block_size));
+        self
+    }
+
+    /// Converts all thumbnails to grayscale after resizing.
+    /// Post-processing steps are applied in the order they were added.
+    pub fn grayscale(mut self) -> Self {
+        self.transforms.push(Transform::Grayscale);
+        self
+    }
+
+    /// Changes the brightness of all thumbnails after resizing like
+    /// [crate::Thumbnail::brightness]. Post-processing steps are applied in the order
+    /// they were added.
+    pub fn brightness(mut self, value: i32) -> Self {
+        self.transforms.push(Transform::Brightness(value));
+        self
+    }
+
+    /// Rounds the corners of all thumbnails after resizing like
+    /// [crate::Thumbnail::round_corners]. Post-processing steps are applied in the order
+    /// they were added.
+    pub fn round_corners(mut self, radius: u32) -> Self {
+        self.transforms
+            .push(Transform::Mask(Shape::Rounded(radius)));
+        self
+    }
+
+    /// Masks all thumbnails with a circle after resizing like [crate::Thumbnail::circle_mask].
+    /// Post-processing steps are applied in the order they were added.
+    pub fn circle_mask(mut self) -> Self {
+        self.transforms.push(Transform::Mask(Shape::Circle));
+        self
+    }
+
+    /// Draws a border onto all thumbnails after resizing like [crate::Thumbnail::border]
+    /// which follows the last mask added before it. Post-processing steps are applied in
+    /// the order they were added.
+    pub fn border(mut self, width: u32, color: Rgba<u8>) -> Self {
+        let shape = self.shape();
+        self.transforms.push(Transform::Border {
+            width,
+            color,
+            shape,
+        });
+        self
+    }
+
+    /// Blends the overlay onto every thumbnail after resizing like [crate::Thumbnail::overlay].
+    /// The opacity must be between 0 and 1.
+    pub fn overlay(
+        mut self,
+        overlay: DynamicImage,
+        position: OverlayPosition,
+        opacity: f32,
+    ) -> Self {
+        self.overlay = Some(Overlay {
+            image: Arc::new(overlay),
+            position,
+            opacity,
+            margin: self.overlay.as_ref().map_or(0, |overlay| overlay.margin),
+        });
+        self
+    }
+
+    /// Sets the distance in pixels the overlay keeps to the edges of the thumbnails for
+    /// positions in the corners. Defaults to 0 and has no effect without an overlay.
+    pub fn overlay_margin(mut self, margin: u32) -> Self {
+        if let Some(overlay) = &mut self.overlay {
+            overlay.margin = margin;
+        }
+        self
+    }
+
+    /// Draws a badge onto the center of thumbnails created from video and audio files
+    /// with a dark scrim behind it. The badge is 30% of the shorter edge of the thumbnail.
+    pub fn badge(mut self, badge: Badge) -> Self {
+        self.badge = Some(badge);
+        self
+    }
+
+    /// Sets whether the badge is drawn onto thumbnails of all media types
+    /// instead of only video and audio files. Defaults to false.
+    pub fn force_badge(mut self, force: bool) -> Self {
+        self.force_badge = force;
+        self
+    }
+
+    /// Sets whether the thumbnails are written deterministically. Png and jpeg thumbnails
+    /// are then written by encoders with all settings fixed explicitly instead of the
+    /// defaults of the image crate, so the same input and options always result in
+    /// the same bytes. No timestamps or software tags are embedded in either format.
+    /// Webp output doesn't contain metadata either and is stable between runs but
+    /// depends on the version of libwebp. Other output formats are written with the
+    /// default encoders of the image crate and aren't covered. Resizing already produces
+    /// the same pixels regardless of the parallelism. Defaults to false.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Removes the borders around the content of the source before resizing, e.g. the
+    /// transparent margins of icons or the white background of product shots. Sources
+    /// without content are reduced to their top left pixel, so fully transparent images
+    /// result in transparent thumbnails instead of an error. Animated thumbnails are
+    /// trimmed to the content of all frames.
+    pub fn trim(mut self, mode: TrimMode) -> Self {
+        self.trim = Some(mode);
+        self
+    }
+
+    /// Rotates the source clockwise after decoding it and before resizing, so the rotation
+    /// is only done once for all sizes. Orientation metadata of the source is applied first.
+    pub fn rotate(mut self, rotation: Rotation) -> Self {
+        self.decode_options.orientation.rotation = Some(rotation);
+        self
+    }
+
+    /// Mirrors the source after decoding and rotating it and before resizing
+    pub fn flip(mut self, direction: FlipDirection) -> Self {
+        self.decode_options.orientation.flip = Some(direction);
+        self
+    }
+
+    /// Sets the index of the frame that is used for animated images like gif and webp.
+    /// Defaults to the first frame. If the animation is shorter the last frame is used.
+    pub fn animation_frame(mut self, index: usize) -> Self {
+        self.decode_options.animation_frame = index;
+        self
+    }
+
+    /// Sets the index of the page that is used for multi-page tiff images. Defaults to the
+    /// first page. Pages past the end fail with [ThumbError::PageOutOfRange].
+    pub fn page(mut self, index: usize) -> Self {
+        self.decode_options.page = index;
+        self
+    }
+
+    /// Sets whether the media type is detected from the content if it is
+    /// `application/octet-stream` or decoding with the given type fails. Defaults to false.
+    pub fn allow_sniffing(mut self, allow_sniffing: bool) -> Self {
+        self.decode_options.sniff = allow_sniffing;
+        self
+    }
+
+    /// Sets the maximum number of pixels of decoded images. Larger inputs are rejected
+    /// before they are decoded. Defaults to 200 megapixels, `None` disables the limit.
+    pub fn max_input_pixels(mut self, max_pixels: Option<u64>) -> Self {
+        self.decode_options.limits.max_pixels = max_pixels;
+        self
+    }
+
+    /// Sets the maximum width or height of decoded images. Larger inputs are rejected
+    /// before they are decoded. Defaults to 65535, `None` disables the limit.
+    pub fn max_input_dimension(mut self, max_dimension: Option<u32>) -> Self {
+        self.decode_options.limits.max_dimension = max_dimension;
+        self
+    }
+
+    /// Registers a handler that decodes custom media types. Handlers registered later take
+    /// precedence over earlier ones and over the built-in handlers. See [FormatHandler].
+    pub fn register_handler<H: FormatHandler + 'static>(mut self, handler: H) -> Self {
+        self.decode_options.registry.register(handler);
+        self
+    }
+
+    /// Sets the maximum number of lines of text files that are rendered onto the preview page.
+    /// Small thumbnails show fewer lines so that the text stays legible. Defaults to 20.
+    #[cfg(feature = "text")]
+    pub fn text_lines(mut self, lines: usize) -> Self {
+        self.decode_options.text.lines = lines;
+        self
+    }
+
+    /// Sets the aspect ratio (width divided by height) of the page that text files are
+    /// rendered onto. Defaults to the portrait aspect ratio of a4 paper.
+    #[cfg(feature = "text")]
+    pub fn text_page_aspect(mut self, aspect: f32) -> Self {
+        self.decode_options.text.page_aspect = aspect;
+        self
+    }
+
+    /// Sets the maximum number of frames that are decoded by [ThumbnailerBuilder::run_animated].
+    /// Longer animations are truncated. Defaults to no limit.
+    pub fn max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = Some(max_frames);
+        self
+    }
+
+    /// Sets whether multiple sizes and frames and the inputs of [ThumbnailerBuilder::run_batch]
+    /// are processed in parallel. Defaults to true.
+    /// A single size is always resized on the calling thread and without the `parallel`
+    /// feature everything is.
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Sets whether sizes that result in the same thumbnail of the source as a size that was
+    /// added before them are skipped, e.g. a size that is added twice, a custom size matching
+    /// a preset or two boxes that the source fits into with the same dimensions. Skipped
+    /// sizes aren't resized, so [ThumbnailerBuilder::run] returns one thumbnail for each
+    /// distinct size in the order they were first added, while
+    /// [ThumbnailerBuilder::run_individual] still returns a result for every added size with
+    /// a copy of the thumbnail of the earlier size, like [crate::create_thumbnails] and
+    /// [crate::Thumbnailer::create] do. Animated thumbnails aren't affected. Defaults to true.
+    pub fn deduplicate_sizes(mut self, deduplicate: bool) -> Self {
+        self.deduplicate_sizes = deduplicate;
+        self
+    }
+
+    /// Sets whether [ThumbnailerBuilder::run_iter] produces the largest size first instead of
+    /// following the order the sizes were added. Defaults to true.
+    pub fn largest_first(mut self, largest_first: bool) -> Self {
+        self.largest_first = largest_first;
+        self
+    }
+
+    /// Sets whether [ThumbnailerBuilder::run_iter] replaces the source with a downscaled copy
+    /// between sizes once it is more than four times as large as the remaining sizes need.
+    /// The copy stays at least twice as large as the largest remaining size, which keeps the
+    /// thumbnails close to resizing from the source while later sizes resize faster and the
+    /// memory of the source is freed early. Defaults to false.
+    pub fn progressive_downscale(mut self, progressive_downscale: bool) -> Self {
+        self.progressive_downscale = progressive_downscale;
+        self
+    }
+
+    /// Sets whether [ThumbnailerBuilder::run_with_stats] measures how long decoding and
+    /// resizing each size took. The clock isn't read while it is disabled, which it is by
+    /// default. Measuring needs a clock and panics on wasm32-unknown-unknown.
+    pub fn collect_stats(mut self, collect_stats: bool) -> Self {
+        self.collect_stats = collect_stats;
+        self
+    }
+
+    /// Sets the thread pool that is used for parallel resizing instead of the global one
+    #[cfg(feature = "parallel")]
+    pub fn thread_pool(mut self, pool: Arc<ThreadPool>) -> Self {
+        self.thread_pool = Some(pool);
+        self
+    }
+
+    /// Sets the frame that is used for video thumbnails.
+    /// Defaults to the frame at 10% of the videos duration.
+    pub fn video_frame(mut self, selector: VideoFrameSelector) -> Self {
+        self.decode_options.video_frame = selector;
+        self
+    }
+
+    /// Sets a callback that is called when a stage of the thumbnail creation starts,
+    /// after each size has been resized and, with [ThumbnailerBuilder::run_encoded],
+    /// before each thumbnail is encoded. The callback is called from the threads of
+    /// the thread pool when resizing in parallel.
+    pub fn on_progress<F: Fn(Progress) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.progress = Some(ProgressCallback::new(callback));
+        self
+    }
+
+    /// Sets a token that cancels the thumbnail creation when it is set to true.
+    /// It is checked between the stages and before each size is resized and
+    /// [ThumbError::Cancelled] is returned once it is set.
+    pub fn cancel_token(mut self, token: Arc<AtomicBool>) -> Self {
+        self.decode_options.cancellation.add_token(token);
+        self
+    }
+
+    /// Sets the longest time that creating thumbnails from a reader may take. The input is
+    /// read into memory and the work is done on a background thread while the calling
+    /// thread waits for at most the timeout and then returns [ThumbError::Timeout].
+    /// The background work stops at its next checkpoint, e.g. before the next frame or
+    /// size is processed. Running external programs like ffmpeg aren't stopped.
+    /// Timeouts need threads and a clock and panic on wasm32-unknown-unknown.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Creates thumbnails of the configured sizes for the given reader providing the content
+    /// as bytes and the mime describing the contents type.
+    /// The thumbnails are returned in the same order as the sizes were added, regardless of
+    /// whether they are resized in parallel, without the sizes that are skipped as duplicates
+    /// by [ThumbnailerBuilder::deduplicate_sizes].
+    pub fn run<R: BufRead + Seek>(&self, reader: R, mime: Mime) -> ThumbResult<Vec<Thumbnail>> {
+        if let Some(timeout) = self.timeout {
+            return self
+                .run_with_timeout(timeout, reader, |builder, reader| builder.run(reader, mime));
+        }
+        self.validate()?;
+        let base = self.decode(reader, mime)?;
+
+        self.run_base(&base)
+    }
+
+    /// Creates thumbnails like [ThumbnailerBuilder::run] and returns them encoded in the
+    /// given format like [Thumbnail::encode]. [Stage::Encoding] is reported before each
+    /// thumbnail is encoded and the cancellation is checked in between.
+    pub fn run_encoded<R: BufRead + Seek>(
+        &self,
+        reader: R,
+        mime: Mime,
+        format: ImageFormat,
+    ) -> ThumbResult<Vec<Vec<u8>>> {
+        if let Some(timeout) = self.timeout {
+            return self.run_with_timeout(timeout, reader, move |builder, reader| {
+                builder.run_encoded(reader, mime, format)
+            });
+        }
+        let thumbnails = self.run(reader, mime)?;
+        let total = thumbnails.len();
+
+        thumbnails
+            .into_iter()
+            .enumerate()
+            .map(|(index, thumbnail)| {
+                self.check_cancelled()?;
+                if let Some(progress) = &self.progress {
+                    progress.report(Stage::Encoding, Some(index as f32 / total as f32));
+                }
+                thumbnail.encode(format)
+            })
+            .collect()
+    }
+
+    /// Creates thumbnails of the configured sizes for each of the inputs like
+    /// [ThumbnailerBuilder::run]. The inputs are processed in parallel on the thread pool and
+    /// the result of each is returned with the index of its input in the order of the inputs,
+    /// so that an input that fails or panics doesn't affect the others. When fewer inputs
+    /// than threads are in flight, their sizes are resized in parallel as well.
+    pub fn run_batch<I, T>(
+        &self,
+        inputs: I,
+        options: BatchOptions,
+    ) -> Vec<(usize, ThumbResult<Vec<Thumbnail>>)>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<BatchInput>,
+    {
+        let inputs: Vec<(usize, BatchInput)> =
+            inputs.into_iter().map(Into::into).enumerate().collect();
+        if options.max_in_flight == Some(0) {
+            return inputs
+                .into_iter()
+                .map(|(index, _)| {
+                    let error = ThumbError::InvalidOptions(
+                        "the maximum number of inputs in flight must be positive".into(),
+                    );
+                    (index, Err(error))
+                })
+                .collect();
+        }
+        let threads = self.threads();
+        let workers = options.max_in_flight.unwrap_or(threads).min(inputs.len());
+        // the other inputs already keep the threads busy, so resizing the sizes of each
+        // input in parallel would only add overhead
+        let builder = self.clone().parallel(self.parallel && workers < threads);
+        #[cfg(feature = "parallel")]
+        if self.parallel && workers > 1 {
+            return self.run_queued(&builder, inputs, workers);
+        }
+
+        inputs
+            .into_iter()
+            .map(|(index, input)| (index, run_input(&builder, input)))
+            .collect()
+    }
+
+    /// Runs the inputs on the given number of workers of the thread pool
+    #[cfg(feature = "parallel")]
+    fn run_queued(
+        &self,
+        builder: &Self,
+        inputs: Vec<(usize, BatchInput)>,
+        workers: usize,
+    ) -> Vec<(usize, ThumbResult<Vec<Thumbnail>>)> {
+        // each worker takes the next input once it is done, which keeps at most one
+        // decoded image per worker in memory
+        let queue = Mutex::new(inputs.into_iter());
+        let work = || {
+            (0..workers)
+                .into_par_iter()
+                .with_max_len(1)
+                .flat_map_iter(|_| {
+                    std::iter::from_fn(|| {
+                        let (index, input) = queue.lock().ok()?.next()?;
+                        Some((index, run_input(builder, input)))
+                    })
+                    .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut results = match &self.thread_pool {
+            Some(pool) => pool.install(work),
+            None => work(),
+        };
+        results.sort_by_key(|(index, _)| *index);
+
+        results
+    }
+
+    /// Decodes the content once and returns an iterator that resizes one size each time it
+    /// is advanced, so that a thumbnail can be encoded and dropped before the next one is
+    /// resized. Each thumbnail is returned with its size and the largest size comes first
+    /// unless [ThumbnailerBuilder::largest_first] is disabled. Duplicate sizes are skipped
+    /// like [ThumbnailerBuilder::run] does. Sizes are resized on the
+    /// calling thread. A size that fails only fails its item, while cancellation ends the
+    /// iterator after returning the error. The timeout is checked while decoding and before
+    /// each size instead of running the work on a background thread.
+    pub fn run_iter<R: BufRead + Seek>(&self, reader: R, mime: Mime) -> ThumbResult<ThumbnailIter> {
+        let mut builder = self.clone();
+        if let Some(timeout) = builder.timeout.take() {
+            let deadline = Instant::now() + timeout;
+            builder.decode_options.cancellation.set_deadline(deadline);
+        }
+        let mode = builder.validate_options()?;
+        let base = builder.decode(reader, mime)?;
+
+        let dpi = decoded_dpi(&base);
+        let badge = builder.badge_for(base.mime.as_ref()).cloned();
+        let trimmed = builder
+            .trim
+            .and_then(|trim| crop_to_content(&base.image, trim_bounds(&base.image, trim)));
+        let source = trimmed.unwrap_or(base.image);
+        let (mut sizes, _) = builder.unique_sizes(source.dimensions(), mode);
+        if builder.largest_first {
+            sizes.sort_by_key(|(_, size)| {
+                let (width, height, _) = builder.target(source.dimensions(), *size, mode);
+                std::cmp::Reverse(width as u64 * height as u64)
+            });
+        }
+
+        Ok(ThumbnailIter::new(builder, source, dpi, badge, mode, sizes))
+    }
+
+    /// Returns whether the source is downscaled between the sizes of
+    /// [ThumbnailerBuilder::run_iter]
+    pub(crate) fn is_progressive(&self) -> bool {
+        self.progressive_downscale
+    }
+
+    /// Creates thumbnails like [ThumbnailerBuilder::run] and returns them with the
+    /// information about the source that was collected while decoding it
+    pub fn run_with_meta<R: BufRead + Seek>(
+        &self,
+        reader: R,
+        mime: Mime,
+    ) -> ThumbResult<(SourceInfo, Vec<Thumbnail>)> {
+        if let Some(timeout) = self.timeout {
+            return self.run_with_timeout(timeout, reader, |builder, reader| {
+                builder.run_with_meta(reader, mime)
+            });
+        }
+        self.validate()?;
+        let base = self.decode(reader, mime)?;
+
+        Ok((base.info(), self.run_base(&base)?))
+    }
+
+    /// Creates thumbnails like [ThumbnailerBuilder::run] and returns them with the time it
+    /// took to decode the source and to resize each size if
+    /// [ThumbnailerBuilder::collect_stats] is enabled. The stats are `None` otherwise.
+    pub fn run_with_stats<R: BufRead + Seek>(
+        &self,
+        reader: R,
+        mime: Mime,
+    ) -> ThumbResult<(Option<GenerationStats>, Vec<Thumbnail>)> {
+        if let Some(timeout) = self.timeout {
+            return self.run_with_timeout(timeout, reader, |builder, reader| {
+                builder.run_with_stats(reader, mime)
+            });
+        }
+        self.validate()?;
+        let start = self.collect_stats.then(Instant::now);
+        let base = self.decode(reader, mime)?;
+        let decode_duration = start.map(|start| start.elapsed());
+        let (thumbnails, positions, per_size) = self.resize_base(&base)?;
+        let thumbnails = self.collect_thumbnails(thumbnails, positions)?;
+        let stats = decode_duration.map(|decode_duration| GenerationStats {
+            decode_duration,
+            source_dimensions: base.dimensions(),
+            per_size,
+            detected_format: base.format(),
+        });
+
+        Ok((stats, thumbnails))
+    }
+
+    /// Creates thumbnails like [ThumbnailerBuilder::run] but returns the result of each size
+    /// separately so that a size that fails doesn't discard the others. The results are
+    /// returned in the same order as the sizes were added, regardless of whether they are
+    /// resized in parallel. Every added size has a result, where sizes that are skipped as
+    /// duplicates get a copy of the result of the earlier size. Errors that affect all sizes
+    /// like decoding errors, invalid options and cancellation are still returned for the
+    /// whole run.
+    pub fn run_individual<R: BufRead + Seek>(
+        &self,
+        reader: R,
+        mime: Mime,
+    ) -> ThumbResult<Vec<(ThumbnailSize, ThumbResult<Thumbnail>)>> {
+        if let Some(timeout) = self.timeout {
+            return self.run_with_timeout(timeout, reader, |builder, reader| {
+                builder.run_individual(reader, mime)
+            });
+        }
+        self.validate_options()?;
+        let base = self.decode(reader, mime)?;
+
+        self.run_base_individual(&base)
+    }
+
+    /// Creates thumbnails like [ThumbnailerBuilder::run] for a reader that can't seek.
+    /// Only media types that can be decoded in a single pass (png, jpeg and gif) are
+    /// supported. Other types return [ThumbError::SeekRequired].
+    pub fn run_unseekable<R: BufRead>(&self, reader: R, mime: Mime) -> ThumbResult<Vec<Thumbnail>> {
+        if !supports_unseekable(&mime) {
+            return Err(ThumbError::SeekRequired(mime));
+        }
+        if let Some(timeout) = self.timeout {
+            return self.run_with_timeout(timeout, reader, |builder, reader| {
+                builder.run_unseekable(reader, mime)
+            });
+        }
+        let mut builder = self.clone();
+        builder.decode_options.forward_only = true;
+
+        builder.run(ForwardReader::new(reader), mime)
+    }
+
+    /// Creates animated thumbnails of the configured sizes from all frames of animated
+    /// gif and webp images. Other inputs create thumbnails with a single frame.
+    /// The thumbnails are returned in the same order as the sizes were added.
+    pub fn run_animated<R: BufRead + Seek>(
+        &self,
+        reader: R,
+        mime: Mime,
+    ) -> ThumbResult<Vec<AnimatedThumbnail>> {
+        if let Some(timeout) = self.timeout {
+            return self.run_with_timeout(timeout, reader, |builder, reader| {
+                builder.run_animated(reader, mime)
+            });
+        }
+        let mode = self.validate()?;
+        self.check_cancelled()?;
+        self.report_decoding(&mime);
+        let mut animation = get_animation(reader, mime, &self.decode_options(), self.max_frames)?;
+        self.check_cancelled()?;
+        if let Some(trim) = self.trim {
+            let bounds = animation
+                .frames
+                .iter()
+                .filter_map(|(image, _)| trim_bounds(image, trim))
+                .reduce(Bounds::union);
+            for (image, _) in &mut animation.frames {
+                if let Some(trimmed) = crop_to_content(image, bounds) {
+                    *image = trimmed;
+                }
+            }
+        }
+        let badge = self.badge_for(animation.mime.as_ref());
+
+        self.sizes
+            .iter()
+            .enumerate()
+            .map(|(index, size)| {
+                let frames = self.map_items(&animation.frames, |(image, delay)| {
+                    self.check_cancelled()?;
+                    let image = self
+                        .resize_image(image, image.dimensions(), *size, mode, badge)
+                        .into_rgba8();
+                    Ok(Frame::from_parts(image, 0, 0, *delay))
+                })?;
+                self.report_resized(index, index + 1, self.sizes.len());
+
+                Ok(AnimatedThumbnail {
+                    frames,
+                    loop_count: animation.loop_count,
+                    requested_size: *size,
+                })
+            })
+            .collect()
+    }
+
+    /// Creates thumbnails of the configured sizes from multiple frames of a video. The video
+    /// is only written and probed once and the frames are extracted in ascending order of
+    /// their position. The results are returned in the same order as the selectors, each
+    /// with the thumbnails in the same order as the sizes were added.
+    #[cfg(feature = "ffmpeg")]
+    pub fn run_video_frames<R: BufRead + Seek>(
+        &self,
+        reader: R,
+        mime: Mime,
+        selectors: &[VideoFrameSelector],
+    ) -> ThumbResult<Vec<(VideoFrameSelector, Vec<Thumbnail>)>> {
+        if let Some(timeout) = self.timeout {
+            let selectors = selectors.to_vec();
+            return self.run_with_timeout(timeout, reader, move |builder, reader| {
+                builder.run_video_frames(reader, mime, &selectors)
+            });
+        }
+        self.validate()?;
+        for selector in selectors {
+            selector.validate()?;
+        }
+        self.check_cancelled()?;
+        self.report_decoding(&mime);
+        let bases = get_video_bases(reader, mime, selectors, &self.decode_options())?;
+
+        selectors
+            .iter()
+            .zip(bases)
+            .map(|(selector, base)| Ok((*selector, self.run_base(&base)?)))
+            .collect()
+    }
+
+    /// Decodes the given reader into a [BaseImage] using the configured options.
+    /// Vector formats are rendered big enough for the configured sizes. With the
+    /// `jpeg-prescale` feature large jpeg images are decoded at a reduced scale that is
+    /// still big enough for them.
+    pub fn decode<R: BufRead + Seek>(&self, reader: R, mime: Mime) -> ThumbResult<BaseImage> {
+        if let Some(timeout) = self.timeout {
+            return self.run_with_timeout(timeout, reader, |builder, reader| {
+                builder.decode(reader, mime)
+            });
+        }
+        self.check_cancelled()?;
+        self.report_decoding(&mime);
+        get_base_image(reader, mime, &self.decode_options())
+    }
+
+    /// Creates thumbnails of the configured sizes from an already decoded image.
+    /// The thumbnails are returned in the same order as [ThumbnailerBuilder::run] does.
+    /// If resizing fails for a size, the error of the first failed size is returned.
+    pub fn run_base(&self, base: &BaseImage) -> ThumbResult<Vec<Thumbnail>> {
+        self.validate()?;
+        let (thumbnails, positions, _) = self.resize_base(base)?;
+
+        self.collect_thumbnails(thumbnails, positions)
+    }
+
+    /// Creates thumbnails from an already decoded image with the result of each size
+    /// returned separately like [ThumbnailerBuilder::run_individual]
+    pub fn run_base_individual(
+        &self,
+        base: &BaseImage,
+    ) -> ThumbResult<Vec<(ThumbnailSize, ThumbResult<Thumbnail>)>> {
+        let (thumbnails, positions, _) = self.resize_base(base)?;
+
+        Ok(self.expand_duplicates(thumbnails, positions))
+    }
+
+    /// Returns the result of each added size, where the sizes that were skipped as
+    /// duplicates get a copy of the result at their position
+    fn expand_duplicates(
+        &self,
+        thumbnails: Vec<ThumbResult<Thumbnail>>,
+        positions: Vec<usize>,
+    ) -> Vec<(ThumbnailSize, ThumbResult<Thumbnail>)> {
+        let mut thumbnails: Vec<Option<ThumbResult<Thumbnail>>> =
+            thumbnails.into_iter().map(Some).collect();
+
+        // duplicates always come after the size they copy
+        let mut originals = vec![0; thumbnails.len()];
+        let mut results: Vec<(ThumbnailSize, ThumbResult<Thumbnail>)> =
+            Vec::with_capacity(self.sizes.len());
+        for (size, position) in self.sizes.iter().zip(positions) {
+            let result = match thumbnails[position].take() {
+                Some(result) => {
+                    originals[position] = results.len();
+                    result
+                }
+                None => duplicate_result(&results[originals[position]].1, *size),
+            };
+            results.push((*size, result));
+        }
+
+        results
+    }
+
+    /// Returns the thumbnails of the distinct sizes, or of every added size if duplicates
+    /// are copied, or the error of the first failed size
+    fn collect_thumbnails(
+        &self,
+        thumbnails: Vec<ThumbResult<Thumbnail>>,
+        positions: Vec<usize>,
+    ) -> ThumbResult<Vec<Thumbnail>> {
+        if !self.copy_duplicates {
+            return thumbnails.into_iter().collect();
+        }
+
+        self.expand_duplicates(thumbnails, positions)
+            .into_iter()
+            .map(|(_, result)| result)
+            .collect()
+    }
+
+    /// Resizes the trimmed image to the distinct sizes and returns their results in the
+    /// order the sizes were first added with the position of the result of each added size.
+    /// The stats of the sizes are only returned if they are collected.
+    fn resize_base(&self, base: &BaseImage) -> ThumbResult<ResizedBase> {
+        let mode = self.validate_options()?;
+        self.check_cancelled()?;
+        let trimmed = self
+            .trim
+            .and_then(|trim| crop_to_content(&base.image, trim_bounds(&base.image, trim)));
+        let source = trimmed.as_ref().unwrap_or(&base.image);
+        let dpi = decoded_dpi(base);
+        let (sizes, positions) = self.unique_sizes(source.dimensions(), mode);
+        let resized =
+            self.resize_images(source, &sizes, mode, self.badge_for(base.mime.as_ref()))?;
+        let per_size = resized
+            .iter()
+            .zip(sizes.iter())
+            .filter_map(|((_, duration), (_, size))| {
+                duration.map(|resize_duration| SizeStats {
+                    size: *size,
+                    resize_duration,
+                })
+            })
+            .collect();
+        let thumbnails = resized
+            .into_iter()
+            .zip(sizes.iter())
+            .map(|((image, _), (_, size))| {
+                image.map(|image| {
+                    let dpi = self.thumbnail_dpi(dpi, source.dimensions(), &image, *size, mode);
+                    self.thumbnail(image, *size, dpi)
+                })
+            })
+            .collect();
+
+        Ok((thumbnails, positions, per_size))
+    }
+
+    /// Returns the sizes that are resized for a source with the given dimensions with their
+    /// index in the order they were added. Sizes with the same output as an earlier size are
+    /// skipped unless deduplication is disabled. The position of the resized size that each
+    /// added size uses is returned as well.
+    pub(crate) fn unique_sizes(
+        &self,
+        source: (u32, u32),
+        mode: ResizeMode,
+    ) -> (Vec<(usize, ThumbnailSize)>, Vec<usize>) {
+        let mut sizes: Vec<(usize, ThumbnailSize)> = Vec::new();
+        let mut targets = Vec::new();
+        let mut positions = Vec::with_capacity(self.sizes.len());
+        for (index, size) in self.sizes.iter().enumerate() {
+            // invalid sizes have no target and fail on their own
+            let target = (self.deduplicate_sizes && size.validate().is_ok())
+                .then(|| self.output(source, *size, mode));
+            let existing =
+                target.and_then(|target| targets.iter().position(|t| *t == Some(target)));
+            match existing {
+                Some(position) => positions.push(position),
+                None => {
+                    positions.push(sizes.len());
+                    sizes.push((index, *size));
+                    targets.push(target);
+                }
+            }
+        }
+
+        (sizes, positions)
+    }
+
+    /// Returns the dimensions and the mode of the thumbnail that resizing a source with the
+    /// given dimensions to the size creates. Boxes that the source fits into with the same
+    /// dimensions and sizes that keep a source that isn't upscaled have the same output.
+    fn output(
+        &self,
+        source: (u32, u32),
+        size: ThumbnailSize,
+        mode: ResizeMode,
+    ) -> (u32, u32, ResizeMode) {
+        match self.target(source, size, mode) {
+            // padded thumbnails always have the dimensions of the box
+            target @ (_, _, ResizeMode::Pad { .. }) => target,
+            (width, height, _) if self.no_upscale && source.0 <= width && source.1 <= height => {
+                (source.0, source.1, ResizeMode::Fit)
+            }
+            (width, height, ResizeMode::Fit) => {
+                let (width, height) = fit_dimensions(source, (width, height));
+                (width, height, ResizeMode::Fit)
+            }
+            target => target,
+        }
+    }
+
+    /// Checks that the configured sizes and options can be used together and
+    /// returns the resulting resize mode
+    fn validate(&self) -> ThumbResult<ResizeMode> {
+        for size in &self.sizes {
+            size.validate()?;
+        }
+
+        self.validate_options()
+    }
+
+    /// Checks the options except for the sizes like [ThumbnailerBuilder::validate]
+    fn validate_options(&self) -> ThumbResult<ResizeMode> {
+        self.decode_options.video_frame.validate()?;
+
+        #[cfg(feature = "text")]
+        if !self.decode_options.text.is_valid() {
+            return Err(ThumbError::InvalidOptions(format!(
+                "invalid text options {:?}",
+                self.decode_options.text
+            )));
+        }
+
+        if self.max_constrained_dimension == 0 {
+            return Err(ThumbError::InvalidOptions(
+                "the maximum constrained dimension must be positive".into(),
+            ));
+        }
+
+        if !self.crop.is_valid() {
+            return Err(ThumbError::InvalidOptions(format!(
+                "invalid crop strategy {:?}",
+                self.crop
+            )));
+        }
+
+        if let Some(sharpen) = self.sharpen {
+            if !sharpen.is_valid() {
+                return Err(ThumbError::InvalidOptions(format!(
+                    "invalid sharpen options {:?}",
+                    sharpen
+                )));
+            }
+        }
+
+        if let Some(transform) = self.transforms.iter().find(|t| !t.is_valid()) {
+            return Err(ThumbError::InvalidOptions(format!(
+                "invalid post-processing step {:?}",
+                transform
+            )));
+        }
+
+        if let Some(overlay) = &self.overlay {
+            if !overlay.is_valid() {
+                return Err(ThumbError::InvalidOptions(format!(
+                    "invalid overlay opacity {}",
+                    overlay.opacity
+                )));
+            }
+        }
+
+        match (self.resize_mode, self.preserve_aspect) {
+            (Some(ResizeMode::Exact), _) | (None, false) => Ok(ResizeMode::Exact),
+            (Some(mode), true) => Ok(mode),
+            (None, true) => Ok(ResizeMode::default()),
+            (Some(mode), false) => Err(ThumbError::InvalidOptions(format!(
+                "resize mode {:?} preserves the aspect ratio but preserve_aspect is disabled",
+                mode
+            ))),
+        }
+    }
+
+    /// Returns a copy of the options that creates the given sizes
+    pub(crate) fn with_sizes(&self, sizes: Vec<ThumbnailSize>) -> Self {
+        Self {
+            sizes,
+            ..self.clone()
+        }
+    }
+
+    /// Sets whether the sizes that are skipped as duplicates get a copy of the thumbnail of
+    /// the earlier size, so that there is a thumbnail for every added size like the free
+    /// functions promise
+    pub(crate) fn copy_duplicates(mut self, copy_duplicates: bool) -> Self {
+        self.copy_duplicates = copy_duplicates;
+        self
+    }
+
+    /// Returns whether a handler for the media type is registered
+    pub(crate) fn supports_mime(&self, mime: &Mime) -> bool {
+        self.decode_options.registry.find(mime).is_some()
+    }
+
+    /// Returns the decode options including the largest requested size
+    fn decode_options(&self) -> DecodeOptions {
+        DecodeOptions {
+            target_size: self.target_size(),
+            ..self.decode_options.clone()
+        }
+    }
+
+    /// Returns the largest requested width and height
+    fn target_size(&self) -> Option<(u32, u32)> {
+        self.sizes
+            .iter()
+            .map(ThumbnailSize::dimensions)
+            .reduce(|(w1, h1), (w2, h2)| (w1.max(w2), h1.max(h2)))
+    }
+
+    /// Returns the shape of the last mask of the post-processing steps
+    fn shape(&self) -> Shape {
+        self.transforms
+            .iter()
+            .rev()
+            .find_map(|transform| match transform {
+                Transform::Mask(shape) => Some(*shape),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns an error if the operation has been cancelled or timed out
+    pub(crate) fn check_cancelled(&self) -> ThumbResult<()> {
+        self.decode_options.cancellation.check()
+    }
+
+    /// Reads the input into memory and runs the operation on a background thread
+    /// with a copy of the options whose deadline ends after the timeout. Panics of the
+    /// operation are resumed on the calling thread.
+    fn run_with_timeout<R, T, F>(&self, timeout: Duration, mut reader: R, f: F) -> ThumbResult<T>
+    where
+        R: Read,
+        T: Send + 'static,
+        F: FnOnce(&Self, Cursor<Vec<u8>>) -> ThumbResult<T> + Send + 'static,
+    {
+        let deadline = Instant::now() + timeout;
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+
+        let mut builder = self.clone();
+        builder.timeout = None;
+        builder.decode_options.cancellation.set_deadline(deadline);
+        let (sender, receiver) = mpsc::channel();
+        let worker = thread::Builder::new()
+            .name(String::from("thumbnailer"))
+            .spawn(move || {
+                // the receiver is gone if the caller stopped waiting
+                let _ = sender.send(f(&builder, Cursor::new(buf)));
+            })?;
+
+        match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => Err(ThumbError::Timeout),
+            Err(RecvTimeoutError::Disconnected) => match worker.join() {
+                Err(payload) => panic::resume_unwind(payload),
+                Ok(()) => Err(ThumbError::Timeout),
+            },
+        }
+    }
+
+    /// Reports the start of decoding or, for videos and audio files, extracting
+    fn report_decoding(&self, mime: &Mime) {
+        if let Some(progress) = &self.progress {
+            let stage = if is_media_mime(Some(mime)) {
+                Stage::Extracting
+            } else {
+                Stage::Decoding
+            };
+            progress.report(stage, None);
+        }
+    }
+
+    /// Reports that the size at the index has been resized and how many of the total
+    /// sizes that are resized are done
+    pub(crate) fn report_resized(&self, index: usize, completed: usize, total: usize) {
+        if let Some(progress) = &self.progress {
+            let stage = Stage::Resizing {
+                size: self.sizes[index],
+                index,
+                total,
+            };
+            progress.report(stage, Some(completed as f32 / total as f32));
+        }
+    }
+
+    /// Returns the badge if it is drawn onto thumbnails of the given media type
+    pub(crate) fn badge_for(&self, mime: Option<&Mime>) -> Option<&Badge> {
+        self.badge
+            .as_ref()
+            .filter(|_| self.force_badge || is_media_mime(mime))
+    }
+
+    /// Resizes the image to the sizes with their index in the order they were added. The
+    /// results are in the order of the sizes with the time resizing took if stats are
+    /// collected. Invalid sizes and panics while resizing only fail the affected size while
+    /// cancellation fails all of them.
+    fn resize_images(
+        &self,
+        image: &DynamicImage,
+        sizes: &[(usize, ThumbnailSize)],
+        mode: ResizeMode,
+        badge: Option<&Badge>,
+    ) -> ThumbResult<Vec<(ThumbResult<DynamicImage>, Option<Duration>)>> {
+        let completed = AtomicUsize::new(0);
+        self.map_items(sizes, |(index, size)| {
+            self.check_cancelled()?;
+            let start = self.collect_stats.then(Instant::now);
+            let resized = self.resize_size(image, image.dimensions(), *size, mode, badge);
+            let duration = start.map(|start| start.elapsed());
+            let completed = completed.fetch_add(1, Ordering::Relaxed) + 1;
+            self.report_resized(*index, completed, sizes.len());
+            Ok((resized, duration))
+        })
+    }
+
+    /// Resizes the image to the size, where the target is computed for a source with the
+    /// given dimensions that the image may be a downscaled copy of. Invalid sizes and
+    /// panics while resizing return an error.
+    pub(crate) fn resize_size(
+        &self,
+        image: &DynamicImage,
+        source: (u32, u32),
+        size: ThumbnailSize,
+        mode: ResizeMode,
+        badge: Option<&Badge>,
+    ) -> ThumbResult<DynamicImage> {
+        size.validate()?;
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            self.resize_image(image, source, size, mode, badge)
+        }))
+        .map_err(|payload| ThumbError::ResizeFailed {
+            size,
+            reason: panic_message(payload.as_ref()),
+        })
+    }
+
+    /// Returns the thumbnail of the resized image with the options of the builder
+    pub(crate) fn thumbnail(
+        &self,
+        image: DynamicImage,
+        size: ThumbnailSize,
+        dpi: Option<(f32, f32)>,
+    ) -> Thumbnail {
+        Thumbnail {
+            dpi,
+            inner: image,
+            requested_size: size,
+            matte: self.matte,
+            deterministic: self.deterministic,
+            shape: self.shape(),
+        }
+    }
+
+    /// Maps the items on the configured thread pool. Single items and disabled
+    /// parallelism skip the thread pool entirely.
+    fn map_items<T, U, F>(&self, items: &[T], f: F) -> ThumbResult<Vec<U>>
+    where
+        T: Sync,
+        U: Send,
+        F: Fn(&T) -> ThumbResult<U> + Sync + Send,
+    {
+        #[cfg(feature = "parallel")]
+        if self.parallel && items.len() > 1 {
+            return match &self.thread_pool {
+                Some(pool) => pool.install(|| items.par_iter().map(f).collect()),
+                None => items.par_iter().map(f).collect(),
+            };
+        }
+
+        items.iter().map(f).collect()
+    }
+
+    /// Returns the number of threads that parallel work is spread over
+    fn threads(&self) -> usize {
+        #[cfg(feature = "parallel")]
+        return match &self.thread_pool {
+            Some(pool) => pool.current_num_threads(),
+            None => rayon::current_num_threads(),
+        };
+        #[cfg(not(feature = "parallel"))]
+        1
+    }
+
+    /// Returns the dimensions and resize mode of the given size for a source with the
+    /// given dimensions
+    pub(crate) fn target(
+        &self,
+        source: (u32, u32),
+        size: ThumbnailSize,
+        mode: ResizeMode,
+    ) -> (u32, u32, ResizeMode) {
+        match SizeConstraint::from(size) {
+            SizeConstraint::BoundingBox(width, height) => (width, height, mode),
+            // the dimensions follow the aspect ratio of the source, so only sources that
+            // exceed the maximum dimension are cropped
+            constraint => {
+                let (width, height) =
+                    constraint.target_dimensions(source, self.max_constrained_dimension);
+                (width, height, ResizeMode::Cover)
+            }
+        }
+    }
+
+    /// Returns the resolution of the decoded image scaled by the factor its content was
+    /// resized with. The resized source is the decoded image after trimming.
+    pub(crate) fn thumbnail_dpi(
+        &self,
+        dpi: Option<(f32, f32)>,
+        (width, height): (u32, u32),
+        thumbnail: &DynamicImage,
+        size: ThumbnailSize,
+        mode: ResizeMode,
+    ) -> Option<(f32, f32)> {
+        let (x, y) = dpi?;
+        let scale_x = thumbnail.width() as f32 / width as f32;
+        let scale_y = thumbnail.height() as f32 / height as f32;
+        let (scale_x, scale_y) = match self.target((width, height), size, mode).2 {
+            ResizeMode::Cover => (scale_x.max(scale_y), scale_x.max(scale_y)),
+            // padded images that fit aren't scaled up
+            ResizeMode::Pad { .. } if self.no_upscale => {
+                let scale = scale_x.min(scale_y).min(1.0);
+                (scale, scale)
+            }
+            ResizeMode::Pad { .. } => (scale_x.min(scale_y), scale_x.min(scale_y)),
+            ResizeMode::Fit | ResizeMode::Exact => (scale_x, scale_y),
+        };
+
+        Some((x * scale_x, y * scale_y))
+    }
+
+    fn resize_image(
+        &self,
+        image: &DynamicImage,
+        source: (u32, u32),
+        size: ThumbnailSize,
+        mode: ResizeMode,
+        badge: Option<&Badge>,
+    ) -> DynamicImage {
+        let (width, height, mode) = match self.target(source, size, mode) {
+            // a downscaled copy of the source has a slightly different aspect ratio
+            (width, height, ResizeMode::Fit) if image.dimensions() != source => {
+                let (width, height) = fit_dimensions(source, (width, height));
+                (width, height, ResizeMode::Exact)
+            }
+            target => target,
+        };
+        let span = trace_span!(
+            "resize",
+            size = %size,
+            width,
+            height,
+            filter = ?self.filter,
+            mode = ?mode,
+            elapsed_ms = tracing::field::Empty,
+        );
+        let start = Start::now();
+        let resized = span.in_scope(|| self.resize_to(image, (width, height), mode, badge));
+        record_elapsed(&span, start);
+
+        resized
+    }
+
+    fn resize_to(
+        &self,
+        image: &DynamicImage,
+        (width, height): (u32, u32),
+        mode: ResizeMode,
+        badge: Option<&Badge>,
+    ) -> DynamicImage {
+        let resized = if self.no_upscale && image.width() <= width && image.height() <= height {
+            match mode {
+                ResizeMode::Pad { color } => pad_image(image, width, height, color),
+                _ => image.clone(),
+            }
+        } else {
+            let resized =
+                resize_image_with_crop(image, width, height, mode, self.filter, self.crop);
+            match self.sharpen {
+                Some(sharpen) => sharpen.apply(resized),
+                None => resized,
+            }
+        };
+
+        let resized = self
+            .transforms
+            .iter()
+            .fold(resized, |image, transform| transform.apply(image));
+        let resized = match badge {
+            Some(badge) => badge.apply(resized),
+            None => resized,
+        };
+        match &self.overlay {
+            Some(overlay) => overlay.apply(resized),
+            None => resized,
+        }
+    }
+}
+
+/// Returns the resolution of the source scaled to the decoded image, which can be smaller
+/// than the source
+pub(crate) fn decoded_dpi(base: &BaseImage) -> Option<(f32, f32)> {
+    let (x, y) = base.info.dpi?;
+    let (width, height) = (base.image.width() as f32, base.image.height() as f32);
+    let decoded = ((width * height) / (base.info.width as f32 * base.info.height as f32)).sqrt();
+
+    Some((x * decoded, y * decoded))
+}
+
+/// Returns a copy of the result of a size for a duplicate size that was skipped.
+/// Only valid sizes are skipped, so their errors are panics while resizing.
+fn duplicate_result(
+    result: &ThumbResult<Thumbnail>,
+    size: ThumbnailSize,
+) -> ThumbResult<Thumbnail> {
+    match result {
+        Ok(thumbnail) => Ok(Thumbnail {
+            requested_size: size,
+            ..thumbnail.clone()
+        }),
+        Err(ThumbError::ResizeFailed { reason, .. }) => Err(ThumbError::ResizeFailed {
+            size,
+            reason: reason.clone(),
+        }),
+        Err(e) => Err(ThumbError::ResizeFailed {
+            size,
+            reason: e.to_string(),
+        }),
+    }
+}
+
+/// Returns the message of a panic payload
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("an unknown panic")
+    }
+}
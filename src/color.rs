@@ -0,0 +1,100 @@
+use image::{DynamicImage, GenericImageView, Rgb};
+use std::cmp::Reverse;
+
+/// The maximum number of pixels sampled along each axis
+const MAX_SAMPLES: u32 = 64;
+
+/// The number of bits per channel the colors are quantized to for the histogram
+const QUANTIZATION_BITS: u32 = 4;
+
+/// Colors of the palette closer to an already selected color than this
+/// squared distance are skipped so that shades of one color aren't repeated
+const MIN_PALETTE_DISTANCE: u32 = 24 * 24;
+
+/// A bucket of similar colors in the histogram
+#[derive(Clone, Copy, Default)]
+struct Bucket {
+    count: u32,
+    sum: [u32; 3],
+}
+
+impl Bucket {
+    fn add(&mut self, pixel: [u8; 3]) {
+        self.count += 1;
+        for (sum, value) in self.sum.iter_mut().zip(pixel) {
+            *sum += value as u32;
+        }
+    }
+
+    fn mean(&self) -> Rgb<u8> {
+        Rgb(self
+            .sum
+            .map(|sum| ((sum + self.count / 2) / self.count) as u8))
+    }
+}
+
+/// Returns the mean color of all pixels that aren't fully transparent
+pub(crate) fn average_color(image: &DynamicImage) -> Option<Rgb<u8>> {
+    let mut bucket = Bucket::default();
+    for pixel in samples(image) {
+        bucket.add(pixel);
+    }
+
+    (bucket.count > 0).then(|| bucket.mean())
+}
+
+/// Returns up to `n` of the most common distinct colors ordered by how common they are.
+/// The colors are taken from a quantized histogram of all pixels that aren't fully
+/// transparent which makes the result deterministic.
+pub(crate) fn palette(image: &DynamicImage, n: usize) -> Vec<Rgb<u8>> {
+    let shift = 8 - QUANTIZATION_BITS;
+    let mut buckets = vec![Bucket::default(); 1 << (QUANTIZATION_BITS * 3)];
+    for pixel in samples(image) {
+        let index = (0..3).fold(0, |index, c| {
+            (index << QUANTIZATION_BITS) | (pixel[c] >> shift) as usize
+        });
+        buckets[index].add(pixel);
+    }
+    let mut buckets: Vec<Bucket> = buckets
+        .into_iter()
+        .filter(|bucket| bucket.count > 0)
+        .collect();
+    // the sort is stable so buckets with the same count stay in index order
+    buckets.sort_by_key(|bucket| Reverse(bucket.count));
+
+    let mut colors: Vec<Rgb<u8>> = Vec::with_capacity(n.min(buckets.len()));
+    for bucket in buckets {
+        if colors.len() >= n {
+            break;
+        }
+        let color = bucket.mean();
+        if colors
+            .iter()
+            .all(|selected| distance(*selected, color) >= MIN_PALETTE_DISTANCE)
+        {
+            colors.push(color);
+        }
+    }
+
+    colors
+}
+
+fn distance(a: Rgb<u8>, b: Rgb<u8>) -> u32 {
+    (0..3)
+        .map(|c| (a[c] as i32 - b[c] as i32).pow(2) as u32)
+        .sum()
+}
+
+/// Returns the colors of an evenly spaced grid of pixels skipping fully transparent ones
+fn samples(image: &DynamicImage) -> impl Iterator<Item = [u8; 3]> + '_ {
+    let (width, height) = image.dimensions();
+    let step_x = width.div_ceil(MAX_SAMPLES).max(1);
+    let step_y = height.div_ceil(MAX_SAMPLES).max(1);
+
+    (0..height).step_by(step_y as usize).flat_map(move |y| {
+        (0..width).step_by(step_x as usize).filter_map(move |x| {
+            let pixel = image.get_pixel(x, y);
+            (pixel[3] > 0).then(|| [pixel[0], pixel[1], pixel[2]])
+        })
+    })
+}
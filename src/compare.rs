@@ -0,0 +1,160 @@
+use crate::resize::resize_image;
+use crate::{FilterType, ResizeMode};
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage, RgbaImage};
+use std::borrow::Cow;
+
+/// The longest edge images are scaled down to before their similarity is computed
+const SIMILARITY_SIZE: u32 = 256;
+
+/// The width and height of the windows the structural similarity is computed in.
+/// Neighbouring windows overlap by half of their size.
+const WINDOW_SIZE: u32 = 8;
+
+/// The factor differences are multiplied with in diff images
+const DIFF_AMPLIFICATION: u32 = 8;
+
+// the stabilizing constants of ssim for values between 0 and 255
+const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// Returns whether both images have the same dimensions and pixels. Images of different
+/// color types are compared with 16 bits per channel.
+pub(crate) fn pixel_eq(a: &DynamicImage, b: &DynamicImage) -> bool {
+    if a.dimensions() != b.dimensions() {
+        return false;
+    }
+    if a.color() == b.color() {
+        return a.as_bytes() == b.as_bytes();
+    }
+
+    a.to_rgba16() == b.to_rgba16()
+}
+
+/// Returns the mean structural similarity (SSIM) of the images between 0 and 1. Both images
+/// are matched like [matched_images] and scaled down to at most 256 pixels first. Colors are
+/// premultiplied with their alpha, so the colors of transparent pixels don't matter.
+pub(crate) fn similarity(a: &DynamicImage, b: &DynamicImage) -> f64 {
+    let (a, b) = matched_images(a, b);
+    let (width, height) = a.dimensions();
+    let scale = (SIMILARITY_SIZE as f64 / width.max(height) as f64).min(1.0);
+    let (width, height) = (
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    );
+    let [a, b] = [a, b].map(|image| {
+        let image = if image.dimensions() == (width, height) {
+            image.into_owned()
+        } else {
+            resize_image(
+                &image,
+                width,
+                height,
+                ResizeMode::Exact,
+                FilterType::Triangle,
+            )
+        };
+        premultiplied_channels(&image.into_rgba8())
+    });
+
+    let mean = a
+        .iter()
+        .zip(&b)
+        .map(|(a, b)| channel_similarity(a, b, width, height))
+        .sum::<f64>()
+        / a.len() as f64;
+
+    mean.clamp(0.0, 1.0)
+}
+
+/// Returns an image of the differences of the images after matching them like
+/// [matched_images]. Equal pixels are a dimmed grayscale of the first image and the
+/// differences of each color channel are amplified 8 times in the same channel.
+/// Differences of the alpha channel are added to all channels.
+pub(crate) fn diff_image(a: &DynamicImage, b: &DynamicImage) -> RgbImage {
+    let (a, b) = matched_images(a, b);
+    let (a, b) = (a.to_rgba8(), b.to_rgba8());
+    let luma = DynamicImage::ImageRgba8(a.clone()).into_luma8();
+
+    RgbImage::from_fn(a.width(), a.height(), |x, y| {
+        let (pa, pb) = (a.get_pixel(x, y), b.get_pixel(x, y));
+        let alpha = pa[3].abs_diff(pb[3]) as u32;
+        let background = luma.get_pixel(x, y)[0] as u32 / 4;
+        Rgb([0, 1, 2].map(|c| {
+            let difference = pa[c].abs_diff(pb[c]) as u32 + alpha;
+            (background + difference * DIFF_AMPLIFICATION).min(255) as u8
+        }))
+    })
+}
+
+/// Returns both images with the same dimensions. If they differ, the image with fewer
+/// pixels is resized to the dimensions of the other one ignoring its aspect ratio.
+fn matched_images<'a>(
+    a: &'a DynamicImage,
+    b: &'a DynamicImage,
+) -> (Cow<'a, DynamicImage>, Cow<'a, DynamicImage>) {
+    let ((a_width, a_height), (b_width, b_height)) = (a.dimensions(), b.dimensions());
+    if (a_width, a_height) == (b_width, b_height) {
+        return (Cow::Borrowed(a), Cow::Borrowed(b));
+    }
+    let resize = |image: &DynamicImage, (width, height)| {
+        Cow::Owned(resize_image(
+            image,
+            width,
+            height,
+            ResizeMode::Exact,
+            FilterType::Lanczos3,
+        ))
+    };
+    if a_width as u64 * a_height as u64 >= b_width as u64 * b_height as u64 {
+        (Cow::Borrowed(a), resize(b, (a_width, a_height)))
+    } else {
+        (resize(a, (b_width, b_height)), Cow::Borrowed(b))
+    }
+}
+
+/// Returns the color channels premultiplied with alpha and the alpha channel
+fn premultiplied_channels(image: &RgbaImage) -> [Vec<f64>; 4] {
+    let mut channels: [Vec<f64>; 4] = Default::default();
+    for pixel in image.pixels() {
+        let alpha = pixel[3] as f64 / 255.0;
+        for (c, channel) in channels.iter_mut().enumerate().take(3) {
+            channel.push(pixel[c] as f64 * alpha);
+        }
+        channels[3].push(pixel[3] as f64);
+    }
+
+    channels
+}
+
+/// Returns the mean ssim of the windows of one channel
+fn channel_similarity(a: &[f64], b: &[f64], width: u32, height: u32) -> f64 {
+    let (window_width, window_height) = (WINDOW_SIZE.min(width), WINDOW_SIZE.min(height));
+    let starts = |size: u32, window: u32| (0..=size - window).step_by((window as usize / 2).max(1));
+    let (mut sum, mut count) = (0.0, 0);
+    for top in starts(height, window_height) {
+        for left in starts(width, window_width) {
+            let indices = (top..top + window_height)
+                .flat_map(|y| (left..left + window_width).map(move |x| (y * width + x) as usize));
+            sum += window_similarity(indices.map(|i| (a[i], b[i])));
+            count += 1;
+        }
+    }
+
+    sum / count as f64
+}
+
+fn window_similarity<I: Iterator<Item = (f64, f64)> + Clone>(values: I) -> f64 {
+    let n = values.clone().count() as f64;
+    let (mean_a, mean_b) = values
+        .clone()
+        .fold((0.0, 0.0), |(sa, sb), (a, b)| (sa + a, sb + b));
+    let (mean_a, mean_b) = (mean_a / n, mean_b / n);
+    let (var_a, var_b, covariance) = values.fold((0.0, 0.0, 0.0), |(va, vb, cov), (a, b)| {
+        let (da, db) = (a - mean_a, b - mean_b);
+        (va + da * da, vb + db * db, cov + da * db)
+    });
+    let (var_a, var_b, covariance) = (var_a / n, var_b / n, covariance / n);
+
+    ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+        / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2))
+}
@@ -0,0 +1,134 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::formats::{get_frames, DecodeOptions};
+use crate::mask::Shape;
+use crate::resize::resize_image;
+use crate::{FilterType, ResizeMode, Thumbnail, ThumbnailSize, DEFAULT_MATTE};
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+use mime::Mime;
+use std::io::{BufRead, Seek};
+
+/// Options for the grid of a contact sheet
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContactSheetOptions {
+    /// The number of cells in each row
+    pub columns: u32,
+
+    /// The number of rows
+    pub rows: u32,
+
+    /// The size of each cell. The images are fitted into the cells
+    /// preserving their aspect ratio.
+    pub cell_size: ThumbnailSize,
+
+    /// The space between cells in pixels
+    pub gap: u32,
+
+    /// The color behind the cells and of cells without an image
+    pub background: Rgba<u8>,
+}
+
+impl Default for ContactSheetOptions {
+    fn default() -> Self {
+        Self {
+            columns: 3,
+            rows: 3,
+            cell_size: ThumbnailSize::Small,
+            gap: 4,
+            background: Rgba([0, 0, 0, 255]),
+        }
+    }
+}
+
+impl ContactSheetOptions {
+    /// Returns the width and height of the whole sheet
+    fn dimensions(&self) -> ThumbResult<(u32, u32)> {
+        self.cell_size.validate()?;
+        if self.columns == 0 || self.rows == 0 {
+            return Err(ThumbError::InvalidOptions(format!(
+                "contact sheet with {} columns and {} rows",
+                self.columns, self.rows
+            )));
+        }
+        let (cell_width, cell_height) = self.cell_size.dimensions();
+        let length = |cells: u32, cell: u32| {
+            cells
+                .checked_mul(cell)?
+                .checked_add((cells - 1).checked_mul(self.gap)?)
+        };
+
+        match (
+            length(self.columns, cell_width),
+            length(self.rows, cell_height),
+        ) {
+            (Some(width), Some(height)) => Ok((width, height)),
+            _ => Err(ThumbError::InvalidOptions(format!(
+                "contact sheet of {}x{} cells is too large",
+                self.columns, self.rows
+            ))),
+        }
+    }
+}
+
+/// Creates a single thumbnail with a grid of images sampled from the source.
+/// Videos are sampled evenly across their duration and the cells are filled with
+/// the pages of documents in order. Other media only fill the first cell.
+/// Cells without an image, e.g. because the document has fewer pages, are left
+/// filled with the background color.
+///
+/// Example:
+/// ```
+/// use thumbnailer::{create_contact_sheet, ContactSheetOptions};
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let file = File::open("tests/assets/test.png").unwrap();
+/// let options = ContactSheetOptions {
+///     columns: 2,
+///     rows: 2,
+///     ..Default::default()
+/// };
+/// let sheet = create_contact_sheet(BufReader::new(file), mime::IMAGE_PNG, options).unwrap();
+/// assert_eq!(sheet.size(), (260, 260));
+/// ```
+pub fn create_contact_sheet<R: BufRead + Seek>(
+    reader: R,
+    mime: Mime,
+    options: ContactSheetOptions,
+) -> ThumbResult<Thumbnail> {
+    let (width, height) = options.dimensions()?;
+    let (cell_width, cell_height) = options.cell_size.dimensions();
+    let cells = options.columns as usize * options.rows as usize;
+    let decode_options = DecodeOptions {
+        target_size: Some((cell_width, cell_height)),
+        ..Default::default()
+    };
+    let frames = get_frames(reader, mime, cells, &decode_options)?;
+
+    let mut canvas = RgbaImage::from_pixel(width, height, options.background);
+    for (i, frame) in frames.iter().enumerate().take(cells) {
+        let frame = match frame {
+            Some(frame) => frame,
+            None => continue,
+        };
+        let cell = resize_image(
+            frame,
+            cell_width,
+            cell_height,
+            ResizeMode::Fit,
+            FilterType::Lanczos3,
+        );
+        let (column, row) = (i as u32 % options.columns, i as u32 / options.columns);
+        let x = column * (cell_width + options.gap) + (cell_width - cell.width()) / 2;
+        let y = row * (cell_height + options.gap) + (cell_height - cell.height()) / 2;
+        imageops::overlay(&mut canvas, &cell.into_rgba8(), x as i64, y as i64);
+    }
+
+    Ok(Thumbnail {
+        inner: DynamicImage::ImageRgba8(canvas),
+        requested_size: ThumbnailSize::from_dimensions(width, height)?,
+        matte: DEFAULT_MATTE,
+        deterministic: false,
+        dpi: None,
+        shape: Shape::Rectangle,
+    })
+}
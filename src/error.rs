@@ -1,43 +1,215 @@
+use crate::ThumbnailSize;
+use image::error::ImageFormatHint;
 use image::ImageError;
 use mime::Mime;
+use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
 use std::io;
 
 pub type ThumbResult<T> = Result<T, ThumbError>;
 
+/// The error type of the underlying cause of decoding and encoding errors
+pub type BoxedError = Box<dyn Error + Send + Sync>;
+
 #[derive(Debug)]
 pub enum ThumbError {
-    IO(io::Error),
+    Io(io::Error),
 
-    Image(image::error::ImageError),
+    UnsupportedMime(Mime),
 
-    Decode,
+    /// The content could not be decoded as the given format, usually because it is corrupt
+    Decode {
+        format: String,
+        source: BoxedError,
+    },
 
-    Unsupported(Mime),
+    /// The thumbnail could not be encoded in the given format
+    Encode {
+        format: String,
+        source: BoxedError,
+    },
 
     NullVideo,
 
-    FFMPEG(String),
+    InvalidSize(u32, u32),
+
+    InvalidQuality(u8),
+
+    InvalidOptions(String),
+
+    NoEmbeddedImage,
+
+    Cancelled,
+
+    /// The operation took longer than the configured timeout
+    Timeout,
+
+    SeekRequired(Mime),
+
+    /// The output format can't be written or isn't known, like the format of a path
+    /// with an unknown extension
+    UnsupportedOutputFormat(ImageFormatHint),
+
+    /// The dimensions of the input exceed the configured limit
+    InputTooLarge {
+        width: u32,
+        height: u32,
+        limit: u64,
+    },
+
+    /// The smallest encoded thumbnail of the given size in bytes exceeds the limit
+    OutputTooLarge {
+        size: usize,
+        limit: usize,
+    },
+
+    /// The archive has more entries than are examined while searching it for an image
+    TooManyEntries {
+        limit: usize,
+    },
+
+    /// Extracting frames of the video at the interval results in more frames than the limit
+    TooManyFrames {
+        count: usize,
+        limit: usize,
+    },
+
+    /// The entry of the archive with the given name decompresses to more bytes than the limit
+    EntryTooLarge {
+        name: String,
+        limit: u64,
+    },
+
+    /// The requested page doesn't exist in a document with the given number of pages
+    PageOutOfRange {
+        page: usize,
+        count: usize,
+    },
+
+    /// Resizing the thumbnail of the given size panicked
+    ResizeFailed {
+        size: ThumbnailSize,
+        reason: String,
+    },
+}
+
+impl ThumbError {
+    /// Creates a decoding error for the given format
+    pub fn decode<F: Into<String>, E: Into<BoxedError>>(format: F, source: E) -> Self {
+        Self::Decode {
+            format: format.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Creates an encoding error for the given format
+    pub(crate) fn encode<F: Into<String>, E: Into<BoxedError>>(format: F, source: E) -> Self {
+        Self::Encode {
+            format: format.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Returns whether the error was caused by an input or output type that isn't supported
+    pub fn is_unsupported(&self) -> bool {
+        matches!(
+            self,
+            ThumbError::UnsupportedMime(_) | ThumbError::UnsupportedOutputFormat(_)
+        )
+    }
+
+    /// Returns whether the error was caused by content that couldn't be decoded
+    pub fn is_corrupt(&self) -> bool {
+        matches!(self, ThumbError::Decode { .. } | ThumbError::NullVideo)
+    }
 }
 
 impl Display for ThumbError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            ThumbError::IO(_) => write!(f, "an io error occurred"),
-            ThumbError::Image(e) => write!(f, "an image error occurred {}", e),
-            ThumbError::Decode => write!(f, "failed to decode image"),
-            ThumbError::Unsupported(mime) => write!(f, "Unsupported media type {}", mime),
+            ThumbError::Io(e) => write!(f, "an io error occurred: {}", e),
+            ThumbError::UnsupportedMime(mime) => write!(f, "Unsupported media type {}", mime),
+            ThumbError::Decode { format, source } => {
+                write!(f, "failed to decode {}: {}", format, source)
+            }
+            ThumbError::Encode { format, source } => {
+                write!(f, "failed to encode {}: {}", format, source)
+            }
             ThumbError::NullVideo => write!(f, "no video data found in file"),
-            ThumbError::FFMPEG(e) => write!(f, "ffmpeg error: {}", e),
+            ThumbError::InvalidSize(width, height) => {
+                write!(f, "invalid thumbnail size {}x{}", width, height)
+            }
+            ThumbError::InvalidQuality(q) => write!(f, "invalid encoding quality {}", q),
+            ThumbError::InvalidOptions(e) => write!(f, "invalid options: {}", e),
+            ThumbError::NoEmbeddedImage => write!(f, "no embedded image found in file"),
+            ThumbError::Cancelled => write!(f, "the operation was cancelled"),
+            ThumbError::Timeout => write!(f, "the operation timed out"),
+            ThumbError::SeekRequired(mime) => {
+                write!(f, "media type {} requires a seekable reader", mime)
+            }
+            ThumbError::UnsupportedOutputFormat(ImageFormatHint::Exact(format)) => {
+                write!(f, "images can't be written as {:?}", format)
+            }
+            ThumbError::UnsupportedOutputFormat(ImageFormatHint::PathExtension(extension)) => {
+                write!(
+                    f,
+                    "no image format is known for the extension {}",
+                    extension.display()
+                )
+            }
+            ThumbError::UnsupportedOutputFormat(_) => {
+                write!(f, "no image format is known for the output")
+            }
+            ThumbError::InputTooLarge {
+                width,
+                height,
+                limit,
+            } => write!(
+                f,
+                "input of {}x{} pixels exceeds the limit of {}",
+                width, height, limit
+            ),
+            ThumbError::OutputTooLarge { size, limit } => write!(
+                f,
+                "the smallest encoded thumbnail of {} bytes exceeds the limit of {}",
+                size, limit
+            ),
+            ThumbError::TooManyEntries { limit } => {
+                write!(f, "the archive has more than {} entries", limit)
+            }
+            ThumbError::TooManyFrames { count, limit } => write!(
+                f,
+                "the video has {} frames at the interval, more than the limit of {}",
+                count, limit
+            ),
+            ThumbError::EntryTooLarge { name, limit } => write!(
+                f,
+                "the archive entry {} decompresses to more than {} bytes",
+                name, limit
+            ),
+            ThumbError::PageOutOfRange { page, count } => write!(
+                f,
+                "page {} is out of range, the document has {} pages",
+                page, count
+            ),
+            ThumbError::ResizeFailed { size, reason } => {
+                write!(
+                    f,
+                    "failed to resize thumbnail of size {:?}: {}",
+                    size, reason
+                )
+            }
         }
     }
 }
 
-impl std::error::Error for ThumbError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+impl Error for ThumbError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            ThumbError::IO(e) => e.source(),
-            ThumbError::Image(i) => i.source(),
+            ThumbError::Io(e) => Some(e),
+            ThumbError::Decode { source, .. } | ThumbError::Encode { source, .. } => {
+                Some(source.as_ref())
+            }
             _ => None,
         }
     }
@@ -45,12 +217,87 @@ impl std::error::Error for ThumbError {
 
 impl From<io::Error> for ThumbError {
     fn from(e: io::Error) -> Self {
-        Self::IO(e)
+        Self::Io(e)
     }
 }
 
-impl From<image::error::ImageError> for ThumbError {
+impl From<ImageError> for ThumbError {
     fn from(e: ImageError) -> Self {
-        Self::Image(e)
+        match e {
+            ImageError::IoError(e) => Self::Io(e),
+            ImageError::Encoding(ref inner) => Self::encode(format_name(inner.format_hint()), e),
+            ImageError::Decoding(ref inner) => Self::decode(format_name(inner.format_hint()), e),
+            ImageError::Unsupported(ref inner) => Self::decode(format_name(inner.format_hint()), e),
+            ImageError::Limits(_) | ImageError::Parameter(_) => Self::decode("image", e),
+        }
     }
 }
+
+fn format_name(hint: ImageFormatHint) -> String {
+    match hint {
+        ImageFormatHint::Exact(format) => format!("{:?}", format).to_lowercase(),
+        ImageFormatHint::Name(name) => name,
+        ImageFormatHint::PathExtension(extension) => extension.to_string_lossy().into_owned(),
+        _ => String::from("image"),
+    }
+}
+
+/// The error returned when a string can't be parsed as a [ThumbnailSize]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseSizeError {
+    input: String,
+}
+
+impl ParseSizeError {
+    pub(crate) fn new<S: Into<String>>(input: S) -> Self {
+        Self {
+            input: input.into(),
+        }
+    }
+}
+
+impl Display for ParseSizeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let presets: Vec<&str> = ThumbnailSize::all()
+            .iter()
+            .filter_map(ThumbnailSize::name)
+            .collect();
+
+        write!(
+            f,
+            "invalid thumbnail size \"{}\", expected one of {}, a number, WIDTHxHEIGHT, \
+            longest:EDGE or shortest:EDGE with non-zero dimensions, optionally followed by \
+            a scale factor like NAME@2x",
+            self.input,
+            presets.join(", ")
+        )
+    }
+}
+
+impl Error for ParseSizeError {}
+
+/// The error returned when a string can't be decoded as an [crate::ImageHash]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseHashError {
+    input: String,
+}
+
+impl ParseHashError {
+    pub(crate) fn new<S: Into<String>>(input: S) -> Self {
+        Self {
+            input: input.into(),
+        }
+    }
+}
+
+impl Display for ParseHashError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid image hash \"{}\", expected 12 base64 characters",
+            self.input
+        )
+    }
+}
+
+impl Error for ParseHashError {}
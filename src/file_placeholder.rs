@@ -0,0 +1,257 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::formats::sniff::extension_from_mime;
+use crate::mask::Shape;
+use crate::{create_thumbnails, Thumbnail, ThumbnailSize, DEFAULT_MATTE};
+use image::{DynamicImage, Rgba, RgbaImage};
+use mime::Mime;
+use std::io::{BufRead, Seek};
+
+/// The width of the file glyph divided by its height
+const GLYPH_ASPECT: f32 = 0.78;
+
+/// The height of the glyph relative to the shorter edge of the fitted area
+const GLYPH_SCALE: f32 = 0.9;
+
+/// The size of the folded corner relative to the width of the glyph
+const FOLD_SCALE: f32 = 0.3;
+
+/// The font size of the label relative to the height of the glyph. Labels that don't fit
+/// are drawn smaller down to the minimum before they are truncated.
+#[cfg(feature = "text")]
+const LABEL_SCALE: f32 = 0.2;
+#[cfg(feature = "text")]
+const MIN_LABEL_SCALE: f32 = 0.12;
+
+/// The width of the label relative to the width of the glyph
+#[cfg(feature = "text")]
+const LABEL_WIDTH: f32 = 0.84;
+
+#[cfg(feature = "text")]
+const FONT: &[u8] = include_bytes!("../assets/DejaVuSansMono.ttf");
+
+#[cfg(feature = "text")]
+const LABEL_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Returns placeholder thumbnails of the requested sizes with a generic file glyph for
+/// files that can't be thumbnailed. The glyph is colored by a hash of the media type, so
+/// files of the same type always get the same color. The label is drawn centered onto the
+/// glyph with the `text` feature and defaults to the uppercase extension or subtype of the
+/// media type. Long labels are truncated with an ellipsis and empty labels draw just the glyph.
+///
+/// Example:
+/// ```
+/// use thumbnailer::{generate_placeholder, ThumbnailSize};
+///
+/// let thumbnails = generate_placeholder(&mime::APPLICATION_PDF, None, [ThumbnailSize::Small]);
+/// assert_eq!(thumbnails[0].size(), (128, 128));
+/// ```
+pub fn generate_placeholder<I: IntoIterator<Item = ThumbnailSize>>(
+    mime: &Mime,
+    label: Option<&str>,
+    sizes: I,
+) -> Vec<Thumbnail> {
+    let label = match label {
+        Some(label) => label.trim().to_string(),
+        None => default_label(mime),
+    };
+    let color = mime_color(mime);
+
+    sizes
+        .into_iter()
+        .map(|size| {
+            let (width, height) = size.dimensions();
+            let image = render_placeholder(width.max(1), height.max(1), color, &label);
+            Thumbnail {
+                inner: DynamicImage::ImageRgba8(image),
+                requested_size: size,
+                matte: DEFAULT_MATTE,
+                deterministic: false,
+                dpi: None,
+                shape: Shape::Rectangle,
+            }
+        })
+        .collect()
+}
+
+/// Creates thumbnails of the requested sizes like [create_thumbnails] and returns
+/// placeholders like [generate_placeholder] if the media type isn't supported, the content
+/// is corrupt or contains no image. Other errors like io errors are still returned.
+pub fn create_thumbnails_or_placeholder<R, I>(
+    reader: R,
+    mime: Mime,
+    sizes: I,
+) -> ThumbResult<Vec<Thumbnail>>
+where
+    R: BufRead + Seek,
+    I: IntoIterator<Item = ThumbnailSize>,
+{
+    let sizes: Vec<ThumbnailSize> = sizes.into_iter().collect();
+
+    match create_thumbnails(reader, mime.clone(), sizes.iter().copied()) {
+        Err(e)
+            if e.is_unsupported() || e.is_corrupt() || matches!(e, ThumbError::NoEmbeddedImage) =>
+        {
+            Ok(generate_placeholder(&mime, None, sizes))
+        }
+        result => result,
+    }
+}
+
+/// Returns the uppercase extension of the media type or its subtype without
+/// vendor and experimental prefixes
+fn default_label(mime: &Mime) -> String {
+    let label = match extension_from_mime(mime) {
+        Some(extension) => extension,
+        None => {
+            let subtype = mime.subtype().as_str();
+            let subtype = subtype.strip_prefix("vnd.").unwrap_or(subtype);
+            subtype.strip_prefix("x-").unwrap_or(subtype)
+        }
+    };
+
+    label.to_uppercase()
+}
+
+/// Returns a color with a hue from the FNV-1a hash of the media type
+fn mime_color(mime: &Mime) -> Rgba<u8> {
+    let hash = mime
+        .essence_str()
+        .to_ascii_lowercase()
+        .bytes()
+        .fold(0x811c_9dc5_u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+        });
+    let [r, g, b] = hsl_to_rgb((hash % 360) as f32, 0.55, 0.45);
+
+    Rgba([r, g, b, 255])
+}
+
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> [u8; 3] {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+
+    [r, g, b].map(|c| ((c + m) * 255.0).round() as u8)
+}
+
+/// Returns how much of the pixel at the position is covered by the span from start to end
+fn span_coverage(position: u32, start: f32, end: f32) -> f32 {
+    let position = position as f32;
+    (end.min(position + 1.0) - start.max(position)).clamp(0.0, 1.0)
+}
+
+/// Renders the glyph of a page with a folded top right corner centered onto a
+/// transparent image with the label on top of it
+#[cfg_attr(not(feature = "text"), allow(unused_variables))]
+fn render_placeholder(width: u32, height: u32, color: Rgba<u8>, label: &str) -> RgbaImage {
+    let glyph_height = (height as f32).min(width as f32 / GLYPH_ASPECT) * GLYPH_SCALE;
+    let glyph_width = glyph_height * GLYPH_ASPECT;
+    let left = (width as f32 - glyph_width) / 2.0;
+    let top = (height as f32 - glyph_height) / 2.0;
+    let (right, bottom) = (left + glyph_width, top + glyph_height);
+    let fold = glyph_width * FOLD_SCALE;
+    let fold_color = [0, 1, 2].map(|c| color[c] / 2 + 128);
+
+    #[cfg_attr(not(feature = "text"), allow(unused_mut))]
+    let mut image = RgbaImage::from_fn(width, height, |x, y| {
+        let coverage = span_coverage(x, left, right) * span_coverage(y, top, bottom);
+        if coverage == 0.0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        // the signed distance of the pixel center to the diagonal of the folded corner
+        let distance =
+            ((x as f32 + 0.5 - (right - fold)) - (y as f32 + 0.5 - top)) / std::f32::consts::SQRT_2;
+        let coverage = coverage * (0.5 - distance).clamp(0.0, 1.0);
+        let folded = span_coverage(x, right - fold, right) * span_coverage(y, top, top + fold);
+        let mut pixel = color;
+        for c in 0..3 {
+            pixel[c] =
+                (color[c] as f32 * (1.0 - folded) + fold_color[c] as f32 * folded).round() as u8;
+        }
+        pixel[3] = (coverage * 255.0).round() as u8;
+
+        pixel
+    });
+    #[cfg(feature = "text")]
+    draw_label(
+        &mut image,
+        label,
+        (left + right) / 2.0,
+        (top + fold + bottom) / 2.0,
+        glyph_width,
+        glyph_height,
+    );
+
+    image
+}
+
+/// Draws the label centered on the position. Labels wider than the glyph are drawn smaller
+/// and truncated with an ellipsis if they still don't fit at the minimum size.
+#[cfg(feature = "text")]
+fn draw_label(
+    image: &mut RgbaImage,
+    label: &str,
+    center_x: f32,
+    center_y: f32,
+    glyph_width: f32,
+    glyph_height: f32,
+) {
+    use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+
+    let mut chars: Vec<char> = label.chars().filter(|c| !c.is_control()).collect();
+    let font = match FontRef::try_from_slice(FONT) {
+        Ok(font) if !chars.is_empty() => font,
+        _ => return,
+    };
+    let available = glyph_width * LABEL_WIDTH;
+    // the font is monospaced, so the width of the label is the advance times its length
+    let advance = font
+        .as_scaled(PxScale::from(1.0))
+        .h_advance(font.glyph_id('0'));
+    let size = (available / (advance * chars.len() as f32))
+        .min(glyph_height * LABEL_SCALE)
+        .max(glyph_height * MIN_LABEL_SCALE);
+    let scale = PxScale::from(size);
+    let scaled = font.as_scaled(scale);
+    let columns = (available / (advance * size)) as usize;
+    if chars.len() > columns {
+        chars.truncate(columns.saturating_sub(1));
+        chars.push('…');
+    }
+
+    let (width, height) = image.dimensions();
+    let mut x = center_x - advance * size * chars.len() as f32 / 2.0;
+    let baseline = center_y + (scaled.ascent() + scaled.descent()) / 2.0;
+    for c in chars {
+        let glyph = font
+            .glyph_id(c)
+            .with_scale_and_position(scale, point(x, baseline));
+        x += advance * size;
+        if let Some(outline) = font.outline_glyph(glyph) {
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i64 + gx as i64;
+                let py = bounds.min.y as i64 + gy as i64;
+                if px < 0 || py < 0 || px as u32 >= width || py as u32 >= height {
+                    return;
+                }
+                let pixel = image.get_pixel_mut(px as u32, py as u32);
+                let coverage = coverage.clamp(0.0, 1.0);
+                for c in 0..3 {
+                    pixel[c] = (pixel[c] as f32 * (1.0 - coverage)
+                        + LABEL_COLOR[c] as f32 * coverage)
+                        .round() as u8;
+                }
+                pixel[3] = pixel[3].max((coverage * 255.0).round() as u8);
+            });
+        }
+    }
+}
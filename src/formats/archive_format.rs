@@ -0,0 +1,199 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::formats::image_format::read_image_from_memory;
+use crate::formats::zip_archive::open_archive;
+use crate::formats::InputLimits;
+use crate::utils::archive_paths::{is_image_path, path_cmp};
+use crate::BaseImage;
+use flate2::bufread::GzDecoder;
+use image::ImageFormat;
+use mime::Mime;
+use std::io::{BufRead, Read, Seek, SeekFrom};
+
+/// The most entries of an archive that are examined while searching for an image
+const MAX_ENTRIES: usize = 10_000;
+
+/// The most bytes an image in an archive may decompress to
+const MAX_ENTRY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The number of bytes read from the start of entries to confirm that they are images
+const MAGIC_LEN: u64 = 32;
+
+/// Returns whether the mime describes a zip archive
+fn is_zip_mime(mime: &Mime) -> bool {
+    mime.type_() == mime::APPLICATION
+        && matches!(
+            mime.subtype().as_str(),
+            "zip" | "x-zip" | "x-zip-compressed"
+        )
+}
+
+/// Returns whether the mime describes a tar archive that might be compressed with gzip
+fn is_tar_mime(mime: &Mime) -> bool {
+    mime.type_() == mime::APPLICATION
+        && matches!(
+            mime.subtype().as_str(),
+            "tar" | "x-tar" | "gzip" | "x-gzip" | "x-gtar" | "x-compressed-tar"
+        )
+}
+
+/// Returns whether the mime describes a zip or (gzipped) tar archive
+pub fn is_archive_mime(mime: &Mime) -> bool {
+    is_zip_mime(mime) || is_tar_mime(mime)
+}
+
+/// Reads the first image of a zip or tar archive. The entries are ordered like a depth first
+/// traversal with natural order in each directory, and the first entry with the extension
+/// of an image that also starts like one is decoded. Archives with more entries than are
+/// examined or an image that decompresses to too many bytes result in an error, and
+/// archives without images return [ThumbError::NoEmbeddedImage].
+pub fn read_archive_image<R: BufRead + Seek>(
+    reader: R,
+    mime: &Mime,
+    limits: &InputLimits,
+) -> ThumbResult<BaseImage> {
+    let (bytes, format) = if is_zip_mime(mime) {
+        read_zip_image(reader)?
+    } else {
+        read_tar_image(reader)?
+    };
+    let image = read_image_from_memory(&bytes, limits)?;
+
+    Ok(BaseImage::new(image, Some(format)))
+}
+
+/// Returns the bytes of the first image of a zip archive
+fn read_zip_image<R: Read + Seek>(reader: R) -> ThumbResult<(Vec<u8>, ImageFormat)> {
+    let mut archive = open_archive(reader, "zip")?;
+    if archive.len() > MAX_ENTRIES {
+        return Err(ThumbError::TooManyEntries { limit: MAX_ENTRIES });
+    }
+    // the central directory lists all entries without decompressing them
+    let mut candidates: Vec<(usize, String)> = (0..archive.len())
+        .filter_map(|index| {
+            let entry = archive.by_index_raw(index).ok()?;
+            let name = entry.name().ok()?.into_owned();
+            (!entry.is_dir() && is_image_path(&name)).then_some((index, name))
+        })
+        .collect();
+    candidates.sort_by(|(_, a), (_, b)| path_cmp(a, b));
+
+    for (index, name) in candidates {
+        let encrypted = archive
+            .by_index_raw(index)
+            .map_err(|e| ThumbError::decode("zip", e))?
+            .encrypted();
+        if encrypted {
+            return Err(ThumbError::decode(
+                "zip",
+                "the archive is password protected",
+            ));
+        }
+        let entry = archive
+            .by_index(index)
+            .map_err(|e| ThumbError::decode("zip", e))?;
+        if let Some(image) = read_image_entry(entry, &name, "zip")? {
+            return Ok(image);
+        }
+    }
+
+    Err(ThumbError::NoEmbeddedImage)
+}
+
+/// Returns the bytes of the first image of a tar archive. Tar archives can't be listed
+/// without reading them, so the images are found in a first pass and the first one is
+/// read in a second pass.
+fn read_tar_image<R: BufRead + Seek>(mut reader: R) -> ThumbResult<(Vec<u8>, ImageFormat)> {
+    let start = reader.stream_position()?;
+    let mut images = Vec::new();
+    let mut archive = open_tar(&mut reader)?;
+    let entries = archive
+        .entries()
+        .map_err(|e| ThumbError::decode("tar", e))?;
+    for (index, entry) in entries.enumerate() {
+        if index >= MAX_ENTRIES {
+            return Err(ThumbError::TooManyEntries { limit: MAX_ENTRIES });
+        }
+        let mut entry = entry.map_err(|e| ThumbError::decode("tar", e))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = tar_entry_name(&entry)?;
+        if !is_image_path(&name) {
+            continue;
+        }
+        let mut magic = Vec::new();
+        (&mut entry)
+            .take(MAGIC_LEN)
+            .read_to_end(&mut magic)
+            .map_err(|e| ThumbError::decode("tar", e))?;
+        if image::guess_format(&magic).is_ok() {
+            images.push((index, name));
+        }
+    }
+    drop(archive);
+    let (index, name) = images
+        .into_iter()
+        .min_by(|(_, a), (_, b)| path_cmp(a, b))
+        .ok_or(ThumbError::NoEmbeddedImage)?;
+
+    reader.seek(SeekFrom::Start(start))?;
+    let mut archive = open_tar(&mut reader)?;
+    let entry = archive
+        .entries()
+        .map_err(|e| ThumbError::decode("tar", e))?
+        .nth(index)
+        .ok_or_else(|| ThumbError::decode("tar", "the archive changed while reading it"))?
+        .map_err(|e| ThumbError::decode("tar", e))?;
+
+    read_image_entry(entry, &name, "tar")?.ok_or(ThumbError::NoEmbeddedImage)
+}
+
+/// Opens a tar archive that is decompressed if it starts like a gzip stream
+fn open_tar<'a, R: BufRead + 'a>(mut reader: R) -> ThumbResult<tar::Archive<Box<dyn Read + 'a>>> {
+    let gzip = reader.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+    let reader: Box<dyn Read + 'a> = if gzip {
+        Box::new(GzDecoder::new(reader))
+    } else {
+        Box::new(reader)
+    };
+
+    Ok(tar::Archive::new(reader))
+}
+
+/// Returns the path of the entry without a leading `./`
+fn tar_entry_name<R: Read>(entry: &tar::Entry<'_, R>) -> ThumbResult<String> {
+    let path = entry.path().map_err(|e| ThumbError::decode("tar", e))?;
+    let name = path.to_string_lossy();
+
+    Ok(name.trim_start_matches("./").to_string())
+}
+
+/// Reads the entry if it starts like an image. Returns `None` for other content.
+fn read_image_entry<E: Read>(
+    mut entry: E,
+    name: &str,
+    format: &str,
+) -> ThumbResult<Option<(Vec<u8>, ImageFormat)>> {
+    let mut buf = Vec::new();
+    (&mut entry)
+        .take(MAGIC_LEN)
+        .read_to_end(&mut buf)
+        .map_err(|e| ThumbError::decode(format, e))?;
+    let image_format = match image::guess_format(&buf) {
+        Ok(image_format) => image_format,
+        Err(_) => return Ok(None),
+    };
+    // one byte more than the limit is read to detect entries that exceed it
+    entry
+        .take(MAX_ENTRY_SIZE + 1 - buf.len() as u64)
+        .read_to_end(&mut buf)
+        .map_err(|e| ThumbError::decode(format, e))?;
+    if buf.len() as u64 > MAX_ENTRY_SIZE {
+        return Err(ThumbError::EntryTooLarge {
+            name: name.to_string(),
+            limit: MAX_ENTRY_SIZE,
+        });
+    }
+
+    Ok(Some((buf, image_format)))
+}
@@ -0,0 +1,34 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::formats::image_format::read_image_from_memory;
+use crate::formats::InputLimits;
+use image::DynamicImage;
+use lofty::config::ParseOptions;
+use lofty::file::TaggedFileExt;
+use lofty::picture::PictureType;
+use lofty::probe::Probe;
+use std::io::{BufRead, Seek};
+
+/// Reads the embedded cover art of an audio file preferring the front cover
+pub fn read_cover_art<R: BufRead + Seek>(
+    reader: R,
+    limits: &InputLimits,
+) -> ThumbResult<DynamicImage> {
+    let tagged_file = Probe::new(reader)
+        .options(ParseOptions::new().read_properties(false))
+        .guess_file_type()?
+        .read()
+        .map_err(|e| ThumbError::decode("audio", e))?;
+
+    let pictures: Vec<_> = tagged_file
+        .tags()
+        .iter()
+        .flat_map(|tag| tag.pictures())
+        .collect();
+    let picture = pictures
+        .iter()
+        .find(|picture| picture.pic_type() == PictureType::CoverFront)
+        .or_else(|| pictures.first())
+        .ok_or(ThumbError::NoEmbeddedImage)?;
+
+    read_image_from_memory(picture.data(), limits)
+}
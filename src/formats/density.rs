@@ -0,0 +1,184 @@
+use crate::error::ThumbResult;
+use mime::Mime;
+use std::convert::TryInto;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+const CM_PER_INCH: f32 = 2.54;
+const METERS_PER_INCH: f32 = 0.0254;
+
+/// The most entries of a tiff directory that are searched for the resolution
+const MAX_TIFF_ENTRIES: u16 = 1024;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const TIFF_X_RESOLUTION: u16 = 282;
+const TIFF_Y_RESOLUTION: u16 = 283;
+const TIFF_RESOLUTION_UNIT: u16 = 296;
+const TIFF_RATIONAL: u16 = 5;
+
+/// Reads the horizontal and vertical resolution in dots per inch that png (pHYs),
+/// jpeg (JFIF and EXIF) and tiff images store in their headers. Returns `None` if the
+/// image doesn't store a resolution or only stores an aspect ratio. The reader is
+/// returned to its position afterwards.
+pub fn read_density<R: Read + Seek>(
+    reader: &mut R,
+    mime: &Mime,
+) -> ThumbResult<Option<(f32, f32)>> {
+    let start = reader.stream_position()?;
+    // broken metadata shouldn't prevent the image from being decoded
+    let density = match mime.essence_str() {
+        "image/png" => read_png_density(reader).ok().flatten(),
+        "image/jpeg" => read_jpeg_density(reader).ok().flatten(),
+        "image/tiff" => read_tiff_density(reader, start).ok().flatten(),
+        _ => None,
+    };
+    reader.seek(SeekFrom::Start(start))?;
+
+    Ok(density.filter(|&(x, y)| x.is_finite() && x > 0.0 && y.is_finite() && y > 0.0))
+}
+
+/// Reads the pHYs chunk which has to come before the image data
+fn read_png_density<R: Read + Seek>(reader: &mut R) -> io::Result<Option<(f32, f32)>> {
+    let mut signature = [0u8; 8];
+    reader.read_exact(&mut signature)?;
+    if signature != PNG_SIGNATURE {
+        return Ok(None);
+    }
+    loop {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        let length = u32::from_be_bytes(header[..4].try_into().unwrap());
+        match &header[4..] {
+            b"pHYs" if length == 9 => {
+                let mut data = [0u8; 9];
+                reader.read_exact(&mut data)?;
+                let x = u32::from_be_bytes(data[..4].try_into().unwrap());
+                let y = u32::from_be_bytes(data[4..8].try_into().unwrap());
+                // the unit is either meters or unknown which means only the aspect ratio is known
+                return Ok((data[8] == 1)
+                    .then_some((x as f32 * METERS_PER_INCH, y as f32 * METERS_PER_INCH)));
+            }
+            b"IDAT" | b"IEND" => return Ok(None),
+            _ => {
+                reader.seek(SeekFrom::Current(length as i64 + 4))?;
+            }
+        }
+    }
+}
+
+/// Reads the density of the JFIF segment or, if it doesn't have a unit,
+/// the resolution of the EXIF segment
+fn read_jpeg_density<R: Read + Seek>(reader: &mut R) -> io::Result<Option<(f32, f32)>> {
+    let mut soi = [0u8; 2];
+    reader.read_exact(&mut soi)?;
+    if soi != [0xff, 0xd8] {
+        return Ok(None);
+    }
+    let mut exif_density = None;
+    loop {
+        let mut marker = [0u8; 2];
+        reader.read_exact(&mut marker)?;
+        if marker[0] != 0xff {
+            return Ok(exif_density);
+        }
+        match marker[1] {
+            // fill bytes
+            0xff => {
+                reader.seek(SeekFrom::Current(-1))?;
+                continue;
+            }
+            // markers without a length
+            0x01 | 0xd0..=0xd7 => continue,
+            // metadata segments come before the frame and scan headers
+            0xc0..=0xcf | 0xd9 | 0xda => return Ok(exif_density),
+            _ => {}
+        }
+        let mut length = [0u8; 2];
+        reader.read_exact(&mut length)?;
+        let length = u16::from_be_bytes(length).saturating_sub(2) as usize;
+        match marker[1] {
+            0xe0 | 0xe1 => {
+                let mut data = vec![0u8; length];
+                reader.read_exact(&mut data)?;
+                if marker[1] == 0xe0 && data.len() >= 12 && data.starts_with(b"JFIF\0") {
+                    let x = u16::from_be_bytes([data[8], data[9]]) as f32;
+                    let y = u16::from_be_bytes([data[10], data[11]]) as f32;
+                    match data[7] {
+                        1 => return Ok(Some((x, y))),
+                        2 => return Ok(Some((x * CM_PER_INCH, y * CM_PER_INCH))),
+                        _ => {}
+                    }
+                } else if marker[1] == 0xe1 && data.starts_with(b"Exif\0\0") {
+                    exif_density = read_tiff_density(&mut Cursor::new(&data[6..]), 0)
+                        .ok()
+                        .flatten();
+                }
+            }
+            _ => {
+                reader.seek(SeekFrom::Current(length as i64))?;
+            }
+        }
+    }
+}
+
+/// Reads the resolution tags of the first directory of a tiff file or EXIF segment
+/// that starts at the given offset
+fn read_tiff_density<R: Read + Seek>(reader: &mut R, base: u64) -> io::Result<Option<(f32, f32)>> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header)?;
+    let little_endian = match &header[..4] {
+        b"II*\0" => true,
+        b"MM\0*" => false,
+        _ => return Ok(None),
+    };
+    let u16_at = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1]];
+        if little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        }
+    };
+    let u32_at = |bytes: &[u8]| {
+        let bytes = bytes[..4].try_into().unwrap();
+        if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    };
+
+    reader.seek(SeekFrom::Start(base + u32_at(&header[4..]) as u64))?;
+    let mut count = [0u8; 2];
+    reader.read_exact(&mut count)?;
+    let count = u16_at(&count).min(MAX_TIFF_ENTRIES);
+    let mut entries = vec![0u8; count as usize * 12];
+    reader.read_exact(&mut entries)?;
+
+    let (mut x_offset, mut y_offset, mut unit) = (None, None, 2);
+    for entry in entries.chunks_exact(12) {
+        let value = &entry[8..];
+        match (u16_at(entry), u16_at(&entry[2..])) {
+            (TIFF_X_RESOLUTION, TIFF_RATIONAL) => x_offset = Some(u32_at(value)),
+            (TIFF_Y_RESOLUTION, TIFF_RATIONAL) => y_offset = Some(u32_at(value)),
+            (TIFF_RESOLUTION_UNIT, _) => unit = u16_at(value),
+            _ => {}
+        }
+    }
+    let mut read_rational = |offset: u32| -> io::Result<f32> {
+        let mut rational = [0u8; 8];
+        reader.seek(SeekFrom::Start(base + offset as u64))?;
+        reader.read_exact(&mut rational)?;
+        Ok(u32_at(&rational) as f32 / u32_at(&rational[4..]) as f32)
+    };
+    let (x, y) = match (x_offset, y_offset) {
+        (Some(x), Some(y)) => (read_rational(x)?, read_rational(y)?),
+        _ => return Ok(None),
+    };
+
+    // the unit is inches by default and also can be centimeters or none
+    Ok(match unit {
+        2 => Some((x, y)),
+        3 => Some((x * CM_PER_INCH, y * CM_PER_INCH)),
+        _ => None,
+    })
+}
@@ -0,0 +1,140 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::formats::image_format::read_image_from_memory;
+use crate::formats::markup::{attribute, elements};
+use crate::formats::zip_archive::{open_archive, read_entry};
+use crate::formats::InputLimits;
+use crate::utils::archive_paths::{is_image_path, natural_cmp};
+use image::DynamicImage;
+use mime::Mime;
+use std::io::{Read, Seek};
+use zip::ZipArchive;
+
+/// The file of epub packages that points to the package document
+const EPUB_CONTAINER: &str = "META-INF/container.xml";
+
+/// Returns whether the mime describes an epub ebook
+pub fn is_epub_mime(mime: &Mime) -> bool {
+    mime.type_() == mime::APPLICATION && mime.subtype() == "epub"
+}
+
+/// Returns whether the mime describes a zip comic book archive (cbz)
+pub fn is_comic_book_mime(mime: &Mime) -> bool {
+    mime.type_() == mime::APPLICATION
+        && (mime.subtype() == "vnd.comicbook" || mime.subtype() == "x-cbz")
+}
+
+/// Reads the cover image of an epub ebook. The cover is the manifest item with the
+/// `cover-image` property (epub 3) or the item referenced by the `cover` meta element (epub 2).
+/// Books without a cover return [ThumbError::NoEmbeddedImage].
+pub fn read_epub_cover<R: Read + Seek>(
+    reader: R,
+    limits: &InputLimits,
+) -> ThumbResult<DynamicImage> {
+    let mut archive = open_archive(reader, "epub")?;
+    let container = read_text(&mut archive, EPUB_CONTAINER)?
+        .ok_or_else(|| ThumbError::decode("epub", "missing container"))?;
+    let package_path = elements(&container, "rootfile")
+        .find_map(|element| attribute(element, "full-path"))
+        .ok_or_else(|| ThumbError::decode("epub", "missing package document"))?;
+    let package = read_text(&mut archive, &package_path)?
+        .ok_or_else(|| ThumbError::decode("epub", "missing package document"))?;
+
+    let href = cover_href(&package).ok_or(ThumbError::NoEmbeddedImage)?;
+    // hrefs are relative to the directory of the package document
+    let directory = package_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+    let path = resolve_path(directory, &percent_decode(&href));
+    let cover = read_entry(&mut archive, &path, "epub")?.ok_or(ThumbError::NoEmbeddedImage)?;
+
+    // covers can also be svg or xhtml documents that aren't decoded here
+    if image::guess_format(&cover).is_err() {
+        return Err(ThumbError::NoEmbeddedImage);
+    }
+    read_image_from_memory(&cover, limits)
+}
+
+/// Reads the first page of a zip comic book archive. The pages are the images of the archive
+/// in natural order so that `page2` comes before `page10`.
+/// Archives without images return [ThumbError::NoEmbeddedImage].
+pub fn read_comic_book_cover<R: Read + Seek>(
+    reader: R,
+    limits: &InputLimits,
+) -> ThumbResult<DynamicImage> {
+    let mut archive = open_archive(reader, "cbz")?;
+    let first_page = archive
+        .file_names()
+        .filter_map(Result::ok)
+        .filter(|name| is_image_path(name))
+        .min_by(|a, b| natural_cmp(a, b))
+        .map(|name| name.into_owned())
+        .ok_or(ThumbError::NoEmbeddedImage)?;
+    let page = read_entry(&mut archive, &first_page, "cbz")?.ok_or(ThumbError::NoEmbeddedImage)?;
+
+    read_image_from_memory(&page, limits)
+}
+
+fn read_text<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    path: &str,
+) -> ThumbResult<Option<String>> {
+    Ok(
+        read_entry(archive, path, "epub")?
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+    )
+}
+
+/// Returns the href of the cover image from the package document of an epub
+fn cover_href(package: &str) -> Option<String> {
+    let items = || elements(package, "item");
+    let epub3_cover = items().find(|item| {
+        attribute(item, "properties")
+            .is_some_and(|properties| properties.split_whitespace().any(|p| p == "cover-image"))
+    });
+    let cover = epub3_cover.or_else(|| {
+        let id = elements(package, "meta")
+            .find(|meta| attribute(meta, "name").as_deref() == Some("cover"))
+            .and_then(|meta| attribute(meta, "content"))?;
+        items().find(|item| attribute(item, "id").as_deref() == Some(id.as_str()))
+    })?;
+
+    attribute(cover, "href")
+}
+
+/// Joins a relative path with a directory of the archive resolving `.` and `..` segments
+fn resolve_path(directory: &str, href: &str) -> String {
+    let mut segments: Vec<&str> = directory.split('/').filter(|s| !s.is_empty()).collect();
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    segments.join("/")
+}
+
+fn percent_decode(href: &str) -> String {
+    let bytes = href.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,75 @@
+use crate::error::{ThumbError, ThumbResult};
+#[cfg(feature = "icc")]
+use crate::formats::icc::convert_to_srgb;
+use crate::formats::InputLimits;
+use crate::utils::bytes::read_bytes;
+use image::{DynamicImage, RgbaImage};
+use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+use mime::Mime;
+use std::io::{BufRead, Seek};
+
+/// Returns whether the mime describes an image in a heif container
+pub fn is_heif_mime(mime: &Mime) -> bool {
+    matches!(
+        mime.subtype().as_str(),
+        "heic" | "heif" | "heic-sequence" | "heif-sequence" | "avif"
+    )
+}
+
+/// Reads the primary image of heic, heif and avif files. Images with a higher bit depth
+/// are rounded to 8 bits per channel.
+pub fn read_heif_image<R: BufRead + Seek>(
+    mut reader: R,
+    limits: &InputLimits,
+) -> ThumbResult<DynamicImage> {
+    let buf = read_bytes(&mut reader)?;
+
+    let lib_heif = LibHeif::new_checked().map_err(|e| ThumbError::decode("heif", e))?;
+    let context = HeifContext::read_from_bytes(&buf).map_err(|e| ThumbError::decode("heif", e))?;
+    let handle = context
+        .primary_image_handle()
+        .map_err(|e| ThumbError::decode("heif", e))?;
+    limits.check((handle.width(), handle.height()))?;
+
+    let bits = handle.luma_bits_per_pixel();
+    let chroma = if bits > 8 {
+        RgbChroma::HdrRgbaLe
+    } else {
+        RgbChroma::Rgba
+    };
+    // the rotation and mirroring properties that mirror the exif orientation of
+    // cameras are applied by libheif while decoding
+    let image = lib_heif
+        .decode(&handle, ColorSpace::Rgb(chroma), None)
+        .map_err(|e| ThumbError::decode("heif", e))?;
+    let (width, height) = (image.width(), image.height());
+    let plane = image
+        .planes()
+        .interleaved
+        .ok_or_else(|| ThumbError::decode("heif", "missing interleaved plane"))?;
+
+    let row_length = width as usize * if bits > 8 { 8 } else { 4 };
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    for row in plane.data.chunks(plane.stride).take(height as usize) {
+        let row = &row[..row_length];
+        if bits > 8 {
+            let max = (1u32 << bits) - 1;
+            pixels.extend(row.chunks_exact(2).map(|sample| {
+                let value = u16::from_le_bytes([sample[0], sample[1]]) as u32;
+                ((value.min(max) * 255 + max / 2) / max) as u8
+            }));
+        } else {
+            pixels.extend_from_slice(row);
+        }
+    }
+    let image = RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| ThumbError::decode("heif", "invalid decoded image size"))?;
+    let image = DynamicImage::ImageRgba8(image);
+    #[cfg(feature = "icc")]
+    let image = convert_to_srgb(
+        image,
+        handle.color_profile_raw().as_ref().map(|p| &p.data[..]),
+    );
+
+    Ok(image)
+}
@@ -0,0 +1,38 @@
+use image::DynamicImage;
+use qcms::{DataType, Intent, Profile, Transform};
+
+/// Converts the pixels of an image with an embedded icc profile to srgb.
+/// Images without a profile, with a profile that can't be read or with a color
+/// type the profile doesn't apply to are returned unchanged which assumes srgb.
+pub fn convert_to_srgb(image: DynamicImage, icc_profile: Option<&[u8]>) -> DynamicImage {
+    if image.color().channel_count() < 3 {
+        return image;
+    }
+    let profile = match icc_profile.and_then(|data| Profile::new_from_slice(data, false)) {
+        Some(profile) => profile,
+        None => return image,
+    };
+    let mut srgb = Profile::new_sRGB();
+    srgb.precache_output_transform();
+
+    let has_alpha = image.color().has_alpha();
+    let data_type = if has_alpha {
+        DataType::RGBA8
+    } else {
+        DataType::RGB8
+    };
+    let transform = match Transform::new(&profile, &srgb, data_type, Intent::Perceptual) {
+        Some(transform) => transform,
+        None => return image,
+    };
+
+    if has_alpha {
+        let mut buffer = image.into_rgba8();
+        transform.apply(&mut buffer);
+        DynamicImage::ImageRgba8(buffer)
+    } else {
+        let mut buffer = image.into_rgb8();
+        transform.apply(&mut buffer);
+        DynamicImage::ImageRgb8(buffer)
+    }
+}
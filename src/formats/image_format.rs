@@ -1,39 +1,419 @@
-use std::io::{BufRead, Read, Seek};
-use image::{DynamicImage, ImageFormat};
+use crate::error::{ThumbError, ThumbResult};
+use crate::formats::density::read_density;
+#[cfg(feature = "icc")]
+use crate::formats::icc::convert_to_srgb;
+use crate::formats::{Cancellation, DecodeOptions, InputLimits};
+use crate::trace::trace_event;
+use crate::utils::bytes::read_bytes;
+use crate::BaseImage;
+use image::codecs::gif::GifDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::metadata::{LoopCount, Orientation};
+use image::{AnimationDecoder, Frames, ImageReader};
+use image::{DynamicImage, ImageDecoder, ImageFormat, RgbImage};
 use mime::Mime;
-use image::io::Reader as ImageReader;
+use std::io::{BufRead, Cursor, Seek};
+#[cfg(feature = "libwebp")]
 use webp::Decoder as WebpDecoder;
-use crate::error::{ThumbError, ThumbResult};
 
 const IMAGE_WEBP_MIME: &str = "image/webp";
+const IMAGE_GIF_MIME: &str = "image/gif";
+const IMAGE_JPEG_MIME: &str = "image/jpeg";
+
+/// Reads an image with a known mime type and returns it with the detected format and
+/// the stored resolution. For animated images the frame with the given index is used.
+pub fn read_image<R: BufRead + Seek>(
+    mut reader: R,
+    mime: Mime,
+    options: &DecodeOptions,
+) -> ThumbResult<BaseImage> {
+    let dpi = if options.forward_only {
+        None
+    } else {
+        read_density(&mut reader, &mime)?
+    };
+    let mut base = read_image_data(reader, mime, options)?;
+    base.info.dpi = dpi;
+
+    Ok(base)
+}
+
+fn read_image_data<R: BufRead + Seek>(
+    reader: R,
+    mime: Mime,
+    options: &DecodeOptions,
+) -> ThumbResult<BaseImage> {
+    let limits = &options.limits;
+    let (image, format) = match mime.essence_str() {
+        IMAGE_WEBP_MIME => (read_webp_image(reader, options)?, Some(ImageFormat::WebP)),
+        IMAGE_GIF_MIME => (read_gif_image(reader, options)?, Some(ImageFormat::Gif)),
+        #[cfg(feature = "jpeg-prescale")]
+        // the image might be rotated afterwards, so both axes need the larger dimension
+        IMAGE_JPEG_MIME => {
+            let target = options.target_size.map(|(width, height)| width.max(height));
+            return read_prescaled_jpeg(reader, target, limits);
+        }
+        #[cfg(not(feature = "jpeg-prescale"))]
+        IMAGE_JPEG_MIME => (read_jpeg_image(reader, limits)?, Some(ImageFormat::Jpeg)),
+        _ => read_generic_image(reader, mime_to_image_format(mime), limits)?,
+    };
+
+    Ok(BaseImage::new(image, format))
+}
+
+/// Reads a jpeg image at the smallest scale of 1/2, 1/4 or 1/8 at which both dimensions
+/// are still at least the given target. The scaling happens in the dct domain while
+/// decoding, so the full resolution image is never allocated. Images that can't be
+/// scaled or decoded this way, or without a target, are decoded at their full size.
+/// The source info contains the full dimensions.
+#[cfg(feature = "jpeg-prescale")]
+fn read_prescaled_jpeg<R: BufRead + Seek>(
+    mut reader: R,
+    target: Option<u32>,
+    limits: &InputLimits,
+) -> ThumbResult<BaseImage> {
+    // the compressed data is kept so that it can be decoded again without seeking
+    let buf = read_bytes(&mut reader)?;
+
+    let format = Some(ImageFormat::Jpeg);
+    let scaled = match target {
+        Some(target) => decode_scaled_jpeg(&buf, target, limits)?,
+        None => None,
+    };
+    match scaled {
+        Some((image, (width, height))) => {
+            let mut base = BaseImage::new(image, format);
+            base.info.width = width;
+            base.info.height = height;
+            Ok(base)
+        }
+        None => Ok(BaseImage::new(decode_jpeg(&buf, limits)?, format)),
+    }
+}
+
+/// Decodes a jpeg image at a reduced scale and returns it with the full dimensions after
+/// applying the orientation. Returns `None` if the image is too small to be scaled or
+/// uses a color type or coding process that the decoder doesn't support.
+#[cfg(feature = "jpeg-prescale")]
+fn decode_scaled_jpeg(
+    bytes: &[u8],
+    target: u32,
+    limits: &InputLimits,
+) -> ThumbResult<Option<(DynamicImage, (u32, u32))>> {
+    use image::GrayImage;
+    use jpeg_decoder::{Decoder, PixelFormat};
+
+    let mut decoder = Decoder::new(bytes);
+    let info = match decoder.read_info().ok().and_then(|_| decoder.info()) {
+        Some(info) => info,
+        None => return Ok(None),
+    };
+    let (width, height) = (info.width as u32, info.height as u32);
+    limits.check((width, height))?;
+    if !matches!(info.pixel_format, PixelFormat::L8 | PixelFormat::RGB24) {
+        return Ok(None);
+    }
+    let requested = [8, 4, 2]
+        .iter()
+        .map(|divisor| (width.div_ceil(*divisor), height.div_ceil(*divisor)))
+        .find(|&(width, height)| width >= target && height >= target);
+    let (scaled_width, scaled_height) = match requested
+        .and_then(|(width, height)| decoder.scale(width as u16, height as u16).ok())
+    {
+        Some((width, height)) if width as u32 >= target && height as u32 >= target => {
+            (width as u32, height as u32)
+        }
+        _ => return Ok(None),
+    };
+    let pixels = match decoder.decode() {
+        Ok(pixels) => pixels,
+        Err(_) => return Ok(None),
+    };
+    let image = match info.pixel_format {
+        PixelFormat::L8 => {
+            GrayImage::from_raw(scaled_width, scaled_height, pixels).map(DynamicImage::ImageLuma8)
+        }
+        _ => RgbImage::from_raw(scaled_width, scaled_height, pixels).map(DynamicImage::ImageRgb8),
+    };
+    #[cfg_attr(not(feature = "icc"), allow(unused_mut))]
+    let mut image = match image {
+        Some(image) => image,
+        None => return Ok(None),
+    };
+    #[cfg(feature = "icc")]
+    {
+        image = convert_to_srgb(image, decoder.icc_profile().as_deref());
+    }
+    // broken exif data shouldn't prevent the image from being decoded
+    let orientation = decoder
+        .exif_data()
+        .and_then(Orientation::from_exif_chunk)
+        .unwrap_or(Orientation::NoTransforms);
+    apply_exif_orientation(&mut image, orientation);
+    let full_size = if image.width() == scaled_width {
+        (width, height)
+    } else {
+        (height, width)
+    };
+    trace_event!(
+        width,
+        height,
+        scaled_width,
+        scaled_height,
+        "decoded the jpeg at a reduced scale"
+    );
+
+    Ok(Some((image, full_size)))
+}
+
+/// Reads a jpeg image
+#[cfg(not(feature = "jpeg-prescale"))]
+fn read_jpeg_image<R: BufRead + Seek>(
+    mut reader: R,
+    limits: &InputLimits,
+) -> ThumbResult<DynamicImage> {
+    let buf = read_bytes(&mut reader)?;
+
+    decode_jpeg(&buf, limits)
+}
+
+/// Decodes a jpeg image. CMYK and YCCK images are converted to rgb.
+fn decode_jpeg(bytes: &[u8], limits: &InputLimits) -> ThumbResult<DynamicImage> {
+    if is_plain_cmyk_jpeg(bytes) {
+        trace_event!("decoding the jpeg as CMYK without an Adobe segment");
+        return decode_plain_cmyk_jpeg(bytes, limits);
+    }
+    let (image, _) = read_generic_image(Cursor::new(bytes), Some(ImageFormat::Jpeg), limits)?;
+
+    Ok(image)
+}
+
+/// Returns whether a jpeg image has four components but no Adobe segment. Decoders
+/// assume that CMYK data is stored inverted like Adobe applications write it, but
+/// without the segment the data is usually stored as is.
+fn is_plain_cmyk_jpeg(bytes: &[u8]) -> bool {
+    if !bytes.starts_with(&[0xff, 0xd8]) {
+        return false;
+    }
+    let (mut position, mut adobe) = (2, false);
+    while let Some(&[0xff, marker, high, low]) = bytes.get(position..position + 4) {
+        match marker {
+            // fill bytes
+            0xff => {
+                position += 1;
+                continue;
+            }
+            // markers without a length
+            0x01 | 0xd0..=0xd7 => {
+                position += 2;
+                continue;
+            }
+            0xee => adobe |= bytes[position + 4..].starts_with(b"Adobe"),
+            // the frame header contains the number of components after the precision
+            // and dimensions, the other markers in the range define tables
+            0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                return !adobe && bytes.get(position + 9) == Some(&4);
+            }
+            0xd9 | 0xda => return false,
+            _ => {}
+        }
+        position += 2 + u16::from_be_bytes([high, low]) as usize;
+    }
+
+    false
+}
+
+/// Decodes a jpeg image with uninverted CMYK data and converts it to rgb
+fn decode_plain_cmyk_jpeg(bytes: &[u8], limits: &InputLimits) -> ThumbResult<DynamicImage> {
+    use zune_core::bytestream::ZCursor;
+    use zune_core::colorspace::ColorSpace;
+    use zune_core::options::DecoderOptions;
+    use zune_jpeg::JpegDecoder;
+
+    // the dimensions are checked against the limits instead of the decoder options
+    let options = DecoderOptions::default()
+        .set_strict_mode(false)
+        .set_max_width(usize::MAX)
+        .set_max_height(usize::MAX)
+        .jpeg_set_out_colorspace(ColorSpace::CMYK);
+    let mut decoder = JpegDecoder::new_with_options(ZCursor::new(bytes), options);
+    decoder
+        .decode_headers()
+        .map_err(|e| ThumbError::decode("jpeg", e))?;
+    let (width, height) = decoder
+        .dimensions()
+        .ok_or_else(|| ThumbError::decode("jpeg", "missing frame header"))?;
+    limits.check((width as u32, height as u32))?;
+    let pixels = decoder
+        .decode()
+        .map_err(|e| ThumbError::decode("jpeg", e))?;
 
-/// Reads an image with a known mime type
-pub fn read_image<R: BufRead + Seek>(reader: R, mime: Mime) -> ThumbResult<DynamicImage> {
+    let rgb = pixels
+        .chunks_exact(4)
+        .flat_map(|cmyk| {
+            let white = 255 - cmyk[3] as u32;
+            [0, 1, 2].map(|c| (((255 - cmyk[c] as u32) * white + 127) / 255) as u8)
+        })
+        .collect();
+    let mut image = RgbImage::from_raw(width as u32, height as u32, rgb)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| ThumbError::decode("jpeg", "truncated CMYK image data"))?;
+    // broken exif data shouldn't prevent the image from being decoded
+    let orientation = decoder
+        .exif()
+        .and_then(|exif| Orientation::from_exif_chunk(exif))
+        .unwrap_or(Orientation::NoTransforms);
+    apply_exif_orientation(&mut image, orientation);
+
+    Ok(image)
+}
+
+/// Reads an image of an unknown format from memory
+#[cfg_attr(
+    not(any(
+        feature = "audio",
+        feature = "office",
+        feature = "ebook",
+        feature = "archive"
+    )),
+    allow(dead_code)
+)]
+pub fn read_image_from_memory(bytes: &[u8], limits: &InputLimits) -> ThumbResult<DynamicImage> {
+    let (image, _) = read_generic_image(Cursor::new(bytes), None, limits)?;
+
+    Ok(image)
+}
+
+/// Returns the frames and loop count of animated gif and webp images.
+/// Frames are decoded lazily. Returns `None` for static images.
+pub fn read_animation<'a, R: BufRead + Seek + 'a>(
+    mut reader: R,
+    mime: &Mime,
+    limits: &InputLimits,
+) -> ThumbResult<Option<(Frames<'a>, LoopCount)>> {
     match mime.essence_str() {
-        IMAGE_WEBP_MIME => read_webp_image(reader),
-        _ => read_generic_image(reader, mime_to_image_format(mime)),
+        IMAGE_GIF_MIME => {
+            let decoder = GifDecoder::new(reader)?;
+            limits.check(decoder.dimensions())?;
+            let loop_count = decoder.loop_count();
+
+            Ok(Some((decoder.into_frames(), loop_count)))
+        }
+        IMAGE_WEBP_MIME => {
+            let mut buf = Vec::new();
+            reader.read_to_end(&mut buf)?;
+            let decoder = WebPDecoder::new(Cursor::new(buf))?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            limits.check(decoder.dimensions())?;
+            let loop_count = decoder.loop_count();
+
+            Ok(Some((decoder.into_frames(), loop_count)))
+        }
+        _ => Ok(None),
     }
 }
 
-/// Reads a webp image
-fn read_webp_image<R: Read>(mut reader: R) -> ThumbResult<DynamicImage> {
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
-    let webp_image = WebpDecoder::new(&buf).decode().ok_or_else(|| ThumbError::Decode)?;
+/// Reads a webp image. Still images are decoded with libwebp if the `libwebp` feature is enabled.
+fn read_webp_image<R: BufRead + Seek>(
+    mut reader: R,
+    options: &DecodeOptions,
+) -> ThumbResult<DynamicImage> {
+    let buf = read_bytes(&mut reader)?;
+
+    #[cfg_attr(not(feature = "icc"), allow(unused_mut))]
+    let mut decoder = WebPDecoder::new(Cursor::new(&*buf))?;
+    options.limits.check(decoder.dimensions())?;
+    if decoder.has_animation() {
+        return read_frame(
+            decoder.into_frames(),
+            options.animation_frame,
+            &options.cancellation,
+        );
+    }
+    #[cfg(feature = "icc")]
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    #[cfg(feature = "libwebp")]
+    let image = WebpDecoder::new(&buf)
+        .decode()
+        .ok_or_else(|| ThumbError::decode("webp", "invalid webp image"))?
+        .to_image();
+    #[cfg(not(feature = "libwebp"))]
+    let image = DynamicImage::from_decoder(decoder)?;
+    #[cfg(feature = "icc")]
+    let image = convert_to_srgb(image, icc_profile.as_deref());
 
-    Ok(webp_image.to_image())
+    Ok(image)
+}
+
+/// Reads a single frame of a gif image
+fn read_gif_image<R: BufRead + Seek>(
+    reader: R,
+    options: &DecodeOptions,
+) -> ThumbResult<DynamicImage> {
+    let decoder = GifDecoder::new(reader)?;
+    options.limits.check(decoder.dimensions())?;
+
+    read_frame(
+        decoder.into_frames(),
+        options.animation_frame,
+        &options.cancellation,
+    )
+}
+
+/// Decodes frames up to the given index and returns it or the last frame
+/// if the animation is shorter. Only one frame is kept in memory at a time.
+fn read_frame(
+    frames: Frames<'_>,
+    index: usize,
+    cancellation: &Cancellation,
+) -> ThumbResult<DynamicImage> {
+    let mut selected = None;
+    for frame in frames.take(index.saturating_add(1)) {
+        cancellation.check()?;
+        selected = Some(frame?);
+    }
+    let frame = selected.ok_or_else(|| ThumbError::decode("image", "animation has no frames"))?;
+
+    Ok(DynamicImage::ImageRgba8(frame.into_buffer()))
 }
 
 /// Reads a generic image
-fn read_generic_image<R: BufRead + Seek>(reader: R, format: Option<ImageFormat>) -> ThumbResult<DynamicImage> {
+fn read_generic_image<R: BufRead + Seek>(
+    reader: R,
+    format: Option<ImageFormat>,
+    limits: &InputLimits,
+) -> ThumbResult<(DynamicImage, Option<ImageFormat>)> {
     let reader = if let Some(format) = format {
         ImageReader::with_format(reader, format)
     } else {
         ImageReader::new(reader).with_guessed_format()?
     };
-    let image = reader.decode()?;
+    let format = reader.format();
+    let mut decoder = reader.into_decoder()?;
+    // the dimensions are read from the header before any pixels are allocated
+    limits.check(decoder.dimensions())?;
+    // broken exif data and color profiles shouldn't prevent the image from being decoded
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    #[cfg(feature = "icc")]
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    #[cfg(feature = "icc")]
+    {
+        image = convert_to_srgb(image, icc_profile.as_deref());
+    }
+    apply_exif_orientation(&mut image, orientation);
 
-    Ok(image)
+    Ok((image, format))
+}
+
+/// Rotates and flips the image like the exif orientation of the source describes
+fn apply_exif_orientation(image: &mut DynamicImage, orientation: Orientation) {
+    if orientation != Orientation::NoTransforms {
+        trace_event!(orientation = ?orientation, "applying the exif orientation");
+    }
+    image.apply_orientation(orientation);
 }
 
 fn mime_to_image_format(mime: Mime) -> Option<ImageFormat> {
@@ -41,7 +421,7 @@ fn mime_to_image_format(mime: Mime) -> Option<ImageFormat> {
         "png" => Some(ImageFormat::Png),
         "jpeg" => Some(ImageFormat::Jpeg),
         "bmp" => Some(ImageFormat::Bmp),
-        "gif" => Some(ImageFormat::Gif),
+        "tiff" => Some(ImageFormat::Tiff),
         _ => None,
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,74 @@
+use crate::error::{ThumbError, ThumbResult};
+#[cfg(feature = "icc")]
+use crate::formats::icc::convert_to_srgb;
+use crate::formats::InputLimits;
+use image::{DynamicImage, GrayAlphaImage, GrayImage, RgbImage, RgbaImage};
+use jxl_oxide::{EnumColourEncoding, JxlImage, PixelFormat, RenderingIntent};
+use mime::Mime;
+use std::io::{BufRead, Seek};
+
+/// Returns whether the mime describes a jpeg xl image
+pub fn is_jxl_mime(mime: &Mime) -> bool {
+    mime.type_() == mime::IMAGE && mime.subtype() == "jxl"
+}
+
+/// Reads the first frame of jpeg xl images. Images are rendered to srgb, which tone maps HDR
+/// content to the brightness of an SDR display instead of clipping it, and rounded to 8 bits
+/// per channel. Images that are stored in the color space of an embedded icc profile are
+/// rendered in that space and converted with the `icc` feature instead, as jxl-oxide can't
+/// convert from arbitrary profiles itself.
+pub fn read_jxl_image<R: BufRead + Seek>(
+    reader: R,
+    limits: &InputLimits,
+) -> ThumbResult<DynamicImage> {
+    let mut jxl = JxlImage::builder()
+        .read(reader)
+        .map_err(|e| ThumbError::decode("jxl", e))?;
+    limits.check((jxl.width(), jxl.height()))?;
+
+    // xyb encoded images describe absolute colors, so their icc profile is only a suggestion
+    let icc_encoded = !jxl.image_header().metadata.xyb_encoded && jxl.original_icc().is_some();
+    if !icc_encoded {
+        let encoding = if jxl.pixel_format().is_grayscale() {
+            EnumColourEncoding::gray_srgb(RenderingIntent::Perceptual)
+        } else {
+            EnumColourEncoding::srgb(RenderingIntent::Perceptual)
+        };
+        jxl.request_color_encoding(encoding);
+    }
+    let render = jxl
+        .render_frame(0)
+        .map_err(|e| ThumbError::decode("jxl", e))?;
+    let mut stream = render.stream();
+    let (width, height) = (stream.width(), stream.height());
+    let mut pixels = vec![0u8; width as usize * height as usize * stream.channels() as usize];
+    stream.write_to_buffer(&mut pixels);
+
+    let image = match jxl.pixel_format() {
+        PixelFormat::Gray => {
+            GrayImage::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8)
+        }
+        PixelFormat::Graya => {
+            GrayAlphaImage::from_raw(width, height, pixels).map(DynamicImage::ImageLumaA8)
+        }
+        PixelFormat::Rgb => RgbImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8),
+        PixelFormat::Rgba => {
+            RgbaImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8)
+        }
+        format => {
+            return Err(ThumbError::decode(
+                "jxl",
+                format!("unsupported pixel format {:?}", format),
+            ))
+        }
+    }
+    .ok_or_else(|| ThumbError::decode("jxl", "invalid decoded image size"))?;
+    #[cfg(feature = "icc")]
+    let image = if icc_encoded {
+        convert_to_srgb(image, jxl.original_icc())
+    } else {
+        image
+    };
+
+    Ok(image)
+}
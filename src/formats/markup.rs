@@ -0,0 +1,41 @@
+/// Returns the attributes of the start tags of all elements with the given local name.
+/// This only covers the small and simple xml files of document containers.
+pub fn elements<'a>(xml: &'a str, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    xml.split('<').skip(1).filter_map(move |tag| {
+        let tag = &tag[..tag.find('>')?];
+        let end = tag
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(tag.len());
+
+        (local_name(&tag[..end]) == name).then(|| &tag[end..])
+    })
+}
+
+/// Returns the value of the attribute with the given local name with entities decoded
+pub fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+    loop {
+        let equals = rest.find('=')?;
+        let key = rest[..equals].trim();
+        let value = rest[equals + 1..].trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let end = value[1..].find(quote)? + 1;
+        if local_name(key) == name {
+            return Some(decode_entities(&value[1..end]));
+        }
+        rest = &value[end + 1..];
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
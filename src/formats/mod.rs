@@ -1,19 +1,465 @@
 use crate::error::{ThumbError, ThumbResult};
-use crate::formats::image_format::read_image;
-use image::DynamicImage;
+use crate::formats::image_format::read_animation;
+use crate::formats::registry::FormatRegistry;
+use crate::formats::sniff::sniff_mime;
+use crate::trace::{record_elapsed, trace_event, trace_span, Start};
+use crate::transform::Orientation;
+use crate::BaseImage;
+use image::metadata::LoopCount;
+use image::{Delay, DynamicImage};
 use mime::Mime;
-use std::io::{BufRead, Seek};
+use std::io::{self, BufRead, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::formats::video_format::get_video_frame;
+#[cfg(feature = "pdf")]
+use crate::formats::pdf_format::read_pdf_pages;
+#[cfg(feature = "text")]
+use crate::formats::text_format::TextOptions;
+#[cfg(feature = "ffmpeg")]
+use crate::formats::video_format::{get_video_frames, get_video_frames_at, get_video_frames_every};
 
+#[cfg(feature = "archive")]
+pub mod archive_format;
+#[cfg(feature = "audio")]
+pub mod audio_format;
+mod density;
+#[cfg(feature = "ebook")]
+pub mod ebook_format;
+#[cfg(feature = "heif")]
+pub mod heif_format;
+#[cfg(feature = "icc")]
+pub mod icc;
 pub mod image_format;
+#[cfg(feature = "jxl")]
+pub mod jxl_format;
+#[cfg(any(feature = "office", feature = "ebook"))]
+mod markup;
+#[cfg(feature = "office")]
+pub mod office_format;
+#[cfg(feature = "pdf")]
+pub mod pdf_format;
+#[cfg(feature = "raw")]
+pub mod raw_format;
+pub(crate) mod registry;
+pub mod sniff;
+#[cfg(feature = "svg")]
+pub mod svg_format;
+#[cfg(feature = "text")]
+pub mod text_format;
+pub mod tiff_format;
+#[cfg(feature = "ffmpeg")]
 pub mod video_format;
+#[cfg(any(feature = "office", feature = "ebook", feature = "archive"))]
+mod zip_archive;
+
+/// Selects the frame of a video that is used for the thumbnail
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VideoFrameSelector {
+    /// The frame at the given timestamp. Timestamps beyond the end of the
+    /// video select the last frame.
+    Timestamp(Duration),
+
+    /// The frame at the given relative position (0.0 - 1.0) of the videos duration
+    Percentage(f32),
+
+    /// The frame with the given index. Indices beyond the end of the video
+    /// select the last frame.
+    Frame(u64),
+}
+
+impl VideoFrameSelector {
+    /// Returns an error if the percentage isn't a finite number
+    pub(crate) fn validate(&self) -> ThumbResult<()> {
+        match *self {
+            VideoFrameSelector::Percentage(percentage) if !percentage.is_finite() => {
+                Err(ThumbError::InvalidOptions(format!(
+                    "invalid video frame percentage {}",
+                    percentage
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for VideoFrameSelector {
+    fn default() -> Self {
+        // skips black intros and logos
+        Self::Percentage(0.1)
+    }
+}
+
+/// Options that influence how the base image is retrieved
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DecodeOptions {
+    pub video_frame: VideoFrameSelector,
+
+    /// The frame of animated images that is used
+    pub animation_frame: usize,
+
+    /// The page of multi-page tiff images that is used
+    pub page: usize,
+
+    /// The largest requested width and height which allows vector formats
+    /// to be rendered and jpeg images to be decoded at a sufficient resolution
+    #[cfg_attr(
+        not(any(
+            feature = "pdf",
+            feature = "svg",
+            feature = "text",
+            feature = "jpeg-prescale"
+        )),
+        allow(dead_code)
+    )]
+    pub target_size: Option<(u32, u32)>,
+
+    /// Whether the media type is detected from the content when it is unknown
+    /// or decoding with the given type fails
+    pub sniff: bool,
+
+    /// Whether the reader can only seek forward, so metadata that is read ahead
+    /// of decoding like the resolution is skipped
+    pub forward_only: bool,
+
+    pub limits: InputLimits,
+
+    /// The rotation and flip applied to the decoded image
+    pub orientation: Orientation,
+
+    #[cfg(feature = "text")]
+    pub text: TextOptions,
+
+    /// The handlers that decode each media type
+    pub registry: FormatRegistry,
+
+    pub cancellation: Cancellation,
+}
+
+/// The default maximum number of pixels of decoded images
+const DEFAULT_MAX_INPUT_PIXELS: u64 = 200_000_000;
+
+/// The default maximum width or height of decoded images
+const DEFAULT_MAX_INPUT_DIMENSION: u32 = 65_535;
+
+/// Limits on the size of images that are decoded to protect against decompression bombs
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct InputLimits {
+    pub max_pixels: Option<u64>,
+    pub max_dimension: Option<u32>,
+}
+
+impl Default for InputLimits {
+    fn default() -> Self {
+        Self {
+            max_pixels: Some(DEFAULT_MAX_INPUT_PIXELS),
+            max_dimension: Some(DEFAULT_MAX_INPUT_DIMENSION),
+        }
+    }
+}
+
+impl InputLimits {
+    /// Returns an error if an image with the given dimensions exceeds the limits
+    pub fn check(&self, (width, height): (u32, u32)) -> ThumbResult<()> {
+        if let Some(limit) = self.max_dimension {
+            if width > limit || height > limit {
+                return Err(ThumbError::InputTooLarge {
+                    width,
+                    height,
+                    limit: limit as u64,
+                });
+            }
+        }
+        if let Some(limit) = self.max_pixels {
+            if width as u64 * height as u64 > limit {
+                return Err(ThumbError::InputTooLarge {
+                    width,
+                    height,
+                    limit,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Stops the thumbnail creation when a token is set or the deadline has passed.
+/// It is checked at checkpoints like before each frame or size is processed.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Cancellation {
+    tokens: Vec<Arc<AtomicBool>>,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    /// Adds a token that cancels the operation when it is set to true
+    pub fn add_token(&mut self, token: Arc<AtomicBool>) {
+        self.tokens.push(token);
+    }
+
+    /// Sets the time after which the operation is stopped. An earlier deadline is kept.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(self.deadline.map_or(deadline, |d| d.min(deadline)));
+    }
+
+    /// Returns whether a token or a deadline can cancel the operation
+    #[cfg(feature = "ffmpeg")]
+    pub fn is_cancellable(&self) -> bool {
+        !self.tokens.is_empty() || self.deadline.is_some()
+    }
+
+    /// Returns an error if the operation has been cancelled or timed out
+    pub fn check(&self) -> ThumbResult<()> {
+        if self
+            .tokens
+            .iter()
+            .any(|token| token.load(Ordering::Relaxed))
+        {
+            return Err(ThumbError::Cancelled);
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(ThumbError::Timeout),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns whether the media type can be decoded in a single forward pass
+/// without seeking the reader
+pub fn supports_unseekable(mime: &Mime) -> bool {
+    matches!(mime.essence_str(), "image/png" | "image/jpeg" | "image/gif")
+}
 
 /// Reads the buffer content into an image that can be used for thumbnail generation
-pub fn get_base_image<R: BufRead + Seek>(reader: R, mime: Mime) -> ThumbResult<DynamicImage> {
+pub fn get_base_image<R: BufRead + Seek>(
+    reader: R,
+    mime: Mime,
+    options: &DecodeOptions,
+) -> ThumbResult<BaseImage> {
+    let span = trace_span!(
+        "decode",
+        mime = %mime,
+        format = tracing::field::Empty,
+        source_width = tracing::field::Empty,
+        source_height = tracing::field::Empty,
+        width = tracing::field::Empty,
+        height = tracing::field::Empty,
+        elapsed_ms = tracing::field::Empty,
+    );
+    let start = Start::now();
+    let base = span.in_scope(|| sniff_and_decode(reader, mime, options))?;
+    // the format of the media type that was decoded, which might have been sniffed
+    let format = base.mime.as_ref().map(|mime| mime.subtype().as_str());
+    span.record("format", format);
+    span.record("source_width", base.info.width);
+    span.record("source_height", base.info.height);
+    span.record("width", base.image.width());
+    span.record("height", base.image.height());
+    record_elapsed(&span, start);
+
+    Ok(base)
+}
+
+/// Decodes the content with the given mime and falls back to the sniffed one if enabled
+fn sniff_and_decode<R: BufRead + Seek>(
+    mut reader: R,
+    mime: Mime,
+    options: &DecodeOptions,
+) -> ThumbResult<BaseImage> {
+    if !options.sniff {
+        return decode_with_mime(reader, mime, options);
+    }
+    let start = reader.stream_position()?;
+    let sniffed = sniff_mime(&mut reader)?;
+
+    if mime == mime::APPLICATION_OCTET_STREAM {
+        let sniffed = sniffed.ok_or(ThumbError::UnsupportedMime(mime))?;
+        trace_event!(sniffed = %sniffed, "using the sniffed media type of unknown content");
+        return decode_with_mime(reader, sniffed, options);
+    }
+    match decode_with_mime(&mut reader, mime.clone(), options) {
+        // forward only readers can't be rewound to decode the content again
+        Err(e) if (e.is_corrupt() || e.is_unsupported()) && !options.forward_only => {
+            match sniffed {
+                Some(sniffed) if sniffed != mime => {
+                    trace_event!(
+                        mime = %mime,
+                        sniffed = %sniffed,
+                        error = %e,
+                        "decoding failed, retrying with the sniffed media type"
+                    );
+                    reader.seek(SeekFrom::Start(start))?;
+                    decode_with_mime(reader, sniffed, options)
+                }
+                _ => Err(e),
+            }
+        }
+        result => result,
+    }
+}
+
+fn decode_with_mime<R: BufRead + Seek>(
+    reader: R,
+    mime: Mime,
+    options: &DecodeOptions,
+) -> ThumbResult<BaseImage> {
+    let format = mime.subtype().to_string();
+    let mut base = read_base_image(reader, mime.clone(), options)
+        .map_err(|e| truncated_as_corrupt(e, &format))?;
+    base.mime = Some(mime);
+    base.image = options.orientation.apply(base.image);
+
+    Ok(base)
+}
+
+fn read_base_image<R: BufRead + Seek>(
+    mut reader: R,
+    mime: Mime,
+    options: &DecodeOptions,
+) -> ThumbResult<BaseImage> {
+    match options.registry.find(&mime) {
+        Some(handler) => handler.read(&mut reader, &mime, options),
+        None => Err(ThumbError::UnsupportedMime(mime)),
+    }
+}
+
+/// Returns up to `count` images sampled from the source. Videos are sampled evenly across
+/// their duration and documents return their first pages. Other media return a single
+/// image. Images that couldn't be extracted are `None` and the result might be shorter
+/// than `count`.
+pub(crate) fn get_frames<R: BufRead + Seek>(
+    reader: R,
+    mime: Mime,
+    #[cfg_attr(not(any(feature = "ffmpeg", feature = "pdf")), allow(unused_variables))]
+    count: usize,
+    options: &DecodeOptions,
+) -> ThumbResult<Vec<Option<DynamicImage>>> {
     match mime.type_() {
-        mime::IMAGE => read_image(reader, mime),
-        mime::VIDEO => get_video_frame(reader, mime),
-        _ => Err(ThumbError::Unsupported(mime)),
+        #[cfg(feature = "ffmpeg")]
+        mime::VIDEO => get_video_frames(reader, mime, count, &options.cancellation),
+        #[cfg(feature = "pdf")]
+        mime::APPLICATION if mime.subtype() == mime::PDF => {
+            let pages = read_pdf_pages(reader, count, options.target_size)?;
+            Ok(pages.into_iter().map(Some).collect())
+        }
+        _ => Ok(vec![Some(get_base_image(reader, mime, options)?.image)]),
+    }
+}
+
+/// Returns a base image of the video for each of the selectors in the same order
+#[cfg(feature = "ffmpeg")]
+pub(crate) fn get_video_bases<R: BufRead + Seek>(
+    reader: R,
+    mime: Mime,
+    selectors: &[VideoFrameSelector],
+    options: &DecodeOptions,
+) -> ThumbResult<Vec<BaseImage>> {
+    if mime.type_() != mime::VIDEO {
+        return Err(ThumbError::UnsupportedMime(mime));
+    }
+    let format = mime.subtype().to_string();
+    let (frames, duration) =
+        get_video_frames_at(reader, mime.clone(), selectors, &options.cancellation)
+            .map_err(|e| truncated_as_corrupt(e, &format))?;
+
+    let bases = frames
+        .into_iter()
+        .map(|image| {
+            let mut base = BaseImage::new(image, None);
+            base.mime = Some(mime.clone());
+            base.info.duration = duration;
+            base.image = options.orientation.apply(base.image);
+            base
+        })
+        .collect();
+
+    Ok(bases)
+}
+
+/// Returns the frames of a video at every multiple of the interval with the orientation
+/// applied and the duration of the video. Each frame is processed as soon as it is
+/// extracted and videos with more than `max_frames` frames are rejected.
+#[cfg(feature = "ffmpeg")]
+pub(crate) fn get_interval_frames<R: BufRead + Seek, F: FnMut(DynamicImage) -> DynamicImage>(
+    reader: R,
+    mime: Mime,
+    interval: Duration,
+    max_frames: usize,
+    mut process: F,
+    options: &DecodeOptions,
+) -> ThumbResult<(Vec<DynamicImage>, Option<Duration>)> {
+    if mime.type_() != mime::VIDEO {
+        return Err(ThumbError::UnsupportedMime(mime));
+    }
+    let format = mime.subtype().to_string();
+    let orientation = &options.orientation;
+
+    get_video_frames_every(
+        reader,
+        mime,
+        interval,
+        max_frames,
+        |frame| process(orientation.apply(frame)),
+        &options.cancellation,
+    )
+    .map_err(|e| truncated_as_corrupt(e, &format))
+}
+
+/// The frames of an animated image
+pub(crate) struct Animation {
+    pub frames: Vec<(DynamicImage, Delay)>,
+    pub loop_count: LoopCount,
+    pub mime: Option<Mime>,
+}
+
+/// Reads up to `max_frames` frames of an animated image. Static images and other media
+/// are read with [get_base_image] and returned as an animation with a single frame.
+pub fn get_animation<R: BufRead + Seek>(
+    mut reader: R,
+    mime: Mime,
+    options: &DecodeOptions,
+    max_frames: Option<usize>,
+) -> ThumbResult<Animation> {
+    if let Some((frames, loop_count)) = read_animation(&mut reader, &mime, &options.limits)? {
+        let format = mime.subtype().to_string();
+        let frames = frames
+            .take(max_frames.unwrap_or(usize::MAX))
+            .map(|frame| {
+                options.cancellation.check()?;
+                let frame = frame?;
+                let delay = frame.delay();
+                let image = DynamicImage::ImageRgba8(frame.into_buffer());
+                Ok((options.orientation.apply(image), delay))
+            })
+            .collect::<ThumbResult<Vec<_>>>()
+            .map_err(|e| truncated_as_corrupt(e, &format))?;
+
+        if !frames.is_empty() {
+            return Ok(Animation {
+                frames,
+                loop_count,
+                mime: Some(mime),
+            });
+        }
+    }
+    reader.rewind()?;
+    let base = get_base_image(reader, mime, options)?;
+
+    Ok(Animation {
+        frames: vec![(base.image, Delay::from_numer_denom_ms(0, 1))],
+        loop_count: LoopCount::Infinite,
+        mime: base.mime,
+    })
+}
+
+/// Content that ends unexpectedly is corrupt and not an io failure
+fn truncated_as_corrupt(error: ThumbError, format: &str) -> ThumbError {
+    match error {
+        ThumbError::Io(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+            ThumbError::decode(format, e)
+        }
+        e => e,
     }
 }
@@ -0,0 +1,62 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::formats::image_format::read_image_from_memory;
+use crate::formats::markup::{attribute, elements};
+use crate::formats::zip_archive::{open_archive, read_entry};
+use crate::formats::InputLimits;
+use image::DynamicImage;
+use mime::Mime;
+use std::io::{Read, Seek};
+
+/// The relationship type of the thumbnail of ooxml packages
+const OOXML_THUMBNAIL_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships/metadata/thumbnail";
+
+/// The relationships of the package root of ooxml packages
+const OOXML_ROOT_RELATIONSHIPS: &str = "_rels/.rels";
+
+/// The preview of odf documents
+const ODF_THUMBNAIL: &str = "Thumbnails/thumbnail.png";
+
+/// Returns whether the mime describes an ooxml (docx, xlsx, pptx) or odf document
+pub fn is_office_mime(mime: &Mime) -> bool {
+    mime.type_() == mime::APPLICATION
+        && (mime
+            .subtype()
+            .as_str()
+            .starts_with("vnd.openxmlformats-officedocument.")
+            || mime
+                .subtype()
+                .as_str()
+                .starts_with("vnd.oasis.opendocument."))
+}
+
+/// Reads the preview that office applications embed into ooxml and odf documents.
+/// Previews in formats that can't be decoded like wmf and documents without a preview
+/// return [ThumbError::NoEmbeddedImage].
+pub fn read_office_preview<R: Read + Seek>(
+    reader: R,
+    limits: &InputLimits,
+) -> ThumbResult<DynamicImage> {
+    let mut archive = open_archive(reader, "office")?;
+    let path = match read_entry(&mut archive, OOXML_ROOT_RELATIONSHIPS, "office")? {
+        Some(relationships) => ooxml_thumbnail_path(&String::from_utf8_lossy(&relationships))
+            .ok_or(ThumbError::NoEmbeddedImage)?,
+        None => ODF_THUMBNAIL.to_string(),
+    };
+    let preview = read_entry(&mut archive, &path, "office")?.ok_or(ThumbError::NoEmbeddedImage)?;
+
+    // windows metafiles are vector images that can't be decoded
+    if image::guess_format(&preview).is_err() {
+        return Err(ThumbError::NoEmbeddedImage);
+    }
+    read_image_from_memory(&preview, limits)
+}
+
+/// Returns the path of the thumbnail from the package relationships of an ooxml document
+fn ooxml_thumbnail_path(relationships: &str) -> Option<String> {
+    elements(relationships, "Relationship")
+        .find(|element| attribute(element, "Type").as_deref() == Some(OOXML_THUMBNAIL_RELATIONSHIP))
+        .and_then(|element| attribute(element, "Target"))
+        // targets are relative to the package root
+        .map(|target| target.trim_start_matches('/').to_string())
+}
@@ -0,0 +1,84 @@
+use crate::error::{ThumbError, ThumbResult};
+use hayro::hayro_interpret::InterpreterSettings;
+use hayro::hayro_syntax::page::Page;
+use hayro::hayro_syntax::{DecryptionError, LoadPdfError, Pdf};
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::vello_cpu::peniko::ImageAlphaType;
+use hayro::{render, PixmapSettings, RenderCache, RenderSettings};
+use image::{DynamicImage, RgbaImage};
+use std::io::Read;
+
+/// The scale used when no target size is known which corresponds to 72 DPI
+const DEFAULT_SCALE: f32 = 1.0;
+
+/// Renders the first page of a pdf document and returns it with the number of pages.
+/// The page is rendered big enough to cover the given target size so that it
+/// doesn't need to be upscaled.
+pub fn read_pdf_page<R: Read>(
+    reader: R,
+    target_size: Option<(u32, u32)>,
+) -> ThumbResult<(DynamicImage, usize)> {
+    let pdf = load_pdf(reader)?;
+    let page = pdf
+        .pages()
+        .first()
+        .ok_or_else(|| ThumbError::decode("pdf", "document has no pages"))?;
+
+    Ok((render_page(page, target_size)?, pdf.pages().len()))
+}
+
+/// Renders up to `count` pages from the start of a pdf document like [read_pdf_page]
+pub fn read_pdf_pages<R: Read>(
+    reader: R,
+    count: usize,
+    target_size: Option<(u32, u32)>,
+) -> ThumbResult<Vec<DynamicImage>> {
+    let pdf = load_pdf(reader)?;
+
+    pdf.pages()
+        .iter()
+        .take(count)
+        .map(|page| render_page(page, target_size))
+        .collect()
+}
+
+fn load_pdf<R: Read>(mut reader: R) -> ThumbResult<Pdf> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+
+    Pdf::new(buf).map_err(|e| match e {
+        LoadPdfError::Decryption(DecryptionError::PasswordProtected) => {
+            ThumbError::decode("pdf", "document is password protected")
+        }
+        LoadPdfError::Decryption(e) => {
+            ThumbError::decode("pdf", format!("failed to decrypt document: {:?}", e))
+        }
+        LoadPdfError::Invalid => ThumbError::decode("pdf", "invalid document"),
+    })
+}
+
+fn render_page(page: &Page, target_size: Option<(u32, u32)>) -> ThumbResult<DynamicImage> {
+    let (page_width, page_height) = page.render_dimensions();
+    let scale = target_size
+        .map(|(width, height)| (width as f32 / page_width).max(height as f32 / page_height))
+        .unwrap_or(DEFAULT_SCALE)
+        // the renderer is limited to u16 dimensions
+        .min(u16::MAX as f32 / page_width.max(page_height))
+        .max(1.0 / page_width.min(page_height));
+    let pixmap = render(
+        page,
+        &RenderCache::new(),
+        &InterpreterSettings::default(),
+        &RenderSettings::default(),
+        &PixmapSettings {
+            x_scale: scale,
+            y_scale: scale,
+            bg_color: WHITE,
+        },
+    );
+    let (width, height) = (pixmap.width() as u32, pixmap.height() as u32);
+    let image = RgbaImage::from_raw(width, height, pixmap.take_rgba8(ImageAlphaType::Alpha))
+        .ok_or_else(|| ThumbError::decode("pdf", "invalid rendered page size"))?;
+
+    Ok(DynamicImage::ImageRgba8(image))
+}
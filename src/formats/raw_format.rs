@@ -0,0 +1,265 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::formats::InputLimits;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use mime::Mime;
+use std::io::{BufRead, Cursor, Read, Seek, SeekFrom};
+
+const TAG_COMPRESSION: u16 = 0x0103;
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x0117;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_JPEG_OFFSET: u16 = 0x0201;
+const TAG_JPEG_LENGTH: u16 = 0x0202;
+
+/// Old style jpeg compression used by CR2 files
+const COMPRESSION_OLD_JPEG: u32 = 6;
+const COMPRESSION_JPEG: u32 = 7;
+
+/// Protects against cyclic or absurdly nested directories
+const MAX_IFDS: usize = 64;
+const MAX_VALUES: u32 = 1024;
+
+/// Returns whether the mime describes a raw camera format with a tiff structure
+pub fn is_raw_mime(mime: &Mime) -> bool {
+    matches!(
+        mime.subtype().as_str(),
+        "x-canon-cr2" | "x-nikon-nef" | "x-sony-arw" | "x-adobe-dng"
+    )
+}
+
+/// Reads the largest embedded jpeg preview of a raw camera file and applies the
+/// orientation of the raw image to it
+pub fn read_raw_preview<R: BufRead + Seek>(
+    reader: R,
+    limits: &InputLimits,
+) -> ThumbResult<DynamicImage> {
+    let mut tiff = TiffReader::new(reader)?;
+    let mut pending = vec![tiff.first_ifd_offset()?];
+    let mut visited = Vec::new();
+    let mut orientation = None;
+    let mut previews = Vec::new();
+
+    while let Some(offset) = pending.pop() {
+        if offset == 0 || visited.contains(&offset) || visited.len() >= MAX_IFDS {
+            continue;
+        }
+        visited.push(offset);
+        let (entries, next) = tiff.read_ifd(offset)?;
+        pending.push(next);
+
+        let value = |tiff: &mut TiffReader<R>, tag| -> ThumbResult<Option<u32>> {
+            match entries.iter().find(|entry| entry.tag == tag) {
+                Some(entry) => Ok(tiff.values(entry)?.first().copied()),
+                None => Ok(None),
+            }
+        };
+        if orientation.is_none() {
+            orientation = value(&mut tiff, TAG_ORIENTATION)?;
+        }
+        if let (Some(offset), Some(length)) = (
+            value(&mut tiff, TAG_JPEG_OFFSET)?,
+            value(&mut tiff, TAG_JPEG_LENGTH)?,
+        ) {
+            previews.push((offset, length));
+        }
+        if let Some(COMPRESSION_OLD_JPEG | COMPRESSION_JPEG) = value(&mut tiff, TAG_COMPRESSION)? {
+            if let (Some(offset), Some(length)) = (
+                value(&mut tiff, TAG_STRIP_OFFSETS)?,
+                value(&mut tiff, TAG_STRIP_BYTE_COUNTS)?,
+            ) {
+                previews.push((offset, length));
+            }
+        }
+        if let Some(entry) = entries.iter().find(|entry| entry.tag == TAG_SUB_IFDS) {
+            pending.extend(tiff.values(entry)?);
+        }
+    }
+
+    // the raw sensor data is often stored as lossless jpeg which can't be decoded
+    // so only baseline and progressive jpegs are considered
+    let mut candidates = Vec::new();
+    for (offset, length) in previews {
+        // a broken preview shouldn't prevent the other previews from being used
+        if let Ok(Some((width, height))) = tiff.jpeg_dimensions(offset, length) {
+            candidates.push((width as u64 * height as u64, offset, length));
+        }
+    }
+    let (_, offset, length) = candidates
+        .into_iter()
+        .max_by_key(|(pixels, ..)| *pixels)
+        .ok_or(ThumbError::NoEmbeddedImage)?;
+
+    let mut buf = Vec::new();
+    tiff.reader.seek(SeekFrom::Start(offset as u64))?;
+    (&mut tiff.reader)
+        .take(length as u64)
+        .read_to_end(&mut buf)?;
+    let decoder = ImageReader::with_format(Cursor::new(buf), ImageFormat::Jpeg).into_decoder()?;
+    limits.check(decoder.dimensions())?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    if let Some(orientation) = orientation.and_then(|value| Orientation::from_exif(value as u8)) {
+        image.apply_orientation(orientation);
+    }
+
+    Ok(image)
+}
+
+/// An entry of an image file directory
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    value: [u8; 4],
+}
+
+/// Reads the directory structure of tiff based files
+struct TiffReader<R> {
+    reader: R,
+    big_endian: bool,
+}
+
+impl<R: Read + Seek> TiffReader<R> {
+    fn new(mut reader: R) -> ThumbResult<Self> {
+        let mut header = [0u8; 4];
+        reader.read_exact(&mut header)?;
+        let big_endian = match &header {
+            b"II*\0" => false,
+            b"MM\0*" => true,
+            _ => return Err(ThumbError::decode("raw", "missing tiff header")),
+        };
+
+        Ok(Self { reader, big_endian })
+    }
+
+    fn first_ifd_offset(&mut self) -> ThumbResult<u32> {
+        self.reader.seek(SeekFrom::Start(4))?;
+        self.read_u32()
+    }
+
+    /// Reads the entries of the directory at the offset and the offset of the next directory
+    fn read_ifd(&mut self, offset: u32) -> ThumbResult<(Vec<Entry>, u32)> {
+        self.reader.seek(SeekFrom::Start(offset as u64))?;
+        let count = self.read_u16()?;
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let tag = self.read_u16()?;
+            let kind = self.read_u16()?;
+            let count = self.read_u32()?;
+            let mut value = [0u8; 4];
+            self.reader.read_exact(&mut value)?;
+            entries.push(Entry {
+                tag,
+                kind,
+                count,
+                value,
+            });
+        }
+        let next = self.read_u32()?;
+
+        Ok((entries, next))
+    }
+
+    /// Returns the integer values of an entry. Values of other types are ignored.
+    fn values(&mut self, entry: &Entry) -> ThumbResult<Vec<u32>> {
+        let size = match entry.kind {
+            // short
+            3 => 2,
+            // long and ifd
+            4 | 13 => 4,
+            _ => return Ok(Vec::new()),
+        };
+        let count = entry.count.min(MAX_VALUES);
+        let mut bytes = vec![0u8; (count * size) as usize];
+        let length = bytes.len();
+        if length <= 4 {
+            bytes.copy_from_slice(&entry.value[..length]);
+        } else {
+            self.reader
+                .seek(SeekFrom::Start(self.u32_from(entry.value) as u64))?;
+            self.reader.read_exact(&mut bytes)?;
+        }
+        let values = bytes
+            .chunks_exact(size as usize)
+            .map(|chunk| match chunk {
+                [a, b] => self.u16_from([*a, *b]) as u32,
+                [a, b, c, d] => self.u32_from([*a, *b, *c, *d]),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        Ok(values)
+    }
+
+    /// Returns the dimensions of a baseline or progressive jpeg at the given offset
+    /// or `None` if the data isn't such a jpeg
+    fn jpeg_dimensions(&mut self, offset: u32, length: u32) -> ThumbResult<Option<(u32, u32)>> {
+        let end = offset as u64 + length as u64;
+        self.reader.seek(SeekFrom::Start(offset as u64))?;
+        if self.read_bytes::<2>()? != [0xFF, 0xD8] {
+            return Ok(None);
+        }
+        while self.reader.stream_position()? < end {
+            let [prefix, marker] = self.read_bytes::<2>()?;
+            if prefix != 0xFF {
+                return Ok(None);
+            }
+            match marker {
+                // padding before a marker
+                0xFF => {
+                    self.reader.seek(SeekFrom::Current(-1))?;
+                }
+                0x01 | 0xD0..=0xD7 => {}
+                0xC0..=0xC2 => {
+                    let [_, _, _precision, h1, h2, w1, w2] = self.read_bytes::<7>()?;
+                    let height = u16::from_be_bytes([h1, h2]) as u32;
+                    let width = u16::from_be_bytes([w1, w2]) as u32;
+                    return Ok(Some((width, height)));
+                }
+                // lossless, hierarchical and arithmetic coding, or the end of the image
+                0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF | 0xD9 | 0xDA => return Ok(None),
+                _ => {
+                    let length = u16::from_be_bytes(self.read_bytes::<2>()?);
+                    self.reader
+                        .seek(SeekFrom::Current(length.saturating_sub(2) as i64))?;
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn read_bytes<const N: usize>(&mut self) -> ThumbResult<[u8; N]> {
+        let mut bytes = [0u8; N];
+        self.reader.read_exact(&mut bytes)?;
+
+        Ok(bytes)
+    }
+
+    fn read_u16(&mut self) -> ThumbResult<u16> {
+        let bytes = self.read_bytes::<2>()?;
+        Ok(self.u16_from(bytes))
+    }
+
+    fn read_u32(&mut self) -> ThumbResult<u32> {
+        let bytes = self.read_bytes::<4>()?;
+        Ok(self.u32_from(bytes))
+    }
+
+    fn u16_from(&self, bytes: [u8; 2]) -> u16 {
+        if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    }
+
+    fn u32_from(&self, bytes: [u8; 4]) -> u32 {
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+}
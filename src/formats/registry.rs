@@ -0,0 +1,307 @@
+use crate::error::ThumbResult;
+#[cfg(feature = "audio")]
+use crate::formats::audio_format::read_cover_art;
+use crate::formats::image_format::read_image;
+use crate::formats::DecodeOptions;
+use crate::BaseImage;
+use image::DynamicImage;
+use mime::Mime;
+use std::fmt;
+use std::io::{BufRead, Seek};
+use std::sync::Arc;
+
+#[cfg(feature = "archive")]
+use crate::formats::archive_format::{is_archive_mime, read_archive_image};
+#[cfg(feature = "ebook")]
+use crate::formats::ebook_format::{
+    is_comic_book_mime, is_epub_mime, read_comic_book_cover, read_epub_cover,
+};
+#[cfg(feature = "heif")]
+use crate::formats::heif_format::{is_heif_mime, read_heif_image};
+#[cfg(feature = "jxl")]
+use crate::formats::jxl_format::{is_jxl_mime, read_jxl_image};
+#[cfg(feature = "office")]
+use crate::formats::office_format::{is_office_mime, read_office_preview};
+#[cfg(feature = "pdf")]
+use crate::formats::pdf_format::read_pdf_page;
+#[cfg(feature = "raw")]
+use crate::formats::raw_format::{is_raw_mime, read_raw_preview};
+#[cfg(feature = "svg")]
+use crate::formats::svg_format::read_svg_image;
+#[cfg(feature = "text")]
+use crate::formats::text_format::{is_text_mime, read_text_preview};
+use crate::formats::tiff_format::{is_tiff_mime, read_tiff_page};
+#[cfg(feature = "ffmpeg")]
+use crate::formats::video_format::get_video_frame;
+
+/// A reader that can be passed to format handlers as a trait object
+pub trait ReadSeek: BufRead + Seek {}
+
+impl<T: BufRead + Seek + ?Sized> ReadSeek for T {}
+
+/// Decodes the base image of custom media types. Handlers are registered with
+/// [crate::ThumbnailerBuilder::register_handler] and are used for all media types with
+/// the essence (type and subtype without parameters) of a supported mime.
+///
+/// If multiple handlers support a media type the last registered one is used. This
+/// includes the built-in handlers, so registering a handler for `image/png` replaces
+/// the built-in png decoder.
+///
+/// Example:
+/// ```
+/// use image::{DynamicImage, Rgb, RgbImage};
+/// use mime::Mime;
+/// use thumbnailer::error::ThumbResult;
+/// use thumbnailer::{FormatHandler, ReadSeek, Thumbnailer, ThumbnailSize};
+/// use std::io::Cursor;
+///
+/// struct SolidHandler(Vec<Mime>);
+///
+/// impl FormatHandler for SolidHandler {
+///     fn supported_mimes(&self) -> &[Mime] {
+///         &self.0
+///     }
+///
+///     fn get_base_image(&self, _reader: &mut dyn ReadSeek) -> ThumbResult<DynamicImage> {
+///         Ok(DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 64, Rgb([255, 0, 0]))))
+///     }
+/// }
+///
+/// let mime: Mime = "application/x-test".parse().unwrap();
+/// let thumbnailer = Thumbnailer::builder()
+///     .register_handler(SolidHandler(vec![mime.clone()]))
+///     .build();
+/// let thumbnails = thumbnailer
+///     .create(Cursor::new(Vec::new()), mime, [ThumbnailSize::Icon])
+///     .unwrap();
+/// ```
+pub trait FormatHandler: Send + Sync {
+    /// Returns the media types that are decoded by the handler
+    fn supported_mimes(&self) -> &[Mime];
+
+    /// Decodes the image that the thumbnails are created from
+    fn get_base_image(&self, reader: &mut dyn ReadSeek) -> ThumbResult<DynamicImage>;
+}
+
+/// Decodes the base image of the media types it supports
+pub(crate) trait Handler: Send + Sync {
+    fn supports(&self, mime: &Mime) -> bool;
+
+    fn read(
+        &self,
+        reader: &mut dyn ReadSeek,
+        mime: &Mime,
+        options: &DecodeOptions,
+    ) -> ThumbResult<BaseImage>;
+}
+
+type ReadFn = fn(&mut dyn ReadSeek, &Mime, &DecodeOptions) -> ThumbResult<BaseImage>;
+
+/// A handler of the formats that are compiled into the crate
+struct BuiltinHandler {
+    supports: fn(&Mime) -> bool,
+    read: ReadFn,
+}
+
+impl Handler for BuiltinHandler {
+    fn supports(&self, mime: &Mime) -> bool {
+        (self.supports)(mime)
+    }
+
+    fn read(
+        &self,
+        reader: &mut dyn ReadSeek,
+        mime: &Mime,
+        options: &DecodeOptions,
+    ) -> ThumbResult<BaseImage> {
+        (self.read)(reader, mime, options)
+    }
+}
+
+fn builtin(supports: fn(&Mime) -> bool, read: ReadFn) -> Arc<dyn Handler> {
+    Arc::new(BuiltinHandler { supports, read })
+}
+
+/// A handler that was registered by users of the crate
+struct CustomHandler<H>(H);
+
+impl<H: FormatHandler> Handler for CustomHandler<H> {
+    fn supports(&self, mime: &Mime) -> bool {
+        self.0
+            .supported_mimes()
+            .iter()
+            .any(|supported| supported.essence_str() == mime.essence_str())
+    }
+
+    fn read(
+        &self,
+        reader: &mut dyn ReadSeek,
+        _: &Mime,
+        options: &DecodeOptions,
+    ) -> ThumbResult<BaseImage> {
+        let image = self.0.get_base_image(reader)?;
+        // the dimensions aren't known before decoding, but oversized images are
+        // still rejected before they are resized
+        options.limits.check((image.width(), image.height()))?;
+
+        Ok(BaseImage::new(image, None))
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref BUILTIN_REGISTRY: FormatRegistry = FormatRegistry::builtin();
+}
+
+/// The handlers that decode the base image of each media type. Handlers registered later
+/// take precedence over earlier ones. Cloning the registry is cheap.
+#[derive(Clone)]
+pub(crate) struct FormatRegistry {
+    handlers: Arc<Vec<Arc<dyn Handler>>>,
+}
+
+impl Default for FormatRegistry {
+    /// Returns the registry of the built-in handlers which is only created once
+    fn default() -> Self {
+        BUILTIN_REGISTRY.clone()
+    }
+}
+
+impl fmt::Debug for FormatRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FormatRegistry")
+            .field("handlers", &self.handlers.len())
+            .finish()
+    }
+}
+
+impl FormatRegistry {
+    /// Creates the registry of the handlers enabled by the crate features. The handlers
+    /// are registered from the most generic to the most specific.
+    // the handlers depend on the enabled features
+    #[allow(clippy::vec_init_then_push)]
+    fn builtin() -> Self {
+        let mut handlers: Vec<Arc<dyn Handler>> = Vec::new();
+        #[cfg(feature = "text")]
+        handlers.push(builtin(is_text_mime, |reader, _, options| {
+            Ok(BaseImage::new(
+                read_text_preview(reader, &options.text, options.target_size)?,
+                None,
+            ))
+        }));
+        #[cfg(feature = "ebook")]
+        handlers.push(builtin(is_comic_book_mime, |reader, _, options| {
+            Ok(BaseImage::new(
+                read_comic_book_cover(reader, &options.limits)?,
+                None,
+            ))
+        }));
+        #[cfg(feature = "ebook")]
+        handlers.push(builtin(is_epub_mime, |reader, _, options| {
+            Ok(BaseImage::new(
+                read_epub_cover(reader, &options.limits)?,
+                None,
+            ))
+        }));
+        #[cfg(feature = "archive")]
+        handlers.push(builtin(is_archive_mime, |reader, mime, options| {
+            read_archive_image(reader, mime, &options.limits)
+        }));
+        #[cfg(feature = "office")]
+        handlers.push(builtin(is_office_mime, |reader, _, options| {
+            Ok(BaseImage::new(
+                read_office_preview(reader, &options.limits)?,
+                None,
+            ))
+        }));
+        #[cfg(feature = "pdf")]
+        handlers.push(builtin(
+            |mime| mime.type_() == mime::APPLICATION && mime.subtype() == mime::PDF,
+            |reader, _, options| {
+                let (image, page_count) = read_pdf_page(reader, options.target_size)?;
+                let mut base = BaseImage::new(image, None);
+                base.info.page_count = Some(page_count);
+                Ok(base)
+            },
+        ));
+        #[cfg(feature = "audio")]
+        handlers.push(builtin(
+            |mime| mime.type_() == mime::AUDIO,
+            |reader, _, options| {
+                Ok(BaseImage::new(
+                    read_cover_art(reader, &options.limits)?,
+                    None,
+                ))
+            },
+        ));
+        #[cfg(feature = "ffmpeg")]
+        handlers.push(builtin(
+            |mime| mime.type_() == mime::VIDEO,
+            |reader, mime, options| {
+                let (image, duration) = get_video_frame(
+                    reader,
+                    mime.clone(),
+                    options.video_frame,
+                    &options.cancellation,
+                )?;
+                let mut base = BaseImage::new(image, None);
+                base.info.duration = duration;
+                Ok(base)
+            },
+        ));
+        handlers.push(builtin(
+            |mime| mime.type_() == mime::IMAGE,
+            |reader, mime, options| read_image(reader, mime.clone(), options),
+        ));
+        handlers.push(builtin(is_tiff_mime, |reader, mime, options| {
+            read_tiff_page(reader, mime.clone(), options)
+        }));
+        #[cfg(feature = "heif")]
+        handlers.push(builtin(is_heif_mime, |reader, mime, options| {
+            let format = (mime.subtype() == "avif").then_some(image::ImageFormat::Avif);
+            Ok(BaseImage::new(
+                read_heif_image(reader, &options.limits)?,
+                format,
+            ))
+        }));
+        #[cfg(feature = "jxl")]
+        handlers.push(builtin(is_jxl_mime, |reader, _, options| {
+            Ok(BaseImage::new(
+                read_jxl_image(reader, &options.limits)?,
+                None,
+            ))
+        }));
+        #[cfg(feature = "raw")]
+        handlers.push(builtin(is_raw_mime, |reader, _, options| {
+            Ok(BaseImage::new(
+                read_raw_preview(reader, &options.limits)?,
+                None,
+            ))
+        }));
+        #[cfg(feature = "svg")]
+        handlers.push(builtin(
+            |mime| mime.type_() == mime::IMAGE && mime.subtype() == mime::SVG,
+            |reader, _, options| {
+                let image = read_svg_image(reader, options.target_size)?;
+                Ok(BaseImage::new(image, None))
+            },
+        ));
+
+        Self {
+            handlers: Arc::new(handlers),
+        }
+    }
+
+    /// Adds a handler that takes precedence over all handlers registered before it
+    pub fn register<H: FormatHandler + 'static>(&mut self, handler: H) {
+        Arc::make_mut(&mut self.handlers).push(Arc::new(CustomHandler(handler)));
+    }
+
+    /// Returns the most recently registered handler that supports the media type
+    pub fn find(&self, mime: &Mime) -> Option<&dyn Handler> {
+        self.handlers
+            .iter()
+            .rev()
+            .find(|handler| handler.supports(mime))
+            .map(|handler| handler.as_ref())
+    }
+}
@@ -0,0 +1,146 @@
+use image::ImageFormat;
+use mime::Mime;
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::str::FromStr;
+
+/// The number of bytes that are inspected to detect the type of content
+const SNIFF_LEN: usize = 512;
+
+/// Detects the media type from the first bytes of the content that are buffered by the
+/// reader. Nothing is consumed, so readers that can't seek back can be sniffed as well.
+pub fn sniff_mime<R: BufRead>(reader: &mut R) -> io::Result<Option<Mime>> {
+    let buf = reader.fill_buf()?;
+    let buf = &buf[..buf.len().min(SNIFF_LEN)];
+
+    Ok(mime_from_magic(buf).and_then(|mime| Mime::from_str(mime).ok()))
+}
+
+/// Returns the media type for the extension of the file name. Unknown extensions return `None`.
+pub fn mime_from_extension(path: &Path) -> Option<Mime> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mime = match extension.as_str() {
+        "svg" => "image/svg+xml",
+        "heic" => "image/heic",
+        "heif" => "image/heif",
+        "jxl" => "image/jxl",
+        "cr2" => "image/x-canon-cr2",
+        "nef" => "image/x-nikon-nef",
+        "arw" => "image/x-sony-arw",
+        "dng" => "image/x-adobe-dng",
+        "mp4" | "m4v" => "video/mp4",
+        "mov" => "video/quicktime",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        "avi" => "video/x-msvideo",
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "m4a" => "audio/mp4",
+        "pdf" => "application/pdf",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        "ods" => "application/vnd.oasis.opendocument.spreadsheet",
+        "odp" => "application/vnd.oasis.opendocument.presentation",
+        "epub" => "application/epub+zip",
+        "cbz" => "application/vnd.comicbook+zip",
+        "zip" => "application/zip",
+        "tar" => "application/x-tar",
+        "tgz" | "gz" => "application/gzip",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "txt" | "log" | "md" | "csv" | "toml" | "yaml" | "yml" | "ini" => "text/plain",
+        "rs" | "c" | "h" | "cpp" | "py" | "js" | "ts" | "go" | "java" | "sh" => "text/plain",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        _ => {
+            return ImageFormat::from_extension(&extension)
+                .and_then(|format| Mime::from_str(format.to_mime_type()).ok());
+        }
+    };
+
+    Mime::from_str(mime).ok()
+}
+
+/// Returns the usual file extension of the media type. Unknown types return `None`.
+pub fn extension_from_mime(mime: &Mime) -> Option<&'static str> {
+    let extension = match mime.essence_str() {
+        "image/svg+xml" => "svg",
+        "image/heic" => "heic",
+        "image/heif" => "heif",
+        "image/jxl" => "jxl",
+        "video/mp4" => "mp4",
+        "video/quicktime" => "mov",
+        "video/x-matroska" => "mkv",
+        "video/webm" => "webm",
+        "video/x-msvideo" => "avi",
+        "audio/mpeg" => "mp3",
+        "audio/flac" => "flac",
+        "audio/mp4" => "m4a",
+        "application/pdf" => "pdf",
+        "application/zip" => "zip",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => "docx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => "xlsx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation" => "pptx",
+        "application/vnd.oasis.opendocument.text" => "odt",
+        "application/vnd.oasis.opendocument.spreadsheet" => "ods",
+        "application/vnd.oasis.opendocument.presentation" => "odp",
+        "application/x-tar" => "tar",
+        "application/gzip" => "gz",
+        "application/epub+zip" => "epub",
+        "application/vnd.comicbook+zip" => "cbz",
+        "application/json" => "json",
+        "application/xml" | "text/xml" => "xml",
+        "text/plain" => "txt",
+        "text/html" => "html",
+        "text/css" => "css",
+        essence => {
+            return ImageFormat::from_mime_type(essence)
+                .and_then(|format| format.extensions_str().first().copied());
+        }
+    };
+
+    Some(extension)
+}
+
+fn mime_from_magic(buf: &[u8]) -> Option<&'static str> {
+    if let Ok(format) = image::guess_format(buf) {
+        if format != ImageFormat::Farbfeld {
+            return Some(format.to_mime_type());
+        }
+    }
+    // a bare jpeg xl codestream or one in an isobmff container
+    if buf.starts_with(&[0xFF, 0x0A]) || buf.starts_with(b"\0\0\0\x0CJXL \r\n\x87\n") {
+        return Some("image/jxl");
+    }
+    if buf.starts_with(b"%PDF-") {
+        return Some("application/pdf");
+    }
+    if buf.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        return Some("video/x-matroska");
+    }
+    if buf.len() >= 12 && &buf[0..4] == b"RIFF" && &buf[8..12] == b"AVI " {
+        return Some("video/x-msvideo");
+    }
+    if buf.len() >= 12 && &buf[4..8] == b"ftyp" {
+        return match &buf[8..12] {
+            b"M4A " | b"M4B " => Some("audio/mp4"),
+            b"heic" | b"heix" | b"heim" | b"heis" | b"mif1" | b"msf1" => Some("image/heic"),
+            b"avif" | b"avis" => Some("image/avif"),
+            b"qt  " => Some("video/quicktime"),
+            _ => Some("video/mp4"),
+        };
+    }
+    if buf.starts_with(b"fLaC") {
+        return Some("audio/flac");
+    }
+    if buf.starts_with(b"ID3") || buf.starts_with(&[0xFF, 0xFB]) {
+        return Some("audio/mpeg");
+    }
+    if String::from_utf8_lossy(buf).contains("<svg") {
+        return Some("image/svg+xml");
+    }
+
+    None
+}
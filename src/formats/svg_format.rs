@@ -0,0 +1,66 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::utils::bytes::read_bytes;
+use image::{DynamicImage, RgbaImage};
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{fontdb, ImageHrefResolver, Options, Tree};
+use std::io::{BufRead, Seek};
+use std::sync::Arc;
+
+/// The largest edge length an svg is rendered at to bound memory usage
+const MAX_RENDER_DIMENSION: f32 = 8192.0;
+
+/// Renders an svg image. The image is rendered big enough to cover the
+/// given target size so that it doesn't need to be upscaled.
+pub fn read_svg_image<R: BufRead + Seek>(
+    mut reader: R,
+    target_size: Option<(u32, u32)>,
+) -> ThumbResult<DynamicImage> {
+    lazy_static::lazy_static! {
+        static ref FONTS: Arc<fontdb::Database> = {
+            let mut fonts = fontdb::Database::new();
+            fonts.load_system_fonts();
+            Arc::new(fonts)
+        };
+    }
+    let buf = read_bytes(&mut reader)?;
+
+    let options = Options {
+        // external resources are ignored, only embedded data is rendered
+        image_href_resolver: ImageHrefResolver {
+            resolve_string: Box::new(|_, _| None),
+            ..ImageHrefResolver::default()
+        },
+        fontdb: FONTS.clone(),
+        ..Options::default()
+    };
+    let tree = Tree::from_data(&buf, &options).map_err(|e| ThumbError::decode("svg", e))?;
+
+    let size = tree.size();
+    let scale = target_size
+        .map(|(width, height)| (width as f32 / size.width()).max(height as f32 / size.height()))
+        .unwrap_or(1.0)
+        .min(MAX_RENDER_DIMENSION / size.width().max(size.height()));
+    let width = (size.width() * scale).round().max(1.0) as u32;
+    let height = (size.height() * scale).round().max(1.0) as u32;
+
+    let mut pixmap = Pixmap::new(width, height)
+        .ok_or_else(|| ThumbError::decode("svg", "invalid image size"))?;
+    resvg::render(
+        &tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    let image = RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| ThumbError::decode("svg", "invalid rendered image size"))?;
+
+    Ok(DynamicImage::ImageRgba8(image))
+}
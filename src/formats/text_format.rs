@@ -0,0 +1,158 @@
+use crate::error::{ThumbError, ThumbResult};
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use image::{DynamicImage, Rgb, RgbImage};
+use mime::Mime;
+use std::io::Read;
+
+/// The monospace font used to render text previews
+const FONT: &[u8] = include_bytes!("../../assets/DejaVuSansMono.ttf");
+
+/// The number of bytes read from the start of text files
+const MAX_TEXT_BYTES: u64 = 64 * 1024;
+
+/// The smallest line height in pixels. Smaller pages show fewer lines
+/// instead of text that can't be read.
+const MIN_LINE_HEIGHT: f32 = 7.0;
+
+/// The page height used when no size has been requested
+const DEFAULT_PAGE_HEIGHT: u32 = 256;
+
+/// The largest edge length of a page to bound memory usage like the render size of svgs
+const MAX_PAGE_DIMENSION: f32 = 8192.0;
+
+const TAB_WIDTH: usize = 4;
+const PAGE_COLOR: Rgb<u8> = Rgb([255, 255, 255]);
+const INK_COLOR: Rgb<u8> = Rgb([32, 32, 32]);
+
+/// Options for the page text files are rendered onto
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct TextOptions {
+    /// The maximum number of lines that are rendered
+    pub lines: usize,
+
+    /// The width of the page divided by its height
+    pub page_aspect: f32,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            lines: 20,
+            // a4 portrait
+            page_aspect: 1.0 / std::f32::consts::SQRT_2,
+        }
+    }
+}
+
+impl TextOptions {
+    pub fn is_valid(&self) -> bool {
+        self.lines > 0 && self.page_aspect.is_finite() && self.page_aspect > 0.0
+    }
+}
+
+/// Returns whether the mime describes a text or source code file
+pub fn is_text_mime(mime: &Mime) -> bool {
+    mime.type_() == mime::TEXT
+        || (mime.type_() == mime::APPLICATION
+            && (mime.subtype() == mime::JSON
+                || mime.subtype() == mime::XML
+                || mime.suffix() == Some(mime::JSON)
+                || mime.suffix() == Some(mime::XML)))
+}
+
+/// Renders the first lines of a text file onto a page. The page is sized to fit into
+/// the target size so that the font size grows with the requested sizes.
+/// Invalid utf-8 is replaced, long lines are truncated and empty files result in a blank page.
+pub fn read_text_preview<R: Read>(
+    reader: R,
+    options: &TextOptions,
+    target_size: Option<(u32, u32)>,
+) -> ThumbResult<DynamicImage> {
+    let mut buf = Vec::new();
+    reader.take(MAX_TEXT_BYTES).read_to_end(&mut buf)?;
+    let text = String::from_utf8_lossy(&buf);
+    let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+
+    let (width, height) = page_size(options.page_aspect, target_size);
+    let mut page = RgbImage::from_pixel(width, height, PAGE_COLOR);
+    let font = FontRef::try_from_slice(FONT).map_err(|e| ThumbError::decode("text", e))?;
+
+    let margin = height as f32 / 16.0;
+    let text_height = height as f32 - 2.0 * margin;
+    let line_count = options
+        .lines
+        .min((text_height / MIN_LINE_HEIGHT) as usize)
+        .max(1);
+    let line_height = text_height / line_count as f32;
+    let scale = PxScale::from(line_height);
+    let scaled = font.as_scaled(scale);
+    let advance = scaled.h_advance(font.glyph_id('0'));
+    let columns = ((width as f32 - 2.0 * margin) / advance).max(0.0) as usize;
+
+    for (row, line) in text.lines().take(line_count).enumerate() {
+        let baseline = margin + row as f32 * line_height + scaled.ascent();
+        for (column, c) in expand_tabs(line).take(columns).enumerate() {
+            if c.is_whitespace() || c.is_control() {
+                continue;
+            }
+            let position = point(margin + column as f32 * advance, baseline);
+            let glyph = font.glyph_id(c).with_scale_and_position(scale, position);
+            if let Some(outline) = font.outline_glyph(glyph) {
+                let bounds = outline.px_bounds();
+                outline.draw(|x, y, coverage| {
+                    let x = bounds.min.x as i64 + x as i64;
+                    let y = bounds.min.y as i64 + y as i64;
+                    if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+                        let pixel = page.get_pixel_mut(x as u32, y as u32);
+                        *pixel = blend(*pixel, coverage);
+                    }
+                });
+            }
+        }
+    }
+
+    Ok(DynamicImage::ImageRgb8(page))
+}
+
+/// Returns the largest page with the aspect ratio that fits into the target size
+/// and whose longer edge is at most [MAX_PAGE_DIMENSION]
+fn page_size(aspect: f32, target_size: Option<(u32, u32)>) -> (u32, u32) {
+    let (target_width, target_height) = target_size.unwrap_or((
+        (DEFAULT_PAGE_HEIGHT as f32 * aspect) as u32,
+        DEFAULT_PAGE_HEIGHT,
+    ));
+    let height = (target_height as f32)
+        .min(target_width as f32 / aspect)
+        .min(MAX_PAGE_DIMENSION / aspect.max(1.0));
+    let width = height * aspect;
+
+    (
+        width.round().max(1.0) as u32,
+        height.round().max(1.0) as u32,
+    )
+}
+
+/// Replaces tabs with spaces up to the next tab stop
+fn expand_tabs(line: &str) -> impl Iterator<Item = char> + '_ {
+    let mut column = 0;
+    line.chars().flat_map(move |c| {
+        let count = if c == '\t' {
+            TAB_WIDTH - column % TAB_WIDTH
+        } else {
+            1
+        };
+        column += count;
+        let c = if c == '\t' { ' ' } else { c };
+        std::iter::repeat_n(c, count)
+    })
+}
+
+fn blend(pixel: Rgb<u8>, coverage: f32) -> Rgb<u8> {
+    let coverage = coverage.clamp(0.0, 1.0);
+    let mut blended = pixel;
+    for (channel, ink) in blended.0.iter_mut().zip(INK_COLOR.0) {
+        *channel = (*channel as f32 * (1.0 - coverage) + ink as f32 * coverage).round() as u8;
+    }
+
+    blended
+}
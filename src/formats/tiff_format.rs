@@ -0,0 +1,112 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::formats::image_format::read_image;
+use crate::formats::DecodeOptions;
+use crate::utils::bytes::read_bytes;
+use crate::BaseImage;
+use mime::Mime;
+use std::convert::TryFrom;
+use std::io::{BufRead, Cursor, Read, Seek, SeekFrom};
+use tiff::decoder::Decoder;
+use tiff::tags::{ByteOrder, IfdPointer};
+
+/// Returns whether the mime describes a tiff image
+pub fn is_tiff_mime(mime: &Mime) -> bool {
+    mime.type_() == mime::IMAGE && mime.subtype() == "tiff"
+}
+
+/// Reads the page with the index of the options from a classic or BigTIFF image and
+/// returns it with the number of pages. Bilevel pages like CCITT fax scans are
+/// returned as grayscale images.
+pub fn read_tiff_page<R: BufRead + Seek>(
+    mut reader: R,
+    mime: Mime,
+    options: &DecodeOptions,
+) -> ThumbResult<BaseImage> {
+    let start = reader.stream_position()?;
+    let pages = read_pages(&mut reader, options.page)?;
+    reader.seek(SeekFrom::Start(start))?;
+    if options.page >= pages.count {
+        return Err(ThumbError::PageOutOfRange {
+            page: options.page,
+            count: pages.count,
+        });
+    }
+
+    let mut base = match pages.selected {
+        // the header is pointed at the directory of the page, so it is read like the first
+        Some(offset) if options.page > 0 => {
+            let mut buf = read_bytes(&mut reader)?.into_owned();
+            set_first_directory(&mut buf, offset, pages.byte_order)?;
+            read_image(Cursor::new(buf), mime, options)?
+        }
+        _ => read_image(reader, mime, options)?,
+    };
+    base.info.page_count = Some(pages.count);
+
+    Ok(base)
+}
+
+/// The directories of a tiff image
+struct Pages {
+    count: usize,
+    /// The offset of the directory of the selected page
+    selected: Option<u64>,
+    byte_order: ByteOrder,
+}
+
+/// Walks the chain of image directories to count the pages and find the offset of
+/// the selected one
+fn read_pages<R: Read + Seek>(reader: R, page: usize) -> ThumbResult<Pages> {
+    let mut decoder = Decoder::new(reader).map_err(|e| ThumbError::decode("tiff", e))?;
+    let mut pages = Pages {
+        count: 1,
+        selected: None,
+        byte_order: decoder.byte_order(),
+    };
+    loop {
+        if pages.count == page + 1 {
+            pages.selected = decoder.ifd_pointer().map(|IfdPointer(offset)| offset);
+        }
+        if !decoder.more_images() {
+            return Ok(pages);
+        }
+        decoder
+            .next_image()
+            .map_err(|e| ThumbError::decode("tiff", e))?;
+        pages.count += 1;
+    }
+}
+
+/// Replaces the offset of the first directory in the header of the tiff image
+fn set_first_directory(buf: &mut [u8], offset: u64, byte_order: ByteOrder) -> ThumbResult<()> {
+    let truncated = || ThumbError::decode("tiff", "truncated header");
+    let little_endian = byte_order == ByteOrder::LittleEndian;
+    let bigtiff = match buf.get(2..4).ok_or_else(truncated)? {
+        [43, 0] => little_endian,
+        [0, 43] => !little_endian,
+        _ => false,
+    };
+
+    if bigtiff {
+        let bytes = if little_endian {
+            offset.to_le_bytes()
+        } else {
+            offset.to_be_bytes()
+        };
+        buf.get_mut(8..16)
+            .ok_or_else(truncated)?
+            .copy_from_slice(&bytes);
+    } else {
+        let offset = u32::try_from(offset).map_err(|e| ThumbError::decode("tiff", e))?;
+        let bytes = if little_endian {
+            offset.to_le_bytes()
+        } else {
+            offset.to_be_bytes()
+        };
+        buf.get_mut(4..8)
+            .ok_or_else(truncated)?
+            .copy_from_slice(&bytes);
+    }
+
+    Ok(())
+}
@@ -1,38 +1,265 @@
 use crate::error::{ThumbError, ThumbResult};
-use crate::utils::ffmpeg_cli::{get_png_frame, is_ffmpeg_installed};
-use image::io::Reader as ImageReader;
+use crate::formats::{Cancellation, VideoFrameSelector};
+use crate::utils::bytes::read_bytes;
+use crate::utils::ffmpeg_cli::{
+    get_last_png_frame, get_png_frame, get_png_frame_by_index, get_video_duration,
+    is_ffmpeg_installed,
+};
+use image::ImageReader;
 use image::{DynamicImage, ImageFormat};
 use mime::Mime;
+use std::cmp::Ordering;
 use std::fs;
 use std::io::{BufRead, Cursor, Seek};
 use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::TempDir;
 
-pub fn get_video_frame<R: BufRead + Seek>(mut reader: R, mime: Mime) -> ThumbResult<DynamicImage> {
-    lazy_static::lazy_static! { static ref FFMPEG_INSTALLED: bool = is_ffmpeg_installed(); }
+/// Returns the selected frame of the video and the duration of the video if it is known
+pub fn get_video_frame<R: BufRead + Seek>(
+    reader: R,
+    mime: Mime,
+    selector: VideoFrameSelector,
+    cancellation: &Cancellation,
+) -> ThumbResult<(DynamicImage, Option<Duration>)> {
+    let video = VideoFile::new(reader, mime, cancellation)?;
+    let image = video.frame(selector)?;
+    let duration = video.duration;
+    video.close()?;
 
-    if !*FFMPEG_INSTALLED {
-        return Err(ThumbError::Unsupported(mime));
+    Ok((image, duration))
+}
+
+/// Returns the frames of the selectors in the same order and the duration of the video.
+/// The video is only written and probed once and the frames are extracted in ascending
+/// order of their position. Selectors beyond the end of the video select the last frame.
+pub fn get_video_frames_at<R: BufRead + Seek>(
+    reader: R,
+    mime: Mime,
+    selectors: &[VideoFrameSelector],
+    cancellation: &Cancellation,
+) -> ThumbResult<(Vec<DynamicImage>, Option<Duration>)> {
+    let video = VideoFile::new(reader, mime, cancellation)?;
+    let mut order: Vec<usize> = (0..selectors.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (video.position(selectors[a]), video.position(selectors[b]));
+        a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+    });
+
+    let mut frames = vec![None; selectors.len()];
+    let mut previous: Option<usize> = None;
+    for index in order {
+        cancellation.check()?;
+        // the same selector is only extracted once
+        let frame = match previous.filter(|&previous| selectors[previous] == selectors[index]) {
+            Some(previous) => frames[previous].clone(),
+            None => Some(video.frame(selectors[index])?),
+        };
+        frames[index] = frame;
+        previous = Some(index);
     }
+    let duration = video.duration;
+    video.close()?;
+
+    Ok((frames.into_iter().flatten().collect(), duration))
+}
+
+/// Returns the frames at every multiple of the interval before the end of the video and
+/// the duration of the video. The duration is compared in whole milliseconds and videos
+/// that are shorter than the interval or without a known duration return the first frame.
+/// Each frame is processed right after it is extracted, so that only the processed frames
+/// are kept, and videos with more than `max_frames` frames fail before any are extracted.
+pub fn get_video_frames_every<R: BufRead + Seek, F: FnMut(DynamicImage) -> DynamicImage>(
+    reader: R,
+    mime: Mime,
+    interval: Duration,
+    max_frames: usize,
+    mut process: F,
+    cancellation: &Cancellation,
+) -> ThumbResult<(Vec<DynamicImage>, Option<Duration>)> {
+    let video = VideoFile::new(reader, mime, cancellation)?;
+    let end = video
+        .duration
+        .map(|duration| Duration::from_millis(duration.as_millis() as u64));
+    let count = end.map_or(1, |end| {
+        let count = end.as_millis().div_ceil(interval.as_millis().max(1)).max(1);
+        count.min(usize::MAX as u128) as usize
+    });
+    if count > max_frames {
+        video.close()?;
+        return Err(ThumbError::TooManyFrames {
+            count,
+            limit: max_frames,
+        });
+    }
+    let mut frames = Vec::with_capacity(count);
+    let mut timestamp = Duration::ZERO;
+    while frames.is_empty() || end.is_some_and(|end| timestamp < end) {
+        cancellation.check()?;
+        let frame = video.frame(VideoFrameSelector::Timestamp(timestamp))?;
+        frames.push(process(frame));
+        timestamp += interval;
+    }
+    let duration = video.duration;
+    video.close()?;
+
+    Ok((frames, duration))
+}
+
+/// Returns `count` frames sampled evenly across the duration of the video. Videos
+/// without a known duration use the first frames instead and frames that can't be
+/// extracted because the video is too short are `None`.
+pub fn get_video_frames<R: BufRead + Seek>(
+    reader: R,
+    mime: Mime,
+    count: usize,
+    cancellation: &Cancellation,
+) -> ThumbResult<Vec<Option<DynamicImage>>> {
+    let video = VideoFile::new(reader, mime, cancellation)?;
+    let frames = (0..count)
+        .map(|i| {
+            cancellation.check()?;
+            let png_bytes = match video.duration {
+                // the middle of each of the equally long sections of the video
+                Some(duration) => get_png_frame(
+                    &video.path,
+                    duration.mul_f64((i as f64 + 0.5) / count as f64),
+                    cancellation,
+                ),
+                None => get_png_frame_by_index(&video.path, i as u64, cancellation),
+            };
+            // a killed ffmpeg doesn't mean that the frame is missing
+            cancellation.check()?;
+            Ok(png_bytes.ok().and_then(|bytes| decode_png(bytes).ok()))
+        })
+        .collect::<ThumbResult<_>>()?;
+    video.close()?;
 
-    let tempdir = tempfile::tempdir()?;
-    let path = PathBuf::from(tempdir.path())
-        .join("video")
-        .with_extension(mime.subtype().as_str());
+    Ok(frames)
+}
+
+/// A video written to a temporary file that ffmpeg can read, so that multiple frames
+/// can be extracted from it
+struct VideoFile {
+    tempdir: TempDir,
+    path: String,
+    duration: Option<Duration>,
+    cancellation: Cancellation,
+}
 
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
-    fs::write(&path, buf)?;
+impl VideoFile {
+    /// Writes the video to a temporary file and reads its duration
+    fn new<R: BufRead + Seek>(
+        mut reader: R,
+        mime: Mime,
+        cancellation: &Cancellation,
+    ) -> ThumbResult<Self> {
+        lazy_static::lazy_static! { static ref FFMPEG_INSTALLED: bool = is_ffmpeg_installed(); }
 
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
+        if !*FFMPEG_INSTALLED {
+            return Err(ThumbError::UnsupportedMime(mime));
+        }
+
+        let tempdir = tempfile::tempdir()?;
+        let path = PathBuf::from(tempdir.path())
+            .join("video")
+            .with_extension(video_extension(&mime));
+
+        fs::write(&path, read_bytes(&mut reader)?)?;
+        let path = path
+            .to_str()
+            .expect("path to tmpdir contains invalid characters")
+            .to_string();
+        let duration = get_duration(&path, cancellation);
+
+        Ok(Self {
+            tempdir,
+            path,
+            duration,
+            cancellation: cancellation.clone(),
+        })
+    }
 
-    let png_bytes = get_png_frame(
-        path.to_str()
-            .expect("path to tmpdir contains invalid characters"),
-        16,
-    )?; // take the 16th frame
-    tempdir.close()?;
-    let img = ImageReader::with_format(Cursor::new(png_bytes), ImageFormat::Png).decode()?;
+    /// Extracts the selected frame
+    fn frame(&self, selector: VideoFrameSelector) -> ThumbResult<DynamicImage> {
+        let path = self.path.as_str();
+        let cancellation = &self.cancellation;
+        let png_bytes = match selector {
+            VideoFrameSelector::Timestamp(timestamp) => {
+                get_frame_at(path, timestamp, self.duration, cancellation)?
+            }
+            VideoFrameSelector::Percentage(percentage) => match self.duration {
+                Some(duration) => {
+                    let timestamp = duration.mul_f64(percentage.clamp(0.0, 1.0) as f64);
+                    get_frame_at(path, timestamp, Some(duration), cancellation)?
+                }
+                None => get_png_frame(path, Duration::ZERO, cancellation)?,
+            },
+            VideoFrameSelector::Frame(index) => {
+                get_png_frame_by_index(path, index, cancellation)
+                    .or_else(|_| get_last_png_frame(path, cancellation))?
+            }
+        };
 
-    Ok(img)
+        decode_png(png_bytes)
+    }
+
+    /// Returns a key that orders selectors by their position in the video. Frame indices
+    /// can't be converted to timestamps without the frame rate, so they are ordered after
+    /// the timestamps.
+    fn position(&self, selector: VideoFrameSelector) -> (u8, f64) {
+        match selector {
+            VideoFrameSelector::Timestamp(timestamp) => (0, timestamp.as_secs_f64()),
+            VideoFrameSelector::Percentage(percentage) => (
+                0,
+                self.duration.map_or(0.0, |duration| {
+                    duration.as_secs_f64() * percentage.clamp(0.0, 1.0) as f64
+                }),
+            ),
+            VideoFrameSelector::Frame(index) => (1, index as f64),
+        }
+    }
+
+    /// Removes the temporary file
+    fn close(self) -> ThumbResult<()> {
+        self.tempdir.close()?;
+
+        Ok(())
+    }
+}
+
+fn decode_png(png_bytes: Vec<u8>) -> ThumbResult<DynamicImage> {
+    let image = ImageReader::with_format(Cursor::new(png_bytes), ImageFormat::Png).decode()?;
+
+    Ok(image)
+}
+
+/// Returns the duration of the video. ffprobe might not be available
+/// or the container might not have a duration.
+fn get_duration(path: &str, cancellation: &Cancellation) -> Option<Duration> {
+    get_video_duration(path, cancellation).ok().flatten()
+}
+
+/// Returns the frame at the given timestamp clamping to the last frame of the video.
+/// The fallback fails right away when the first attempt was cancelled.
+fn get_frame_at(
+    path: &str,
+    timestamp: Duration,
+    duration: Option<Duration>,
+    cancellation: &Cancellation,
+) -> ThumbResult<Vec<u8>> {
+    if duration.is_some_and(|duration| timestamp >= duration) {
+        return get_last_png_frame(path, cancellation);
+    }
+    get_png_frame(path, timestamp, cancellation).or_else(|_| get_last_png_frame(path, cancellation))
+}
+
+/// Returns the file extension for the video mime so that ffmpeg can pick the right demuxer
+fn video_extension(mime: &Mime) -> &str {
+    match mime.subtype().as_str() {
+        "x-matroska" => "mkv",
+        "quicktime" => "mov",
+        "x-msvideo" => "avi",
+        subtype => subtype,
+    }
 }
@@ -0,0 +1,37 @@
+use crate::error::{ThumbError, ThumbResult};
+use std::io::{Read, Seek};
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// The largest file that is read from an archive to protect against zip bombs
+#[cfg_attr(not(any(feature = "office", feature = "ebook")), allow(dead_code))]
+const MAX_ENTRY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Opens a zip archive. Invalid archives are decode errors of the given format.
+pub fn open_archive<R: Read + Seek>(reader: R, format: &str) -> ThumbResult<ZipArchive<R>> {
+    ZipArchive::new(reader).map_err(|e| ThumbError::decode(format, e))
+}
+
+/// Returns the content of the file with the given path or `None` if it doesn't exist
+#[cfg_attr(not(any(feature = "office", feature = "ebook")), allow(dead_code))]
+pub fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    path: &str,
+    format: &str,
+) -> ThumbResult<Option<Vec<u8>>> {
+    let entry = match archive.by_name(path) {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(ThumbError::decode(format, e)),
+    };
+    if entry.size() > MAX_ENTRY_SIZE {
+        return Err(ThumbError::decode(format, "embedded file is too large"));
+    }
+    let mut buf = Vec::with_capacity(entry.size() as usize);
+    entry
+        .take(MAX_ENTRY_SIZE)
+        .read_to_end(&mut buf)
+        .map_err(|e| ThumbError::decode(format, e))?;
+
+    Ok(Some(buf))
+}
@@ -0,0 +1,117 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::Thumbnail;
+use image::codecs::ico::{IcoEncoder, IcoFrame};
+use image::ExtendedColorType;
+use std::io::{Cursor, Seek, Write};
+
+/// The largest width and height of entries of ico files
+const MAX_ICO_DIMENSION: u32 = 256;
+
+/// Writes the thumbnails as the entries of a multi-resolution ico file. Each entry is
+/// encoded as a png. Thumbnails with the same dimensions are only written once and the
+/// entries are sorted from the smallest to the largest one.
+pub fn write_ico<W: Write + Seek>(thumbnails: &[Thumbnail], writer: &mut W) -> ThumbResult<()> {
+    let thumbnails = unique_sizes(thumbnails)?;
+    if let Some(thumbnail) = thumbnails.iter().find(|thumbnail| {
+        let (width, height) = thumbnail.size();
+        width > MAX_ICO_DIMENSION || height > MAX_ICO_DIMENSION
+    }) {
+        let (width, height) = thumbnail.size();
+        return Err(ThumbError::InvalidOptions(format!(
+            "ico entries can be at most {0}x{0} pixels, got {1}x{2}",
+            MAX_ICO_DIMENSION, width, height
+        )));
+    }
+
+    let frames = thumbnails
+        .into_iter()
+        .map(|thumbnail| {
+            let (width, height) = thumbnail.size();
+            let png = encode_png(thumbnail)?;
+            Ok(IcoFrame::with_encoded(
+                png,
+                width,
+                height,
+                ExtendedColorType::Rgba8,
+            )?)
+        })
+        .collect::<ThumbResult<Vec<_>>>()?;
+    IcoEncoder::new(writer).encode_images(&frames)?;
+
+    Ok(())
+}
+
+/// Writes the thumbnails as the entries of a multi-resolution icns file. Each entry is
+/// encoded as a png. Thumbnails need to be square with one of the dimensions
+/// 16, 32, 64, 128, 256, 512 or 1024 pixels.
+#[cfg(feature = "icns")]
+pub fn write_icns<W: Write + Seek>(thumbnails: &[Thumbnail], writer: &mut W) -> ThumbResult<()> {
+    let mut entries = Vec::new();
+    for thumbnail in unique_sizes(thumbnails)? {
+        let (width, height) = thumbnail.size();
+        let kind = match (width, height) {
+            (16, 16) => b"icp4",
+            (32, 32) => b"icp5",
+            (64, 64) => b"icp6",
+            (128, 128) => b"ic07",
+            (256, 256) => b"ic08",
+            (512, 512) => b"ic09",
+            (1024, 1024) => b"ic10",
+            _ => {
+                return Err(ThumbError::InvalidOptions(format!(
+                    "icns entries need to be 16, 32, 64, 128, 256, 512 or 1024 pixels \
+                    squares, got {}x{}",
+                    width, height
+                )))
+            }
+        };
+        entries.push((kind, encode_png(thumbnail)?));
+    }
+
+    // the lengths include the eight bytes of the type and length fields
+    let length = 8 + entries.iter().map(|(_, png)| 8 + png.len()).sum::<usize>();
+    writer.write_all(b"icns")?;
+    writer.write_all(&icns_length(length)?)?;
+    for (kind, png) in entries {
+        writer.write_all(kind)?;
+        writer.write_all(&icns_length(8 + png.len())?)?;
+        writer.write_all(&png)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the big endian bytes of a length field of an icns file
+#[cfg(feature = "icns")]
+fn icns_length(length: usize) -> ThumbResult<[u8; 4]> {
+    use std::convert::TryFrom;
+
+    u32::try_from(length)
+        .map(u32::to_be_bytes)
+        .map_err(|_| ThumbError::InvalidOptions("icns files are limited to 4GiB".into()))
+}
+
+/// Returns the thumbnails with distinct dimensions sorted by their size
+fn unique_sizes(thumbnails: &[Thumbnail]) -> ThumbResult<Vec<&Thumbnail>> {
+    if thumbnails.is_empty() {
+        return Err(ThumbError::InvalidOptions(
+            "icons need at least one thumbnail".into(),
+        ));
+    }
+    let mut thumbnails: Vec<&Thumbnail> = thumbnails.iter().collect();
+    // the sort is stable so the first of thumbnails with the same dimensions is kept
+    thumbnails.sort_by_key(|thumbnail| {
+        let (width, height) = thumbnail.size();
+        (width as u64 * height as u64, width)
+    });
+    thumbnails.dedup_by_key(|thumbnail| thumbnail.size());
+
+    Ok(thumbnails)
+}
+
+fn encode_png(thumbnail: &Thumbnail) -> ThumbResult<Vec<u8>> {
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.clone().write_png(&mut buf)?;
+
+    Ok(buf.into_inner())
+}
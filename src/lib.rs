@@ -20,72 +20,891 @@
 //!
 //! ```
 
-use crate::error::ThumbResult;
-use image::imageops::FilterType;
-use image::{DynamicImage, GenericImageView, ImageFormat};
+use crate::error::{ThumbError, ThumbResult};
+use crate::formats::sniff::mime_from_extension;
+use crate::mask::Shape;
+use crate::resize::is_high_precision;
+use crate::trace::encode_traced;
+use crate::transform::Transform;
+#[cfg(feature = "avif-encode")]
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::{JpegEncoder, PixelDensity, PixelDensityUnit};
+use image::codecs::png::{FilterType as PngFilterType, PngEncoder};
+use image::error::ImageFormatHint;
+use image::{DynamicImage, GenericImageView, ImageFormat, Rgb, RgbImage, Rgba};
 use mime::Mime;
-use rayon::prelude::*;
-use std::io::{BufRead, Seek, Write};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Seek, Write};
+use std::path::Path;
+#[cfg(feature = "libwebp")]
+use webp::Encoder as WebpEncoder;
 
-use crate::formats::get_base_image;
-pub use size::ThumbnailSize;
+pub use animated::AnimatedThumbnail;
+#[cfg(feature = "tokio")]
+pub use async_api::create_thumbnails_async;
+pub use badge::Badge;
+pub use base_image::{BaseImage, SourceInfo};
+pub use batch::{create_thumbnails_batch, BatchInput, BatchOptions};
+pub use builder::ThumbnailerBuilder;
+pub use contact_sheet::{create_contact_sheet, ContactSheetOptions};
+pub use file_placeholder::{create_thumbnails_or_placeholder, generate_placeholder};
+pub use formats::registry::{FormatHandler, ReadSeek};
+pub use formats::VideoFrameSelector;
+#[cfg(feature = "icns")]
+pub use icon::write_icns;
+pub use icon::write_ico;
+/// The version of the image crate used by the thumbnailer
+pub use image;
+pub use image::imageops::FilterType;
+pub use overlay::OverlayPosition;
+pub use perceptual_hash::{HashAlg, ImageHash};
+pub use preview::{create_preview, create_preview_with_info, PreviewInfo, PreviewSource};
+pub use progress::{Progress, Stage};
+pub use resize::{CropStrategy, ResizeMode};
+pub use save::SaveOptions;
+pub use size::{ScaledSize, SizeConstraint, ThumbnailSize};
+pub use sprite_sheet::{SpriteCell, SpriteSheet};
+pub use stats::{GenerationStats, SizeStats};
+#[cfg(feature = "ffmpeg")]
+pub use storyboard::generate_storyboard;
+pub use storyboard::{Storyboard, StoryboardCue, StoryboardOptions};
+pub use thumbnail_iter::ThumbnailIter;
+pub use thumbnailer::Thumbnailer;
+pub use transform::{FlipDirection, Rotation};
+pub use trim::TrimMode;
+use write_options::jfif_density;
+#[cfg(feature = "avif-encode")]
+pub use write_options::AvifOptions;
+pub use write_options::{Dithering, JpegOptions, PaletteOptions, PngOptions};
 
+mod animated;
+#[cfg(feature = "tokio")]
+mod async_api;
+mod badge;
+mod base_image;
+mod batch;
+mod builder;
+mod color;
+mod compare;
+mod contact_sheet;
 pub mod error;
+mod file_placeholder;
 mod formats;
+mod icon;
+mod mask;
+mod overlay;
+mod perceptual_hash;
+#[cfg(feature = "blurhash")]
+mod placeholder;
+mod preview;
+mod progress;
+mod quantize;
+mod resize;
+mod save;
 mod size;
+mod sprite_sheet;
+mod stats;
+mod storyboard;
+mod thumbnail_iter;
+mod thumbnailer;
+mod trace;
+mod transform;
+mod trim;
 pub(crate) mod utils;
+mod write_options;
+#[cfg(feature = "xdg")]
+pub mod xdg;
+
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+const DEFAULT_WEBP_QUALITY: u8 = 80;
+// thumbnails are small so encoding time matters more than the last bit of compression
+#[cfg(feature = "avif-encode")]
+const DEFAULT_AVIF_QUALITY: u8 = 60;
+#[cfg(feature = "avif-encode")]
+const DEFAULT_AVIF_SPEED: u8 = 8;
+const DEFAULT_MATTE: Rgb<u8> = Rgb([255, 255, 255]);
+// lower qualities look worse than a smaller image
+const MIN_BOUNDED_QUALITY: u8 = 10;
+const BOUNDED_DOWNSCALE_FACTOR: f32 = 0.75;
+const MAX_BOUNDED_DOWNSCALES: usize = 8;
 
 #[derive(Clone, Debug)]
 pub struct Thumbnail {
     inner: DynamicImage,
+    requested_size: ThumbnailSize,
+    matte: Rgb<u8>,
+    deterministic: bool,
+    dpi: Option<(f32, f32)>,
+    shape: Shape,
 }
 
 impl Thumbnail {
+    /// Writes the bytes of the image in the given format. The image is converted to a
+    /// color type the format supports. Jpeg and webp are written with the default quality.
+    pub fn write_to<W: Write + Seek>(self, writer: &mut W, format: ImageFormat) -> ThumbResult<()> {
+        match format {
+            ImageFormat::Png if self.deterministic || self.dpi.is_some() => {
+                self.write_png_with_options(writer, PngOptions::default())
+            }
+            ImageFormat::Jpeg => self.write_jpeg_with_quality(writer, DEFAULT_JPEG_QUALITY),
+            ImageFormat::WebP => self.write_webp_with_quality(writer, DEFAULT_WEBP_QUALITY),
+            #[cfg(feature = "avif-encode")]
+            ImageFormat::Avif => self.write_avif(writer),
+            _ if !format.writing_enabled() => Err(ThumbError::UnsupportedOutputFormat(
+                ImageFormatHint::Exact(format),
+            )),
+            _ => encode_traced(writer, format.extensions_str()[0], self.size(), |writer| {
+                let image = match format {
+                    ImageFormat::Pnm => DynamicImage::ImageRgb8(self.flatten()),
+                    ImageFormat::Farbfeld => DynamicImage::ImageRgba16(self.inner.into_rgba16()),
+                    ImageFormat::Hdr => DynamicImage::ImageRgb32F(
+                        DynamicImage::ImageRgb8(self.flatten()).into_rgb32f(),
+                    ),
+                    ImageFormat::OpenExr => DynamicImage::ImageRgba32F(self.inner.into_rgba32f()),
+                    _ => DynamicImage::ImageRgba8(self.inner.into_rgba8()),
+                };
+                image.write_to(writer, format)?;
+
+                Ok(())
+            }),
+        }
+    }
+
+    /// Returns the bytes of the image encoded in the given format like [Thumbnail::write_to]
+    pub fn encode(self, format: ImageFormat) -> ThumbResult<Vec<u8>> {
+        let mut buf = Cursor::new(Vec::new());
+        self.write_to(&mut buf, format)?;
+
+        Ok(buf.into_inner())
+    }
+
+    /// Returns the bytes of the image encoded in the given format with at most `max_bytes`.
+    /// Jpeg, webp and avif images are written with the highest quality down to 10 that fits,
+    /// which is found with a binary search below the default quality. If even the lowest
+    /// quality or a lossless format is too large, the image is scaled down by a quarter until
+    /// it fits. Returns [ThumbError::OutputTooLarge] with the size of the smallest attempt
+    /// if the image still doesn't fit after scaling it down eight times.
+    pub fn encode_bounded(self, format: ImageFormat, max_bytes: usize) -> ThumbResult<Vec<u8>> {
+        let default_quality = match format {
+            ImageFormat::Jpeg => Some(DEFAULT_JPEG_QUALITY),
+            #[cfg(feature = "libwebp")]
+            ImageFormat::WebP => Some(DEFAULT_WEBP_QUALITY),
+            #[cfg(feature = "avif-encode")]
+            ImageFormat::Avif => Some(DEFAULT_AVIF_QUALITY),
+            _ => None,
+        };
+        let mut thumbnail = self;
+        let mut smallest = usize::MAX;
+
+        for step in 0..=MAX_BOUNDED_DOWNSCALES {
+            if step > 0 {
+                let (width, height) = thumbnail.size();
+                if width <= 1 && height <= 1 {
+                    break;
+                }
+                let scale = |dimension: u32| {
+                    ((dimension as f32 * BOUNDED_DOWNSCALE_FACTOR).round() as u32).max(1)
+                };
+                thumbnail.inner =
+                    thumbnail
+                        .inner
+                        .resize_exact(scale(width), scale(height), FilterType::Lanczos3);
+                thumbnail.dpi = thumbnail.dpi.map(|(x, y)| {
+                    (
+                        x * scale(width) as f32 / width as f32,
+                        y * scale(height) as f32 / height as f32,
+                    )
+                });
+            }
+            let mut attempt = |quality: Option<u8>| -> ThumbResult<Option<Vec<u8>>> {
+                let encoded = thumbnail.clone().encode_with_quality(format, quality)?;
+                smallest = smallest.min(encoded.len());
+                Ok(Some(encoded).filter(|encoded| encoded.len() <= max_bytes))
+            };
+            if let Some(encoded) = attempt(default_quality)? {
+                return Ok(encoded);
+            }
+            let default_quality = match default_quality {
+                Some(quality) if quality > MIN_BOUNDED_QUALITY => quality,
+                _ => continue,
+            };
+            let mut best = match attempt(Some(MIN_BOUNDED_QUALITY))? {
+                Some(encoded) => encoded,
+                None => continue,
+            };
+            // the highest quality that fits is between the lowest and the default quality
+            let (mut low, mut high) = (MIN_BOUNDED_QUALITY + 1, default_quality - 1);
+            while low <= high {
+                let quality = low + (high - low) / 2;
+                match attempt(Some(quality))? {
+                    Some(encoded) => {
+                        best = encoded;
+                        low = quality + 1;
+                    }
+                    None => high = quality - 1,
+                }
+            }
+
+            return Ok(best);
+        }
+
+        Err(ThumbError::OutputTooLarge {
+            size: smallest,
+            limit: max_bytes,
+        })
+    }
+
+    /// Encodes the image with the given quality for lossy formats or the default options
+    fn encode_with_quality(self, format: ImageFormat, quality: Option<u8>) -> ThumbResult<Vec<u8>> {
+        let mut buf = Cursor::new(Vec::new());
+        match (format, quality) {
+            (ImageFormat::Jpeg, Some(quality)) => {
+                self.write_jpeg_with_quality(&mut buf, quality)?
+            }
+            (ImageFormat::WebP, Some(quality)) => {
+                self.write_webp_with_quality(&mut buf, quality)?
+            }
+            #[cfg(feature = "avif-encode")]
+            (ImageFormat::Avif, Some(quality)) => self.write_avif_with_options(
+                &mut buf,
+                AvifOptions {
+                    quality,
+                    ..AvifOptions::default()
+                },
+            )?,
+            _ => self.write_to(&mut buf, format)?,
+        }
+
+        Ok(buf.into_inner())
+    }
+
+    /// Writes the bytes of the image in the format described by the mime like [Thumbnail::write_to]
+    pub fn write_with_mime<W: Write + Seek>(self, writer: &mut W, mime: &Mime) -> ThumbResult<()> {
+        let format = ImageFormat::from_mime_type(mime.essence_str())
+            .ok_or_else(|| ThumbError::UnsupportedMime(mime.clone()))?;
+
+        self.write_to(writer, format)
+    }
+
     /// Writes the bytes of the image in a png format
     pub fn write_png<W: Write + Seek>(self, writer: &mut W) -> ThumbResult<()> {
-        let image = DynamicImage::ImageRgba8(self.inner.into_rgba8());
-        image.write_to(writer, ImageFormat::Png)?;
+        self.write_to(writer, ImageFormat::Png)
+    }
 
-        Ok(())
+    /// Writes the bytes of the image in a png format with the given encoder options.
+    /// The resolution is written to a pHYs chunk if it is known. With a palette the
+    /// colors are reduced to an indexed image whose transparency is written to a tRNS chunk.
+    pub fn write_png_with_options<W: Write + Seek>(
+        self,
+        writer: &mut W,
+        options: PngOptions,
+    ) -> ThumbResult<()> {
+        if let Some(palette) = &options.palette {
+            palette.validate()?;
+        }
+        encode_traced(writer, "png", self.size(), |writer| {
+            let (deterministic, dpi) = (self.deterministic, self.dpi);
+            let image = self.inner.into_rgba8();
+            if let Some(palette) = options.palette {
+                let indexed = quantize::quantize(&image, &palette, self.matte);
+                return write_options::write_indexed_png(
+                    writer,
+                    &indexed,
+                    options.compression,
+                    options.interlaced,
+                    dpi,
+                );
+            }
+            // the encoder of the image crate can't write the resolution
+            if options.interlaced || deterministic || dpi.is_some() {
+                return write_options::write_filtered_png(
+                    writer,
+                    &image,
+                    options.compression,
+                    options.interlaced,
+                    dpi,
+                );
+            }
+            let encoder =
+                PngEncoder::new_with_quality(writer, options.compression, PngFilterType::default());
+            DynamicImage::ImageRgba8(image).write_with_encoder(encoder)?;
+
+            Ok(())
+        })
     }
 
-    /// Writes the bytes of the image in a jpeg format
+    /// Writes the bytes of the image in a jpeg format with the default quality
     pub fn write_jpeg<W: Write + Seek>(self, writer: &mut W) -> ThumbResult<()> {
-        let image = DynamicImage::ImageRgb8(self.inner.into_rgb8());
-        image.write_to(writer, ImageFormat::Jpeg)?;
+        self.write_to(writer, ImageFormat::Jpeg)
+    }
+
+    /// Writes the bytes of the image in a jpeg format with the given quality (1-100)
+    pub fn write_jpeg_with_quality<W: Write + Seek>(
+        self,
+        writer: &mut W,
+        quality: u8,
+    ) -> ThumbResult<()> {
+        self.write_jpeg_with_options(
+            writer,
+            JpegOptions {
+                quality,
+                ..JpegOptions::default()
+            },
+        )
+    }
+
+    /// Writes the bytes of the image in a jpeg format with the given encoder options.
+    /// The resolution is written to the JFIF segment if it is known.
+    pub fn write_jpeg_with_options<W: Write + Seek>(
+        self,
+        writer: &mut W,
+        options: JpegOptions,
+    ) -> ThumbResult<()> {
+        let quality = options.quality;
+        if quality == 0 || quality > 100 {
+            return Err(ThumbError::InvalidQuality(quality));
+        }
+        encode_traced(writer, "jpeg", self.size(), |writer| {
+            let (deterministic, dpi) = (self.deterministic, self.dpi);
+            let image = self.flatten();
+            if options.progressive || deterministic {
+                return write_options::write_explicit_jpeg(
+                    writer,
+                    &image,
+                    quality,
+                    options.progressive,
+                    dpi,
+                );
+            }
+            let mut encoder = JpegEncoder::new_with_quality(writer, quality);
+            if let Some((x, y)) = dpi {
+                encoder.set_pixel_density(PixelDensity {
+                    density: (jfif_density(x), jfif_density(y)),
+                    unit: PixelDensityUnit::Inches,
+                });
+            }
+            DynamicImage::ImageRgb8(image).write_with_encoder(encoder)?;
+
+            Ok(())
+        })
+    }
+
+    /// Writes the bytes of the image in a jpeg format with the default quality.
+    /// Transparent areas are blended onto the given background color.
+    pub fn write_jpeg_with_background<W: Write + Seek>(
+        mut self,
+        writer: &mut W,
+        background: Rgb<u8>,
+    ) -> ThumbResult<()> {
+        self.matte = background;
+        self.write_jpeg(writer)
+    }
+
+    /// Writes the bytes of the image in a lossy webp format with the default quality
+    pub fn write_webp<W: Write + Seek>(self, writer: &mut W) -> ThumbResult<()> {
+        self.write_to(writer, ImageFormat::WebP)
+    }
+
+    /// Writes the bytes of the image in a lossy webp format with the given quality (0-100).
+    /// The alpha channel is preserved if the source image has one.
+    /// Without the `libwebp` feature the image is written lossless and the quality is ignored.
+    #[cfg_attr(not(feature = "libwebp"), allow(unused_variables))]
+    pub fn write_webp_with_quality<W: Write + Seek>(
+        self,
+        writer: &mut W,
+        quality: u8,
+    ) -> ThumbResult<()> {
+        encode_traced(writer, "webp", self.size(), |writer| {
+            let image = if self.inner.color().has_alpha() {
+                DynamicImage::ImageRgba8(self.inner.into_rgba8())
+            } else {
+                DynamicImage::ImageRgb8(self.inner.into_rgb8())
+            };
+            #[cfg(feature = "libwebp")]
+            {
+                let encoder =
+                    WebpEncoder::from_image(&image).map_err(|e| ThumbError::encode("webp", e))?;
+                let memory = encoder.encode(quality.min(100) as f32);
+                writer.write_all(&memory)?;
+            }
+            #[cfg(not(feature = "libwebp"))]
+            image.write_to(writer, ImageFormat::WebP)?;
+
+            Ok(())
+        })
+    }
+
+    /// Writes the bytes of the image in an avif format with the default options
+    #[cfg(feature = "avif-encode")]
+    pub fn write_avif<W: Write + Seek>(self, writer: &mut W) -> ThumbResult<()> {
+        self.write_avif_with_options(writer, AvifOptions::default())
+    }
+
+    /// Writes the bytes of the image in an avif format with the given encoder options
+    #[cfg(feature = "avif-encode")]
+    pub fn write_avif_with_options<W: Write + Seek>(
+        self,
+        writer: &mut W,
+        options: AvifOptions,
+    ) -> ThumbResult<()> {
+        if options.quality == 0 || options.quality > 100 {
+            return Err(ThumbError::InvalidQuality(options.quality));
+        }
+        if options.speed == 0 || options.speed > 10 {
+            return Err(ThumbError::InvalidOptions(format!(
+                "invalid avif encoder speed {}",
+                options.speed
+            )));
+        }
+        encode_traced(writer, "avif", self.size(), |writer| {
+            let image = if options.alpha && self.inner.color().has_alpha() {
+                DynamicImage::ImageRgba8(self.inner.into_rgba8())
+            } else {
+                DynamicImage::ImageRgb8(self.flatten())
+            };
+            image.write_with_encoder(AvifEncoder::new_with_speed_quality(
+                writer,
+                options.speed,
+                options.quality,
+            ))?;
 
-        Ok(())
+            Ok(())
+        })
+    }
+
+    /// Saves the thumbnail to the file at the path in the format inferred from its extension.
+    /// The thumbnail is written to a temporary file in the same directory that is renamed
+    /// into place, so readers never see a partially written file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> ThumbResult<()> {
+        self.save_with_options(path, SaveOptions::default())
+    }
+
+    /// Saves the thumbnail to the file at the path in the given format like [Thumbnail::save]
+    pub fn save_with_format<P: AsRef<Path>>(
+        &self,
+        path: P,
+        format: ImageFormat,
+    ) -> ThumbResult<()> {
+        self.save_with_options(
+            path,
+            SaveOptions {
+                format: Some(format),
+                ..SaveOptions::default()
+            },
+        )
+    }
+
+    /// Saves the thumbnail to the file at the path with the given options like [Thumbnail::save]
+    pub fn save_with_options<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: SaveOptions,
+    ) -> ThumbResult<()> {
+        let path = path.as_ref();
+        let format = options.format_for(path)?;
+
+        save::write_atomic(path, options.create_dirs, |writer| {
+            self.clone().write_to(writer, format)
+        })
+    }
+
+    /// Sets whether png and jpeg output is written by encoders with explicitly fixed
+    /// settings so that the same pixels always result in the same bytes.
+    /// See [ThumbnailerBuilder::deterministic] for the guarantees.
+    pub fn deterministic(self, deterministic: bool) -> Self {
+        Self {
+            deterministic,
+            ..self
+        }
     }
 
     /// Returns the size of the thumbnail as width,  height
     pub fn size(&self) -> (u32, u32) {
         self.inner.dimensions()
     }
+
+    /// Returns the horizontal and vertical resolution in dots per inch. It is the resolution
+    /// stored in the source scaled by the factor the source was resized with, so that the
+    /// thumbnail has the same physical size. Returns `None` if the source doesn't store
+    /// a resolution.
+    pub fn dpi(&self) -> Option<(f32, f32)> {
+        self.dpi
+    }
+
+    /// Returns the size that was requested when creating the thumbnail.
+    /// The actual dimensions returned by [Thumbnail::size] can differ from it
+    /// depending on the aspect ratio of the source.
+    pub fn requested_size(&self) -> ThumbnailSize {
+        self.requested_size
+    }
+
+    /// Returns the most common color of the thumbnail. Similar colors are grouped so that
+    /// a photo of mostly sky returns a blue instead of the mean of all colors.
+    /// Fully transparent pixels are ignored and the matte color is returned if all
+    /// pixels are transparent.
+    pub fn dominant_color(&self) -> Rgb<u8> {
+        self.palette(1).pop().unwrap_or(self.matte)
+    }
+
+    /// Returns the mean color of all pixels that aren't fully transparent or the
+    /// matte color if all pixels are transparent
+    pub fn average_color(&self) -> Rgb<u8> {
+        color::average_color(&self.inner).unwrap_or(self.matte)
+    }
+
+    /// Returns up to `n` distinct colors ordered from the most to the least common one.
+    /// Fully transparent pixels are ignored.
+    pub fn palette(&self, n: usize) -> Vec<Rgb<u8>> {
+        color::palette(&self.inner, n)
+    }
+
+    /// Returns the perceptual hash of the thumbnail. The hash is computed from a grayscale
+    /// copy scaled to 32x32 pixels, so thumbnails of different sizes created from the same
+    /// source have the same hash. Transparent areas are blended onto the matte color.
+    pub fn perceptual_hash(&self, alg: HashAlg) -> ImageHash {
+        perceptual_hash::perceptual_hash(&self.inner, self.matte, alg)
+    }
+
+    /// Returns the blurhash of the thumbnail with the given number of components
+    /// on each axis (1-9). The hash is computed from a copy scaled down to 64 pixels.
+    #[cfg(feature = "blurhash")]
+    pub fn blurhash(&self, x_components: u32, y_components: u32) -> ThumbResult<String> {
+        placeholder::blurhash(&self.inner, x_components, y_components)
+    }
+
+    /// Returns the thumbhash of the thumbnail. The hash is computed from a copy
+    /// scaled down to 64 pixels.
+    #[cfg(feature = "blurhash")]
+    pub fn thumbhash(&self) -> Vec<u8> {
+        placeholder::thumbhash(&self.inner)
+    }
+
+    /// Returns whether the thumbnail has the same width and height as the other one
+    pub fn dimensions_eq(&self, other: &Thumbnail) -> bool {
+        self.size() == other.size()
+    }
+
+    /// Returns whether the thumbnails have the same dimensions and pixels regardless of the
+    /// format they were decoded from. Thumbnails of different color types are compared with
+    /// 16 bits per channel.
+    pub fn pixel_eq(&self, other: &Thumbnail) -> bool {
+        compare::pixel_eq(&self.inner, &other.inner)
+    }
+
+    /// Returns the structural similarity (SSIM) of the thumbnails between 0 and 1, where 1
+    /// means that they are identical. The similarity is computed on copies scaled down to
+    /// 256 pixels, so small encoding artifacts barely matter and tests of golden thumbnails
+    /// can assert a similarity above e.g. 0.98. If the dimensions differ, the thumbnail
+    /// with fewer pixels is first resized to the dimensions of the other one.
+    pub fn similarity(&self, other: &Thumbnail) -> f64 {
+        compare::similarity(&self.inner, &other.inner)
+    }
+
+    /// Returns an image that visualizes the differences to the other thumbnail. Equal pixels
+    /// are a dimmed grayscale of this thumbnail and differences are amplified 8 times in the
+    /// color channel they occur in, while differences of the alpha channel are white. If the
+    /// dimensions differ, the thumbnail with fewer pixels is first resized to the dimensions
+    /// of the other one.
+    pub fn diff_image(&self, other: &Thumbnail) -> Thumbnail {
+        Thumbnail::from(DynamicImage::ImageRgb8(compare::diff_image(
+            &self.inner,
+            &other.inner,
+        )))
+    }
+
+    /// Blends the overlay onto the thumbnail like a watermark. The alpha channel of the
+    /// overlay is multiplied by the opacity (0-1) and overlays larger than the thumbnail
+    /// are scaled down to fit.
+    pub fn overlay(&mut self, overlay: &DynamicImage, position: OverlayPosition, opacity: f32) {
+        self.overlay_with_margin(overlay, position, opacity, 0)
+    }
+
+    /// Blends the overlay onto the thumbnail like [Thumbnail::overlay] keeping the given
+    /// distance in pixels to the edges for positions in the corners
+    pub fn overlay_with_margin(
+        &mut self,
+        overlay: &DynamicImage,
+        position: OverlayPosition,
+        opacity: f32,
+        margin: u32,
+    ) {
+        let image = std::mem::replace(&mut self.inner, DynamicImage::new_rgba8(0, 0));
+        self.inner = overlay::apply_overlay(image, overlay, position, opacity, margin);
+    }
+
+    /// Blurs the thumbnail with a gaussian blur of the given sigma.
+    /// Sigmas that aren't positive leave the thumbnail unchanged.
+    pub fn blur(self, sigma: f32) -> Self {
+        self.transform(Transform::Blur(sigma))
+    }
+
+    /// Replaces blocks of the given size in pixels with a single color. A block size
+    /// of 0 leaves the thumbnail unchanged and larger blocks than the thumbnail
+    /// turn it into a single color.
+    pub fn pixelate(self, block_size: u32) -> Self {
+        self.transform(Transform::Pixelate(block_size))
+    }
+
+    /// Converts the thumbnail to grayscale keeping the alpha channel
+    pub fn grayscale(self) -> Self {
+        self.transform(Transform::Grayscale)
+    }
+
+    /// Adds the value to all color channels of the thumbnail.
+    /// Negative values darken the thumbnail.
+    pub fn brightness(self, value: i32) -> Self {
+        self.transform(Transform::Brightness(value))
+    }
+
+    /// Rounds the corners of the thumbnail with the given radius in pixels. The corners
+    /// become transparent with anti-aliased edges and are filled with the matte color in
+    /// formats without an alpha channel. Radii larger than half of the shorter edge are
+    /// clamped to it which turns the thumbnail into a circle or stadium.
+    pub fn round_corners(self, radius: u32) -> Self {
+        self.transform(Transform::Mask(Shape::Rounded(radius)))
+    }
+
+    /// Makes everything outside of the largest circle centered in the thumbnail
+    /// transparent like [Thumbnail::round_corners]
+    pub fn circle_mask(self) -> Self {
+        self.transform(Transform::Mask(Shape::Circle))
+    }
+
+    /// Draws a border of the given width in pixels inside the edges of the thumbnail.
+    /// The border follows the rounded corners or circle of a previous mask.
+    pub fn border(self, width: u32, color: Rgba<u8>) -> Self {
+        let shape = self.shape;
+        self.transform(Transform::Border {
+            width,
+            color,
+            shape,
+        })
+    }
+
+    fn transform(self, transform: Transform) -> Self {
+        Self {
+            inner: transform.apply(self.inner),
+            shape: match transform {
+                Transform::Mask(shape) => shape,
+                _ => self.shape,
+            },
+            ..self
+        }
+    }
+
+    /// Blends the image onto the matte color for formats without an alpha channel
+    fn flatten(self) -> RgbImage {
+        if !self.inner.color().has_alpha() {
+            return self.inner.into_rgb8();
+        }
+        let Rgb(matte) = self.matte;
+        if is_high_precision(&self.inner) {
+            // blending before quantizing avoids rounding twice
+            let image = self.inner.into_rgba16();
+            return RgbImage::from_fn(image.width(), image.height(), |x, y| {
+                let pixel = image.get_pixel(x, y);
+                let alpha = pixel[3] as u64;
+                Rgb([0, 1, 2].map(|c| {
+                    let blended = pixel[c] as u64 * alpha + matte[c] as u64 * 257 * (65535 - alpha);
+                    ((blended + 65535 * 257 / 2) / (65535 * 257)) as u8
+                }))
+            });
+        }
+        let image = self.inner.into_rgba8();
+
+        RgbImage::from_fn(image.width(), image.height(), |x, y| {
+            let pixel = image.get_pixel(x, y);
+            let alpha = pixel[3] as u32;
+            Rgb([0, 1, 2].map(|c| {
+                ((pixel[c] as u32 * alpha + matte[c] as u32 * (255 - alpha) + 127) / 255) as u8
+            }))
+        })
+    }
+
+    /// Returns a reference to the image of the thumbnail
+    pub fn as_image(&self) -> &DynamicImage {
+        &self.inner
+    }
+
+    /// Returns the image of the thumbnail
+    pub fn into_inner(self) -> DynamicImage {
+        self.inner
+    }
+}
+
+impl From<DynamicImage> for Thumbnail {
+    /// Creates a thumbnail from an image. The requested size is set to the images dimensions.
+    fn from(image: DynamicImage) -> Self {
+        let (width, height) = image.dimensions();
+        let requested_size = ThumbnailSize::from_dimensions(width, height)
+            .unwrap_or(ThumbnailSize::Custom { width, height });
+
+        Self {
+            inner: image,
+            requested_size,
+            matte: DEFAULT_MATTE,
+            deterministic: false,
+            dpi: None,
+            shape: Shape::Rectangle,
+        }
+    }
 }
 
 /// Creates thumbnails of the requested sizes for the given reader providing the content as bytes and
-/// the mime describing the contents type
+/// the mime describing the contents type.
+/// The thumbnails are returned in the same order as the requested sizes. Sizes that create the
+/// same thumbnail as an earlier size are only resized once and get a copy of it.
 pub fn create_thumbnails<R: BufRead + Seek, I: IntoIterator<Item = ThumbnailSize>>(
     reader: R,
     mime: Mime,
     sizes: I,
 ) -> ThumbResult<Vec<Thumbnail>> {
-    let image = get_base_image(reader, mime)?;
-    let sizes: Vec<ThumbnailSize> = sizes.into_iter().collect();
-    let thumbnails = resize_images(image, &sizes)
-        .into_iter()
-        .map(|image| Thumbnail { inner: image })
-        .collect();
-
-    Ok(thumbnails)
+    Thumbnailer::shared().create(reader, mime, sizes)
 }
 
-fn resize_images(image: DynamicImage, sizes: &[ThumbnailSize]) -> Vec<DynamicImage> {
-    sizes
-        .into_par_iter()
-        .map(|size| {
-            let (width, height) = size.dimensions();
-            image.resize(width, height, FilterType::Lanczos3)
-        })
-        .collect()
+/// Creates thumbnails of the requested sizes like [create_thumbnails] and returns them with
+/// the information about the source that was collected while decoding it
+pub fn create_thumbnails_with_meta<R: BufRead + Seek, I: IntoIterator<Item = ThumbnailSize>>(
+    reader: R,
+    mime: Mime,
+    sizes: I,
+) -> ThumbResult<(SourceInfo, Vec<Thumbnail>)> {
+    Thumbnailer::shared().create_with_meta(reader, mime, sizes)
+}
+
+/// Decodes the content once and returns an iterator that resizes the requested sizes one at a
+/// time as it is advanced, largest first, like [ThumbnailerBuilder::run_iter]. Only the
+/// decoded image and the thumbnail that was returned last need to be in memory at once.
+///
+/// Example:
+/// ```
+/// use thumbnailer::{create_thumbnails_iter, ThumbnailSize};
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let file = File::open("tests/assets/test.png").unwrap();
+/// let sizes = [ThumbnailSize::Small, ThumbnailSize::Large];
+/// for thumbnail in create_thumbnails_iter(BufReader::new(file), mime::IMAGE_PNG, sizes).unwrap() {
+///     let (size, thumbnail) = thumbnail.unwrap();
+///     let png = thumbnail.encode(thumbnailer::image::ImageFormat::Png).unwrap();
+/// }
+/// ```
+pub fn create_thumbnails_iter<R: BufRead + Seek, I: IntoIterator<Item = ThumbnailSize>>(
+    reader: R,
+    mime: Mime,
+    sizes: I,
+) -> ThumbResult<ThumbnailIter> {
+    Thumbnailer::shared().create_iter(reader, mime, sizes)
+}
+
+/// Creates thumbnails of the requested sizes like [create_thumbnails] but returns the result
+/// of each size separately, in the same order as the requested sizes. A size that fails to
+/// resize doesn't discard the others. Errors that affect all sizes like decoding errors are
+/// returned for the whole call.
+pub fn create_thumbnails_individual<R: BufRead + Seek, I: IntoIterator<Item = ThumbnailSize>>(
+    reader: R,
+    mime: Mime,
+    sizes: I,
+) -> ThumbResult<Vec<(ThumbnailSize, ThumbResult<Thumbnail>)>> {
+    Thumbnailer::shared().create_individual(reader, mime, sizes)
+}
+
+/// Creates thumbnails of the requested sizes for multiple frames of a video that are
+/// selected by timestamps, percentages or indices. The video is only read once and the
+/// results are returned in the same order as the selectors.
+#[cfg(feature = "ffmpeg")]
+pub fn create_video_thumbnails<R: BufRead + Seek, I: IntoIterator<Item = ThumbnailSize>>(
+    reader: R,
+    mime: Mime,
+    selectors: &[VideoFrameSelector],
+    sizes: I,
+) -> ThumbResult<Vec<(VideoFrameSelector, Vec<Thumbnail>)>> {
+    Thumbnailer::shared().create_video_thumbnails(reader, mime, selectors, sizes)
+}
+
+/// Creates thumbnails of the requested sizes like [create_thumbnails] from content in memory
+pub fn create_thumbnails_from_bytes<I: IntoIterator<Item = ThumbnailSize>>(
+    bytes: &[u8],
+    mime: Mime,
+    sizes: I,
+) -> ThumbResult<Vec<Thumbnail>> {
+    create_thumbnails(Cursor::new(bytes), mime, sizes)
+}
+
+/// Creates thumbnails of the requested sizes like [create_thumbnails] from content in memory
+/// that is shared with other owners, e.g. an `Arc<[u8]>` or `bytes::Bytes`. The content
+/// isn't copied into another buffer and decoders that work on slices read it directly.
+///
+/// Example:
+/// ```
+/// use thumbnailer::{create_thumbnails_shared, ThumbnailSize};
+/// use std::sync::Arc;
+///
+/// let bytes: Arc<[u8]> = std::fs::read("tests/assets/test.png").unwrap().into();
+/// let thumbnails = create_thumbnails_shared(bytes.clone(), mime::IMAGE_PNG, [ThumbnailSize::Small]).unwrap();
+/// assert_eq!(thumbnails.len(), 1);
+/// ```
+pub fn create_thumbnails_shared<B, I>(bytes: B, mime: Mime, sizes: I) -> ThumbResult<Vec<Thumbnail>>
+where
+    B: AsRef<[u8]> + Send + Sync,
+    I: IntoIterator<Item = ThumbnailSize>,
+{
+    create_thumbnails(Cursor::new(bytes.as_ref()), mime, sizes)
+}
+
+/// Creates thumbnails of the requested sizes like [create_thumbnails] for a file.
+/// The media type is guessed from the extension of the file and detected from the
+/// content if the extension is unknown or doesn't match the content.
+/// Errors opening the file are returned as [ThumbError::Io] including the path.
+pub fn create_thumbnails_from_path<P: AsRef<Path>, I: IntoIterator<Item = ThumbnailSize>>(
+    path: P,
+    sizes: I,
+) -> ThumbResult<Vec<Thumbnail>> {
+    let (reader, mime) = open_path(path.as_ref())?;
+
+    Thumbnailer::shared_sniffing().create(reader, mime, sizes)
+}
+
+/// Opens the file and guesses its media type from the extension. Files without a known
+/// extension are octet streams whose type has to be detected from the content.
+pub(crate) fn open_path(path: &Path) -> ThumbResult<(BufReader<File>, Mime)> {
+    let file = File::open(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    let mime = mime_from_extension(path).unwrap_or(mime::APPLICATION_OCTET_STREAM);
+
+    Ok((BufReader::new(file), mime))
+}
+
+/// Creates thumbnails of the requested sizes like [create_thumbnails] for content of an
+/// unknown type. The type is detected from the content.
+pub fn create_thumbnails_guess<R: BufRead + Seek, I: IntoIterator<Item = ThumbnailSize>>(
+    reader: R,
+    sizes: I,
+) -> ThumbResult<Vec<Thumbnail>> {
+    Thumbnailer::shared_sniffing().create(reader, mime::APPLICATION_OCTET_STREAM, sizes)
+}
+
+/// Creates thumbnails of the requested sizes like [create_thumbnails] for a reader that can't seek.
+/// Returns [ThumbError::SeekRequired] for media types that can't be decoded without seeking.
+pub fn create_thumbnails_unseekable<R: BufRead, I: IntoIterator<Item = ThumbnailSize>>(
+    reader: R,
+    mime: Mime,
+    sizes: I,
+) -> ThumbResult<Vec<Thumbnail>> {
+    Thumbnailer::shared().create_unseekable(reader, mime, sizes)
+}
+
+/// Creates thumbnails of the requested sizes like [create_thumbnails] using the given filter
+/// for resizing
+pub fn create_thumbnails_with_filter<R: BufRead + Seek, I: IntoIterator<Item = ThumbnailSize>>(
+    reader: R,
+    mime: Mime,
+    sizes: I,
+    filter: FilterType,
+) -> ThumbResult<Vec<Thumbnail>> {
+    ThumbnailerBuilder::new()
+        .sizes(sizes)
+        .filter(filter)
+        .copy_duplicates(true)
+        .run(reader, mime)
 }
@@ -0,0 +1,109 @@
+use crate::resize::is_high_precision;
+use image::{DynamicImage, Rgba};
+
+/// The outline of a thumbnail that masks and borders follow
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Shape {
+    #[default]
+    Rectangle,
+
+    /// A rectangle with corners of the given radius in pixels. Radii larger than half
+    /// of the shorter edge are clamped to it which results in a circle or stadium.
+    Rounded(u32),
+
+    /// The largest circle centered in the image
+    Circle,
+}
+
+impl Shape {
+    /// Returns the signed distance of the center of the pixel to the outline,
+    /// which is negative inside the shape
+    fn distance(&self, x: u32, y: u32, width: u32, height: u32) -> f32 {
+        let (half_width, half_height) = (width as f32 / 2.0, height as f32 / 2.0);
+        let dx = (x as f32 + 0.5 - half_width).abs();
+        let dy = (y as f32 + 0.5 - half_height).abs();
+        let radius = match *self {
+            Shape::Rectangle => 0.0,
+            Shape::Rounded(radius) => (radius as f32).min(half_width.min(half_height)),
+            Shape::Circle => return dx.hypot(dy) - half_width.min(half_height),
+        };
+        // the distance to the rectangle that is inset by the radius
+        let qx = dx - (half_width - radius);
+        let qy = dy - (half_height - radius);
+
+        qx.max(0.0).hypot(qy.max(0.0)) + qx.max(qy).min(0.0) - radius
+    }
+
+    /// Returns the fraction of the pixel that is covered by the shape shrunk by the inset
+    fn coverage(&self, x: u32, y: u32, width: u32, height: u32, inset: f32) -> f32 {
+        (0.5 - self.distance(x, y, width, height) - inset).clamp(0.0, 1.0)
+    }
+}
+
+/// Makes the pixels outside of the shape transparent. Pixels on the outline keep
+/// the fraction of their alpha that is covered by the shape for anti-aliased edges.
+pub(crate) fn mask(image: DynamicImage, shape: Shape) -> DynamicImage {
+    map_pixels(image, |pixel, x, y, width, height| {
+        pixel[3] *= shape.coverage(x, y, width, height, 0.0);
+    })
+}
+
+/// Draws a border of the given width inside the outline of the shape. The border
+/// replaces the image where it covers it and the outside of the shape stays unchanged.
+pub(crate) fn border(
+    image: DynamicImage,
+    shape: Shape,
+    width: u32,
+    color: Rgba<u8>,
+) -> DynamicImage {
+    if width == 0 {
+        return image;
+    }
+    let [red, green, blue, alpha] = color.0.map(|c| c as f32 / 255.0);
+    map_pixels(image, |pixel, x, y, image_width, image_height| {
+        let outer = shape.coverage(x, y, image_width, image_height, 0.0);
+        let inner = shape.coverage(x, y, image_width, image_height, width as f32);
+        let stroke = outer - inner;
+        if stroke <= 0.0 {
+            return;
+        }
+        // the alpha of masked images already contains the coverage of the outline,
+        // so it is divided out to get the alpha of the content under the border
+        let content = if outer > 0.0 {
+            (pixel[3] / outer).min(1.0)
+        } else {
+            0.0
+        };
+        let under = (1.0 - alpha) * content;
+        let result = stroke * (alpha + under) + inner * content;
+        if result > 0.0 {
+            for (channel, border) in [red, green, blue].iter().enumerate() {
+                let premultiplied = stroke * (alpha * border + under * pixel[channel])
+                    + inner * content * pixel[channel];
+                pixel[channel] = premultiplied / result;
+            }
+        }
+        pixel[3] = result;
+    })
+}
+
+/// Calls the function with every pixel as straight alpha floats and its position.
+/// Images with more than 8 bits per channel keep 16 bits of precision.
+fn map_pixels<F: Fn(&mut Rgba<f32>, u32, u32, u32, u32)>(
+    image: DynamicImage,
+    f: F,
+) -> DynamicImage {
+    let high_precision = is_high_precision(&image);
+    let mut buffer = image.into_rgba32f();
+    let (width, height) = buffer.dimensions();
+    for (x, y, pixel) in buffer.enumerate_pixels_mut() {
+        f(pixel, x, y, width, height);
+    }
+    let image = DynamicImage::ImageRgba32F(buffer);
+
+    if high_precision {
+        DynamicImage::ImageRgba16(image.into_rgba16())
+    } else {
+        DynamicImage::ImageRgba8(image.into_rgba8())
+    }
+}
@@ -0,0 +1,128 @@
+use crate::resize::resize_image;
+use crate::{FilterType, ResizeMode};
+use image::{DynamicImage, GenericImageView, Rgba};
+use std::sync::Arc;
+
+/// The position of an overlay on a thumbnail
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverlayPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+
+    /// Centered on the thumbnail ignoring the margin
+    Center,
+
+    /// The top left corner of the overlay is placed at the given coordinates
+    /// ignoring the margin. Parts outside of the thumbnail are cut off.
+    Absolute(u32, u32),
+}
+
+/// An image that is blended onto thumbnails
+#[derive(Clone, Debug)]
+pub(crate) struct Overlay {
+    pub image: Arc<DynamicImage>,
+    pub position: OverlayPosition,
+    pub opacity: f32,
+    pub margin: u32,
+}
+
+impl Overlay {
+    /// Returns whether the opacity is between 0 and 1
+    pub fn is_valid(&self) -> bool {
+        (0.0..=1.0).contains(&self.opacity)
+    }
+
+    /// Blends the overlay onto the image
+    pub fn apply(&self, image: DynamicImage) -> DynamicImage {
+        apply_overlay(image, &self.image, self.position, self.opacity, self.margin)
+    }
+}
+
+/// Blends the overlay onto the image. Overlays that don't fit into the image
+/// within the margin are scaled down.
+pub(crate) fn apply_overlay(
+    image: DynamicImage,
+    overlay: &DynamicImage,
+    position: OverlayPosition,
+    opacity: f32,
+    margin: u32,
+) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    let max_width = width.saturating_sub(margin.saturating_mul(2)).max(1);
+    let max_height = height.saturating_sub(margin.saturating_mul(2)).max(1);
+    let overlay = if overlay.width() > max_width || overlay.height() > max_height {
+        resize_image(
+            overlay,
+            max_width,
+            max_height,
+            ResizeMode::Fit,
+            FilterType::Triangle,
+        )
+        .into_rgba8()
+    } else {
+        overlay.to_rgba8()
+    };
+
+    let (overlay_width, overlay_height) = overlay.dimensions();
+    let right = width.saturating_sub(overlay_width.saturating_add(margin));
+    let bottom = height.saturating_sub(overlay_height.saturating_add(margin));
+    let (left, top) = match position {
+        OverlayPosition::TopLeft => (margin, margin),
+        OverlayPosition::TopRight => (right, margin),
+        OverlayPosition::BottomLeft => (margin, bottom),
+        OverlayPosition::BottomRight => (right, bottom),
+        OverlayPosition::Center => (
+            width.saturating_sub(overlay_width) / 2,
+            height.saturating_sub(overlay_height) / 2,
+        ),
+        OverlayPosition::Absolute(x, y) => (x, y),
+    };
+
+    let has_alpha = image.color().has_alpha();
+    let mut canvas = image.into_rgba8();
+    // NaN opacities of overlays applied directly to a thumbnail hide the overlay
+    let opacity = if opacity.is_nan() {
+        0.0
+    } else {
+        opacity.clamp(0.0, 1.0)
+    };
+    for (x, y, pixel) in overlay.enumerate_pixels() {
+        let (x, y) = (left as u64 + x as u64, top as u64 + y as u64);
+        if x < width as u64 && y < height as u64 {
+            let target = canvas.get_pixel_mut(x as u32, y as u32);
+            *target = blend(*target, *pixel, opacity);
+        }
+    }
+
+    if has_alpha {
+        DynamicImage::ImageRgba8(canvas)
+    } else {
+        DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(canvas).into_rgb8())
+    }
+}
+
+/// Composites the source pixel over the destination pixel with the source alpha
+/// multiplied by the opacity
+fn blend(destination: Rgba<u8>, source: Rgba<u8>, opacity: f32) -> Rgba<u8> {
+    let source_alpha = source[3] as f32 / 255.0 * opacity;
+    let destination_alpha = destination[3] as f32 / 255.0;
+    let alpha = source_alpha + destination_alpha * (1.0 - source_alpha);
+    if alpha <= 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let channel = |c: usize| {
+        let value = (source[c] as f32 * source_alpha
+            + destination[c] as f32 * destination_alpha * (1.0 - source_alpha))
+            / alpha;
+        value.round() as u8
+    };
+
+    Rgba([
+        channel(0),
+        channel(1),
+        channel(2),
+        (alpha * 255.0).round() as u8,
+    ])
+}
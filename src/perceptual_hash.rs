@@ -0,0 +1,237 @@
+use crate::error::ParseHashError;
+use crate::resize::resize_image;
+use crate::{FilterType, ResizeMode};
+use image::{DynamicImage, Rgb};
+use std::convert::TryInto;
+use std::f64::consts::PI;
+use std::fmt;
+
+/// The width and height of the grayscale copy all hashes are computed from
+/// so that they don't depend on the size of the thumbnail
+const HASH_SOURCE_SIZE: u32 = 32;
+
+/// The width and height of the grid of bits of a hash
+const HASH_GRID_SIZE: usize = 8;
+
+const BASE64_CHARACTERS: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The algorithm used to compute a perceptual hash
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HashAlg {
+    /// Compares each pixel to the mean brightness (aHash).
+    /// Fast but sensitive to changes in brightness and contrast.
+    Average,
+
+    /// Compares the brightness of horizontally neighbouring pixels (dHash)
+    Difference,
+
+    /// Compares the low frequencies of the discrete cosine transform to their
+    /// median (pHash). The most robust against recompression and small edits.
+    Perceptual,
+}
+
+impl HashAlg {
+    fn tag(&self) -> u8 {
+        match self {
+            HashAlg::Average => 0,
+            HashAlg::Difference => 1,
+            HashAlg::Perceptual => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(HashAlg::Average),
+            1 => Some(HashAlg::Difference),
+            2 => Some(HashAlg::Perceptual),
+            _ => None,
+        }
+    }
+}
+
+/// A 64 bit perceptual hash of an image. Similar images have hashes
+/// with a small hamming distance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ImageHash {
+    alg: HashAlg,
+    bits: u64,
+}
+
+impl ImageHash {
+    /// Returns the algorithm the hash was computed with
+    pub fn alg(&self) -> HashAlg {
+        self.alg
+    }
+
+    /// Returns the bits of the hash in row-major order starting at the most significant bit
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Returns the number of bits that differ between the hashes (0-64).
+    /// Only hashes computed with the same algorithm can be compared meaningfully.
+    pub fn hamming_distance(&self, other: &ImageHash) -> u32 {
+        (self.bits ^ other.bits).count_ones()
+    }
+
+    /// Encodes the algorithm and the bits of the hash as 12 base64 characters
+    pub fn to_base64(&self) -> String {
+        let mut bytes = [0u8; 9];
+        bytes[0] = self.alg.tag();
+        bytes[1..].copy_from_slice(&self.bits.to_be_bytes());
+
+        bytes
+            .chunks(3)
+            .flat_map(|chunk| {
+                let group = (chunk[0] as u32) << 16 | (chunk[1] as u32) << 8 | chunk[2] as u32;
+                [18, 12, 6, 0]
+                    .map(|shift| BASE64_CHARACTERS[(group >> shift & 0x3F) as usize] as char)
+            })
+            .collect()
+    }
+
+    /// Decodes a hash encoded with [ImageHash::to_base64]
+    pub fn from_base64(encoded: &str) -> Result<Self, ParseHashError> {
+        let error = || ParseHashError::new(encoded);
+        if encoded.len() != 12 {
+            return Err(error());
+        }
+        let values = encoded
+            .bytes()
+            .map(|c| BASE64_CHARACTERS.iter().position(|b| *b == c))
+            .collect::<Option<Vec<usize>>>()
+            .ok_or_else(error)?;
+        let bytes: Vec<u8> = values
+            .chunks(4)
+            .flat_map(|chunk| {
+                let group = chunk
+                    .iter()
+                    .fold(0u32, |group, value| group << 6 | *value as u32);
+                [16, 8, 0].map(|shift| (group >> shift) as u8)
+            })
+            .collect();
+        let alg = HashAlg::from_tag(bytes[0]).ok_or_else(error)?;
+        let bits = u64::from_be_bytes(bytes[1..].try_into().map_err(|_| error())?);
+
+        Ok(Self { alg, bits })
+    }
+}
+
+impl fmt::Display for ImageHash {
+    /// Writes the hash encoded like [ImageHash::to_base64]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_base64())
+    }
+}
+
+/// Computes the perceptual hash of the image from a grayscale copy scaled to 32x32 pixels.
+/// Transparent areas are blended onto the matte like in formats without an alpha channel.
+pub(crate) fn perceptual_hash(image: &DynamicImage, matte: Rgb<u8>, alg: HashAlg) -> ImageHash {
+    let source = grayscale(image, matte);
+    let bits = match alg {
+        HashAlg::Average => {
+            let pixels = downscale(&source, HASH_GRID_SIZE, HASH_GRID_SIZE);
+            let mean = pixels.iter().sum::<f64>() / pixels.len() as f64;
+            to_bits(pixels.iter().map(|pixel| *pixel > mean))
+        }
+        HashAlg::Difference => {
+            let pixels = downscale(&source, HASH_GRID_SIZE + 1, HASH_GRID_SIZE);
+            to_bits(pixels.chunks(HASH_GRID_SIZE + 1).flat_map(|row| {
+                row.windows(2)
+                    .map(|pair| pair[1] > pair[0])
+                    .collect::<Vec<bool>>()
+            }))
+        }
+        HashAlg::Perceptual => {
+            let coefficients = low_frequencies(&source);
+            // the dc coefficient is the mean brightness and would dominate the median
+            let mut sorted = coefficients[1..].to_vec();
+            sorted.sort_by(f64::total_cmp);
+            let median = sorted[sorted.len() / 2];
+            to_bits(coefficients.iter().map(|coefficient| *coefficient > median))
+        }
+    };
+
+    ImageHash { alg, bits }
+}
+
+/// Returns the brightness of the pixels of the image scaled to 32x32 pixels
+fn grayscale(image: &DynamicImage, Rgb(matte): Rgb<u8>) -> Vec<f64> {
+    let image = resize_image(
+        image,
+        HASH_SOURCE_SIZE,
+        HASH_SOURCE_SIZE,
+        ResizeMode::Exact,
+        FilterType::Triangle,
+    )
+    .into_rgba32f();
+
+    image
+        .pixels()
+        .map(|pixel| {
+            let alpha = pixel[3] as f64;
+            let [r, g, b] = [0, 1, 2]
+                .map(|c| pixel[c] as f64 * alpha + matte[c] as f64 / 255.0 * (1.0 - alpha));
+            // the luma weights of the image crate
+            0.2126 * r + 0.7152 * g + 0.0722 * b
+        })
+        .collect()
+}
+
+/// Scales the 32x32 brightness values down to the given size by averaging areas
+fn downscale(source: &[f64], width: usize, height: usize) -> Vec<f64> {
+    let size = HASH_SOURCE_SIZE as usize;
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let (top, bottom) = (y * size / height, ((y + 1) * size).div_ceil(height));
+        for x in 0..width {
+            let (left, right) = (x * size / width, ((x + 1) * size).div_ceil(width));
+            let mut sum = 0.0;
+            for row in top..bottom {
+                sum += source[row * size + left..row * size + right]
+                    .iter()
+                    .sum::<f64>();
+            }
+            pixels.push(sum / ((bottom - top) * (right - left)) as f64);
+        }
+    }
+
+    pixels
+}
+
+/// Returns the 8x8 lowest frequencies of the two dimensional dct of the 32x32 brightness values
+fn low_frequencies(source: &[f64]) -> Vec<f64> {
+    let size = HASH_SOURCE_SIZE as usize;
+    let cosines: Vec<f64> = (0..HASH_GRID_SIZE)
+        .flat_map(|u| {
+            (0..size).map(move |x| ((2 * x + 1) as f64 * u as f64 * PI / (2 * size) as f64).cos())
+        })
+        .collect();
+
+    // transforms the rows first and the columns of the result second
+    let mut rows = vec![0.0; size * HASH_GRID_SIZE];
+    for y in 0..size {
+        for u in 0..HASH_GRID_SIZE {
+            rows[y * HASH_GRID_SIZE + u] = (0..size)
+                .map(|x| source[y * size + x] * cosines[u * size + x])
+                .sum();
+        }
+    }
+    let mut coefficients = Vec::with_capacity(HASH_GRID_SIZE * HASH_GRID_SIZE);
+    for v in 0..HASH_GRID_SIZE {
+        for u in 0..HASH_GRID_SIZE {
+            coefficients.push(
+                (0..size)
+                    .map(|y| rows[y * HASH_GRID_SIZE + u] * cosines[v * size + y])
+                    .sum(),
+            );
+        }
+    }
+
+    coefficients
+}
+
+fn to_bits<I: Iterator<Item = bool>>(bits: I) -> u64 {
+    bits.fold(0, |hash, bit| hash << 1 | bit as u64)
+}
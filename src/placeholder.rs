@@ -0,0 +1,134 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::resize::resize_image;
+use crate::{FilterType, ResizeMode};
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use std::f64::consts::PI;
+
+/// The maximum width and height of the image the placeholders are computed from
+const MAX_PLACEHOLDER_SIZE: u32 = 64;
+
+const BASE83_CHARACTERS: &[u8] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+/// Returns the blurhash of the image with the given number of components on each axis (1-9).
+/// The rounding follows the reference implementation so that the hashes are identical.
+pub(crate) fn blurhash(
+    image: &DynamicImage,
+    x_components: u32,
+    y_components: u32,
+) -> ThumbResult<String> {
+    if !(1..=9).contains(&x_components) || !(1..=9).contains(&y_components) {
+        return Err(ThumbError::InvalidOptions(format!(
+            "blurhash components must be between 1 and 9 but are {}x{}",
+            x_components, y_components
+        )));
+    }
+    let image = downscale(image);
+    let (width, height) = image.dimensions();
+    let linear: Vec<[f64; 3]> = image
+        .pixels()
+        .map(|pixel| [0, 1, 2].map(|c| srgb_to_linear(pixel[c])))
+        .collect();
+
+    let mut factors = Vec::with_capacity((x_components * y_components) as usize);
+    for j in 0..y_components {
+        for i in 0..x_components {
+            let normalisation = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+            let mut factor = [0.0; 3];
+            for y in 0..height {
+                let basis_y = (PI * j as f64 * y as f64 / height as f64).cos();
+                for x in 0..width {
+                    let basis =
+                        normalisation * (PI * i as f64 * x as f64 / width as f64).cos() * basis_y;
+                    let pixel = linear[(y * width + x) as usize];
+                    for c in 0..3 {
+                        factor[c] += basis * pixel[c];
+                    }
+                }
+            }
+            let scale = 1.0 / (width * height) as f64;
+            factors.push(factor.map(|value| value * scale));
+        }
+    }
+
+    let (dc, ac) = factors.split_first().expect("at least one component");
+    let mut hash = String::new();
+    encode_base83((x_components - 1) + (y_components - 1) * 9, 1, &mut hash);
+    let maximum = if ac.is_empty() {
+        encode_base83(0, 1, &mut hash);
+        1.0
+    } else {
+        let actual = ac
+            .iter()
+            .flatten()
+            .fold(0.0f64, |maximum, value| maximum.max(value.abs()));
+        let quantised = ((actual * 166.0 - 0.5).floor() as i64).clamp(0, 82) as u32;
+        encode_base83(quantised, 1, &mut hash);
+        (quantised + 1) as f64 / 166.0
+    };
+    let dc = dc.map(|value| linear_to_srgb(value) as u32);
+    encode_base83((dc[0] << 16) | (dc[1] << 8) | dc[2], 4, &mut hash);
+    for factor in ac {
+        let [r, g, b] = factor.map(|value| {
+            let value = (value / maximum).abs().sqrt().copysign(value);
+            ((value * 9.0 + 9.5).floor() as i64).clamp(0, 18) as u32
+        });
+        encode_base83(r * 19 * 19 + g * 19 + b, 2, &mut hash);
+    }
+
+    Ok(hash)
+}
+
+/// Returns the thumbhash of the image
+pub(crate) fn thumbhash(image: &DynamicImage) -> Vec<u8> {
+    let image = downscale(image);
+
+    thumbhash::rgba_to_thumb_hash(
+        image.width() as usize,
+        image.height() as usize,
+        image.as_raw(),
+    )
+}
+
+fn srgb_to_linear(value: u8) -> f64 {
+    let value = value as f64 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f64) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+    if value <= 0.0031308 {
+        (value * 12.92 * 255.0 + 0.5) as u8
+    } else {
+        ((1.055 * value.powf(1.0 / 2.4) - 0.055) * 255.0 + 0.5) as u8
+    }
+}
+
+fn encode_base83(value: u32, length: u32, hash: &mut String) {
+    for i in 1..=length {
+        let digit = (value / 83u32.pow(length - i)) % 83;
+        hash.push(BASE83_CHARACTERS[digit as usize] as char);
+    }
+}
+
+/// Scales the image down so that computing the placeholders doesn't depend on
+/// the size of the thumbnail
+fn downscale(image: &DynamicImage) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    if width <= MAX_PLACEHOLDER_SIZE && height <= MAX_PLACEHOLDER_SIZE {
+        return image.to_rgba8();
+    }
+
+    resize_image(
+        image,
+        MAX_PLACEHOLDER_SIZE,
+        MAX_PLACEHOLDER_SIZE,
+        ResizeMode::Fit,
+        FilterType::Triangle,
+    )
+    .into_rgba8()
+}
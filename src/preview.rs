@@ -0,0 +1,138 @@
+use crate::error::{ThumbError, ThumbResult};
+#[cfg(feature = "raw")]
+use crate::formats::raw_format::is_raw_mime;
+use crate::{SizeConstraint, SourceInfo, ThumbnailSize, ThumbnailerBuilder};
+use image::ImageFormat;
+use mime::Mime;
+use std::io::{BufRead, Seek};
+
+/// How the image of a preview was extracted from the content
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PreviewSource {
+    /// The content was decoded as an image. Animated images use their first frame.
+    Image,
+
+    /// An image embedded in the content was used, like the preview of raw camera files and
+    /// office documents or the cover of audio files, ebooks and archives
+    Embedded,
+
+    /// A frame of the video was extracted
+    VideoFrame,
+
+    /// The first page of the document was rendered
+    FirstPage,
+
+    /// The content was rendered as text
+    Text,
+}
+
+/// Information about how a preview was created
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreviewInfo {
+    /// How the image was extracted from the content
+    pub source: PreviewSource,
+
+    /// The media type the content was decoded as
+    pub mime: Mime,
+
+    /// Whether the media type was detected from the content, because the declared one
+    /// was unknown or decoding it failed
+    pub sniffed: bool,
+
+    /// Information about the decoded source
+    pub info: SourceInfo,
+
+    /// The dimensions of the encoded preview
+    pub dimensions: (u32, u32),
+}
+
+/// Creates a single preview whose longer edge is at most `max_dimension` and returns it
+/// encoded in the given format. Jpeg, webp and avif previews use the default quality.
+/// The content is decoded as the given media type and as the type detected from the
+/// content if that fails or the type is unknown. Raw camera files use their embedded
+/// preview and animations their first frame. Sources smaller than the maximum dimension
+/// are kept at their size.
+///
+/// Example:
+/// ```
+/// use image::ImageFormat;
+/// use std::fs::File;
+/// use std::io::BufReader;
+/// use thumbnailer::create_preview;
+///
+/// let file = File::open("tests/assets/test.png").unwrap();
+/// let webp = create_preview(BufReader::new(file), mime::IMAGE_PNG, 512, ImageFormat::WebP)
+///     .unwrap();
+/// ```
+pub fn create_preview<R: BufRead + Seek>(
+    reader: R,
+    mime: Mime,
+    max_dimension: u32,
+    format: ImageFormat,
+) -> ThumbResult<Vec<u8>> {
+    create_preview_with_info(reader, mime, max_dimension, format).map(|(bytes, _)| bytes)
+}
+
+/// Creates a preview like [create_preview] and returns it with the information about
+/// how it was created
+pub fn create_preview_with_info<R: BufRead + Seek>(
+    reader: R,
+    mime: Mime,
+    max_dimension: u32,
+    format: ImageFormat,
+) -> ThumbResult<(Vec<u8>, PreviewInfo)> {
+    if max_dimension == 0 {
+        return Err(ThumbError::InvalidOptions(
+            "the maximum dimension of a preview must be positive".into(),
+        ));
+    }
+    let builder = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Constrained(SizeConstraint::LongestEdge(
+            max_dimension,
+        ))])
+        .no_upscale(true)
+        .allow_sniffing(true);
+    let base = builder.decode(reader, mime.clone())?;
+    let decoded = base.mime.clone().unwrap_or_else(|| mime.clone());
+
+    let thumbnail = builder.run_base(&base)?.remove(0);
+    let dimensions = thumbnail.size();
+    let bytes = thumbnail.encode(format)?;
+    let info = PreviewInfo {
+        source: preview_source(&decoded),
+        sniffed: decoded.essence_str() != mime.essence_str(),
+        mime: decoded,
+        info: base.info(),
+        dimensions,
+    };
+
+    Ok((bytes, info))
+}
+
+/// Returns how the built-in handler of the media type extracts the image
+fn preview_source(mime: &Mime) -> PreviewSource {
+    let structured = [mime.subtype(), mime.suffix().unwrap_or(mime.subtype())];
+    if mime.type_() == mime::VIDEO {
+        PreviewSource::VideoFrame
+    } else if mime.type_() == mime::TEXT
+        || (mime.type_() == mime::APPLICATION
+            && (structured.contains(&mime::JSON) || structured.contains(&mime::XML)))
+    {
+        PreviewSource::Text
+    } else if mime.subtype() == mime::PDF {
+        PreviewSource::FirstPage
+    } else if mime.type_() == mime::IMAGE && !is_embedded_preview(mime) {
+        PreviewSource::Image
+    } else {
+        PreviewSource::Embedded
+    }
+}
+
+/// Returns whether images of the media type are embedded previews instead of the image itself
+#[cfg_attr(not(feature = "raw"), allow(unused_variables))]
+fn is_embedded_preview(mime: &Mime) -> bool {
+    #[cfg(feature = "raw")]
+    return is_raw_mime(mime);
+    #[cfg(not(feature = "raw"))]
+    false
+}
@@ -0,0 +1,59 @@
+use crate::ThumbnailSize;
+use std::fmt;
+use std::sync::Arc;
+
+/// The step of thumbnail creation that is reported to the progress callback
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stage {
+    /// The source image or document is being decoded
+    Decoding,
+
+    /// A frame or embedded image is being extracted from a video or audio file
+    Extracting,
+
+    /// A thumbnail has been resized. The index is the position of the size in the
+    /// order the sizes were added and the total is the number of sizes that are resized,
+    /// which doesn't include sizes that are skipped as duplicates.
+    Resizing {
+        size: ThumbnailSize,
+        index: usize,
+        total: usize,
+    },
+
+    /// A thumbnail is being encoded into the output format. This is only reported by
+    /// [crate::ThumbnailerBuilder::run_encoded], as the other methods return decoded
+    /// thumbnails that are encoded by the caller.
+    Encoding,
+}
+
+/// The progress reported by [crate::ThumbnailerBuilder::on_progress]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Progress {
+    /// The current stage
+    pub stage: Stage,
+
+    /// The completed fraction of the stage between 0 and 1 if it is known.
+    /// Resizing reports the fraction of sizes that are done and encoding the fraction
+    /// of thumbnails that were encoded before the current one.
+    pub fraction: Option<f32>,
+}
+
+/// The progress callback of the builder
+#[derive(Clone)]
+pub(crate) struct ProgressCallback(Arc<dyn Fn(Progress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new<F: Fn(Progress) + Send + Sync + 'static>(callback: F) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub fn report(&self, stage: Stage, fraction: Option<f32>) {
+        (self.0)(Progress { stage, fraction })
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
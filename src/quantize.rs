@@ -0,0 +1,208 @@
+use crate::write_options::{Dithering, PaletteOptions};
+use color_quant::NeuQuant;
+use image::{Rgb, RgbaImage};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// The color of pixels that are written fully transparent
+const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+/// Images with fewer pixels train the network with all of them
+const FULL_SAMPLE_PIXELS: usize = 256 * 256;
+
+/// An image whose pixels are indices into a palette of rgba colors
+pub(crate) struct IndexedImage {
+    pub width: u32,
+    pub height: u32,
+    /// The colors with all semi-transparent colors before the opaque ones, so that the
+    /// transparency chunk only needs entries for them
+    pub palette: Vec<[u8; 4]>,
+    pub indices: Vec<u8>,
+}
+
+/// Reduces the colors of the image to a palette of at most the configured number.
+/// Images that already have few enough colors keep them exactly, others are quantized
+/// with a NeuQuant network. The options have to be valid.
+pub(crate) fn quantize(
+    image: &RgbaImage,
+    options: &PaletteOptions,
+    matte: Rgb<u8>,
+) -> IndexedImage {
+    let max_colors = usize::from(options.max_colors).clamp(2, 256);
+    let pixels: Vec<[u8; 4]> = image
+        .pixels()
+        .map(|pixel| match pixel.0 {
+            [.., alpha] if alpha <= options.alpha_threshold => TRANSPARENT,
+            [r, g, b, alpha] if options.binary_alpha && alpha < 255 => {
+                let blend = |channel: u8, matte: u8| {
+                    ((channel as u32 * alpha as u32 + matte as u32 * (255 - alpha as u32) + 127)
+                        / 255) as u8
+                };
+                [
+                    blend(r, matte[0]),
+                    blend(g, matte[1]),
+                    blend(b, matte[2]),
+                    255,
+                ]
+            }
+            color => color,
+        })
+        .collect();
+
+    let (palette, indices) = match exact_palette(&pixels, max_colors) {
+        Some(indexed) => indexed,
+        None => quantize_pixels(
+            &pixels,
+            image.width() as usize,
+            max_colors,
+            options.dithering,
+        ),
+    };
+    let (palette, indices) = sort_palette(palette, indices);
+
+    IndexedImage {
+        width: image.width(),
+        height: image.height(),
+        palette,
+        indices,
+    }
+}
+
+/// Returns the distinct colors and the index of each pixel if there are at most the
+/// given number of colors
+fn exact_palette(pixels: &[[u8; 4]], max_colors: usize) -> Option<(Vec<[u8; 4]>, Vec<u8>)> {
+    let mut palette = Vec::new();
+    let mut positions: HashMap<[u8; 4], u8> = HashMap::new();
+    let mut indices = Vec::with_capacity(pixels.len());
+    for pixel in pixels {
+        let index = match positions.get(pixel) {
+            Some(index) => *index,
+            None => {
+                if palette.len() == max_colors {
+                    return None;
+                }
+                let index = u8::try_from(palette.len()).ok()?;
+                positions.insert(*pixel, index);
+                palette.push(*pixel);
+                index
+            }
+        };
+        indices.push(index);
+    }
+
+    Some((palette, indices))
+}
+
+/// Trains a network with the visible pixels and maps all pixels to its colors. Transparent
+/// pixels get their own entry and neither take nor spread errors while dithering.
+fn quantize_pixels(
+    pixels: &[[u8; 4]],
+    width: usize,
+    max_colors: usize,
+    dithering: Dithering,
+) -> (Vec<[u8; 4]>, Vec<u8>) {
+    let visible: Vec<u8> = pixels
+        .iter()
+        .filter(|pixel| **pixel != TRANSPARENT)
+        .flatten()
+        .copied()
+        .collect();
+    let has_transparent = visible.len() < pixels.len() * 4;
+    let colors = max_colors - usize::from(has_transparent);
+    let sample_factor = if visible.len() / 4 < FULL_SAMPLE_PIXELS {
+        1
+    } else {
+        10
+    };
+    let network = NeuQuant::new(sample_factor, colors, &visible);
+
+    let mut palette: Vec<[u8; 4]> = network
+        .color_map_rgba()
+        .chunks_exact(4)
+        .map(|color| [color[0], color[1], color[2], color[3]])
+        .collect();
+    // the network has at most 256 colors, so the indices fit into a byte
+    let transparent = palette.len() as u8;
+    if has_transparent {
+        palette.push(TRANSPARENT);
+    }
+
+    let indices = match dithering {
+        Dithering::None => pixels
+            .iter()
+            .map(|pixel| match *pixel {
+                TRANSPARENT => transparent,
+                color => network.index_of(&color) as u8,
+            })
+            .collect(),
+        Dithering::FloydSteinberg => dither(pixels, width, &palette, transparent, |color| {
+            network.index_of(color)
+        }),
+    };
+
+    (palette, indices)
+}
+
+/// Maps the pixels to the palette with Floyd-Steinberg dithering of the color channels.
+/// The errors are stored in sixteenths for the current and the next row, which have a
+/// padding column on both sides.
+fn dither<F: Fn(&[u8; 4]) -> usize>(
+    pixels: &[[u8; 4]],
+    width: usize,
+    palette: &[[u8; 4]],
+    transparent: u8,
+    index_of: F,
+) -> Vec<u8> {
+    let mut indices = Vec::with_capacity(pixels.len());
+    let mut current = vec![[0i32; 3]; width + 2];
+    let mut next = vec![[0i32; 3]; width + 2];
+    for row in pixels.chunks(width.max(1)) {
+        for (x, pixel) in row.iter().enumerate() {
+            if *pixel == TRANSPARENT {
+                indices.push(transparent);
+                continue;
+            }
+            let mut color = *pixel;
+            for (channel, error) in color.iter_mut().zip(current[x + 1].iter()) {
+                *channel = (*channel as i32 + error / 16).clamp(0, 255) as u8;
+            }
+            let index = index_of(&color);
+            for channel in 0..3 {
+                let error = color[channel] as i32 - palette[index][channel] as i32;
+                current[x + 2][channel] += error * 7;
+                next[x][channel] += error * 3;
+                next[x + 1][channel] += error * 5;
+                next[x + 2][channel] += error;
+            }
+            indices.push(index as u8);
+        }
+        std::mem::swap(&mut current, &mut next);
+        next.iter_mut().for_each(|error| *error = [0; 3]);
+    }
+
+    indices
+}
+
+/// Removes the colors that no pixel uses and moves the semi-transparent colors in front
+/// of the opaque ones
+fn sort_palette(palette: Vec<[u8; 4]>, indices: Vec<u8>) -> (Vec<[u8; 4]>, Vec<u8>) {
+    let mut used = vec![false; palette.len()];
+    for index in &indices {
+        used[*index as usize] = true;
+    }
+    let mut order: Vec<usize> = (0..palette.len()).filter(|index| used[*index]).collect();
+    order.sort_by_key(|index| palette[*index][3] == 255);
+
+    let mut positions = vec![0u8; palette.len()];
+    for (position, index) in order.iter().enumerate() {
+        positions[*index] = position as u8;
+    }
+
+    (
+        order.iter().map(|index| palette[*index]).collect(),
+        indices
+            .iter()
+            .map(|index| positions[*index as usize])
+            .collect(),
+    )
+}
@@ -0,0 +1,379 @@
+use crate::FilterType;
+use image::imageops;
+use image::{DynamicImage, GenericImageView, ImageBuffer, Pixel, Rgba, RgbaImage};
+
+/// The largest width or height of the copy the attention of an image is computed from
+const ATTENTION_SIZE: u32 = 256;
+
+/// Describes how an image is fitted into the requested thumbnail dimensions
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ResizeMode {
+    /// Scales the image to fit within the dimensions preserving the aspect ratio
+    #[default]
+    Fit,
+
+    /// Scales the image to cover the dimensions preserving the aspect ratio
+    /// and crops the overflow. The kept region is selected by the [CropStrategy] which
+    /// defaults to the center. The result has exactly the requested dimensions.
+    Cover,
+
+    /// Stretches the image to exactly the requested dimensions
+    Exact,
+
+    /// Scales the image to fit within the dimensions preserving the aspect ratio and
+    /// centers it on a background of the given color. The result has exactly the
+    /// requested dimensions.
+    Pad { color: Rgba<u8> },
+}
+
+/// Describes which region of the image is kept by [ResizeMode::Cover]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum CropStrategy {
+    /// Keeps the center of the image
+    #[default]
+    Center,
+
+    /// Keeps the region with the most edges and saturated colors, which is usually the
+    /// subject of the image. The attention is computed on a copy scaled down to 256 pixels.
+    Attention,
+
+    /// Keeps the region around a known focal point given as the horizontal and vertical
+    /// position relative to the image dimensions (0.0 - 1.0)
+    Fixed(f32, f32),
+}
+
+impl CropStrategy {
+    /// Returns whether the focal point of fixed crops is within the image
+    pub(crate) fn is_valid(&self) -> bool {
+        match *self {
+            CropStrategy::Fixed(x, y) => (0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y),
+            _ => true,
+        }
+    }
+}
+
+/// Returns the largest dimensions with the aspect ratio of the source that fit into the
+/// given ones, rounded like [DynamicImage::resize]
+pub(crate) fn fit_dimensions(
+    (width, height): (u32, u32),
+    (max_width, max_height): (u32, u32),
+) -> (u32, u32) {
+    let ratio = (max_width as f64 / width as f64).min(max_height as f64 / height as f64);
+    let scale = |dimension: u32| ((dimension as f64 * ratio).round() as u32).max(1);
+
+    (scale(width), scale(height))
+}
+
+/// Resizes the image to the given dimensions with the given mode
+pub(crate) fn resize_image(
+    image: &DynamicImage,
+    width: u32,
+    height: u32,
+    mode: ResizeMode,
+    filter: FilterType,
+) -> DynamicImage {
+    resize_image_with_crop(image, width, height, mode, filter, CropStrategy::Center)
+}
+
+/// Resizes the image like [resize_image] keeping the region selected by the crop
+/// strategy for [ResizeMode::Cover]
+pub(crate) fn resize_image_with_crop(
+    image: &DynamicImage,
+    width: u32,
+    height: u32,
+    mode: ResizeMode,
+    filter: FilterType,
+    crop: CropStrategy,
+) -> DynamicImage {
+    match mode {
+        ResizeMode::Fit => image.resize(width, height, filter),
+        ResizeMode::Cover => {
+            let region = cover_crop(image, (width, height), crop);
+            resize_region(image, region, width, height, filter)
+        }
+        ResizeMode::Exact => image.resize_exact(width, height, filter),
+        ResizeMode::Pad { color } => {
+            pad_image(&image.resize(width, height, filter), width, height, color)
+        }
+    }
+}
+
+/// Resizes the region of the image given by its position and dimensions to exactly the
+/// given dimensions. The region is read through a view, so the image isn't copied.
+fn resize_region(
+    image: &DynamicImage,
+    region: (u32, u32, u32, u32),
+    width: u32,
+    height: u32,
+    filter: FilterType,
+) -> DynamicImage {
+    match image {
+        DynamicImage::ImageLuma8(buffer) => {
+            DynamicImage::ImageLuma8(resize_view(buffer, region, width, height, filter))
+        }
+        DynamicImage::ImageLumaA8(buffer) => {
+            DynamicImage::ImageLumaA8(resize_view(buffer, region, width, height, filter))
+        }
+        DynamicImage::ImageRgb8(buffer) => {
+            DynamicImage::ImageRgb8(resize_view(buffer, region, width, height, filter))
+        }
+        DynamicImage::ImageRgba8(buffer) => {
+            DynamicImage::ImageRgba8(resize_view(buffer, region, width, height, filter))
+        }
+        DynamicImage::ImageLuma16(buffer) => {
+            DynamicImage::ImageLuma16(resize_view(buffer, region, width, height, filter))
+        }
+        DynamicImage::ImageLumaA16(buffer) => {
+            DynamicImage::ImageLumaA16(resize_view(buffer, region, width, height, filter))
+        }
+        DynamicImage::ImageRgb16(buffer) => {
+            DynamicImage::ImageRgb16(resize_view(buffer, region, width, height, filter))
+        }
+        DynamicImage::ImageRgba16(buffer) => {
+            DynamicImage::ImageRgba16(resize_view(buffer, region, width, height, filter))
+        }
+        DynamicImage::ImageRgb32F(buffer) => {
+            DynamicImage::ImageRgb32F(resize_view(buffer, region, width, height, filter))
+        }
+        DynamicImage::ImageRgba32F(buffer) => {
+            DynamicImage::ImageRgba32F(resize_view(buffer, region, width, height, filter))
+        }
+        _ => {
+            let (x, y, region_width, region_height) = region;
+            image
+                .crop_imm(x, y, region_width, region_height)
+                .resize_exact(width, height, filter)
+        }
+    }
+}
+
+fn resize_view<P: Pixel + 'static>(
+    buffer: &ImageBuffer<P, Vec<P::Subpixel>>,
+    (x, y, region_width, region_height): (u32, u32, u32, u32),
+    width: u32,
+    height: u32,
+    filter: FilterType,
+) -> ImageBuffer<P, Vec<P::Subpixel>> {
+    imageops::resize(
+        &*buffer.view(x, y, region_width, region_height),
+        width,
+        height,
+        filter,
+    )
+}
+
+/// Centers the image on a background of the given dimensions and color.
+/// Images with more than 8 bits per channel keep 16 bits of precision.
+pub(crate) fn pad_image(
+    image: &DynamicImage,
+    width: u32,
+    height: u32,
+    color: Rgba<u8>,
+) -> DynamicImage {
+    let x = (width as i64 - image.width() as i64) / 2;
+    let y = (height as i64 - image.height() as i64) / 2;
+    if is_high_precision(image) {
+        let mut canvas = ImageBuffer::from_pixel(width, height, Rgba(color.0.map(widen)));
+        imageops::overlay(&mut canvas, &image.to_rgba16(), x, y);
+        return DynamicImage::ImageRgba16(canvas);
+    }
+    let mut canvas = RgbaImage::from_pixel(width, height, color);
+    imageops::overlay(&mut canvas, &image.to_rgba8(), x, y);
+
+    DynamicImage::ImageRgba8(canvas)
+}
+
+/// Returns whether the channels of the image have more than 8 bits
+pub(crate) fn is_high_precision(image: &DynamicImage) -> bool {
+    let color = image.color();
+    color.bytes_per_pixel() > color.channel_count()
+}
+
+/// Converts an 8 bit channel value to 16 bits
+fn widen(value: u8) -> u16 {
+    value as u16 * 257
+}
+
+/// Calculates the region of the source that has the aspect ratio of the target.
+/// Cropping is done before scaling so that degenerate sources don't need huge intermediates.
+/// The region always spans the whole source along one axis, so the strategy only
+/// selects the offset along the other one.
+fn cover_crop(
+    image: &DynamicImage,
+    target: (u32, u32),
+    crop: CropStrategy,
+) -> (u32, u32, u32, u32) {
+    let (src_width, src_height) = (image.width() as u64, image.height() as u64);
+    let (width, height) = (target.0 as u64, target.1 as u64);
+
+    if src_width * height > src_height * width {
+        let crop_width = ((src_height * width + height / 2) / height).clamp(1, src_width);
+        let x = crop_offset(image, crop_width, true, crop);
+        (x as u32, 0, crop_width as u32, src_height as u32)
+    } else {
+        let crop_height = ((src_width * height + width / 2) / width).clamp(1, src_height);
+        let y = crop_offset(image, crop_height, false, crop);
+        (0, y as u32, src_width as u32, crop_height as u32)
+    }
+}
+
+/// Returns the offset of the crop window with the given length along the horizontal
+/// or vertical axis of the image
+fn crop_offset(image: &DynamicImage, length: u64, horizontal: bool, crop: CropStrategy) -> u64 {
+    let total = if horizontal {
+        image.width()
+    } else {
+        image.height()
+    } as u64;
+    let free = total - length;
+
+    match crop {
+        CropStrategy::Center => free / 2,
+        CropStrategy::Fixed(x, y) => {
+            let focus = if horizontal { x } else { y } as f64 * total as f64;
+            (focus - length as f64 / 2.0)
+                .round()
+                .clamp(0.0, free as f64) as u64
+        }
+        CropStrategy::Attention if free == 0 => 0,
+        CropStrategy::Attention => {
+            let profile = attention_profile(image, horizontal);
+            let scale = profile.len() as f64 / total as f64;
+            let window = ((length as f64 * scale).round() as usize).clamp(1, profile.len());
+            let offset = best_window(&profile, window) as f64 / scale;
+            (offset.round() as u64).min(free)
+        }
+    }
+}
+
+/// Returns the attention of each column or row of a scaled down copy of the image.
+/// The attention of a pixel is the strength of the edge at it and its saturation.
+fn attention_profile(image: &DynamicImage, horizontal: bool) -> Vec<f64> {
+    let small = if image.width() > ATTENTION_SIZE || image.height() > ATTENTION_SIZE {
+        image.resize(ATTENTION_SIZE, ATTENTION_SIZE, FilterType::Triangle)
+    } else {
+        image.clone()
+    }
+    .into_rgb8();
+    let (width, height) = small.dimensions();
+    let luma = |x: u32, y: u32| {
+        let [r, g, b] = small.get_pixel(x, y).0.map(|c| c as f64 / 255.0);
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    };
+
+    let mut profile = vec![0.0; if horizontal { width } else { height } as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let neighbours = [
+                luma(x.saturating_sub(1), y),
+                luma((x + 1).min(width - 1), y),
+                luma(x, y.saturating_sub(1)),
+                luma(x, (y + 1).min(height - 1)),
+            ];
+            let edge = (4.0 * luma(x, y) - neighbours.iter().sum::<f64>()).abs();
+            let pixel = small.get_pixel(x, y).0;
+            let max = *pixel.iter().max().unwrap() as f64;
+            let min = *pixel.iter().min().unwrap() as f64;
+            let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+
+            profile[if horizontal { x } else { y } as usize] += edge + 0.2 * saturation;
+        }
+    }
+
+    profile
+}
+
+/// Returns the start of the window with the highest sum. Ties are resolved towards the center.
+fn best_window(profile: &[f64], window: usize) -> usize {
+    let center = (profile.len() - window) as f64 / 2.0;
+    let mut sum: f64 = profile[..window].iter().sum();
+    let mut best = (sum, 0);
+    for start in 1..=profile.len() - window {
+        sum += profile[start + window - 1] - profile[start - 1];
+        let closer = (start as f64 - center).abs() < (best.1 as f64 - center).abs();
+        // sums are accumulated incrementally and differ slightly for equal windows
+        if sum > best.0 + 1e-9 || (sum > best.0 - 1e-9 && closer) {
+            best = (sum, start);
+        }
+    }
+
+    best.1
+}
+
+/// Options of the unsharp mask applied after resizing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Sharpen {
+    pub amount: f32,
+    pub radius: f32,
+    pub threshold: i32,
+}
+
+/// Thumbnails up to this size are sharpened with the full amount
+const FULL_SHARPEN_DIMENSION: f32 = 256.0;
+
+impl Sharpen {
+    /// Returns whether the amount is positive or zero and the radius positive
+    pub fn is_valid(&self) -> bool {
+        self.amount.is_finite()
+            && self.amount >= 0.0
+            && self.radius.is_finite()
+            && self.radius > 0.0
+    }
+
+    /// Applies an unsharp mask to the image. Larger images are sharpened less
+    /// because they lose less detail when downscaling. Images with more than 8 bits
+    /// per channel keep 16 bits of precision.
+    pub fn apply(&self, image: DynamicImage) -> DynamicImage {
+        let largest = image.width().max(image.height()) as f32;
+        let amount = self.amount * (FULL_SHARPEN_DIMENSION / largest).min(1.0);
+        if amount == 0.0 {
+            return image;
+        }
+        if is_high_precision(&image) {
+            return self.apply_high_precision(image, amount);
+        }
+        let has_alpha = image.color().has_alpha();
+        let blurred = image.blur(self.radius).into_rgba8();
+        let mut sharpened = image.into_rgba8();
+
+        for (pixel, blurred) in sharpened.pixels_mut().zip(blurred.pixels()) {
+            for channel in 0..3 {
+                let diff = pixel[channel] as i32 - blurred[channel] as i32;
+                if diff.abs() > self.threshold {
+                    let value = pixel[channel] as f32 + diff as f32 * amount;
+                    pixel[channel] = value.round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+
+        if has_alpha {
+            DynamicImage::ImageRgba8(sharpened)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(sharpened).into_rgb8())
+        }
+    }
+
+    /// Applies the unsharp mask with 16 bits per channel
+    fn apply_high_precision(&self, image: DynamicImage, amount: f32) -> DynamicImage {
+        let has_alpha = image.color().has_alpha();
+        let threshold = self.threshold.saturating_mul(257);
+        let blurred = image.blur(self.radius).into_rgba16();
+        let mut sharpened = image.into_rgba16();
+
+        for (pixel, blurred) in sharpened.pixels_mut().zip(blurred.pixels()) {
+            for channel in 0..3 {
+                let diff = pixel[channel] as i32 - blurred[channel] as i32;
+                if diff.abs() > threshold {
+                    let value = pixel[channel] as f32 + diff as f32 * amount;
+                    pixel[channel] = value.round().clamp(0.0, 65535.0) as u16;
+                }
+            }
+        }
+
+        if has_alpha {
+            DynamicImage::ImageRgba16(sharpened)
+        } else {
+            DynamicImage::ImageRgb16(DynamicImage::ImageRgba16(sharpened).into_rgb16())
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::error::{ThumbError, ThumbResult};
+use image::error::ImageFormatHint;
+use image::ImageFormat;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+/// Options for saving thumbnails to files
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SaveOptions {
+    /// The format of the file. If not set the format is inferred from the extension.
+    pub format: Option<ImageFormat>,
+
+    /// Creates missing parent directories of the file
+    pub create_dirs: bool,
+}
+
+impl SaveOptions {
+    /// Returns the format of the file at the given path
+    pub(crate) fn format_for(&self, path: &Path) -> ThumbResult<ImageFormat> {
+        let format = match self.format {
+            Some(format) => format,
+            None => ImageFormat::from_path(path).map_err(|_| {
+                let hint = path
+                    .extension()
+                    .map_or(ImageFormatHint::Unknown, |extension| {
+                        ImageFormatHint::PathExtension(extension.into())
+                    });
+                ThumbError::UnsupportedOutputFormat(hint)
+            })?,
+        };
+        if format.writing_enabled() {
+            Ok(format)
+        } else {
+            Err(ThumbError::UnsupportedOutputFormat(ImageFormatHint::Exact(
+                format,
+            )))
+        }
+    }
+}
+
+/// Writes a file by writing to a temporary file in the same directory and renaming
+/// it into place, so that the file at the path is either the old or the complete new
+/// one. The temporary file is removed if writing fails.
+pub(crate) fn write_atomic<F>(path: &Path, create_dirs: bool, write: F) -> ThumbResult<()>
+where
+    F: FnOnce(&mut BufWriter<&mut File>) -> ThumbResult<()>,
+{
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    if create_dirs {
+        fs::create_dir_all(dir)?;
+    }
+    let prefix = format!(
+        ".{}",
+        path.file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default()
+    );
+    let mut file = tempfile::Builder::new()
+        .prefix(&prefix)
+        .suffix(".tmp")
+        .tempfile_in(dir)?;
+
+    {
+        let mut writer = BufWriter::new(file.as_file_mut());
+        write(&mut writer)?;
+        writer.flush()?;
+    }
+    file.as_file().sync_all()?;
+    // temporary files are only readable by the user
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(file.path(), fs::Permissions::from_mode(0o644))?;
+    }
+    persist(file, path)?;
+    sync_dir(dir);
+
+    Ok(())
+}
+
+/// Renames the temporary file to the path. Renaming over an existing file isn't
+/// supported on every platform and file system, so the existing file is removed
+/// before retrying.
+fn persist(file: NamedTempFile, path: &Path) -> ThumbResult<()> {
+    let error = match file.persist(path) {
+        Ok(_) => return Ok(()),
+        Err(error) => error,
+    };
+    if !path.is_file() {
+        return Err(error.error.into());
+    }
+    fs::remove_file(path)?;
+    error.file.persist(path).map_err(|e| e.error)?;
+
+    Ok(())
+}
+
+/// Flushes the rename to disk. Not all platforms support opening directories,
+/// so errors are ignored.
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
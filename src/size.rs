@@ -1,24 +1,388 @@
+use crate::error::{ParseSizeError, ThumbError, ThumbResult};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
 
-/// Represents fixed sizes of a thumbnail
-#[derive(Clone, Copy, Debug)]
+/// The maximum width or height of a scaled size
+const MAX_SCALED_DIMENSION: u32 = 65535;
+
+/// Represents fixed sizes of a thumbnail. Sizes can be parsed from and displayed as
+/// strings like `small`, `320x240`, `shortest:128` or `medium@2x`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ThumbnailSize {
     Icon,
     Small,
     Medium,
     Large,
+    /// A 960x960 preview like the large images of web galleries
+    Preview,
     Larger,
-    Custom((u32, u32))
+    /// A 2048x2048 size of full screen images on high density displays
+    XLarge,
+    Custom {
+        width: u32,
+        height: u32,
+    },
+
+    /// A size whose dimensions depend on the aspect ratio of the source
+    Constrained(SizeConstraint),
+
+    /// A size whose dimensions are multiplied for high density displays.
+    /// Created with [ThumbnailSize::scaled].
+    Scaled(ScaledSize),
+}
+
+/// A size that is multiplied by an integer factor like the `@2x` assets of a platform.
+/// The requested size of the thumbnails keeps the factor, so they can be named like
+/// `thumb_medium@2x.png`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScaledSize {
+    base: SizeConstraint,
+    factor: u32,
+}
+
+impl ScaledSize {
+    /// Returns the size before it was scaled
+    pub fn base(&self) -> ThumbnailSize {
+        ThumbnailSize::from(self.base)
+    }
+
+    /// Returns the factor the dimensions of the base size are multiplied with
+    pub fn factor(&self) -> u32 {
+        self.factor
+    }
+
+    /// Returns the constraint of the base size with multiplied dimensions
+    fn constraint(&self) -> SizeConstraint {
+        match self.base {
+            SizeConstraint::BoundingBox(width, height) => {
+                SizeConstraint::BoundingBox(width * self.factor, height * self.factor)
+            }
+            SizeConstraint::LongestEdge(edge) => SizeConstraint::LongestEdge(edge * self.factor),
+            SizeConstraint::ShortestEdge(edge) => SizeConstraint::ShortestEdge(edge * self.factor),
+        }
+    }
+}
+
+/// Describes the dimensions of a thumbnail relative to the aspect ratio of the source
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SizeConstraint {
+    /// The thumbnail is fitted into the width and height with the configured resize mode
+    BoundingBox(u32, u32),
+
+    /// The longer edge of the thumbnail has the given length
+    LongestEdge(u32),
+
+    /// The shorter edge of the thumbnail has the given length. The longer edge is limited
+    /// to [ThumbnailerBuilder::max_constrained_dimension](crate::ThumbnailerBuilder::max_constrained_dimension)
+    /// and sources with a more extreme aspect ratio are cropped.
+    ShortestEdge(u32),
+}
+
+impl SizeConstraint {
+    /// Returns the dimensions of a thumbnail of a source with the given dimensions.
+    /// Longer edges of shortest edge constraints are limited to the maximum dimension.
+    pub(crate) fn target_dimensions(
+        &self,
+        (width, height): (u32, u32),
+        max_dimension: u32,
+    ) -> (u32, u32) {
+        let (long, short) = (width.max(height) as u64, width.min(height).max(1) as u64);
+        let (long, short) = match *self {
+            SizeConstraint::BoundingBox(width, height) => return (width, height),
+            SizeConstraint::LongestEdge(edge) => {
+                let edge = edge as u64;
+                (edge, ((short * edge + long / 2) / long).max(1))
+            }
+            SizeConstraint::ShortestEdge(edge) => {
+                let edge = edge as u64;
+                let long = (long * edge + short / 2) / short;
+                (long.min(max_dimension as u64).max(edge), edge)
+            }
+        };
+        let (long, short) = (long as u32, short as u32);
+
+        if width >= height {
+            (long, short)
+        } else {
+            (short, long)
+        }
+    }
+}
+
+impl From<ThumbnailSize> for SizeConstraint {
+    fn from(size: ThumbnailSize) -> Self {
+        match size {
+            ThumbnailSize::Constrained(constraint) => constraint,
+            ThumbnailSize::Scaled(scaled) => scaled.constraint(),
+            size => {
+                let (width, height) = size.dimensions();
+                SizeConstraint::BoundingBox(width, height)
+            }
+        }
+    }
+}
+
+impl From<SizeConstraint> for ThumbnailSize {
+    /// Converts bounding boxes to presets or custom sizes
+    fn from(constraint: SizeConstraint) -> Self {
+        match constraint {
+            SizeConstraint::BoundingBox(width, height) => {
+                ThumbnailSize::from_dimensions(width, height)
+                    .unwrap_or(ThumbnailSize::Custom { width, height })
+            }
+            constraint => ThumbnailSize::Constrained(constraint),
+        }
+    }
 }
 
 impl ThumbnailSize {
+    const PRESETS: [ThumbnailSize; 7] = [
+        ThumbnailSize::Icon,
+        ThumbnailSize::Small,
+        ThumbnailSize::Medium,
+        ThumbnailSize::Large,
+        ThumbnailSize::Preview,
+        ThumbnailSize::Larger,
+        ThumbnailSize::XLarge,
+    ];
+
+    /// Creates a size from the given dimensions. If the dimensions match one
+    /// of the presets the preset is returned, otherwise a custom size.
+    /// Zero width or height results in an error.
+    pub fn from_dimensions(width: u32, height: u32) -> ThumbResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(ThumbError::InvalidSize(width, height));
+        }
+        let size = Self::PRESETS
+            .iter()
+            .find(|preset| preset.dimensions() == (width, height))
+            .copied()
+            .unwrap_or(ThumbnailSize::Custom { width, height });
+
+        Ok(size)
+    }
+
+    /// Returns the width and height of the size. Edge constraints return a square
+    /// of the edge length as the actual dimensions depend on the source.
     pub fn dimensions(&self) -> (u32, u32) {
         match self {
             ThumbnailSize::Icon => (64, 64),
             ThumbnailSize::Small => (128, 128),
             ThumbnailSize::Medium => (256, 256),
             ThumbnailSize::Large => (512, 512),
+            ThumbnailSize::Preview => (960, 960),
             ThumbnailSize::Larger => (1024, 1024),
-            ThumbnailSize::Custom(size) => *size,
+            ThumbnailSize::XLarge => (2048, 2048),
+            ThumbnailSize::Custom { width, height } => (*width, *height),
+            ThumbnailSize::Constrained(SizeConstraint::BoundingBox(width, height)) => {
+                (*width, *height)
+            }
+            ThumbnailSize::Constrained(
+                SizeConstraint::LongestEdge(edge) | SizeConstraint::ShortestEdge(edge),
+            ) => (*edge, *edge),
+            ThumbnailSize::Scaled(scaled) => {
+                ThumbnailSize::Constrained(scaled.constraint()).dimensions()
+            }
+        }
+    }
+
+    /// Returns the size with its dimensions multiplied by the factor, like the `@2x` and
+    /// `@3x` sizes of high density displays. Scaling a scaled size multiplies the factors
+    /// and a total factor of 1 returns the base size. Factors of zero and dimensions above
+    /// 65535 pixels result in an error.
+    pub fn scaled(self, factor: u32) -> ThumbResult<Self> {
+        let (base, base_factor) = match self {
+            ThumbnailSize::Scaled(scaled) => (scaled.base, scaled.factor),
+            size => (SizeConstraint::from(size), 1),
+        };
+        let base_size = ThumbnailSize::from(base);
+        let invalid = |reason: &str| {
+            ThumbError::InvalidOptions(format!(
+                "can't scale {} by {}: {}",
+                base_size, factor, reason
+            ))
+        };
+        let factor = match base_factor.checked_mul(factor) {
+            Some(0) => return Err(invalid("the factor is zero")),
+            Some(1) => return Ok(base_size),
+            Some(factor) => factor,
+            None => return Err(invalid("the factor is too large")),
+        };
+        let (width, height) = base_size.dimensions();
+        let fits = |dimension: u32| {
+            dimension
+                .checked_mul(factor)
+                .is_some_and(|dimension| dimension <= MAX_SCALED_DIMENSION)
+        };
+        if !fits(width) || !fits(height) {
+            return Err(invalid(&format!(
+                "the dimensions exceed {} pixels",
+                MAX_SCALED_DIMENSION
+            )));
+        }
+
+        Ok(ThumbnailSize::Scaled(ScaledSize { base, factor }))
+    }
+
+    /// Returns the factor the size is scaled by, which is 1 for sizes that aren't scaled
+    pub fn scale_factor(&self) -> u32 {
+        match self {
+            ThumbnailSize::Scaled(scaled) => scaled.factor,
+            _ => 1,
         }
     }
-}
\ No newline at end of file
+
+    /// Returns the size without a scale factor
+    pub fn unscaled(&self) -> ThumbnailSize {
+        match self {
+            ThumbnailSize::Scaled(scaled) => scaled.base(),
+            size => *size,
+        }
+    }
+
+    /// Checks that the size can be used to resize an image
+    pub(crate) fn validate(&self) -> ThumbResult<()> {
+        match self.dimensions() {
+            (0, height) => Err(ThumbError::InvalidSize(0, height)),
+            (width, 0) => Err(ThumbError::InvalidSize(width, 0)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns all presets ordered from smallest to largest
+    pub fn all() -> &'static [ThumbnailSize] {
+        &Self::PRESETS
+    }
+
+    /// Returns the lowercase name of the preset or `None` for custom sizes
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        match self {
+            ThumbnailSize::Icon => Some("icon"),
+            ThumbnailSize::Small => Some("small"),
+            ThumbnailSize::Medium => Some("medium"),
+            ThumbnailSize::Large => Some("large"),
+            ThumbnailSize::Preview => Some("preview"),
+            ThumbnailSize::Larger => Some("larger"),
+            ThumbnailSize::XLarge => Some("xlarge"),
+            ThumbnailSize::Custom { .. }
+            | ThumbnailSize::Constrained(_)
+            | ThumbnailSize::Scaled(_) => None,
+        }
+    }
+}
+
+impl FromStr for ThumbnailSize {
+    type Err = ParseSizeError;
+
+    /// Parses the name of a preset case-insensitively, a number for a square size,
+    /// `WIDTHxHEIGHT`, `longest:EDGE` or `shortest:EDGE`. Dimensions that match a
+    /// preset return the preset. Any of them can be followed by a scale factor like
+    /// `medium@2x`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = s.trim();
+        if let Some((base, factor)) = input.split_once('@') {
+            let factor = factor
+                .trim()
+                .strip_suffix(['x', 'X'])
+                .and_then(|factor| factor.parse::<u32>().ok());
+            return match (ThumbnailSize::from_str(base), factor) {
+                (Ok(base), Some(factor)) => base.scaled(factor).map_err(|_| ParseSizeError::new(s)),
+                _ => Err(ParseSizeError::new(s)),
+            };
+        }
+        if let Some((kind, edge)) = input.split_once(':') {
+            let constraint = match kind.trim().to_ascii_lowercase().as_str() {
+                "longest" => SizeConstraint::LongestEdge,
+                "shortest" => SizeConstraint::ShortestEdge,
+                _ => return Err(ParseSizeError::new(s)),
+            };
+            return match edge.trim().parse::<u32>() {
+                Ok(edge) if edge > 0 => Ok(ThumbnailSize::Constrained(constraint(edge))),
+                _ => Err(ParseSizeError::new(s)),
+            };
+        }
+        if let Some(preset) = Self::PRESETS
+            .iter()
+            .find(|preset| preset.name().is_some_and(|n| n.eq_ignore_ascii_case(input)))
+        {
+            return Ok(*preset);
+        }
+        let dimension = |value: &str| value.trim().parse::<u32>().ok();
+        let dimensions = match input.split_once(['x', 'X']) {
+            Some((width, height)) => dimension(width).zip(dimension(height)),
+            None => dimension(input).map(|size| (size, size)),
+        };
+
+        dimensions
+            .and_then(|(width, height)| Self::from_dimensions(width, height).ok())
+            .ok_or_else(|| ParseSizeError::new(s))
+    }
+}
+
+impl fmt::Display for ThumbnailSize {
+    /// Writes the name of presets, `WIDTHxHEIGHT` for custom sizes,
+    /// `longest:EDGE` or `shortest:EDGE` for edge constraints and the base size
+    /// followed by the factor like `medium@2x` for scaled sizes
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.name(), self) {
+            (Some(name), _) => f.write_str(name),
+            (_, ThumbnailSize::Scaled(scaled)) => write!(f, "{}@{}x", scaled.base(), scaled.factor),
+            (_, ThumbnailSize::Constrained(SizeConstraint::LongestEdge(edge))) => {
+                write!(f, "longest:{}", edge)
+            }
+            (_, ThumbnailSize::Constrained(SizeConstraint::ShortestEdge(edge))) => {
+                write!(f, "shortest:{}", edge)
+            }
+            _ => {
+                let (width, height) = self.dimensions();
+                write!(f, "{}x{}", width, height)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ThumbnailSize {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ThumbnailSize {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SizeVisitor)
+    }
+}
+
+/// Deserializes sizes from strings like [ThumbnailSize::from_str] and bare numbers
+#[cfg(feature = "serde")]
+struct SizeVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for SizeVisitor {
+    type Value = ThumbnailSize;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a thumbnail preset name, a number, WIDTHxHEIGHT or an edge constraint")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        value.parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        u32::try_from(value)
+            .ok()
+            .and_then(|size| ThumbnailSize::from_dimensions(size, size).ok())
+            .ok_or_else(|| E::custom(ParseSizeError::new(value.to_string())))
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        u64::try_from(value)
+            .map_err(|_| E::custom(ParseSizeError::new(value.to_string())))
+            .and_then(|value| self.visit_u64(value))
+    }
+}
@@ -0,0 +1,201 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::mask::Shape;
+use crate::resize::resize_image;
+use crate::{FilterType, ResizeMode, Thumbnail, ThumbnailSize, DEFAULT_MATTE};
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+
+/// The position of an image in a sprite sheet
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SpriteCell {
+    /// The index of the image in the order it has been pushed
+    pub index: usize,
+
+    /// The horizontal position of the image in pixels
+    pub x: u32,
+
+    /// The vertical position of the image in pixels
+    pub y: u32,
+
+    /// The width of the image in pixels
+    pub width: u32,
+
+    /// The height of the image in pixels
+    pub height: u32,
+}
+
+/// Packs thumbnails into sprite sheets, e.g. for seek previews of videos.
+/// The images are placed into a grid of cells row by row. Images larger than the cells
+/// are scaled down to fit and all images are centered in their cell. When a sheet would
+/// exceed the maximum height the next images are placed onto a new sheet.
+///
+/// Example:
+/// ```
+/// use thumbnailer::{create_thumbnails, SpriteSheet, ThumbnailSize};
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let file = File::open("tests/assets/test.png").unwrap();
+/// let thumbnails = create_thumbnails(BufReader::new(file), mime::IMAGE_PNG, [ThumbnailSize::Icon; 5]).unwrap();
+/// let sheets = SpriteSheet::new(64, 64, 4)
+///     .padding(2)
+///     .extend(thumbnails)
+///     .finish()
+///     .unwrap();
+/// let (sheet, cells) = &sheets[0];
+/// assert_eq!(sheet.size(), (262, 130));
+/// assert_eq!(cells.len(), 5);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SpriteSheet {
+    cell_width: u32,
+    cell_height: u32,
+    columns: u32,
+    padding: u32,
+    background: Rgba<u8>,
+    max_height: Option<u32>,
+    images: Vec<DynamicImage>,
+}
+
+impl SpriteSheet {
+    /// Creates an empty sprite sheet with cells of the given size and the given number
+    /// of cells in each row
+    pub fn new(cell_width: u32, cell_height: u32, columns: u32) -> Self {
+        Self {
+            cell_width,
+            cell_height,
+            columns,
+            padding: 0,
+            background: Rgba([0, 0, 0, 0]),
+            max_height: None,
+            images: Vec::new(),
+        }
+    }
+
+    /// Sets the space between cells in pixels. Defaults to 0.
+    pub fn padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the color of the space around the images. Defaults to transparent.
+    pub fn background(mut self, color: Rgba<u8>) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Sets the maximum height of a sheet. Images that don't fit are placed onto
+    /// additional sheets. Defaults to no limit.
+    pub fn max_height(mut self, max_height: u32) -> Self {
+        self.max_height = Some(max_height);
+        self
+    }
+
+    /// Adds a thumbnail to the next cell
+    pub fn push(mut self, thumbnail: Thumbnail) -> Self {
+        self.images.push(thumbnail.into_inner());
+        self
+    }
+
+    /// Adds multiple thumbnails to the next cells in order
+    pub fn extend<I: IntoIterator<Item = Thumbnail>>(mut self, thumbnails: I) -> Self {
+        self.images
+            .extend(thumbnails.into_iter().map(Thumbnail::into_inner));
+        self
+    }
+
+    /// Packs the images into sheets and returns each sheet with the positions of its images.
+    /// Returns no sheets if no images were added.
+    pub fn finish(self) -> ThumbResult<Vec<(Thumbnail, Vec<SpriteCell>)>> {
+        let rows_per_sheet = self.rows_per_sheet()?;
+        let width = self.length(self.columns, self.cell_width)?;
+        let cells_per_sheet = (self.columns as usize).saturating_mul(rows_per_sheet as usize);
+
+        self.images
+            .chunks(cells_per_sheet)
+            .enumerate()
+            .map(|(sheet, images)| {
+                let rows = (images.len() as u32).div_ceil(self.columns);
+                let height = self.length(rows, self.cell_height)?;
+                let mut canvas = RgbaImage::from_pixel(width, height, self.background);
+                let mut cells = Vec::with_capacity(images.len());
+
+                for (i, image) in images.iter().enumerate() {
+                    let image =
+                        if image.width() > self.cell_width || image.height() > self.cell_height {
+                            resize_image(
+                                image,
+                                self.cell_width,
+                                self.cell_height,
+                                ResizeMode::Fit,
+                                FilterType::Lanczos3,
+                            )
+                        } else {
+                            image.clone()
+                        };
+                    let (column, row) = (i as u32 % self.columns, i as u32 / self.columns);
+                    let x = column * self.cell_width
+                        + column * self.padding
+                        + (self.cell_width - image.width()) / 2;
+                    let y = row * self.cell_height
+                        + row * self.padding
+                        + (self.cell_height - image.height()) / 2;
+                    imageops::overlay(&mut canvas, &image.to_rgba8(), x as i64, y as i64);
+                    cells.push(SpriteCell {
+                        index: sheet * cells_per_sheet + i,
+                        x,
+                        y,
+                        width: image.width(),
+                        height: image.height(),
+                    });
+                }
+
+                let thumbnail = Thumbnail {
+                    inner: DynamicImage::ImageRgba8(canvas),
+                    requested_size: ThumbnailSize::from_dimensions(width, height)?,
+                    matte: DEFAULT_MATTE,
+                    deterministic: false,
+                    dpi: None,
+                    shape: Shape::Rectangle,
+                };
+                Ok((thumbnail, cells))
+            })
+            .collect()
+    }
+
+    /// Returns the number of rows that fit into the maximum height
+    fn rows_per_sheet(&self) -> ThumbResult<u32> {
+        if self.cell_width == 0 || self.cell_height == 0 {
+            return Err(ThumbError::InvalidSize(self.cell_width, self.cell_height));
+        }
+        if self.columns == 0 {
+            return Err(ThumbError::InvalidOptions(
+                "sprite sheet without columns".to_string(),
+            ));
+        }
+        match self.max_height {
+            Some(max_height) if max_height < self.cell_height => {
+                Err(ThumbError::InvalidOptions(format!(
+                    "sprite sheet cells with a height of {} exceed the maximum height of {}",
+                    self.cell_height, max_height
+                )))
+            }
+            Some(max_height) => Ok(((max_height as u64 + self.padding as u64)
+                / (self.cell_height as u64 + self.padding as u64))
+                as u32),
+            None => Ok(u32::MAX),
+        }
+    }
+
+    /// Returns the length of the given number of cells including the padding between them
+    fn length(&self, cells: u32, cell: u32) -> ThumbResult<u32> {
+        cells
+            .checked_mul(cell)
+            .and_then(|length| length.checked_add((cells - 1).checked_mul(self.padding)?))
+            .ok_or_else(|| {
+                ThumbError::InvalidOptions(format!(
+                    "sprite sheet with {} cells of {} pixels is too large",
+                    cells, cell
+                ))
+            })
+    }
+}
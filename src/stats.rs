@@ -0,0 +1,34 @@
+use crate::ThumbnailSize;
+use image::ImageFormat;
+use std::time::Duration;
+
+/// The timing and dimensions of creating thumbnails that
+/// [ThumbnailerBuilder::run_with_stats](crate::ThumbnailerBuilder::run_with_stats) returns
+/// when [ThumbnailerBuilder::collect_stats](crate::ThumbnailerBuilder::collect_stats) is enabled
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenerationStats {
+    /// The time it took to decode the source, including extracting frames of videos and
+    /// rendering documents
+    pub decode_duration: Duration,
+
+    /// The width and height of the decoded image that the thumbnails were resized from,
+    /// which can be smaller than the source when it was decoded at a reduced scale
+    pub source_dimensions: (u32, u32),
+
+    /// The stats of each size that was resized in the order the sizes were added, without
+    /// the sizes that were skipped as duplicates
+    pub per_size: Vec<SizeStats>,
+
+    /// The detected format of the source if it is an image format
+    pub detected_format: Option<ImageFormat>,
+}
+
+/// The timing of resizing a single size
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SizeStats {
+    /// The requested size
+    pub size: ThumbnailSize,
+
+    /// The time it took to resize the source to the size including its post-processing steps
+    pub resize_duration: Duration,
+}
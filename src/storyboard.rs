@@ -0,0 +1,267 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::resize::resize_image;
+use crate::{FilterType, ResizeMode, SpriteCell, SpriteSheet, Thumbnail, ThumbnailSize};
+use std::io::Write;
+use std::time::Duration;
+
+#[cfg(feature = "ffmpeg")]
+use crate::formats::{get_interval_frames, DecodeOptions};
+#[cfg(feature = "ffmpeg")]
+use image::DynamicImage;
+#[cfg(feature = "ffmpeg")]
+use mime::Mime;
+#[cfg(feature = "ffmpeg")]
+use std::io::{BufRead, Seek};
+
+/// Options for the frames and sprite sheets of a storyboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StoryboardOptions {
+    /// The time between two frames. It's rounded down to whole milliseconds
+    /// which are the precision of WebVTT timestamps.
+    pub interval: Duration,
+
+    /// The size of each cell. The frames are fitted into the cells
+    /// preserving their aspect ratio.
+    pub cell_size: ThumbnailSize,
+
+    /// The number of cells in each row of the sprite sheets
+    pub columns: u32,
+
+    /// The maximum height of a sprite sheet. Frames that don't fit are placed
+    /// onto additional sheets.
+    pub max_height: Option<u32>,
+
+    /// The maximum number of frames that [generate_storyboard] extracts. Videos that
+    /// have more frames at the interval fail with [ThumbError::TooManyFrames].
+    pub max_frames: usize,
+}
+
+impl Default for StoryboardOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(10),
+            cell_size: ThumbnailSize::Custom {
+                width: 160,
+                height: 90,
+            },
+            columns: 10,
+            max_height: None,
+            max_frames: 1000,
+        }
+    }
+}
+
+impl StoryboardOptions {
+    /// Returns the interval in whole milliseconds
+    fn interval_millis(&self) -> ThumbResult<u64> {
+        match self.interval.as_millis() {
+            0 => Err(ThumbError::InvalidOptions(format!(
+                "storyboard interval of {:?} is shorter than a millisecond",
+                self.interval
+            ))),
+            millis => Ok(millis.min(u64::MAX as u128) as u64),
+        }
+    }
+}
+
+/// The time range in which a cell of a storyboard is shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StoryboardCue {
+    /// The start of the time range
+    pub start: Duration,
+
+    /// The end of the time range which is always after the start
+    pub end: Duration,
+
+    /// The index of the sprite sheet that contains the cell
+    pub sheet: usize,
+
+    /// The position of the frame in the sprite sheet
+    pub cell: SpriteCell,
+}
+
+/// Frames of a video packed into sprite sheets with the time ranges that they cover,
+/// as used by video players for previews while seeking
+#[derive(Clone, Debug)]
+pub struct Storyboard {
+    sheets: Vec<Thumbnail>,
+    cues: Vec<StoryboardCue>,
+}
+
+impl Storyboard {
+    /// Creates a storyboard from frames that are taken at every multiple of the interval.
+    /// The last cue ends at the end of the video or after the interval when the duration
+    /// isn't known or ends before the frame.
+    pub fn from_frames<I: IntoIterator<Item = Thumbnail>>(
+        frames: I,
+        duration: Option<Duration>,
+        options: StoryboardOptions,
+    ) -> ThumbResult<Self> {
+        let interval = options.interval_millis()?;
+        options.cell_size.validate()?;
+        let (cell_width, cell_height) = options.cell_size.dimensions();
+        let frames = frames.into_iter().map(|frame| {
+            let image = resize_image(
+                frame.as_image(),
+                cell_width,
+                cell_height,
+                ResizeMode::Fit,
+                FilterType::Lanczos3,
+            );
+            Thumbnail::from(image)
+        });
+        let mut sprite_sheet = SpriteSheet::new(cell_width, cell_height, options.columns);
+        if let Some(max_height) = options.max_height {
+            sprite_sheet = sprite_sheet.max_height(max_height);
+        }
+        let packed = sprite_sheet.extend(frames).finish()?;
+
+        let count = packed.iter().map(|(_, cells)| cells.len()).sum::<usize>();
+        let end = duration.map(|duration| duration.as_millis().min(u64::MAX as u128) as u64);
+        let mut sheets = Vec::with_capacity(packed.len());
+        let mut cues = Vec::with_capacity(count);
+        for (sheet, (thumbnail, cells)) in packed.into_iter().enumerate() {
+            sheets.push(thumbnail);
+            for cell in cells {
+                let start = (cell.index as u64).saturating_mul(interval);
+                let next = start.saturating_add(interval);
+                let end = match end {
+                    Some(end) if cell.index + 1 == count && end > start => end,
+                    _ => next,
+                };
+                cues.push(StoryboardCue {
+                    start: Duration::from_millis(start),
+                    end: Duration::from_millis(end),
+                    sheet,
+                    cell,
+                });
+            }
+        }
+
+        Ok(Self { sheets, cues })
+    }
+
+    /// Returns the sprite sheets in order
+    pub fn sheets(&self) -> &[Thumbnail] {
+        &self.sheets
+    }
+
+    /// Returns the sprite sheets
+    pub fn into_sheets(self) -> Vec<Thumbnail> {
+        self.sheets
+    }
+
+    /// Returns the cues in the order of their time ranges
+    pub fn cues(&self) -> &[StoryboardCue] {
+        &self.cues
+    }
+
+    /// Writes a WebVTT file that maps the time ranges to the cells of the sprite sheets.
+    /// `{}` in the url pattern is replaced with the index of the sheet, e.g. `thumbs-{}.jpg`,
+    /// and patterns without it are used for every sheet.
+    ///
+    /// Example:
+    /// ```
+    /// use thumbnailer::{Storyboard, StoryboardOptions, Thumbnail};
+    /// use thumbnailer::image::{DynamicImage, RgbImage};
+    /// use std::time::Duration;
+    ///
+    /// let frame = Thumbnail::from(DynamicImage::ImageRgb8(RgbImage::new(320, 180)));
+    /// let storyboard = Storyboard::from_frames(
+    ///     vec![frame; 2],
+    ///     Some(Duration::from_secs(15)),
+    ///     StoryboardOptions::default(),
+    /// )
+    /// .unwrap();
+    /// let mut vtt = Vec::new();
+    /// storyboard.write_vtt(&mut vtt, "thumbs.jpg").unwrap();
+    /// assert!(String::from_utf8(vtt).unwrap().ends_with(
+    ///     "00:00:10.000 --> 00:00:15.000\nthumbs.jpg#xywh=160,0,160,90\n"
+    /// ));
+    /// ```
+    pub fn write_vtt<W: Write>(&self, mut writer: W, image_url_pattern: &str) -> ThumbResult<()> {
+        writeln!(writer, "WEBVTT")?;
+        for cue in &self.cues {
+            let url = image_url_pattern.replace("{}", &cue.sheet.to_string());
+            let cell = &cue.cell;
+            write!(
+                writer,
+                "\n{} --> {}\n{}#xywh={},{},{},{}\n",
+                vtt_timestamp(cue.start),
+                vtt_timestamp(cue.end),
+                url,
+                cell.x,
+                cell.y,
+                cell.width,
+                cell.height
+            )?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Creates a storyboard of a video with a frame at every multiple of the interval.
+/// Videos that are shorter than the interval have a single cue. The orientation of
+/// the video isn't changed. Each frame is fitted into the cell size right after it is
+/// extracted, so that the full resolution frames aren't kept.
+///
+/// Example:
+/// ```no_run
+/// use thumbnailer::{generate_storyboard, StoryboardOptions};
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let file = File::open("tests/assets/test.mp4").unwrap();
+/// let storyboard = generate_storyboard(BufReader::new(file), "video/mp4".parse().unwrap(), StoryboardOptions::default()).unwrap();
+/// for (i, sheet) in storyboard.sheets().iter().enumerate() {
+///     sheet.save(format!("thumbs-{}.jpg", i)).unwrap();
+/// }
+/// storyboard.write_vtt(File::create("thumbs.vtt").unwrap(), "thumbs-{}.jpg").unwrap();
+/// ```
+#[cfg(feature = "ffmpeg")]
+pub fn generate_storyboard<R: BufRead + Seek>(
+    reader: R,
+    mime: Mime,
+    options: StoryboardOptions,
+) -> ThumbResult<Storyboard> {
+    let interval = Duration::from_millis(options.interval_millis()?);
+    options.cell_size.validate()?;
+    let (cell_width, cell_height) = options.cell_size.dimensions();
+    let decode_options = DecodeOptions {
+        target_size: Some((cell_width, cell_height)),
+        ..Default::default()
+    };
+    let fit = |frame: DynamicImage| {
+        resize_image(
+            &frame,
+            cell_width,
+            cell_height,
+            ResizeMode::Fit,
+            FilterType::Lanczos3,
+        )
+    };
+    let (frames, duration) = get_interval_frames(
+        reader,
+        mime,
+        interval,
+        options.max_frames,
+        fit,
+        &decode_options,
+    )?;
+
+    Storyboard::from_frames(frames.into_iter().map(Thumbnail::from), duration, options)
+}
+
+/// Formats the duration as a WebVTT timestamp with hours, minutes, seconds and milliseconds
+fn vtt_timestamp(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
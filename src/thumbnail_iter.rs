@@ -0,0 +1,133 @@
+use crate::error::ThumbResult;
+use crate::{Badge, ResizeMode, Thumbnail, ThumbnailSize, ThumbnailerBuilder};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::fmt;
+use std::vec;
+
+/// An iterator that resizes a decoded image to one size at a time as it is advanced.
+/// It is returned by [ThumbnailerBuilder::run_iter] and [crate::create_thumbnails_iter].
+pub struct ThumbnailIter {
+    builder: ThumbnailerBuilder,
+    image: DynamicImage,
+    dimensions: (u32, u32),
+    dpi: Option<(f32, f32)>,
+    badge: Option<Badge>,
+    mode: ResizeMode,
+    remaining: vec::IntoIter<(usize, ThumbnailSize)>,
+    completed: usize,
+    finished: bool,
+}
+
+impl ThumbnailIter {
+    /// Creates the iterator for the source, which is the decoded image after trimming,
+    /// and the sizes with their index in the order they were added
+    pub(crate) fn new(
+        builder: ThumbnailerBuilder,
+        source: DynamicImage,
+        dpi: Option<(f32, f32)>,
+        badge: Option<Badge>,
+        mode: ResizeMode,
+        sizes: Vec<(usize, ThumbnailSize)>,
+    ) -> Self {
+        Self {
+            builder,
+            dimensions: (source.width(), source.height()),
+            image: source,
+            dpi,
+            badge,
+            mode,
+            remaining: sizes.into_iter(),
+            completed: 0,
+            finished: false,
+        }
+    }
+
+    /// Returns the sizes that haven't been resized yet in the order they are produced
+    pub fn remaining_sizes(&self) -> impl Iterator<Item = ThumbnailSize> + '_ {
+        self.remaining.as_slice().iter().map(|(_, size)| *size)
+    }
+
+    /// Replaces the image with a copy that is still at least twice as large as the given and
+    /// the remaining sizes need, if that is at most half as large as the current image
+    fn downscale_for(&mut self, size: ThumbnailSize) {
+        let needed = 2.0
+            * std::iter::once(size)
+                .chain(self.remaining_sizes())
+                .map(|size| self.scale(size))
+                .fold(0.0, f64::max);
+        let current = self.image.width() as f64 / self.dimensions.0 as f64;
+        if needed <= 0.0 || current < 2.0 * needed {
+            return;
+        }
+
+        let (width, height) = self.dimensions;
+        let scaled = |dimension: u32| ((dimension as f64 * needed).ceil() as u32).max(1);
+        self.image = self
+            .image
+            .resize_exact(scaled(width), scaled(height), FilterType::Triangle);
+    }
+
+    /// Returns the factor the source is scaled by for the size. The larger factor of both
+    /// axes is used, so that sizes that crop or stretch the source are covered as well.
+    fn scale(&self, size: ThumbnailSize) -> f64 {
+        let (width, height, _) = self.builder.target(self.dimensions, size, self.mode);
+
+        (width as f64 / self.dimensions.0 as f64).max(height as f64 / self.dimensions.1 as f64)
+    }
+}
+
+impl Iterator for ThumbnailIter {
+    type Item = ThumbResult<(ThumbnailSize, Thumbnail)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let (index, size) = self.remaining.next()?;
+        if let Err(e) = self.builder.check_cancelled() {
+            self.finished = true;
+            return Some(Err(e));
+        }
+        if self.builder.is_progressive() {
+            self.downscale_for(size);
+        }
+
+        let builder = &self.builder;
+        let result = builder
+            .resize_size(
+                &self.image,
+                self.dimensions,
+                size,
+                self.mode,
+                self.badge.as_ref(),
+            )
+            .map(|image| {
+                let dpi = builder.thumbnail_dpi(self.dpi, self.dimensions, &image, size, self.mode);
+                (size, builder.thumbnail(image, size, dpi))
+            });
+        self.completed += 1;
+        let total = self.completed + self.remaining.len();
+        builder.report_resized(index, self.completed, total);
+
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.finished {
+            (0, Some(0))
+        } else {
+            (0, Some(self.remaining.len()))
+        }
+    }
+}
+
+impl fmt::Debug for ThumbnailIter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThumbnailIter")
+            .field("dimensions", &self.dimensions)
+            .field("remaining", &self.remaining.as_slice())
+            .field("completed", &self.completed)
+            .finish()
+    }
+}
@@ -0,0 +1,219 @@
+use crate::error::ThumbResult;
+#[cfg(feature = "ffmpeg")]
+use crate::VideoFrameSelector;
+use crate::{
+    BaseImage, GenerationStats, SourceInfo, Thumbnail, ThumbnailIter, ThumbnailSize,
+    ThumbnailerBuilder,
+};
+use mime::Mime;
+use std::io::{BufRead, Seek};
+use std::sync::Arc;
+
+lazy_static::lazy_static! {
+    static ref DEFAULT_THUMBNAILER: Thumbnailer = Thumbnailer::default();
+    static ref SNIFFING_THUMBNAILER: Thumbnailer =
+        Thumbnailer::builder().allow_sniffing(true).build();
+}
+
+/// A reusable thumbnailer with a fixed configuration. It is cheap to clone and can be
+/// shared between threads, so the configuration and the registry of format handlers
+/// are only set up once.
+///
+/// Example:
+/// ```
+/// use thumbnailer::{FilterType, Thumbnailer, ThumbnailSize};
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let thumbnailer = Thumbnailer::builder()
+///     .max_input_pixels(Some(50_000_000))
+///     .filter(FilterType::Triangle)
+///     .build();
+/// let file = File::open("tests/assets/test.png").unwrap();
+/// let thumbnails = thumbnailer
+///     .create(BufReader::new(file), mime::IMAGE_PNG, [ThumbnailSize::Small])
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Thumbnailer {
+    config: Arc<ThumbnailerBuilder>,
+}
+
+impl Thumbnailer {
+    /// Returns a builder for configuring the thumbnailer. Sizes added to the builder are
+    /// ignored because the sizes are passed to each call.
+    pub fn builder() -> ThumbnailerBuilder {
+        ThumbnailerBuilder::new()
+    }
+
+    /// Returns the thumbnailer that is used by [crate::create_thumbnails]
+    pub(crate) fn shared() -> &'static Self {
+        &DEFAULT_THUMBNAILER
+    }
+
+    /// Returns the thumbnailer that detects the media type from the content and is used
+    /// by [crate::create_thumbnails_guess] and [crate::create_thumbnails_from_path]
+    pub(crate) fn shared_sniffing() -> &'static Self {
+        &SNIFFING_THUMBNAILER
+    }
+
+    /// Creates thumbnails of the requested sizes like [ThumbnailerBuilder::run].
+    /// The thumbnails are returned in the same order as the requested sizes. Sizes that
+    /// create the same thumbnail as an earlier size are only resized once and get a copy.
+    pub fn create<R, I, S>(&self, reader: R, mime: Mime, sizes: I) -> ThumbResult<Vec<Thumbnail>>
+    where
+        R: BufRead + Seek,
+        I: IntoIterator<Item = S>,
+        S: Into<ThumbnailSize>,
+    {
+        self.with_sizes(sizes).run(reader, mime)
+    }
+
+    /// Creates thumbnails like [Thumbnailer::create] and returns them with the
+    /// information about the source like [ThumbnailerBuilder::run_with_meta]
+    pub fn create_with_meta<R, I, S>(
+        &self,
+        reader: R,
+        mime: Mime,
+        sizes: I,
+    ) -> ThumbResult<(SourceInfo, Vec<Thumbnail>)>
+    where
+        R: BufRead + Seek,
+        I: IntoIterator<Item = S>,
+        S: Into<ThumbnailSize>,
+    {
+        self.with_sizes(sizes).run_with_meta(reader, mime)
+    }
+
+    /// Creates thumbnails like [Thumbnailer::create] and returns them with the time each
+    /// stage took if the thumbnailer collects stats like [ThumbnailerBuilder::run_with_stats]
+    pub fn create_with_stats<R, I, S>(
+        &self,
+        reader: R,
+        mime: Mime,
+        sizes: I,
+    ) -> ThumbResult<(Option<GenerationStats>, Vec<Thumbnail>)>
+    where
+        R: BufRead + Seek,
+        I: IntoIterator<Item = S>,
+        S: Into<ThumbnailSize>,
+    {
+        self.with_sizes(sizes).run_with_stats(reader, mime)
+    }
+
+    /// Creates thumbnails like [Thumbnailer::create] with the result of each size
+    /// returned separately like [ThumbnailerBuilder::run_individual]
+    pub fn create_individual<R, I, S>(
+        &self,
+        reader: R,
+        mime: Mime,
+        sizes: I,
+    ) -> ThumbResult<Vec<(ThumbnailSize, ThumbResult<Thumbnail>)>>
+    where
+        R: BufRead + Seek,
+        I: IntoIterator<Item = S>,
+        S: Into<ThumbnailSize>,
+    {
+        self.with_sizes(sizes).run_individual(reader, mime)
+    }
+
+    /// Returns an iterator that resizes the requested sizes one at a time like
+    /// [ThumbnailerBuilder::run_iter]
+    pub fn create_iter<R, I, S>(
+        &self,
+        reader: R,
+        mime: Mime,
+        sizes: I,
+    ) -> ThumbResult<ThumbnailIter>
+    where
+        R: BufRead + Seek,
+        I: IntoIterator<Item = S>,
+        S: Into<ThumbnailSize>,
+    {
+        self.with_sizes(sizes).run_iter(reader, mime)
+    }
+
+    /// Creates thumbnails like [Thumbnailer::create] for a reader that can't seek
+    /// like [ThumbnailerBuilder::run_unseekable]
+    pub fn create_unseekable<R, I, S>(
+        &self,
+        reader: R,
+        mime: Mime,
+        sizes: I,
+    ) -> ThumbResult<Vec<Thumbnail>>
+    where
+        R: BufRead,
+        I: IntoIterator<Item = S>,
+        S: Into<ThumbnailSize>,
+    {
+        self.with_sizes(sizes).run_unseekable(reader, mime)
+    }
+
+    /// Creates thumbnails of the requested sizes from multiple frames of a video like
+    /// [ThumbnailerBuilder::run_video_frames]. The results are returned in the same order
+    /// as the selectors.
+    #[cfg(feature = "ffmpeg")]
+    pub fn create_video_thumbnails<R, I, S>(
+        &self,
+        reader: R,
+        mime: Mime,
+        selectors: &[VideoFrameSelector],
+        sizes: I,
+    ) -> ThumbResult<Vec<(VideoFrameSelector, Vec<Thumbnail>)>>
+    where
+        R: BufRead + Seek,
+        I: IntoIterator<Item = S>,
+        S: Into<ThumbnailSize>,
+    {
+        self.with_sizes(sizes)
+            .run_video_frames(reader, mime, selectors)
+    }
+
+    /// Decodes the given reader into a [BaseImage] that can be resized multiple times
+    /// with [Thumbnailer::create_from_base]. Vector formats are rendered big enough for
+    /// the given sizes.
+    pub fn decode<R, I, S>(&self, reader: R, mime: Mime, sizes: I) -> ThumbResult<BaseImage>
+    where
+        R: BufRead + Seek,
+        I: IntoIterator<Item = S>,
+        S: Into<ThumbnailSize>,
+    {
+        self.with_sizes(sizes).decode(reader, mime)
+    }
+
+    /// Creates thumbnails of the requested sizes from an already decoded image
+    pub fn create_from_base<I, S>(&self, base: &BaseImage, sizes: I) -> ThumbResult<Vec<Thumbnail>>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<ThumbnailSize>,
+    {
+        self.with_sizes(sizes).run_base(base)
+    }
+
+    /// Returns whether a handler for the media type is registered
+    pub fn supports_mime(&self, mime: &Mime) -> bool {
+        self.config.supports_mime(mime)
+    }
+
+    /// Returns the configuration with the sizes of a single call, which returns a thumbnail
+    /// for each of them
+    fn with_sizes<I, S>(&self, sizes: I) -> ThumbnailerBuilder
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<ThumbnailSize>,
+    {
+        self.config
+            .with_sizes(sizes.into_iter().map(Into::into).collect())
+            .copy_duplicates(true)
+    }
+}
+
+impl ThumbnailerBuilder {
+    /// Creates a reusable [Thumbnailer] with the configured options.
+    /// The configured sizes are ignored because they are passed to each call.
+    pub fn build(self) -> Thumbnailer {
+        Thumbnailer {
+            config: Arc::new(self.with_sizes(Vec::new())),
+        }
+    }
+}
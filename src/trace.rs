@@ -0,0 +1,106 @@
+//! Spans and events of the `tracing` feature. Without the feature the macros expand to
+//! nothing and spans are a zero sized type, so no subscriber is required either way.
+
+use crate::error::ThumbResult;
+use std::io::{Seek, Write};
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::Span;
+
+/// A span that doesn't record anything
+#[cfg(not(feature = "tracing"))]
+#[derive(Clone, Debug)]
+pub(crate) struct Span;
+
+#[cfg(not(feature = "tracing"))]
+impl Span {
+    pub fn record<V>(&self, _field: &str, _value: V) -> &Self {
+        self
+    }
+
+    pub fn in_scope<T, F: FnOnce() -> T>(&self, f: F) -> T {
+        f()
+    }
+}
+
+/// Creates a debug level span with structured fields like [tracing::debug_span]
+macro_rules! trace_span {
+    ($($args:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!($($args)*);
+        #[cfg(not(feature = "tracing"))]
+        let span = crate::trace::Span;
+        span
+    }};
+}
+
+/// Emits a debug level event with structured fields like [tracing::debug]
+macro_rules! trace_event {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($args)*);
+    };
+}
+
+pub(crate) use trace_event;
+pub(crate) use trace_span;
+
+/// The time a span started. The clock is only read with the feature, as it isn't available
+/// on every target and panics on wasm32-unknown-unknown.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Start {
+    #[cfg(feature = "tracing")]
+    instant: Instant,
+}
+
+impl Start {
+    pub fn now() -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            instant: Instant::now(),
+        }
+    }
+}
+
+/// Records the milliseconds since the start in the `elapsed_ms` field of the span
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn record_elapsed(span: &Span, start: Start) {
+    #[cfg(feature = "tracing")]
+    span.record("elapsed_ms", start.instant.elapsed().as_secs_f64() * 1000.0);
+}
+
+/// Runs the encoder in an `encode` span that records the number of bytes it wrote.
+/// The stream position is only queried with the feature, as it flushes buffered writers.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn encode_traced<W, F>(
+    writer: &mut W,
+    format: &str,
+    (width, height): (u32, u32),
+    encode: F,
+) -> ThumbResult<()>
+where
+    W: Write + Seek,
+    F: FnOnce(&mut W) -> ThumbResult<()>,
+{
+    let span = trace_span!(
+        "encode",
+        format,
+        width,
+        height,
+        bytes = tracing::field::Empty,
+        elapsed_ms = tracing::field::Empty,
+    );
+    let start = Start::now();
+    #[cfg(feature = "tracing")]
+    let position = writer.stream_position().ok();
+    span.in_scope(|| encode(writer))?;
+    #[cfg(feature = "tracing")]
+    if let (Some(position), Ok(end)) = (position, writer.stream_position()) {
+        span.record("bytes", end.saturating_sub(position));
+    }
+    record_elapsed(&span, start);
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use crate::mask::{border, mask, Shape};
+use crate::FilterType;
+use image::{DynamicImage, GenericImageView, Rgba};
+
+/// A clockwise rotation that is applied to the source before resizing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
+/// The axis the source is mirrored along before resizing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FlipDirection {
+    /// Mirrors the image from left to right
+    Horizontal,
+
+    /// Mirrors the image from top to bottom
+    Vertical,
+}
+
+/// The rotation and flip that are applied to the source after it has been decoded
+/// and oriented according to its metadata. The rotation is applied before the flip.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Orientation {
+    pub rotation: Option<Rotation>,
+    pub flip: Option<FlipDirection>,
+}
+
+impl Orientation {
+    pub fn apply(&self, image: DynamicImage) -> DynamicImage {
+        let image = match self.rotation {
+            Some(Rotation::Cw90) => image.rotate90(),
+            Some(Rotation::Cw180) => image.rotate180(),
+            Some(Rotation::Cw270) => image.rotate270(),
+            None => image,
+        };
+        match self.flip {
+            Some(FlipDirection::Horizontal) => image.fliph(),
+            Some(FlipDirection::Vertical) => image.flipv(),
+            None => image,
+        }
+    }
+}
+
+/// A post-processing step applied to thumbnails after resizing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Transform {
+    Blur(f32),
+    Pixelate(u32),
+    Grayscale,
+    Brightness(i32),
+    Mask(Shape),
+    /// A border that follows the shape of the last mask applied before it
+    Border {
+        width: u32,
+        color: Rgba<u8>,
+        shape: Shape,
+    },
+}
+
+impl Transform {
+    /// Returns whether the blur sigma is positive or zero
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Transform::Blur(sigma) => sigma.is_finite() && *sigma >= 0.0,
+            _ => true,
+        }
+    }
+
+    pub fn apply(&self, image: DynamicImage) -> DynamicImage {
+        match *self {
+            Transform::Blur(sigma) => blur(image, sigma),
+            Transform::Pixelate(block_size) => pixelate(image, block_size),
+            Transform::Grayscale => image.grayscale(),
+            Transform::Brightness(value) => image.brighten(value),
+            Transform::Mask(shape) => mask(image, shape),
+            Transform::Border {
+                width,
+                color,
+                shape,
+            } => border(image, shape, width, color),
+        }
+    }
+}
+
+/// Applies a gaussian blur. Sigmas that aren't positive leave the image unchanged.
+fn blur(image: DynamicImage, sigma: f32) -> DynamicImage {
+    if sigma.is_finite() && sigma > 0.0 {
+        image.blur(sigma)
+    } else {
+        image
+    }
+}
+
+/// Replaces blocks of pixels with a single color by scaling the image down and up again.
+/// A block size of 0 is treated as 1 and block sizes larger than the image as the size
+/// of the image.
+fn pixelate(image: DynamicImage, block_size: u32) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    let block_size = block_size.clamp(1, width.max(height).max(1));
+    if block_size == 1 || width == 0 || height == 0 {
+        return image;
+    }
+    let small = image.resize_exact(
+        width.div_ceil(block_size),
+        height.div_ceil(block_size),
+        FilterType::Nearest,
+    );
+    // the upscaled image covers whole blocks and is cropped to the original size
+    // so that every block has the exact size even at the right and bottom edges
+    small
+        .resize_exact(
+            small.width() * block_size,
+            small.height() * block_size,
+            FilterType::Nearest,
+        )
+        .crop_imm(0, 0, width, height)
+}
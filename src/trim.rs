@@ -0,0 +1,179 @@
+use image::{DynamicImage, GenericImageView, Rgba};
+
+/// The tolerance of [TrimMode::Auto] for images without transparent borders
+const AUTO_TOLERANCE: u8 = 8;
+
+/// Images with a longer edge are scanned in blocks of at most this many per edge first
+const COARSE_SIZE: u32 = 256;
+
+/// How the borders around the content of the source are detected before resizing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrimMode {
+    /// Removes borders of fully transparent pixels
+    Transparent,
+
+    /// Removes borders of pixels whose channels, including alpha, differ from the color
+    /// by at most the tolerance
+    Color { color: Rgba<u8>, tolerance: u8 },
+
+    /// Removes transparent borders of images with alpha whose top left pixel is
+    /// transparent and borders with the color of the top left pixel otherwise
+    Auto,
+}
+
+impl TrimMode {
+    /// Resolves the automatic mode for the image
+    fn resolve(&self, image: &DynamicImage) -> TrimMode {
+        match *self {
+            TrimMode::Auto if image.width() == 0 || image.height() == 0 => TrimMode::Transparent,
+            TrimMode::Auto => {
+                let corner = image.get_pixel(0, 0);
+                if image.color().has_alpha() && corner[3] == 0 {
+                    TrimMode::Transparent
+                } else {
+                    TrimMode::Color {
+                        color: corner,
+                        tolerance: AUTO_TOLERANCE,
+                    }
+                }
+            }
+            mode => mode,
+        }
+    }
+
+    /// Returns whether the pixel belongs to a border that is removed
+    fn is_border(&self, pixel: Rgba<u8>) -> bool {
+        match *self {
+            TrimMode::Transparent => pixel[3] == 0,
+            TrimMode::Color { color, tolerance } => pixel
+                .0
+                .iter()
+                .zip(color.0)
+                .all(|(a, b)| a.abs_diff(b) <= tolerance),
+            TrimMode::Auto => false,
+        }
+    }
+}
+
+/// A rectangle of the source with its left, top, right and bottom edge, where the
+/// right and bottom edges are exclusive
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Bounds {
+    left: u32,
+    top: u32,
+    right: u32,
+    bottom: u32,
+}
+
+impl Bounds {
+    /// Returns the smallest bounds that contain both
+    pub fn union(self, other: Bounds) -> Bounds {
+        Bounds {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+}
+
+/// Crops the image to the bounds of its content. Images without content, e.g. fully
+/// transparent ones, are reduced to their top left pixel so that trimming never results
+/// in an empty image. Returns `None` if nothing is trimmed.
+pub(crate) fn crop_to_content(
+    image: &DynamicImage,
+    bounds: Option<Bounds>,
+) -> Option<DynamicImage> {
+    let (width, height) = image.dimensions();
+    let bounds = bounds.unwrap_or(Bounds {
+        left: 0,
+        top: 0,
+        right: width.min(1),
+        bottom: height.min(1),
+    });
+    if (bounds.left, bounds.top, bounds.right, bounds.bottom) == (0, 0, width, height) {
+        return None;
+    }
+
+    Some(image.crop_imm(
+        bounds.left,
+        bounds.top,
+        bounds.right - bounds.left,
+        bounds.bottom - bounds.top,
+    ))
+}
+
+/// Returns the bounds of the content inside the borders of the image or `None` if
+/// the image only consists of borders
+pub(crate) fn trim_bounds(image: &DynamicImage, mode: TrimMode) -> Option<Bounds> {
+    let (width, height) = image.dimensions();
+    let mode = mode.resolve(image);
+    let search = if width.max(height) > COARSE_SIZE {
+        coarse_bounds(image, mode)?
+    } else {
+        Bounds {
+            left: 0,
+            top: 0,
+            right: width,
+            bottom: height,
+        }
+    };
+
+    content_bounds(search, |x, y| !mode.is_border(image.get_pixel(x, y)))
+}
+
+/// Finds the blocks of the image that contain any content and returns the area of the
+/// full image that they cover. Each block keeps the minimum and maximum of each channel,
+/// which both belong to the border only if all pixels of the block do, so that thin or
+/// faint content isn't lost like it would be by averaging.
+fn coarse_bounds(image: &DynamicImage, mode: TrimMode) -> Option<Bounds> {
+    let (width, height) = image.dimensions();
+    let block = width.max(height).div_ceil(COARSE_SIZE);
+    let columns = width.div_ceil(block);
+    let rows = height.div_ceil(block);
+
+    let mut ranges = vec![([u8::MAX; 4], [u8::MIN; 4]); (columns * rows) as usize];
+    for (x, y, pixel) in image.pixels() {
+        let (min, max) = &mut ranges[((y / block) * columns + x / block) as usize];
+        for (channel, value) in pixel.0.iter().enumerate() {
+            min[channel] = min[channel].min(*value);
+            max[channel] = max[channel].max(*value);
+        }
+    }
+    let blocks = Bounds {
+        left: 0,
+        top: 0,
+        right: columns,
+        bottom: rows,
+    };
+    let coarse = content_bounds(blocks, |x, y| {
+        let (min, max) = ranges[(y * columns + x) as usize];
+        !(mode.is_border(Rgba(min)) && mode.is_border(Rgba(max)))
+    })?;
+
+    Some(Bounds {
+        left: coarse.left * block,
+        top: coarse.top * block,
+        right: (coarse.right * block).min(width),
+        bottom: (coarse.bottom * block).min(height),
+    })
+}
+
+/// Scans the rows and columns of the search area from its edges inwards and returns
+/// the bounds of the positions that are content
+fn content_bounds<F: Fn(u32, u32) -> bool>(search: Bounds, is_content: F) -> Option<Bounds> {
+    let row = |y: u32| (search.left..search.right).any(|x| is_content(x, y));
+
+    let top = (search.top..search.bottom).find(|&y| row(y))?;
+    let bottom = (top..search.bottom).rev().find(|&y| row(y))? + 1;
+    let column = |x: u32| (top..bottom).any(|y| is_content(x, y));
+    let left = (search.left..search.right).find(|&x| column(x))?;
+    let right = (left..search.right).rev().find(|&x| column(x))? + 1;
+
+    Some(Bounds {
+        left,
+        top,
+        right,
+        bottom,
+    })
+}
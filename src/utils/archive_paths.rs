@@ -0,0 +1,85 @@
+use std::cmp::Ordering;
+
+/// The file extensions of images in archives
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp", "tif", "tiff"];
+
+/// Returns whether the archive file is an image that isn't hidden or a macos resource fork
+pub fn is_image_path(name: &str) -> bool {
+    if name.ends_with('/') || name.starts_with("__MACOSX/") {
+        return false;
+    }
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    if file_name.starts_with('.') {
+        return false;
+    }
+
+    file_name.rsplit_once('.').is_some_and(|(_, extension)| {
+        IMAGE_EXTENSIONS
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(extension))
+    })
+}
+
+/// Compares paths by their segments like a depth first traversal that lists the
+/// entries of each directory in natural order
+#[cfg(feature = "archive")]
+pub fn path_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.split('/'), b.split('/'));
+    loop {
+        match (a.next(), b.next()) {
+            (Some(x), Some(y)) => match natural_cmp(x, y) {
+                Ordering::Equal => {}
+                ordering => return ordering,
+            },
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+        }
+    }
+}
+
+/// Compares file names with runs of digits compared by their numeric value
+/// and the remaining text compared case insensitively
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        match (a.chars().next(), b.chars().next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x_digits, x_rest) = split_digits(a);
+                let (y_digits, y_rest) = split_digits(b);
+                let (x_number, y_number) = (
+                    x_digits.trim_start_matches('0'),
+                    y_digits.trim_start_matches('0'),
+                );
+                let ordering = x_number
+                    .len()
+                    .cmp(&y_number.len())
+                    .then_with(|| x_number.cmp(y_number));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a = x_rest;
+                b = y_rest;
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a = &a[x.len_utf8()..];
+                b = &b[y.len_utf8()..];
+            }
+        }
+    }
+}
+
+fn split_digits(text: &str) -> (&str, &str) {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+
+    text.split_at(end)
+}
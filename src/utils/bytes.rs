@@ -0,0 +1,27 @@
+use std::borrow::Cow;
+use std::io::{self, BufRead, Seek, SeekFrom};
+
+/// Returns the remaining content of the reader. Readers that already hold all of it in
+/// their buffer, like cursors over slices and vectors, return it without copying, so
+/// decoders that work on slices don't need another copy of the input.
+pub fn read_bytes<R: BufRead + Seek + ?Sized>(reader: &mut R) -> io::Result<Cow<'_, [u8]>> {
+    if let Some(remaining) = remaining_len(reader) {
+        if reader.fill_buf()?.len() as u64 == remaining {
+            return Ok(Cow::Borrowed(reader.fill_buf()?));
+        }
+    }
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+
+    Ok(Cow::Owned(buf))
+}
+
+/// Returns the number of bytes after the current position or `None` if the reader
+/// can't seek to its end
+fn remaining_len<R: Seek + ?Sized>(reader: &mut R) -> Option<u64> {
+    let position = reader.stream_position().ok()?;
+    let end = reader.seek(SeekFrom::End(0)).ok()?;
+    reader.seek(SeekFrom::Start(position)).ok()?;
+
+    Some(end.saturating_sub(position))
+}
@@ -1,51 +1,209 @@
 use crate::error::ThumbError;
+use crate::formats::Cancellation;
 use crate::ThumbResult;
 use std::ffi::OsStr;
-use std::io::ErrorKind;
-use std::process::{Command, Stdio};
+use std::io::{self, ErrorKind, Read};
+use std::process::{Child, Command, Output, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 const FFMPEG: &str = "ffmpeg";
+const FFPROBE: &str = "ffprobe";
 
-/// Runs ffmpeg to retrieve a png video frame
-pub fn get_png_frame(video_file: &str, index: usize) -> ThumbResult<Vec<u8>> {
-    ffmpeg([
-        "-loglevel",
-        "panic",
-        "-i",
-        video_file,
-        "-vf",
-        format!("select=eq(n\\,{})", index).as_str(),
-        "-vframes",
-        "1",
-        "-c:v",
-        "png",
-        "-movflags",
-        "empty_moov",
-        "-f",
-        "image2pipe",
-        "pipe:1",
-    ])
+/// How often a running process is checked for cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runs ffmpeg to retrieve a png video frame at the given timestamp
+pub fn get_png_frame(
+    video_file: &str,
+    timestamp: Duration,
+    cancellation: &Cancellation,
+) -> ThumbResult<Vec<u8>> {
+    ffmpeg(
+        [
+            "-loglevel",
+            "error",
+            "-ss",
+            format!("{:.3}", timestamp.as_secs_f64()).as_str(),
+            "-i",
+            video_file,
+            "-frames:v",
+            "1",
+            "-c:v",
+            "png",
+            "-f",
+            "image2pipe",
+            "pipe:1",
+        ],
+        cancellation,
+    )
+}
+
+/// Runs ffmpeg to retrieve the png video frame with the given index
+pub fn get_png_frame_by_index(
+    video_file: &str,
+    index: u64,
+    cancellation: &Cancellation,
+) -> ThumbResult<Vec<u8>> {
+    ffmpeg(
+        [
+            "-loglevel",
+            "error",
+            "-i",
+            video_file,
+            "-vf",
+            format!("select=eq(n\\,{})", index).as_str(),
+            "-frames:v",
+            "1",
+            "-c:v",
+            "png",
+            "-f",
+            "image2pipe",
+            "pipe:1",
+        ],
+        cancellation,
+    )
+}
+
+/// Runs ffmpeg to retrieve the last video frame as png
+pub fn get_last_png_frame(video_file: &str, cancellation: &Cancellation) -> ThumbResult<Vec<u8>> {
+    ffmpeg(
+        [
+            "-loglevel",
+            "error",
+            "-sseof",
+            "-1",
+            "-i",
+            video_file,
+            "-vf",
+            "reverse",
+            "-frames:v",
+            "1",
+            "-c:v",
+            "png",
+            "-f",
+            "image2pipe",
+            "pipe:1",
+        ],
+        cancellation,
+    )
+}
+
+/// Runs ffprobe to retrieve the duration of a video. Returns `None` if the
+/// container doesn't provide a duration.
+pub fn get_video_duration(
+    video_file: &str,
+    cancellation: &Cancellation,
+) -> ThumbResult<Option<Duration>> {
+    let output = run(
+        FFPROBE,
+        [
+            "-loglevel",
+            "error",
+            "-show_entries",
+            "format=duration",
+            "-of",
+            "default=noprint_wrappers=1:nokey=1",
+            video_file,
+        ],
+        cancellation,
+    )?;
+    let duration = String::from_utf8_lossy(&output)
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(Duration::from_secs_f64);
+
+    Ok(duration)
 }
 
 /// Runs ffmpeg with the given args
-fn ffmpeg<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(args: I) -> ThumbResult<Vec<u8>> {
-    let child = Command::new(FFMPEG)
+fn ffmpeg<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
+    args: I,
+    cancellation: &Cancellation,
+) -> ThumbResult<Vec<u8>> {
+    run(FFMPEG, args, cancellation)
+}
+
+/// Runs the given command and returns its stdout. The process is killed once the
+/// operation is cancelled or times out, e.g. when ffmpeg spins on a corrupt video.
+fn run<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
+    program: &str,
+    args: I,
+    cancellation: &Cancellation,
+) -> ThumbResult<Vec<u8>> {
+    cancellation.check()?;
+    let child = Command::new(program)
         .args(args)
         .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn()?;
 
-    let output = child.wait_with_output()?;
+    let output = if cancellation.is_cancellable() {
+        wait_cancellable(child, cancellation)?
+    } else {
+        child.wait_with_output()?
+    };
     if output.status.success() && !output.stdout.is_empty() {
         Ok(output.stdout)
     } else {
-        Err(ThumbError::FFMPEG(
+        Err(ThumbError::decode(
+            "video",
             String::from_utf8_lossy(&output.stderr[..]).to_string(),
         ))
     }
 }
 
+/// Waits for the process while checking the cancellation. The output is read on
+/// background threads, so that the process doesn't block on a full pipe.
+fn wait_cancellable(mut child: Child, cancellation: &Cancellation) -> ThumbResult<Output> {
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let status = loop {
+        if let Err(e) = cancellation.check() {
+            // the process might have exited in the meantime
+            let _ = child.kill();
+            child.wait()?;
+            return Err(e);
+        }
+        match child.try_wait()? {
+            Some(status) => break status,
+            None => thread::sleep(POLL_INTERVAL),
+        }
+    };
+    let join = |handle: JoinHandle<io::Result<Vec<u8>>>| {
+        handle
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("reading the output panicked")))
+    };
+
+    Ok(Output {
+        status,
+        stdout: join(stdout)?,
+        stderr: join(stderr)?,
+    })
+}
+
+/// Reads the pipe to its end on a new thread
+fn read_in_background<R: Read + Send + 'static>(
+    pipe: Option<R>,
+) -> JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut buf)?;
+        }
+        Ok(buf)
+    })
+}
+
 pub fn is_ffmpeg_installed() -> bool {
-    match Command::new("ffmpeg").args(["-loglevel", "quiet"]).spawn() {
+    match Command::new(FFMPEG)
+        .args(["-loglevel", "quiet", "-version"])
+        .stdout(Stdio::null())
+        .spawn()
+    {
         Ok(_) => true,
         Err(e) => !matches!(e.kind(), ErrorKind::NotFound),
     }
@@ -0,0 +1,54 @@
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+
+/// Wraps a reader that can't seek. Seeking is only supported forward by
+/// skipping bytes, other seeks result in an error.
+pub struct ForwardReader<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R: BufRead> ForwardReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, position: 0 }
+    }
+
+    fn skip(&mut self, amount: u64) -> io::Result<u64> {
+        let skipped = io::copy(&mut (&mut self.inner).take(amount), &mut io::sink())?;
+        self.position += skipped;
+
+        Ok(self.position)
+    }
+}
+
+impl<R: BufRead> Read for ForwardReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.position += read as u64;
+
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for ForwardReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount);
+        self.position += amount as u64;
+    }
+}
+
+impl<R: BufRead> Seek for ForwardReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Start(offset) if offset >= self.position => self.skip(offset - self.position),
+            SeekFrom::Current(offset) if offset >= 0 => self.skip(offset as u64),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the reader can only seek forward",
+            )),
+        }
+    }
+}
@@ -1 +1,6 @@
+#[cfg(any(feature = "ebook", feature = "archive"))]
+pub mod archive_paths;
+pub mod bytes;
+#[cfg(feature = "ffmpeg")]
 pub mod ffmpeg_cli;
+pub mod forward_reader;
@@ -0,0 +1,394 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::quantize::IndexedImage;
+use crate::DEFAULT_JPEG_QUALITY;
+#[cfg(feature = "avif-encode")]
+use crate::{DEFAULT_AVIF_QUALITY, DEFAULT_AVIF_SPEED};
+use image::codecs::png::CompressionType;
+use image::{RgbImage, RgbaImage};
+use jpeg_encoder::{
+    ChromaSubsamplingMethod, PixelDensity, PixelDensityUnit, QuantizationTableType, SamplingFactor,
+};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::io::Write;
+use std::iter::StepBy;
+use std::ops::Range;
+
+/// The start and step size on both axes of the seven passes of adam7 interlacing
+const ADAM7_PASSES: [(u32, u32, u32, u32); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Encoder options for writing png thumbnails
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PngOptions {
+    /// Writes the image with adam7 interlacing so that it can be displayed
+    /// incrementally while loading
+    pub interlaced: bool,
+
+    /// The compression of the image data
+    pub compression: CompressionType,
+
+    /// Writes an indexed image with a palette of the quantized colors instead of rgba
+    /// pixels, which is a lot smaller for small thumbnails
+    pub palette: Option<PaletteOptions>,
+}
+
+/// Options for quantizing png thumbnails to a palette
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PaletteOptions {
+    /// The most colors of the palette (2-256) including the transparent color.
+    /// Images with more colors are quantized, others keep their exact colors.
+    pub max_colors: u16,
+
+    /// How quantization errors are spread to neighbouring pixels
+    pub dithering: Dithering,
+
+    /// Pixels with an alpha at or below the threshold are written fully transparent.
+    /// Others keep their alpha unless `binary_alpha` is set.
+    pub alpha_threshold: u8,
+
+    /// Blends semi-transparent pixels above the threshold onto the matte color, so that
+    /// every pixel is either fully transparent or opaque
+    pub binary_alpha: bool,
+}
+
+impl Default for PaletteOptions {
+    fn default() -> Self {
+        Self {
+            max_colors: 256,
+            dithering: Dithering::default(),
+            alpha_threshold: 8,
+            binary_alpha: false,
+        }
+    }
+}
+
+impl PaletteOptions {
+    /// Returns an error if the number of colors can't be written as a palette
+    pub(crate) fn validate(&self) -> ThumbResult<()> {
+        if (2..=256).contains(&self.max_colors) {
+            Ok(())
+        } else {
+            Err(ThumbError::InvalidOptions(format!(
+                "a palette can't have {} colors",
+                self.max_colors
+            )))
+        }
+    }
+}
+
+/// How the errors of quantizing colors are spread to the neighbouring pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dithering {
+    /// Every pixel gets the closest color of the palette, which keeps flat areas clean
+    None,
+
+    /// Spreads the error with Floyd-Steinberg dithering, which smooths gradients
+    #[default]
+    FloydSteinberg,
+}
+
+/// Encoder options for writing jpeg thumbnails
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JpegOptions {
+    /// Writes the image as a progressive jpeg with multiple scans that
+    /// refine the image while loading
+    pub progressive: bool,
+
+    /// The quality of the image (1-100)
+    pub quality: u8,
+}
+
+impl Default for JpegOptions {
+    fn default() -> Self {
+        Self {
+            progressive: false,
+            quality: DEFAULT_JPEG_QUALITY,
+        }
+    }
+}
+
+/// Encoder options for writing avif thumbnails
+#[cfg(feature = "avif-encode")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AvifOptions {
+    /// The quality of the image (1-100)
+    pub quality: u8,
+
+    /// The speed of the encoder (1-10). Faster speeds compress slightly worse.
+    pub speed: u8,
+
+    /// Keeps the alpha channel of transparent thumbnails. If disabled transparent
+    /// areas are blended onto the matte color.
+    pub alpha: bool,
+}
+
+#[cfg(feature = "avif-encode")]
+impl Default for AvifOptions {
+    fn default() -> Self {
+        Self {
+            quality: DEFAULT_AVIF_QUALITY,
+            speed: DEFAULT_AVIF_SPEED,
+            alpha: true,
+        }
+    }
+}
+
+/// The single pass of images that aren't interlaced
+const SINGLE_PASS: [(u32, u32, u32, u32); 1] = [(0, 0, 1, 1)];
+
+/// Writes an rgba png with rows filtered by the paeth filter and compressed with a fixed
+/// zlib implementation so that the output only depends on the pixels and options.
+/// Interlaced images are written with adam7 interlacing where each pass is filtered and
+/// all passes are compressed together into a single zlib stream.
+pub(crate) fn write_filtered_png<W: Write>(
+    writer: &mut W,
+    image: &RgbaImage,
+    compression: CompressionType,
+    interlaced: bool,
+    dpi: Option<(f32, f32)>,
+) -> ThumbResult<()> {
+    let (width, height) = image.dimensions();
+    let (level, filter) = compression_level(compression);
+
+    let mut data = Vec::new();
+    let mut previous = Vec::new();
+    for_each_row(width, height, interlaced, |y, columns, first| {
+        let row: Vec<u8> = columns.flat_map(|x| image.get_pixel(x, y).0).collect();
+        if first {
+            previous = vec![0u8; row.len()];
+        }
+        if filter {
+            data.push(4);
+            data.extend(paeth_filter(&row, &previous));
+        } else {
+            data.push(0);
+            data.extend_from_slice(&row);
+        }
+        previous = row;
+    });
+
+    let mut info = png::Info::with_size(width, height);
+    info.color_type = png::ColorType::Rgba;
+    info.bit_depth = png::BitDepth::Eight;
+    write_png_data(writer, info, &data, level, interlaced, dpi)
+}
+
+/// Writes an indexed png with the smallest bit depth that fits the palette, where the
+/// alpha of the semi-transparent colors at the start of the palette is written to a
+/// transparency chunk. Rows aren't filtered, which compresses indices best, and are
+/// compressed like [write_filtered_png] does.
+pub(crate) fn write_indexed_png<W: Write>(
+    writer: &mut W,
+    image: &IndexedImage,
+    compression: CompressionType,
+    interlaced: bool,
+    dpi: Option<(f32, f32)>,
+) -> ThumbResult<()> {
+    let (width, height) = (image.width, image.height);
+    let (level, _) = compression_level(compression);
+    let (bit_depth, bits) = match image.palette.len() {
+        0..=2 => (png::BitDepth::One, 1),
+        3..=4 => (png::BitDepth::Two, 2),
+        5..=16 => (png::BitDepth::Four, 4),
+        _ => (png::BitDepth::Eight, 8),
+    };
+
+    let mut data = Vec::new();
+    for_each_row(width, height, interlaced, |y, columns, _| {
+        data.push(0);
+        let mut byte = 0u8;
+        let mut filled = 0;
+        for x in columns {
+            let index = image.indices[(y * width + x) as usize];
+            byte |= index << (8 - bits - filled);
+            filled += bits;
+            if filled == 8 {
+                data.push(byte);
+                byte = 0;
+                filled = 0;
+            }
+        }
+        if filled > 0 {
+            data.push(byte);
+        }
+    });
+
+    let mut info = png::Info::with_size(width, height);
+    info.color_type = png::ColorType::Indexed;
+    info.bit_depth = bit_depth;
+    info.palette = Some(Cow::Owned(
+        image
+            .palette
+            .iter()
+            .flat_map(|&[r, g, b, _]| [r, g, b])
+            .collect(),
+    ));
+    let alpha: Vec<u8> = image
+        .palette
+        .iter()
+        .map(|color| color[3])
+        .take_while(|alpha| *alpha < 255)
+        .collect();
+    if !alpha.is_empty() {
+        info.trns = Some(Cow::Owned(alpha));
+    }
+    write_png_data(writer, info, &data, level, interlaced, dpi)
+}
+
+/// Returns the zlib level and whether rows are filtered for the compression
+fn compression_level(compression: CompressionType) -> (u8, bool) {
+    match compression {
+        CompressionType::Uncompressed => (0, false),
+        CompressionType::Fast => (1, true),
+        CompressionType::Best => (9, true),
+        CompressionType::Level(level) => (level.min(9), level > 0),
+        _ => (6, true),
+    }
+}
+
+/// Calls the function with the y coordinate and the x coordinates of each row of the
+/// passes of the image and whether it is the first row of its pass. Passes without pixels
+/// are skipped.
+fn for_each_row<F: FnMut(u32, StepBy<Range<u32>>, bool)>(
+    width: u32,
+    height: u32,
+    interlaced: bool,
+    mut f: F,
+) {
+    let passes: &[(u32, u32, u32, u32)] = if interlaced {
+        &ADAM7_PASSES
+    } else {
+        &SINGLE_PASS
+    };
+
+    for &(x0, y0, dx, dy) in passes {
+        let pass_width = (width + dx - 1 - x0.min(width)) / dx;
+        if pass_width == 0 || y0 >= height {
+            continue;
+        }
+        for y in (y0..height).step_by(dy as usize) {
+            f(y, (x0..width).step_by(dx as usize), y == y0);
+        }
+    }
+}
+
+/// Compresses the filtered rows into a single data chunk and writes them with the header
+/// and the resolution
+fn write_png_data<W: Write>(
+    writer: &mut W,
+    mut info: png::Info,
+    data: &[u8],
+    level: u8,
+    interlaced: bool,
+    dpi: Option<(f32, f32)>,
+) -> ThumbResult<()> {
+    let data = miniz_oxide::deflate::compress_to_vec_zlib(data, level);
+
+    info.interlaced = interlaced;
+    info.pixel_dims = dpi.map(|(x, y)| png::PixelDimensions {
+        xppu: pixels_per_meter(x),
+        yppu: pixels_per_meter(y),
+        unit: png::Unit::Meter,
+    });
+    let mut writer = png::Encoder::with_info(writer, info)
+        .and_then(|encoder| encoder.write_header())
+        .map_err(png_error)?;
+    writer
+        .write_chunk(png::chunk::IDAT, &data)
+        .map_err(png_error)?;
+    writer.finish().map_err(png_error)?;
+
+    Ok(())
+}
+
+/// Failures of the writer are io errors and not encoding errors
+fn png_error(error: png::EncodingError) -> ThumbError {
+    match error {
+        png::EncodingError::IoError(e) => ThumbError::Io(e),
+        e => ThumbError::encode("png", e),
+    }
+}
+
+/// Applies the paeth filter with a distance of four bytes to a row of rgba pixels
+fn paeth_filter<'a>(row: &'a [u8], previous: &'a [u8]) -> impl Iterator<Item = u8> + 'a {
+    (0..row.len()).map(move |i| {
+        let left = if i >= 4 { row[i - 4] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= 4 { previous[i - 4] } else { 0 };
+        let estimate = left as i16 + up as i16 - up_left as i16;
+        let (dl, du, dul) = (
+            (estimate - left as i16).abs(),
+            (estimate - up as i16).abs(),
+            (estimate - up_left as i16).abs(),
+        );
+        let predictor = if dl <= du && dl <= dul {
+            left
+        } else if du <= dul {
+            up
+        } else {
+            up_left
+        };
+
+        row[i].wrapping_sub(predictor)
+    })
+}
+
+/// Writes a jpeg with all encoder settings set explicitly instead of relying on defaults
+pub(crate) fn write_explicit_jpeg<W: Write>(
+    writer: &mut W,
+    image: &RgbImage,
+    quality: u8,
+    progressive: bool,
+    dpi: Option<(f32, f32)>,
+) -> ThumbResult<()> {
+    let (width, height) = image.dimensions();
+    let (width, height) = match (u16::try_from(width), u16::try_from(height)) {
+        (Ok(width), Ok(height)) => (width, height),
+        _ => return Err(ThumbError::InvalidSize(width, height)),
+    };
+    let mut encoder = jpeg_encoder::Encoder::new(writer, quality);
+    encoder.set_progressive(progressive);
+    // chroma subsampling is disabled for high qualities
+    encoder.set_sampling_factor(if quality < 90 {
+        SamplingFactor::F_2_2
+    } else {
+        SamplingFactor::F_1_1
+    });
+    encoder.set_chroma_subsampling_method(ChromaSubsamplingMethod::Nearest);
+    encoder.set_quantization_tables(
+        QuantizationTableType::Default,
+        QuantizationTableType::Default,
+    );
+    encoder.set_density(match dpi {
+        Some((x, y)) => PixelDensity {
+            density: (jfif_density(x), jfif_density(y)),
+            unit: PixelDensityUnit::Inches,
+        },
+        None => PixelDensity::default(),
+    });
+    encoder.set_optimized_huffman_tables(false);
+    encoder.set_restart_interval(0);
+    encoder
+        .encode(image.as_raw(), width, height, jpeg_encoder::ColorType::Rgb)
+        .map_err(|e| ThumbError::encode("jpeg", e))?;
+
+    Ok(())
+}
+
+/// Converts a resolution in dots per inch to the pixels per meter of png images
+fn pixels_per_meter(dpi: f32) -> u32 {
+    (dpi / 0.0254).round().max(1.0) as u32
+}
+
+/// Converts a resolution in dots per inch to the density of JFIF segments
+pub(crate) fn jfif_density(dpi: f32) -> u16 {
+    dpi.round().clamp(1.0, u16::MAX as f32) as u16
+}
@@ -0,0 +1,267 @@
+//! Storage of thumbnails in the shared thumbnail cache described by the
+//! [freedesktop thumbnail specification](https://specifications.freedesktop.org/thumbnail-spec/latest/)
+
+use crate::error::{ThumbError, ThumbResult};
+use crate::resize::resize_image;
+use crate::{FilterType, ResizeMode, Thumbnail};
+use std::env;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tempfile::NamedTempFile;
+
+const KEY_URI: &str = "Thumb::URI";
+const KEY_MTIME: &str = "Thumb::MTime";
+const KEY_SIZE: &str = "Thumb::Size";
+
+/// The size classes of the thumbnail cache
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum XdgSize {
+    /// Thumbnails of up to 128x128 pixels
+    Normal,
+
+    /// Thumbnails of up to 256x256 pixels
+    Large,
+
+    /// Thumbnails of up to 512x512 pixels
+    XLarge,
+
+    /// Thumbnails of up to 1024x1024 pixels
+    XXLarge,
+}
+
+impl XdgSize {
+    /// Returns the maximum width and height of thumbnails in this class
+    pub fn max_dimension(&self) -> u32 {
+        match self {
+            XdgSize::Normal => 128,
+            XdgSize::Large => 256,
+            XdgSize::XLarge => 512,
+            XdgSize::XXLarge => 1024,
+        }
+    }
+
+    fn dir_name(&self) -> &'static str {
+        match self {
+            XdgSize::Normal => "normal",
+            XdgSize::Large => "large",
+            XdgSize::XLarge => "x-large",
+            XdgSize::XXLarge => "xx-large",
+        }
+    }
+}
+
+/// A thumbnail cache directory, usually `~/.cache/thumbnails`
+#[derive(Clone, Debug)]
+pub struct XdgCache {
+    root: PathBuf,
+}
+
+impl XdgCache {
+    /// Returns the cache in `$XDG_CACHE_HOME/thumbnails` or `$HOME/.cache/thumbnails`
+    pub fn new() -> ThumbResult<Self> {
+        let cache_home = env::var_os("XDG_CACHE_HOME")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .ok_or_else(|| {
+                ThumbError::InvalidOptions("no cache directory could be determined".into())
+            })?;
+
+        Ok(Self::with_root(cache_home.join("thumbnails")))
+    }
+
+    /// Returns the cache in the given directory
+    pub fn with_root<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns the path a thumbnail for the uri is stored at
+    pub fn path_for_uri(&self, uri: &str, size: XdgSize) -> PathBuf {
+        self.root
+            .join(size.dir_name())
+            .join(format!("{:x}.png", md5::compute(uri)))
+    }
+
+    /// Stores the thumbnail for the uri of a file with the given modification time
+    /// in seconds. Thumbnails bigger than the size class are scaled down.
+    pub fn store_for_uri(
+        &self,
+        uri: &str,
+        mtime: u64,
+        thumbnail: Thumbnail,
+        size: XdgSize,
+    ) -> ThumbResult<PathBuf> {
+        self.store(uri, mtime, None, thumbnail, size)
+    }
+
+    /// Stores the thumbnail for a local file. The uri, modification time and file size
+    /// are taken from the file.
+    pub fn store_for_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        thumbnail: Thumbnail,
+        size: XdgSize,
+    ) -> ThumbResult<PathBuf> {
+        let (uri, mtime, file_size) = file_info(path.as_ref())?;
+
+        self.store(&uri, mtime, Some(file_size), thumbnail, size)
+    }
+
+    /// Returns the path of a stored thumbnail for the uri if it was created for the
+    /// given modification time
+    pub fn lookup_for_uri(&self, uri: &str, mtime: u64, size: XdgSize) -> Option<PathBuf> {
+        let path = self.path_for_uri(uri, size);
+        let chunks = read_text_chunks(&path).ok()?;
+        let matches = |key: &str, value: &str| {
+            chunks
+                .iter()
+                .any(|(keyword, text)| keyword == key && text == value)
+        };
+
+        if matches(KEY_URI, uri) && matches(KEY_MTIME, &mtime.to_string()) {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the path of a stored thumbnail for a local file if it is still valid
+    pub fn lookup_for_file<P: AsRef<Path>>(&self, path: P, size: XdgSize) -> Option<PathBuf> {
+        let (uri, mtime, _) = file_info(path.as_ref()).ok()?;
+
+        self.lookup_for_uri(&uri, mtime, size)
+    }
+
+    fn store(
+        &self,
+        uri: &str,
+        mtime: u64,
+        file_size: Option<u64>,
+        thumbnail: Thumbnail,
+        size: XdgSize,
+    ) -> ThumbResult<PathBuf> {
+        let path = self.path_for_uri(uri, size);
+        let dir = self.root.join(size.dir_name());
+        create_private_dir(&dir)?;
+
+        let max = size.max_dimension();
+        let image = if thumbnail.inner.width() > max || thumbnail.inner.height() > max {
+            resize_image(
+                &thumbnail.inner,
+                max,
+                max,
+                ResizeMode::Fit,
+                FilterType::Lanczos3,
+            )
+        } else {
+            thumbnail.inner
+        };
+        let image = image.into_rgba8();
+
+        let mut chunks = vec![(KEY_URI, uri.to_string()), (KEY_MTIME, mtime.to_string())];
+        if let Some(file_size) = file_size {
+            chunks.push((KEY_SIZE, file_size.to_string()));
+        }
+
+        // the temporary file is created with 0600 permissions and renamed
+        // into place so other readers never see partially written thumbnails
+        let mut file = NamedTempFile::new_in(&dir)?;
+        {
+            let mut encoder = png::Encoder::new(file.as_file_mut(), image.width(), image.height());
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            for (keyword, text) in chunks {
+                encoder
+                    .add_text_chunk(keyword.to_string(), text)
+                    .map_err(|e| ThumbError::encode("png", e))?;
+            }
+            let mut writer = encoder
+                .write_header()
+                .map_err(|e| ThumbError::encode("png", e))?;
+            writer
+                .write_image_data(image.as_raw())
+                .map_err(|e| ThumbError::encode("png", e))?;
+            writer.finish().map_err(|e| ThumbError::encode("png", e))?;
+        }
+        file.persist(&path).map_err(|e| e.error)?;
+
+        Ok(path)
+    }
+}
+
+/// Stores the thumbnail in the default cache like [XdgCache::store_for_uri]
+pub fn store_for_uri(
+    uri: &str,
+    mtime: u64,
+    thumbnail: Thumbnail,
+    size: XdgSize,
+) -> ThumbResult<PathBuf> {
+    XdgCache::new()?.store_for_uri(uri, mtime, thumbnail, size)
+}
+
+/// Looks up a thumbnail in the default cache like [XdgCache::lookup_for_uri]
+pub fn lookup_for_uri(uri: &str, mtime: u64, size: XdgSize) -> Option<PathBuf> {
+    XdgCache::new().ok()?.lookup_for_uri(uri, mtime, size)
+}
+
+/// Returns the file uri of an absolute path with reserved characters escaped
+pub fn file_uri<P: AsRef<Path>>(path: P) -> ThumbResult<String> {
+    let path = fs::canonicalize(path)?;
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    Ok(uri)
+}
+
+/// Returns the uri, modification time in seconds and size of a file
+fn file_info(path: &Path) -> ThumbResult<(String, u64, u64)> {
+    let metadata = fs::metadata(path)?;
+    let mtime = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    Ok((file_uri(path)?, mtime, metadata.len()))
+}
+
+/// Reads all tEXt chunks of a png file
+fn read_text_chunks(path: &Path) -> ThumbResult<Vec<(String, String)>> {
+    let decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| ThumbError::decode("png", e))?;
+    // text chunks are allowed after the image data as well
+    reader.finish().map_err(|e| ThumbError::decode("png", e))?;
+    let chunks = reader
+        .info()
+        .uncompressed_latin1_text
+        .iter()
+        .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+        .collect();
+
+    Ok(chunks)
+}
+
+/// Creates a directory that is only accessible by the user
+fn create_private_dir(path: &Path) -> ThumbResult<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(path)?;
+
+    Ok(())
+}
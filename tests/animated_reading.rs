@@ -0,0 +1,72 @@
+use image::{ImageFormat, Rgba, RgbaImage};
+use mime::Mime;
+use std::io::Cursor;
+use std::str::FromStr;
+use thumbnailer::{Thumbnail, ThumbnailSize, ThumbnailerBuilder};
+
+const GIF_BYTES: &[u8] = include_bytes!("assets/animated.gif");
+const WEBP_BYTES: &[u8] = include_bytes!("assets/animated.webp");
+
+fn create_thumbnail(bytes: &[u8], mime: Mime, frame: Option<usize>) -> Thumbnail {
+    let mut builder = ThumbnailerBuilder::new().size(ThumbnailSize::Custom {
+        width: 80,
+        height: 40,
+    });
+    if let Some(frame) = frame {
+        builder = builder.animation_frame(frame);
+    }
+
+    builder
+        .run(Cursor::new(bytes), mime)
+        .unwrap()
+        .pop()
+        .unwrap()
+}
+
+fn pixels(thumbnail: Thumbnail) -> RgbaImage {
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+
+    image::load_from_memory_with_format(buf.get_ref(), ImageFormat::Png)
+        .unwrap()
+        .into_rgba8()
+}
+
+fn assert_color(pixel: &Rgba<u8>, expected: [u8; 4]) {
+    for (actual, expected) in pixel.0.iter().zip(expected) {
+        assert!(
+            (*actual as i16 - expected as i16).abs() < 16,
+            "{:?} != {:?}",
+            pixel,
+            expected
+        );
+    }
+}
+
+#[test]
+fn it_uses_the_first_frame_of_gifs() {
+    let image = pixels(create_thumbnail(GIF_BYTES, mime::IMAGE_GIF, None));
+    assert_eq!(image.dimensions(), (80, 40));
+    assert_color(image.get_pixel(40, 20), [255, 0, 0, 255]);
+    assert_color(image.get_pixel(0, 0), [0, 0, 0, 0]);
+}
+
+#[test]
+fn it_selects_frames_of_gifs() {
+    let image = pixels(create_thumbnail(GIF_BYTES, mime::IMAGE_GIF, Some(5)));
+    assert_color(image.get_pixel(40, 20), [255, 0, 255, 255]);
+
+    let image = pixels(create_thumbnail(GIF_BYTES, mime::IMAGE_GIF, Some(100)));
+    assert_color(image.get_pixel(40, 20), [255, 0, 255, 255]);
+}
+
+#[test]
+fn it_selects_frames_of_animated_webp() {
+    let webp_mime = Mime::from_str("image/webp").unwrap();
+    let image = pixels(create_thumbnail(WEBP_BYTES, webp_mime.clone(), None));
+    assert_color(image.get_pixel(40, 20), [255, 0, 0, 255]);
+    assert_color(image.get_pixel(0, 0), [0, 0, 0, 0]);
+
+    let image = pixels(create_thumbnail(WEBP_BYTES, webp_mime, Some(5)));
+    assert_color(image.get_pixel(40, 20), [255, 0, 255, 255]);
+}
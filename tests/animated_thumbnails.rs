@@ -0,0 +1,103 @@
+use image::codecs::gif::GifDecoder;
+#[cfg(feature = "libwebp")]
+use image::codecs::webp::WebPDecoder;
+#[cfg(not(feature = "libwebp"))]
+use image::error::ImageFormatHint;
+use image::AnimationDecoder;
+#[cfg(not(feature = "libwebp"))]
+use image::ImageFormat;
+use std::io::Cursor;
+use std::str::FromStr;
+use std::time::Duration;
+#[cfg(not(feature = "libwebp"))]
+use thumbnailer::error::ThumbError;
+use thumbnailer::{AnimatedThumbnail, ThumbnailSize, ThumbnailerBuilder};
+
+const GIF_BYTES: &[u8] = include_bytes!("assets/animated.gif");
+const WEBP_BYTES: &[u8] = include_bytes!("assets/animated.webp");
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+fn create_animated_thumbnail(
+    builder: ThumbnailerBuilder,
+    bytes: &[u8],
+    mime: mime::Mime,
+) -> AnimatedThumbnail {
+    builder
+        .size(ThumbnailSize::Custom {
+            width: 20,
+            height: 10,
+        })
+        .run_animated(Cursor::new(bytes), mime)
+        .unwrap()
+        .pop()
+        .unwrap()
+}
+
+#[test]
+fn it_keeps_all_frames_of_gifs() {
+    let thumbnail =
+        create_animated_thumbnail(ThumbnailerBuilder::new(), GIF_BYTES, mime::IMAGE_GIF);
+    assert_eq!(thumbnail.frame_count(), 6);
+    assert_eq!(thumbnail.size(), (20, 10));
+
+    let mut buf = Vec::new();
+    thumbnail.write_gif(&mut buf).unwrap();
+    let frames = GifDecoder::new(Cursor::new(buf))
+        .unwrap()
+        .into_frames()
+        .collect_frames()
+        .unwrap();
+    assert_eq!(frames.len(), 6);
+    assert_eq!(frames[0].buffer().dimensions(), (20, 10));
+    assert_eq!(
+        Duration::from(frames[0].delay()),
+        Duration::from_millis(100)
+    );
+}
+
+#[cfg(feature = "libwebp")]
+#[test]
+fn it_writes_animated_webp() {
+    let webp_mime = mime::Mime::from_str("image/webp").unwrap();
+    let thumbnail = create_animated_thumbnail(ThumbnailerBuilder::new(), WEBP_BYTES, webp_mime);
+    assert_eq!(thumbnail.frame_count(), 6);
+
+    let mut buf = Vec::new();
+    thumbnail.write_webp(&mut buf).unwrap();
+    let decoder = WebPDecoder::new(Cursor::new(buf)).unwrap();
+    assert!(decoder.has_animation());
+    assert_eq!(decoder.into_frames().count(), 6);
+}
+
+#[cfg(not(feature = "libwebp"))]
+#[test]
+fn it_does_not_write_animated_webp_without_libwebp() {
+    let webp_mime = mime::Mime::from_str("image/webp").unwrap();
+    let thumbnail = create_animated_thumbnail(ThumbnailerBuilder::new(), WEBP_BYTES, webp_mime);
+
+    let result = thumbnail.write_webp(&mut Vec::new());
+    assert!(matches!(
+        result,
+        Err(ThumbError::UnsupportedOutputFormat(ImageFormatHint::Exact(
+            ImageFormat::WebP
+        )))
+    ));
+}
+
+#[test]
+fn it_truncates_animations() {
+    let builder = ThumbnailerBuilder::new().max_frames(2);
+    let thumbnail = create_animated_thumbnail(builder, GIF_BYTES, mime::IMAGE_GIF);
+    assert_eq!(thumbnail.frame_count(), 2);
+}
+
+#[test]
+fn it_creates_single_frame_animations_for_static_images() {
+    let thumbnail =
+        create_animated_thumbnail(ThumbnailerBuilder::new(), PNG_BYTES, mime::IMAGE_PNG);
+    assert_eq!(thumbnail.frame_count(), 1);
+
+    let mut buf = Vec::new();
+    thumbnail.write_gif(&mut buf).unwrap();
+    assert!(!buf.is_empty());
+}
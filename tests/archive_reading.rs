@@ -0,0 +1,85 @@
+#![cfg(feature = "archive")]
+
+mod common;
+
+use common::{center_pixel, create_thumbnail};
+use thumbnailer::error::ThumbError;
+use thumbnailer::image::Rgb;
+
+const ZIP_BYTES: &[u8] = include_bytes!("assets/images.zip");
+const TAR_BYTES: &[u8] = include_bytes!("assets/images.tar");
+const TAR_GZ_BYTES: &[u8] = include_bytes!("assets/images.tar.gz");
+const NO_IMAGES_BYTES: &[u8] = include_bytes!("assets/no_images.zip");
+const ENCRYPTED_BYTES: &[u8] = include_bytes!("assets/encrypted.zip");
+const MANY_ENTRIES_BYTES: &[u8] = include_bytes!("assets/many_entries.tar.gz");
+const LARGE_ENTRY_BYTES: &[u8] = include_bytes!("assets/large_entry.zip");
+
+#[test]
+fn it_reads_the_first_image_of_archives() {
+    // fake.png isn't an image despite its name, hidden files and resource forks are
+    // skipped and img2 comes before img10 and z.png in the same traversal
+    for (bytes, mime) in [
+        (ZIP_BYTES, "application/zip"),
+        (ZIP_BYTES, "application/x-zip-compressed"),
+        (TAR_BYTES, "application/x-tar"),
+        (TAR_GZ_BYTES, "application/gzip"),
+        (TAR_GZ_BYTES, "application/x-compressed-tar"),
+    ] {
+        let thumbnail = create_thumbnail(bytes, mime).unwrap();
+
+        assert_eq!(thumbnail.size(), (128, 96), "{}", mime);
+        assert_eq!(center_pixel(&thumbnail), Rgb([0, 255, 0]), "{}", mime);
+    }
+}
+
+#[test]
+fn it_returns_no_embedded_image_without_images() {
+    let result = create_thumbnail(NO_IMAGES_BYTES, "application/zip");
+
+    assert!(
+        matches!(result, Err(ThumbError::NoEmbeddedImage)),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn it_rejects_password_protected_archives() {
+    let error = create_thumbnail(ENCRYPTED_BYTES, "application/zip").unwrap_err();
+
+    assert!(error.is_corrupt(), "{:?}", error);
+    assert!(error.to_string().contains("password"), "{}", error);
+}
+
+#[test]
+fn it_limits_the_examined_entries() {
+    let result = create_thumbnail(MANY_ENTRIES_BYTES, "application/gzip");
+
+    assert!(
+        matches!(result, Err(ThumbError::TooManyEntries { limit: 10_000 })),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn it_limits_the_decompressed_size_of_entries() {
+    let result = create_thumbnail(LARGE_ENTRY_BYTES, "application/zip");
+
+    match result {
+        Err(ThumbError::EntryTooLarge { name, limit }) => {
+            assert_eq!(name, "bomb.png");
+            assert_eq!(limit, 64 * 1024 * 1024);
+        }
+        result => panic!("{:?}", result),
+    }
+}
+
+#[test]
+fn it_rejects_content_that_is_not_an_archive() {
+    for mime in ["application/zip", "application/x-tar"] {
+        let error = create_thumbnail(b"not an archive at all", mime).unwrap_err();
+
+        assert!(error.is_corrupt(), "{}: {:?}", mime, error);
+    }
+}
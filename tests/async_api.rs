@@ -0,0 +1,39 @@
+#![cfg(feature = "tokio")]
+
+use image::ImageFormat;
+use thumbnailer::{create_thumbnails_async, ThumbnailSize, ThumbnailerBuilder};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+#[tokio::test]
+async fn it_creates_thumbnails_async() {
+    let thumbnails = create_thumbnails_async(
+        PNG_BYTES,
+        mime::IMAGE_PNG,
+        [ThumbnailSize::Small, ThumbnailSize::Medium],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(thumbnails.len(), 2);
+    assert_eq!(thumbnails[0].size(), (128, 95));
+}
+
+#[tokio::test]
+async fn it_writes_thumbnails_async() {
+    let mut thumbnails = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .run_async(PNG_BYTES, mime::IMAGE_PNG)
+        .await
+        .unwrap();
+    let thumbnail = thumbnails.pop().unwrap();
+
+    let mut png = Vec::new();
+    thumbnail.clone().write_png_async(&mut png).await.unwrap();
+    let image = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap();
+    assert_eq!((image.width(), image.height()), (128, 95));
+
+    let mut jpeg = Vec::new();
+    thumbnail.write_jpeg_async(&mut jpeg).await.unwrap();
+    image::load_from_memory_with_format(&jpeg, ImageFormat::Jpeg).unwrap();
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "audio")]
+
+mod common;
+
+use common::{center_pixel, create_thumbnail};
+use image::Rgb;
+use thumbnailer::error::ThumbError;
+
+const MP3_BYTES: &[u8] = include_bytes!("assets/cover.mp3");
+const MP3_NO_COVER_BYTES: &[u8] = include_bytes!("assets/no_cover.mp3");
+const FLAC_BYTES: &[u8] = include_bytes!("assets/cover.flac");
+const M4A_BYTES: &[u8] = include_bytes!("assets/cover.m4a");
+
+#[test]
+fn it_prefers_the_front_cover_for_mp3() {
+    let thumbnail = create_thumbnail(MP3_BYTES, "audio/mpeg").unwrap();
+    assert_eq!(center_pixel(&thumbnail), Rgb([0, 255, 0]));
+}
+
+#[test]
+fn it_reads_cover_art_for_flac() {
+    let thumbnail = create_thumbnail(FLAC_BYTES, "audio/flac").unwrap();
+    assert_eq!(center_pixel(&thumbnail), Rgb([0, 255, 0]));
+}
+
+#[test]
+fn it_reads_cover_art_for_m4a() {
+    let thumbnail = create_thumbnail(M4A_BYTES, "audio/mp4").unwrap();
+    assert_eq!(center_pixel(&thumbnail), Rgb([0, 255, 0]));
+}
+
+#[test]
+fn it_fails_for_audio_without_cover_art() {
+    let result = create_thumbnail(MP3_NO_COVER_BYTES, "audio/mpeg");
+    assert!(matches!(result, Err(ThumbError::NoEmbeddedImage)));
+}
@@ -0,0 +1,95 @@
+use mime::Mime;
+use std::io::Cursor;
+use std::str::FromStr;
+use thumbnailer::image::{DynamicImage, Rgba, RgbaImage};
+use thumbnailer::{Badge, ThumbnailSize, ThumbnailerBuilder};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+fn center_pixels(builder: ThumbnailerBuilder, bytes: &[u8], mime: &str) -> Vec<Rgba<u8>> {
+    let image = builder
+        .size(ThumbnailSize::Small)
+        .run(Cursor::new(bytes), Mime::from_str(mime).unwrap())
+        .unwrap()
+        .pop()
+        .unwrap()
+        .into_inner()
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+
+    // a small area in the center covered by the graphic and the scrim
+    (0..5)
+        .flat_map(|dy| (0..5).map(move |dx| (width / 2 - 2 + dx, height / 2 - 2 + dy)))
+        .map(|(x, y)| *image.get_pixel(x, y))
+        .collect()
+}
+
+#[test]
+fn it_does_not_draw_badges_on_images() {
+    let plain = center_pixels(ThumbnailerBuilder::new(), PNG_BYTES, "image/png");
+    let badged = center_pixels(
+        ThumbnailerBuilder::new().badge(Badge::Play),
+        PNG_BYTES,
+        "image/png",
+    );
+
+    assert_eq!(plain, badged);
+}
+
+#[test]
+fn it_draws_forced_badges_on_images() {
+    let plain = center_pixels(ThumbnailerBuilder::new(), PNG_BYTES, "image/png");
+    let badged = center_pixels(
+        ThumbnailerBuilder::new()
+            .badge(Badge::Play)
+            .force_badge(true),
+        PNG_BYTES,
+        "image/png",
+    );
+
+    assert_ne!(plain, badged);
+}
+
+#[test]
+fn it_draws_custom_badges() {
+    let red = DynamicImage::ImageRgba8(RgbaImage::from_pixel(8, 8, Rgba([255, 0, 0, 255])));
+    let badged = center_pixels(
+        ThumbnailerBuilder::new()
+            .badge(Badge::Custom(red))
+            .force_badge(true),
+        PNG_BYTES,
+        "image/png",
+    );
+
+    assert!(badged.iter().all(|pixel| *pixel == Rgba([255, 0, 0, 255])));
+}
+
+#[test]
+#[cfg(feature = "audio")]
+fn it_draws_badges_on_audio_covers() {
+    const MP3_BYTES: &[u8] = include_bytes!("assets/cover.mp3");
+
+    let plain = center_pixels(ThumbnailerBuilder::new(), MP3_BYTES, "audio/mpeg");
+    let badged = center_pixels(
+        ThumbnailerBuilder::new().badge(Badge::Audio),
+        MP3_BYTES,
+        "audio/mpeg",
+    );
+
+    assert_ne!(plain, badged);
+}
+
+#[test]
+#[cfg(feature = "ffmpeg")]
+fn it_draws_badges_on_videos() {
+    const VIDEO_BYTES: &[u8] = include_bytes!("assets/test.mp4");
+
+    let plain = center_pixels(ThumbnailerBuilder::new(), VIDEO_BYTES, "video/mp4");
+    let badged = center_pixels(
+        ThumbnailerBuilder::new().badge(Badge::Play),
+        VIDEO_BYTES,
+        "video/mp4",
+    );
+
+    assert_ne!(plain, badged);
+}
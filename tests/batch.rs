@@ -0,0 +1,187 @@
+#![cfg(feature = "parallel")]
+
+use image::{DynamicImage, Rgb, RgbImage};
+use mime::Mime;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use thumbnailer::error::{ThumbError, ThumbResult};
+use thumbnailer::{
+    create_thumbnails_batch, BatchInput, BatchOptions, FormatHandler, ReadSeek, ThumbnailSize,
+    ThumbnailerBuilder,
+};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+/// Decodes a solid image slowly while counting how many decodes run at the same time
+struct CountingHandler {
+    mimes: Vec<Mime>,
+    running: AtomicUsize,
+    peak: Arc<AtomicUsize>,
+}
+
+impl FormatHandler for CountingHandler {
+    fn supported_mimes(&self) -> &[Mime] {
+        &self.mimes
+    }
+
+    fn get_base_image(&self, _reader: &mut dyn ReadSeek) -> ThumbResult<DynamicImage> {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(running, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(20));
+        self.running.fetch_sub(1, Ordering::SeqCst);
+
+        Ok(DynamicImage::ImageRgb8(RgbImage::from_pixel(
+            32,
+            32,
+            Rgb([255, 0, 0]),
+        )))
+    }
+}
+
+/// Panics while decoding
+struct PanickingHandler(Vec<Mime>);
+
+impl FormatHandler for PanickingHandler {
+    fn supported_mimes(&self) -> &[Mime] {
+        &self.0
+    }
+
+    fn get_base_image(&self, _reader: &mut dyn ReadSeek) -> ThumbResult<DynamicImage> {
+        panic!("broken decoder")
+    }
+}
+
+fn pool(threads: usize) -> Arc<rayon::ThreadPool> {
+    Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap(),
+    )
+}
+
+/// Returns the most decodes that ran at the same time for a batch of eight inputs
+fn peak_in_flight(options: BatchOptions) -> usize {
+    let mime: Mime = "application/x-counting".parse().unwrap();
+    let peak = Arc::new(AtomicUsize::new(0));
+    let builder = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Icon])
+        .thread_pool(pool(4))
+        .register_handler(CountingHandler {
+            mimes: vec![mime.clone()],
+            running: AtomicUsize::new(0),
+            peak: peak.clone(),
+        });
+    let inputs = (0..8).map(|_| BatchInput::reader(Cursor::new(Vec::new()), mime.clone()));
+
+    let results = builder.run_batch(inputs, options);
+
+    assert!(results.iter().all(|(_, result)| result.is_ok()));
+    peak.load(Ordering::SeqCst)
+}
+
+#[test]
+fn it_returns_the_results_in_the_order_of_the_inputs() {
+    let inputs = vec![
+        BatchInput::from(PathBuf::from("tests/assets/test.png")),
+        BatchInput::from(PathBuf::from("tests/assets/missing.png")),
+        BatchInput::reader(Cursor::new(PNG_BYTES), mime::IMAGE_PNG),
+        BatchInput::reader(Cursor::new(b"not a png".to_vec()), mime::IMAGE_PNG),
+        BatchInput::from(PathBuf::from("tests/assets/test.jpg")),
+    ];
+
+    let results = create_thumbnails_batch(
+        inputs,
+        [ThumbnailSize::Icon, ThumbnailSize::Small],
+        BatchOptions::default(),
+    );
+
+    let indices: Vec<usize> = results.iter().map(|(index, _)| *index).collect();
+    assert_eq!(indices, vec![0, 1, 2, 3, 4]);
+    for index in [0, 2, 4] {
+        assert_eq!(results[index].1.as_ref().unwrap().len(), 2);
+    }
+    assert!(matches!(results[1].1, Err(ThumbError::Io(_))));
+    assert!(results[3].1.as_ref().unwrap_err().is_corrupt());
+}
+
+#[test]
+fn it_creates_the_same_thumbnails_as_single_runs() {
+    let builder = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Icon, ThumbnailSize::Medium])
+        .thread_pool(pool(2));
+    let expected: Vec<Vec<u8>> = builder
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap()
+        .into_iter()
+        .map(|thumbnail| thumbnail.into_inner().into_bytes())
+        .collect();
+    let inputs = (0..4).map(|_| (Cursor::new(PNG_BYTES), mime::IMAGE_PNG));
+
+    for (_, result) in builder.run_batch(inputs, BatchOptions::default()) {
+        let bytes: Vec<Vec<u8>> = result
+            .unwrap()
+            .into_iter()
+            .map(|thumbnail| thumbnail.into_inner().into_bytes())
+            .collect();
+        assert_eq!(bytes, expected);
+    }
+}
+
+#[test]
+fn it_limits_the_inputs_in_flight() {
+    let limited = |max_in_flight| {
+        peak_in_flight(BatchOptions {
+            max_in_flight: Some(max_in_flight),
+        })
+    };
+
+    assert!((2..=4).contains(&peak_in_flight(BatchOptions::default())));
+    assert!(limited(2) <= 2);
+    assert_eq!(limited(1), 1);
+}
+
+#[test]
+fn it_isolates_panicking_inputs() {
+    let mime: Mime = "application/x-panicking".parse().unwrap();
+    let builder = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Icon])
+        .thread_pool(pool(2))
+        .register_handler(PanickingHandler(vec![mime.clone()]));
+    let inputs = vec![
+        BatchInput::reader(Cursor::new(PNG_BYTES), mime::IMAGE_PNG),
+        BatchInput::reader(Cursor::new(Vec::new()), mime),
+        BatchInput::reader(Cursor::new(PNG_BYTES), mime::IMAGE_PNG),
+    ];
+
+    let results = builder.run_batch(inputs, BatchOptions::default());
+
+    assert!(results[0].1.is_ok());
+    assert!(results[2].1.is_ok());
+    match &results[1].1 {
+        Err(e @ ThumbError::Decode { .. }) => assert!(e.to_string().contains("broken decoder")),
+        result => panic!("expected a decoding error, got {:?}", result.as_ref().err()),
+    }
+}
+
+#[test]
+fn it_rejects_a_limit_of_zero_inputs_in_flight() {
+    let inputs = (0..2).map(|_| (Cursor::new(PNG_BYTES), mime::IMAGE_PNG));
+
+    let results = create_thumbnails_batch(
+        inputs,
+        [ThumbnailSize::Icon],
+        BatchOptions {
+            max_in_flight: Some(0),
+        },
+    );
+
+    assert_eq!(results.len(), 2);
+    for (_, result) in results {
+        assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+    }
+}
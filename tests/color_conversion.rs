@@ -0,0 +1,113 @@
+use std::io::Cursor;
+use thumbnailer::image::{self, ColorType, ImageFormat, Rgb, Rgba};
+use thumbnailer::{ResizeMode, ThumbnailSize, ThumbnailerBuilder};
+
+const CMYK_ADOBE_BYTES: &[u8] = include_bytes!("assets/cmyk_adobe.jpg");
+const CMYK_PLAIN_BYTES: &[u8] = include_bytes!("assets/cmyk_plain.jpg");
+const YCCK_ADOBE_BYTES: &[u8] = include_bytes!("assets/ycck_adobe.jpg");
+const RGBA16_BYTES: &[u8] = include_bytes!("assets/rgba16.png");
+
+/// Returns the center pixels of the cyan, magenta, yellow and gray quadrants
+fn read_quadrants(bytes: &[u8]) -> [Rgb<u8>; 4] {
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Icon)
+        .no_upscale(true)
+        .run(Cursor::new(bytes), mime::IMAGE_JPEG)
+        .unwrap()
+        .pop()
+        .unwrap();
+    let image = thumbnail.as_image().to_rgb8();
+
+    [(16, 16), (48, 16), (16, 48), (48, 48)].map(|(x, y)| *image.get_pixel(x, y))
+}
+
+#[test]
+fn it_converts_adobe_cmyk_jpegs() {
+    assert_quadrants(read_quadrants(CMYK_ADOBE_BYTES));
+}
+
+#[test]
+fn it_converts_adobe_ycck_jpegs() {
+    assert_quadrants(read_quadrants(YCCK_ADOBE_BYTES));
+}
+
+#[test]
+fn it_converts_cmyk_jpegs_without_adobe_segment() {
+    // the data of these images isn't inverted
+    assert_quadrants(read_quadrants(CMYK_PLAIN_BYTES));
+}
+
+#[test]
+fn it_keeps_16_bits_while_padding_and_sharpening() {
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Custom {
+            width: 96,
+            height: 64,
+        })
+        .resize_mode(ResizeMode::Pad {
+            color: Rgba([0, 0, 0, 0]),
+        })
+        .sharpen(1.0, 1.0, 0)
+        .run(Cursor::new(RGBA16_BYTES), mime::IMAGE_PNG)
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(thumbnail.as_image().color(), ColorType::Rgba16);
+    let image = thumbnail.as_image().to_rgba16();
+
+    // 8 bits per channel would store 101 * 257 = 25957
+    let pixel = image.get_pixel(48, 8);
+    for (actual, expected) in pixel.0.iter().zip([25900, 12979, 51400, 65535]) {
+        assert!((*actual as i32 - expected).abs() <= 16, "{:?}", pixel);
+    }
+    assert_eq!(image.get_pixel(4, 8).0, [0, 0, 0, 0]);
+}
+
+#[test]
+fn it_rounds_16_bit_images_when_encoding() {
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Icon)
+        .no_upscale(true)
+        .run(Cursor::new(RGBA16_BYTES), mime::IMAGE_PNG)
+        .unwrap()
+        .pop()
+        .unwrap();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap().to_rgba8();
+
+    // truncating would result in [100, 50, 200, 255]
+    assert_eq!(image.get_pixel(32, 8).0, [101, 51, 200, 255]);
+}
+
+#[test]
+fn it_blends_16_bit_alpha_before_quantizing() {
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Icon)
+        .no_upscale(true)
+        .matte(Rgb([0, 0, 0]))
+        .run(Cursor::new(RGBA16_BYTES), mime::IMAGE_PNG)
+        .unwrap()
+        .pop()
+        .unwrap();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_to(&mut buf, ImageFormat::Pnm).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap().to_rgb8();
+
+    // blending the quantized values would result in 101 * 128 / 255 = 51
+    assert_eq!(image.get_pixel(32, 48).0, [50, 50, 50]);
+}
+
+fn assert_quadrants(pixels: [Rgb<u8>; 4]) {
+    let expected = [[0, 255, 255], [255, 0, 255], [255, 255, 0], [127, 127, 127]];
+    for (actual, expected) in pixels.iter().zip(expected) {
+        for (a, e) in actual.0.iter().zip(expected) {
+            assert!(
+                (*a as i16 - e as i16).abs() <= 4,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "icc")]
+
+use std::io::Cursor;
+use thumbnailer::image::Rgb;
+use thumbnailer::{ThumbnailSize, ThumbnailerBuilder};
+
+const DISPLAY_P3_BYTES: &[u8] = include_bytes!("assets/display_p3.png");
+const BROKEN_ICC_BYTES: &[u8] = include_bytes!("assets/broken_icc.png");
+
+/// Returns the center pixels of the colored left half and the gray right half
+fn read_pixels(bytes: &[u8]) -> (Rgb<u8>, Rgb<u8>) {
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Icon)
+        .no_upscale(true)
+        .run(Cursor::new(bytes), mime::IMAGE_PNG)
+        .unwrap()
+        .pop()
+        .unwrap();
+    let image = thumbnail.as_image().to_rgb8();
+
+    (*image.get_pixel(8, 8), *image.get_pixel(24, 8))
+}
+
+#[test]
+fn it_converts_wide_gamut_images_to_srgb() {
+    let (red, gray) = read_pixels(DISPLAY_P3_BYTES);
+
+    // display p3 colors are more saturated than the same values in srgb
+    assert_close(red, [216, 94, 96]);
+    // both spaces share the white point so gray stays gray
+    assert_close(gray, [128, 128, 128]);
+}
+
+#[test]
+fn it_assumes_srgb_for_broken_profiles() {
+    let (red, gray) = read_pixels(BROKEN_ICC_BYTES);
+
+    assert_eq!(red, Rgb([200, 100, 100]));
+    assert_eq!(gray, Rgb([128, 128, 128]));
+}
+
+fn assert_close(actual: Rgb<u8>, expected: [u8; 3]) {
+    for (a, e) in actual.0.iter().zip(expected) {
+        assert!(
+            (*a as i16 - e as i16).abs() <= 4,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use thumbnailer::image::{DynamicImage, Rgb, Rgba, RgbaImage};
+use thumbnailer::Thumbnail;
+
+fn sky_thumbnail() -> Thumbnail {
+    // a slightly varying blue sky above a smaller brown ground
+    let image = RgbaImage::from_fn(100, 100, |x, y| {
+        if y < 70 {
+            Rgba([40 + (x % 3) as u8, 110, 220 - (y % 4) as u8, 255])
+        } else {
+            Rgba([120, 80, 40, 255])
+        }
+    });
+
+    Thumbnail::from(DynamicImage::ImageRgba8(image))
+}
+
+#[test]
+fn it_returns_the_dominant_color() {
+    let color = sky_thumbnail().dominant_color();
+
+    assert!(color[2] > 200 && color[0] < 60, "{:?}", color);
+}
+
+#[test]
+fn it_returns_the_average_color() {
+    let color = sky_thumbnail().average_color();
+
+    // the mean of sky and ground is much less saturated than the dominant color
+    assert!((color[0] as i32 - 64).abs() <= 2, "{:?}", color);
+    assert!((color[1] as i32 - 101).abs() <= 2, "{:?}", color);
+    assert!((color[2] as i32 - 165).abs() <= 2, "{:?}", color);
+}
+
+#[test]
+fn it_returns_a_palette_ordered_by_frequency() {
+    let image = RgbaImage::from_fn(30, 10, |x, _| match x {
+        0..=14 => Rgba([255, 0, 0, 255]),
+        15..=24 => Rgba([0, 255, 0, 255]),
+        _ => Rgba([0, 0, 255, 255]),
+    });
+    let thumbnail = Thumbnail::from(DynamicImage::ImageRgba8(image));
+
+    let palette = thumbnail.palette(5);
+    assert_eq!(
+        palette,
+        vec![Rgb([255, 0, 0]), Rgb([0, 255, 0]), Rgb([0, 0, 255])]
+    );
+    assert_eq!(thumbnail.palette(5), palette);
+    assert_eq!(thumbnail.palette(1), vec![Rgb([255, 0, 0])]);
+}
+
+#[test]
+fn it_ignores_transparent_pixels() {
+    let image = RgbaImage::from_fn(10, 10, |x, _| {
+        if x < 7 {
+            Rgba([255, 0, 0, 0])
+        } else {
+            Rgba([0, 255, 0, 255])
+        }
+    });
+    let thumbnail = Thumbnail::from(DynamicImage::ImageRgba8(image));
+    assert_eq!(thumbnail.dominant_color(), Rgb([0, 255, 0]));
+    assert_eq!(thumbnail.average_color(), Rgb([0, 255, 0]));
+
+    let transparent = Thumbnail::from(DynamicImage::ImageRgba8(RgbaImage::new(4, 4)));
+    assert_eq!(transparent.palette(3), vec![]);
+    assert_eq!(transparent.average_color(), Rgb([255, 255, 255]));
+}
@@ -0,0 +1,29 @@
+//! Helpers shared by the tests of formats that embed or contain an image.
+//! Not every test uses all of them.
+#![allow(dead_code)]
+
+use mime::Mime;
+use std::io::Cursor;
+use std::str::FromStr;
+use thumbnailer::error::ThumbResult;
+use thumbnailer::image::Rgb;
+use thumbnailer::{create_thumbnails, Thumbnail, ThumbnailSize};
+
+/// Creates a single small thumbnail of the content
+pub fn create_thumbnail(bytes: &[u8], mime: &str) -> ThumbResult<Thumbnail> {
+    let mut thumbnails = create_thumbnails(
+        Cursor::new(bytes),
+        Mime::from_str(mime).unwrap(),
+        [ThumbnailSize::Small],
+    )?;
+
+    Ok(thumbnails.pop().unwrap())
+}
+
+pub fn center_pixel(thumbnail: &Thumbnail) -> Rgb<u8> {
+    let (width, height) = thumbnail.size();
+    *thumbnail
+        .as_image()
+        .to_rgb8()
+        .get_pixel(width / 2, height / 2)
+}
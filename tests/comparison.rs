@@ -0,0 +1,92 @@
+use std::io::Cursor;
+use thumbnailer::image::{DynamicImage, GenericImageView, ImageFormat, Rgba, RgbaImage};
+use thumbnailer::{create_thumbnails, Thumbnail, ThumbnailSize};
+
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+
+fn thumbnail(size: ThumbnailSize) -> Thumbnail {
+    create_thumbnails(Cursor::new(JPG_BYTES), mime::IMAGE_JPEG, [size])
+        .unwrap()
+        .pop()
+        .unwrap()
+}
+
+/// Returns the thumbnail after encoding and decoding it in the format
+fn reencoded(thumbnail: &Thumbnail, format: ImageFormat) -> Thumbnail {
+    let bytes = thumbnail.clone().encode(format).unwrap();
+
+    Thumbnail::from(thumbnailer::image::load_from_memory(&bytes).unwrap())
+}
+
+#[test]
+fn it_compares_pixels_regardless_of_the_encoding() {
+    let original = thumbnail(ThumbnailSize::Small);
+    let png = reencoded(&original, ImageFormat::Png);
+    let jpeg = reencoded(&original, ImageFormat::Jpeg);
+
+    assert!(original.dimensions_eq(&jpeg));
+    assert!(original.pixel_eq(&png));
+    assert!(!original.pixel_eq(&jpeg));
+    // a 16 bit copy has the same pixels
+    let wide = Thumbnail::from(DynamicImage::ImageRgba16(original.as_image().to_rgba16()));
+    assert!(original.pixel_eq(&wide));
+}
+
+#[test]
+fn it_measures_the_similarity_of_thumbnails() {
+    let original = thumbnail(ThumbnailSize::Small);
+    let jpeg = reencoded(&original, ImageFormat::Jpeg);
+
+    assert_eq!(original.similarity(&original), 1.0);
+    let recompressed = original.similarity(&jpeg);
+    assert!(
+        recompressed > 0.98 && recompressed < 1.0,
+        "{}",
+        recompressed
+    );
+
+    let inverted = {
+        let mut image = original.as_image().clone();
+        image.invert();
+        Thumbnail::from(image)
+    };
+    let different = original.similarity(&inverted);
+    assert!(different < 0.5, "{}", different);
+    assert_eq!(different, inverted.similarity(&original));
+}
+
+#[test]
+fn it_resizes_thumbnails_of_different_sizes_to_compare_them() {
+    let small = thumbnail(ThumbnailSize::Small);
+    let large = thumbnail(ThumbnailSize::Large);
+
+    assert!(!small.dimensions_eq(&large));
+    assert!(!small.pixel_eq(&large));
+    let similarity = small.similarity(&large);
+    assert!(similarity > 0.9, "{}", similarity);
+    assert_eq!(small.diff_image(&large).size(), large.size());
+    assert_eq!(large.diff_image(&small).size(), large.size());
+}
+
+#[test]
+fn it_visualizes_differences() {
+    let base = RgbaImage::from_pixel(20, 10, Rgba([0, 0, 0, 255]));
+    let mut changed = base.clone();
+    changed.put_pixel(5, 5, Rgba([10, 0, 0, 255]));
+    changed.put_pixel(6, 5, Rgba([0, 0, 0, 0]));
+    let base = Thumbnail::from(DynamicImage::ImageRgba8(base));
+    let changed = Thumbnail::from(DynamicImage::ImageRgba8(changed));
+
+    let diff = base.diff_image(&changed);
+    let diff = diff.as_image();
+    assert_eq!(diff.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+    // the difference of 10 in red is amplified
+    assert_eq!(diff.get_pixel(5, 5), Rgba([80, 0, 0, 255]));
+    // alpha differences are white
+    assert_eq!(diff.get_pixel(6, 5), Rgba([255, 255, 255, 255]));
+    assert!(base
+        .diff_image(&base)
+        .as_image()
+        .pixels()
+        .all(|(_, _, p)| p == Rgba([0, 0, 0, 255])));
+}
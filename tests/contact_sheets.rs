@@ -0,0 +1,83 @@
+use mime::Mime;
+use std::io::Cursor;
+use std::str::FromStr;
+use thumbnailer::error::ThumbError;
+use thumbnailer::image::{Rgba, RgbaImage};
+use thumbnailer::{create_contact_sheet, ContactSheetOptions, ThumbnailSize};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+const BACKGROUND: Rgba<u8> = Rgba([255, 0, 255, 255]);
+
+fn sheet(bytes: &[u8], mime: &str, columns: u32, rows: u32) -> RgbaImage {
+    let options = ContactSheetOptions {
+        columns,
+        rows,
+        cell_size: ThumbnailSize::Icon,
+        gap: 2,
+        background: BACKGROUND,
+    };
+    let thumbnail =
+        create_contact_sheet(Cursor::new(bytes), Mime::from_str(mime).unwrap(), options).unwrap();
+
+    thumbnail.into_inner().into_rgba8()
+}
+
+/// Returns the center pixel of the cell
+fn cell_center(sheet: &RgbaImage, column: u32, row: u32) -> Rgba<u8> {
+    *sheet.get_pixel(column * 66 + 32, row * 66 + 32)
+}
+
+#[test]
+fn it_fills_missing_cells_with_the_background() {
+    let sheet = sheet(PNG_BYTES, "image/png", 3, 2);
+
+    assert_eq!(sheet.dimensions(), (196, 130));
+    assert_ne!(cell_center(&sheet, 0, 0), BACKGROUND);
+    for (column, row) in [(1, 0), (2, 0), (0, 1), (1, 1), (2, 1)] {
+        assert_eq!(cell_center(&sheet, column, row), BACKGROUND);
+    }
+    // the gap between the cells
+    assert_eq!(sheet.get_pixel(65, 32), &BACKGROUND);
+}
+
+#[test]
+fn it_rejects_empty_grids() {
+    let options = ContactSheetOptions {
+        columns: 0,
+        ..Default::default()
+    };
+    let result = create_contact_sheet(Cursor::new(PNG_BYTES), mime::IMAGE_PNG, options);
+
+    assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+}
+
+#[test]
+#[cfg(feature = "pdf")]
+fn it_creates_contact_sheets_of_document_pages() {
+    const PDF_BYTES: &[u8] = include_bytes!("assets/multipage.pdf");
+
+    let sheet = sheet(PDF_BYTES, "application/pdf", 2, 2);
+
+    assert_eq!(sheet.dimensions(), (130, 130));
+    for (column, row) in [(0, 0), (1, 0), (0, 1)] {
+        assert_ne!(cell_center(&sheet, column, row), BACKGROUND);
+    }
+    // the document only has three pages
+    assert_eq!(cell_center(&sheet, 1, 1), BACKGROUND);
+}
+
+#[test]
+#[cfg(feature = "ffmpeg")]
+fn it_creates_contact_sheets_of_videos() {
+    const VIDEO_BYTES: &[u8] = include_bytes!("assets/test.mp4");
+
+    let sheet = sheet(VIDEO_BYTES, "video/mp4", 3, 3);
+
+    assert_eq!(sheet.dimensions(), (196, 196));
+    for row in 0..3 {
+        for column in 0..3 {
+            assert_ne!(cell_center(&sheet, column, row), BACKGROUND);
+        }
+    }
+}
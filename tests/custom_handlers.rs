@@ -0,0 +1,149 @@
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+use mime::Mime;
+use std::io::Cursor;
+use thumbnailer::error::{ThumbError, ThumbResult};
+use thumbnailer::{FormatHandler, ReadSeek, ThumbnailSize, Thumbnailer, ThumbnailerBuilder};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+/// Returns an image of a single color for the supported mimes
+struct SolidHandler {
+    mimes: Vec<Mime>,
+    color: Rgb<u8>,
+    size: u32,
+}
+
+impl SolidHandler {
+    fn new(mime: &str, color: [u8; 3]) -> Self {
+        Self {
+            mimes: vec![mime.parse().unwrap()],
+            color: Rgb(color),
+            size: 256,
+        }
+    }
+}
+
+impl FormatHandler for SolidHandler {
+    fn supported_mimes(&self) -> &[Mime] {
+        &self.mimes
+    }
+
+    fn get_base_image(&self, reader: &mut dyn ReadSeek) -> ThumbResult<DynamicImage> {
+        let mut header = [0u8; 4];
+        reader.read_exact(&mut header)?;
+        if &header != b"TEST" {
+            return Err(ThumbError::decode("x-test", "missing header"));
+        }
+
+        Ok(DynamicImage::ImageRgb8(RgbImage::from_pixel(
+            self.size, self.size, self.color,
+        )))
+    }
+}
+
+fn test_mime() -> Mime {
+    "application/x-test".parse().unwrap()
+}
+
+fn center_color(thumbnailer: &Thumbnailer, bytes: &[u8], mime: Mime) -> [u8; 3] {
+    let thumbnail = thumbnailer
+        .create(Cursor::new(bytes), mime, [ThumbnailSize::Icon])
+        .unwrap()
+        .pop()
+        .unwrap();
+    let image = thumbnail.as_image();
+    let (width, height) = image.dimensions();
+
+    image.to_rgb8().get_pixel(width / 2, height / 2).0
+}
+
+#[test]
+fn it_creates_thumbnails_with_custom_handlers() {
+    let thumbnailer = Thumbnailer::builder()
+        .register_handler(SolidHandler::new("application/x-test", [0, 128, 255]))
+        .build();
+
+    assert!(thumbnailer.supports_mime(&test_mime()));
+    let thumbnails = thumbnailer
+        .create(Cursor::new(b"TEST"), test_mime(), [ThumbnailSize::Small])
+        .unwrap();
+
+    assert_eq!(thumbnails[0].as_image().dimensions(), (128, 128));
+    assert_eq!(
+        center_color(&thumbnailer, b"TEST", test_mime()),
+        [0, 128, 255]
+    );
+}
+
+#[test]
+fn it_matches_mimes_without_parameters() {
+    let thumbnailer = Thumbnailer::builder()
+        .register_handler(SolidHandler::new("application/x-test", [0, 128, 255]))
+        .build();
+    let mime: Mime = "application/x-test; version=2".parse().unwrap();
+
+    assert_eq!(center_color(&thumbnailer, b"TEST", mime), [0, 128, 255]);
+}
+
+#[test]
+fn it_returns_errors_of_custom_handlers() {
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .register_handler(SolidHandler::new("application/x-test", [0, 128, 255]))
+        .run(Cursor::new(b"NOPE"), test_mime());
+
+    assert!(matches!(result, Err(ThumbError::Decode { .. })));
+}
+
+#[test]
+fn it_uses_the_last_registered_handler() {
+    let thumbnailer = Thumbnailer::builder()
+        .register_handler(SolidHandler::new("application/x-test", [255, 0, 0]))
+        .register_handler(SolidHandler::new("application/x-test", [0, 255, 0]))
+        .build();
+
+    assert_eq!(
+        center_color(&thumbnailer, b"TEST", test_mime()),
+        [0, 255, 0]
+    );
+}
+
+#[test]
+fn it_overrides_builtin_handlers() {
+    let thumbnailer = Thumbnailer::builder()
+        .register_handler(SolidHandler::new("image/png", [0, 0, 255]))
+        .build();
+
+    assert_eq!(
+        center_color(&thumbnailer, b"TEST", mime::IMAGE_PNG),
+        [0, 0, 255]
+    );
+    assert_ne!(
+        center_color(&Thumbnailer::default(), PNG_BYTES, mime::IMAGE_PNG),
+        [0, 0, 255]
+    );
+}
+
+#[test]
+fn it_applies_input_limits_to_custom_handlers() {
+    let mut handler = SolidHandler::new("application/x-test", [0, 128, 255]);
+    handler.size = 1000;
+    let thumbnailer = Thumbnailer::builder()
+        .max_input_pixels(Some(1000))
+        .register_handler(handler)
+        .build();
+
+    let result = thumbnailer.create(Cursor::new(b"TEST"), test_mime(), [ThumbnailSize::Small]);
+
+    assert!(matches!(result, Err(ThumbError::InputTooLarge { .. })));
+}
+
+#[test]
+fn it_keeps_handlers_of_other_instances() {
+    let custom = Thumbnailer::builder()
+        .register_handler(SolidHandler::new("application/x-test", [0, 128, 255]))
+        .build();
+
+    assert!(custom.supports_mime(&test_mime()));
+    assert!(!Thumbnailer::default().supports_mime(&test_mime()));
+}
@@ -0,0 +1,119 @@
+use std::convert::TryInto;
+use std::io::Cursor;
+use thumbnailer::image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use thumbnailer::{JpegOptions, PngOptions, Thumbnail, ThumbnailSize, ThumbnailerBuilder};
+
+/// Returns a small png with some detail so that the runs stay fast
+fn source_png() -> Vec<u8> {
+    let image = RgbaImage::from_fn(160, 120, |x, y| {
+        Rgba([
+            (x * 7 % 256) as u8,
+            (y * 13 % 256) as u8,
+            ((x ^ y) * 3 % 256) as u8,
+            255,
+        ])
+    });
+    let mut buf = Cursor::new(Vec::new());
+    DynamicImage::ImageRgba8(image)
+        .write_to(&mut buf, ImageFormat::Png)
+        .unwrap();
+
+    buf.into_inner()
+}
+
+fn create_thumbnails(source: &[u8]) -> Vec<Thumbnail> {
+    ThumbnailerBuilder::new()
+        .sizes([
+            ThumbnailSize::Icon,
+            ThumbnailSize::Small,
+            ThumbnailSize::Custom {
+                width: 96,
+                height: 48,
+            },
+        ])
+        .deterministic(true)
+        .parallel(true)
+        .run(Cursor::new(source), mime::IMAGE_PNG)
+        .unwrap()
+}
+
+/// Writes every thumbnail as png, jpeg, interlaced png and progressive jpeg
+fn write_all(thumbnails: Vec<Thumbnail>) -> Vec<Vec<u8>> {
+    let mut outputs = Vec::new();
+    for thumbnail in thumbnails {
+        let mut png = Cursor::new(Vec::new());
+        thumbnail.clone().write_png(&mut png).unwrap();
+        let mut jpeg = Cursor::new(Vec::new());
+        thumbnail.clone().write_jpeg(&mut jpeg).unwrap();
+        let mut interlaced = Cursor::new(Vec::new());
+        let options = PngOptions {
+            interlaced: true,
+            ..Default::default()
+        };
+        thumbnail
+            .clone()
+            .write_png_with_options(&mut interlaced, options)
+            .unwrap();
+        let mut progressive = Cursor::new(Vec::new());
+        let options = JpegOptions {
+            progressive: true,
+            ..Default::default()
+        };
+        thumbnail
+            .write_jpeg_with_options(&mut progressive, options)
+            .unwrap();
+
+        outputs.extend([png, jpeg, interlaced, progressive].map(Cursor::into_inner));
+    }
+
+    outputs
+}
+
+/// Returns the types of all chunks of a png
+fn png_chunks(png: &[u8]) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut offset = 8;
+    while offset + 8 <= png.len() {
+        let length = u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap()) as usize;
+        chunks.push(String::from_utf8_lossy(&png[offset + 4..offset + 8]).to_string());
+        offset += length + 12;
+    }
+
+    chunks
+}
+
+#[test]
+fn it_writes_identical_bytes_across_runs() {
+    let source = source_png();
+    let expected = write_all(create_thumbnails(&source));
+
+    for _ in 0..100 {
+        assert!(write_all(create_thumbnails(&source)) == expected);
+    }
+}
+
+#[test]
+fn it_does_not_embed_metadata() {
+    let outputs = write_all(create_thumbnails(&source_png()));
+
+    for png in outputs.iter().step_by(2) {
+        assert_eq!(png_chunks(png), ["IHDR", "IDAT", "IEND"]);
+    }
+    for jpeg in outputs.iter().skip(1).step_by(2) {
+        // exif, xmp and comment segments
+        assert!(!jpeg
+            .windows(2)
+            .any(|m| m == [0xFF, 0xE1] || m == [0xFF, 0xFE]));
+    }
+}
+
+#[test]
+fn it_keeps_the_pixels_of_deterministic_png_output() {
+    let thumbnail = create_thumbnails(&source_png()).pop().unwrap();
+    let expected = thumbnail.as_image().to_rgba8();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+
+    let decoded = thumbnailer::image::load_from_memory(buf.get_ref()).unwrap();
+    assert_eq!(decoded.into_rgba8(), expected);
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "ebook")]
+
+mod common;
+
+use common::{center_pixel, create_thumbnail};
+use thumbnailer::error::ThumbError;
+use thumbnailer::image::Rgb;
+
+const EPUB_MIME: &str = "application/epub+zip";
+const CBZ_MIME: &str = "application/vnd.comicbook+zip";
+
+const EPUB_BYTES: &[u8] = include_bytes!("assets/cover.epub");
+const EPUB_NO_COVER_BYTES: &[u8] = include_bytes!("assets/no_cover.epub");
+const CBZ_BYTES: &[u8] = include_bytes!("assets/pages.cbz");
+const CBZ_NO_PAGES_BYTES: &[u8] = include_bytes!("assets/no_pages.cbz");
+
+#[test]
+fn it_reads_epub_covers() {
+    let thumbnail = create_thumbnail(EPUB_BYTES, EPUB_MIME).unwrap();
+
+    assert_eq!(thumbnail.size(), (96, 128));
+    assert_eq!(center_pixel(&thumbnail), Rgb([255, 0, 0]));
+}
+
+#[test]
+fn it_reads_the_first_page_of_comic_books_in_natural_order() {
+    for mime in [CBZ_MIME, "application/x-cbz"] {
+        let thumbnail = create_thumbnail(CBZ_BYTES, mime).unwrap();
+
+        // page2 is portrait while page10 is landscape
+        assert_eq!(thumbnail.size().1, 128);
+        assert_eq!(center_pixel(&thumbnail), Rgb([0, 0, 255]));
+    }
+}
+
+#[test]
+fn it_returns_no_embedded_image_without_covers() {
+    for (bytes, mime) in [
+        (EPUB_NO_COVER_BYTES, EPUB_MIME),
+        (CBZ_NO_PAGES_BYTES, CBZ_MIME),
+    ] {
+        let result = create_thumbnail(bytes, mime);
+        assert!(
+            matches!(result, Err(ThumbError::NoEmbeddedImage)),
+            "{:?}",
+            result.err()
+        );
+    }
+}
+
+#[test]
+fn it_fails_to_decode_corrupt_archives() {
+    for (bytes, mime) in [
+        (&EPUB_BYTES[..EPUB_BYTES.len() / 2], EPUB_MIME),
+        (b"not a zip archive".as_slice(), CBZ_MIME),
+    ] {
+        let result = create_thumbnail(bytes, mime);
+        assert!(
+            matches!(result, Err(ThumbError::Decode { .. })),
+            "{:?}",
+            result.err()
+        );
+    }
+}
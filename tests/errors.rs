@@ -0,0 +1,63 @@
+use std::error::Error;
+use std::io::{self, Cursor, Seek, SeekFrom, Write};
+use thumbnailer::error::ThumbError;
+use thumbnailer::{create_thumbnails, ThumbnailSize};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+/// A writer that fails on every write
+struct FailingWriter;
+
+impl Write for FailingWriter {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("disk full"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for FailingWriter {
+    fn seek(&mut self, _: SeekFrom) -> io::Result<u64> {
+        Ok(0)
+    }
+}
+
+#[test]
+fn it_reports_corrupt_files() {
+    let reader = Cursor::new(&PNG_BYTES[..PNG_BYTES.len() / 2]);
+    let error = create_thumbnails(reader, mime::IMAGE_PNG, [ThumbnailSize::Small]).unwrap_err();
+
+    assert!(matches!(error, ThumbError::Decode { .. }), "{:?}", error);
+    assert!(error.is_corrupt());
+    assert!(!error.is_unsupported());
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn it_reports_unsupported_mimes() {
+    let reader = Cursor::new(PNG_BYTES);
+    let error = create_thumbnails(reader, mime::FONT_WOFF, [ThumbnailSize::Small]).unwrap_err();
+
+    assert!(matches!(error, ThumbError::UnsupportedMime(_)));
+    assert!(error.is_unsupported());
+    assert!(!error.is_corrupt());
+}
+
+#[test]
+fn it_reports_io_errors() {
+    let thumbnail = create_thumbnails(
+        Cursor::new(PNG_BYTES),
+        mime::IMAGE_PNG,
+        [ThumbnailSize::Small],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let error = thumbnail.write_png(&mut FailingWriter).unwrap_err();
+
+    assert!(matches!(error, ThumbError::Io(_)), "{:?}", error);
+    assert!(!error.is_corrupt());
+    assert_eq!(error.source().unwrap().to_string(), "disk full");
+}
@@ -0,0 +1,157 @@
+use std::io::{self, BufRead, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::str::FromStr;
+use thumbnailer::error::ThumbError;
+use thumbnailer::image::{GenericImageView, Rgba, RgbaImage};
+use thumbnailer::{
+    create_thumbnails_or_placeholder, generate_placeholder, Thumbnail, ThumbnailSize,
+};
+
+fn placeholder(mime: &str, label: Option<&str>, size: ThumbnailSize) -> Thumbnail {
+    generate_placeholder(&mime::Mime::from_str(mime).unwrap(), label, [size])
+        .pop()
+        .unwrap()
+}
+
+/// Returns the color in the middle of the glyph below the label
+fn glyph_color(thumbnail: &Thumbnail) -> Rgba<u8> {
+    let (width, height) = thumbnail.size();
+    thumbnail.as_image().get_pixel(width / 2, height * 9 / 10)
+}
+
+/// Compares the image to the png snapshot in the assets. The snapshot is written
+/// instead if the `UPDATE_SNAPSHOTS` environment variable is set.
+fn assert_snapshot(name: &str, image: RgbaImage) {
+    let path = Path::new("tests/assets/snapshots").join(format!("{}.png", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        image.save(&path).unwrap();
+    }
+    let snapshot = thumbnailer::image::open(&path).unwrap().to_rgba8();
+
+    assert_eq!(image.dimensions(), snapshot.dimensions(), "{}", name);
+    let differing = image
+        .pixels()
+        .zip(snapshot.pixels())
+        .filter(|(a, b)| a.0.iter().zip(b.0).any(|(a, b)| a.abs_diff(b) > 2))
+        .count();
+    assert_eq!(differing, 0, "{} pixels of {} differ", differing, name);
+}
+
+#[test]
+fn it_renders_placeholders_of_the_requested_sizes() {
+    let scaled = ThumbnailSize::Icon.scaled(2).unwrap();
+    let sizes = [
+        ThumbnailSize::Icon,
+        ThumbnailSize::Custom {
+            width: 200,
+            height: 100,
+        },
+        scaled,
+    ];
+    let thumbnails = generate_placeholder(&mime::APPLICATION_PDF, None, sizes);
+
+    let dimensions: Vec<(u32, u32)> = thumbnails.iter().map(Thumbnail::size).collect();
+    assert_eq!(dimensions, [(64, 64), (200, 100), (128, 128)]);
+    assert_eq!(thumbnails[2].requested_size(), scaled);
+    for thumbnail in &thumbnails {
+        // the glyph is centered on a transparent background
+        assert_eq!(thumbnail.as_image().get_pixel(0, 0)[3], 0);
+        assert_eq!(glyph_color(thumbnail)[3], 255);
+    }
+}
+
+#[test]
+fn it_colors_placeholders_by_media_type() {
+    let pdf = placeholder("application/pdf", Some(""), ThumbnailSize::Small);
+    let labeled_pdf = placeholder("Application/PDF", Some("DOC"), ThumbnailSize::Small);
+    let zip = placeholder("application/zip", Some(""), ThumbnailSize::Small);
+
+    assert_eq!(glyph_color(&pdf), glyph_color(&labeled_pdf));
+    assert_ne!(glyph_color(&pdf), glyph_color(&zip));
+}
+
+#[test]
+fn it_matches_the_snapshot_of_a_glyph() {
+    let thumbnail = placeholder("application/zip", Some(""), ThumbnailSize::Small);
+
+    assert_snapshot("placeholder_glyph", thumbnail.as_image().to_rgba8());
+}
+
+#[cfg(feature = "text")]
+#[test]
+fn it_matches_the_snapshots_of_labels() {
+    let pdf = placeholder("application/pdf", None, ThumbnailSize::Medium);
+    assert_snapshot("placeholder_pdf", pdf.as_image().to_rgba8());
+
+    let long = placeholder(
+        "application/octet-stream",
+        Some("VERYLONGEXTENSION"),
+        ThumbnailSize::Small,
+    );
+    assert_snapshot("placeholder_truncated", long.as_image().to_rgba8());
+}
+
+#[cfg(feature = "text")]
+#[test]
+fn it_draws_labels_onto_the_glyph() {
+    let empty = placeholder("application/pdf", Some(""), ThumbnailSize::Small);
+    let labeled = placeholder("application/pdf", None, ThumbnailSize::Small);
+
+    assert_ne!(empty.as_image(), labeled.as_image());
+}
+
+/// A reader that fails after the first bytes
+struct FailingReader(Cursor<Vec<u8>>);
+
+impl Read for FailingReader {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::other("disconnected"))
+    }
+}
+
+impl BufRead for FailingReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Err(io::Error::other("disconnected"))
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.0.consume(amount)
+    }
+}
+
+impl Seek for FailingReader {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        self.0.seek(position)
+    }
+}
+
+#[test]
+fn it_falls_back_to_placeholders() {
+    let sizes = [ThumbnailSize::Icon, ThumbnailSize::Small];
+    let unsupported = create_thumbnails_or_placeholder(
+        Cursor::new(b"PK\x03\x04"),
+        mime::Mime::from_str("application/zip").unwrap(),
+        sizes,
+    )
+    .unwrap();
+    assert_eq!(unsupported.len(), 2);
+    assert_eq!(unsupported[1].size(), (128, 128));
+
+    let corrupt =
+        create_thumbnails_or_placeholder(Cursor::new(b"not a png"), mime::IMAGE_PNG, sizes)
+            .unwrap();
+    assert_eq!(corrupt[0].size(), (64, 64));
+    assert_eq!(corrupt[0].as_image().get_pixel(0, 0)[3], 0);
+}
+
+#[test]
+fn it_returns_io_errors_instead_of_placeholders() {
+    let result = create_thumbnails_or_placeholder(
+        FailingReader(Cursor::new(Vec::new())),
+        mime::IMAGE_PNG,
+        [ThumbnailSize::Icon],
+    );
+
+    assert!(matches!(result, Err(ThumbError::Io(_))), "{:?}", result);
+}
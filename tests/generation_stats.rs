@@ -0,0 +1,110 @@
+use std::io::Cursor;
+use std::time::Duration;
+use thumbnailer::image::ImageFormat;
+use thumbnailer::{SizeStats, ThumbnailSize, Thumbnailer, ThumbnailerBuilder};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+
+fn sizes(per_size: &[SizeStats]) -> Vec<ThumbnailSize> {
+    per_size.iter().map(|stats| stats.size).collect()
+}
+
+#[test]
+fn it_collects_the_stats_of_each_stage() {
+    let sizes_added = [ThumbnailSize::Medium, ThumbnailSize::Small];
+    let (stats, thumbnails) = ThumbnailerBuilder::new()
+        .sizes(sizes_added)
+        .collect_stats(true)
+        .run_with_stats(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+    let stats = stats.unwrap();
+
+    assert_eq!(thumbnails.len(), 2);
+    assert!(stats.decode_duration > Duration::ZERO);
+    assert_eq!(stats.source_dimensions, (792, 589));
+    assert_eq!(stats.detected_format, Some(ImageFormat::Png));
+    assert_eq!(sizes(&stats.per_size), sizes_added);
+    for size in &stats.per_size {
+        assert!(size.resize_duration > Duration::ZERO, "{:?}", size);
+    }
+}
+
+#[test]
+fn it_doesnt_collect_stats_by_default() {
+    let builder = ThumbnailerBuilder::new().size(ThumbnailSize::Small);
+    let (stats, thumbnails) = builder
+        .run_with_stats(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+    let expected = builder
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert_eq!(stats, None);
+    assert_eq!(thumbnails.len(), 1);
+    assert!(thumbnails[0].pixel_eq(&expected[0]));
+}
+
+#[test]
+fn it_skips_the_stats_of_duplicate_sizes() {
+    let (stats, _) = ThumbnailerBuilder::new()
+        .sizes([
+            ThumbnailSize::Small,
+            ThumbnailSize::Icon,
+            ThumbnailSize::Small,
+        ])
+        .collect_stats(true)
+        .run_with_stats(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert_eq!(
+        sizes(&stats.unwrap().per_size),
+        [ThumbnailSize::Small, ThumbnailSize::Icon]
+    );
+}
+
+#[test]
+fn it_collects_stats_sequentially_and_with_a_timeout() {
+    for builder in [
+        ThumbnailerBuilder::new().parallel(false),
+        ThumbnailerBuilder::new().timeout(Duration::from_secs(60)),
+    ] {
+        let (stats, _) = builder
+            .sizes([ThumbnailSize::Small, ThumbnailSize::Medium])
+            .collect_stats(true)
+            .run_with_stats(Cursor::new(JPG_BYTES), mime::IMAGE_JPEG)
+            .unwrap();
+        let stats = stats.unwrap();
+
+        assert!(stats.decode_duration > Duration::ZERO);
+        assert_eq!(stats.detected_format, Some(ImageFormat::Jpeg));
+        assert_eq!(stats.per_size.len(), 2);
+    }
+}
+
+#[test]
+fn it_collects_stats_of_reusable_thumbnailers() {
+    let thumbnailer = Thumbnailer::builder().collect_stats(true).build();
+    let (stats, thumbnails) = thumbnailer
+        .create_with_stats(
+            Cursor::new(PNG_BYTES),
+            mime::IMAGE_PNG,
+            [ThumbnailSize::Icon],
+        )
+        .unwrap();
+    let stats = stats.unwrap();
+
+    assert_eq!(thumbnails[0].requested_size(), ThumbnailSize::Icon);
+    assert_eq!(sizes(&stats.per_size), [ThumbnailSize::Icon]);
+    assert_eq!(stats.source_dimensions, (792, 589));
+}
+
+#[test]
+fn it_fails_like_run() {
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .collect_stats(true)
+        .run_with_stats(Cursor::new(&PNG_BYTES[..64]), mime::IMAGE_PNG);
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "heif")]
+
+use mime::Mime;
+use std::io::Cursor;
+use std::str::FromStr;
+use thumbnailer::{create_thumbnails_guess, BaseImage, ThumbnailSize};
+
+const AVIF_10_BIT_BYTES: &[u8] = include_bytes!("assets/gradient.avif");
+
+#[test]
+fn it_reads_10_bit_avif_images() {
+    let base = BaseImage::decode(
+        Cursor::new(AVIF_10_BIT_BYTES),
+        Mime::from_str("image/avif").unwrap(),
+    )
+    .unwrap();
+    assert_eq!(base.dimensions(), (64, 32));
+    assert_eq!(base.format(), Some(image::ImageFormat::Avif));
+
+    let thumbnails = base.thumbnails([ThumbnailSize::Icon]).unwrap();
+    let image = thumbnails[0].as_image().to_rgb8();
+    let pixel = image.get_pixel(image.width() / 2, image.height() / 2);
+    let expected = [128, 128, 127];
+    for (value, expected) in pixel.0.iter().zip(expected) {
+        assert!((*value as i32 - expected).abs() <= 8, "{:?}", pixel);
+    }
+}
+
+#[test]
+fn it_detects_avif_images() {
+    let thumbnails =
+        create_thumbnails_guess(Cursor::new(AVIF_10_BIT_BYTES), [ThumbnailSize::Icon]).unwrap();
+    assert_eq!(thumbnails.len(), 1);
+}
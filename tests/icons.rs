@@ -0,0 +1,145 @@
+use image::ImageFormat;
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{write_ico, ResizeMode, Thumbnail, ThumbnailSize, ThumbnailerBuilder};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+/// Returns square thumbnails of the given sizes
+fn square_thumbnails<I: IntoIterator<Item = ThumbnailSize>>(sizes: I) -> Vec<Thumbnail> {
+    ThumbnailerBuilder::new()
+        .sizes(sizes)
+        .resize_mode(ResizeMode::Cover)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap()
+}
+
+/// Returns the dimensions of the entries in the directory of an ico file
+fn ico_entries(bytes: &[u8]) -> Vec<(u32, u32)> {
+    assert_eq!(&bytes[..4], [0, 0, 1, 0]);
+    let count = u16::from_le_bytes([bytes[4], bytes[5]]) as usize;
+
+    (0..count)
+        .map(|i| {
+            let entry = &bytes[6 + i * 16..];
+            // a dimension of 0 means 256 pixels
+            let dimension = |byte: u8| if byte == 0 { 256 } else { byte as u32 };
+            (dimension(entry[0]), dimension(entry[1]))
+        })
+        .collect()
+}
+
+#[test]
+fn it_writes_ico_files_with_all_sizes() {
+    let mut thumbnails = square_thumbnails([ThumbnailSize::Medium, ThumbnailSize::Icon]);
+    thumbnails.extend(
+        ThumbnailerBuilder::new()
+            .size(ThumbnailSize::Small)
+            .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+            .unwrap(),
+    );
+    let mut buf = Cursor::new(Vec::new());
+    write_ico(&thumbnails, &mut buf).unwrap();
+
+    assert_eq!(
+        ico_entries(buf.get_ref()),
+        [(64, 64), (128, 95), (256, 256)]
+    );
+    // the largest entry is decoded
+    let image = image::load_from_memory_with_format(buf.get_ref(), ImageFormat::Ico).unwrap();
+    assert_eq!((image.width(), image.height()), (256, 256));
+}
+
+#[test]
+fn it_writes_duplicate_sizes_once() {
+    let thumbnails = square_thumbnails([
+        ThumbnailSize::Small,
+        ThumbnailSize::Icon,
+        ThumbnailSize::Small,
+        ThumbnailSize::Custom {
+            width: 64,
+            height: 64,
+        },
+    ]);
+    let mut buf = Cursor::new(Vec::new());
+    write_ico(&thumbnails, &mut buf).unwrap();
+
+    assert_eq!(ico_entries(buf.get_ref()), [(64, 64), (128, 128)]);
+}
+
+#[test]
+fn it_rejects_ico_entries_larger_than_256_pixels() {
+    let thumbnails = square_thumbnails([ThumbnailSize::Icon, ThumbnailSize::Large]);
+    let result = write_ico(&thumbnails, &mut Cursor::new(Vec::new()));
+
+    assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+    assert!(matches!(
+        write_ico(&[], &mut Cursor::new(Vec::new())),
+        Err(ThumbError::InvalidOptions(_))
+    ));
+}
+
+#[cfg(feature = "icns")]
+#[test]
+fn it_writes_icns_files_with_all_sizes() {
+    use std::convert::TryInto;
+    use thumbnailer::write_icns;
+
+    let thumbnails = square_thumbnails([
+        ThumbnailSize::Large,
+        ThumbnailSize::Icon,
+        ThumbnailSize::Custom {
+            width: 16,
+            height: 16,
+        },
+        ThumbnailSize::Icon,
+    ]);
+    let mut buf = Cursor::new(Vec::new());
+    write_icns(&thumbnails, &mut buf).unwrap();
+    let bytes = buf.into_inner();
+
+    assert_eq!(&bytes[..4], b"icns");
+    let length = |offset: usize| u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    assert_eq!(length(4) as usize, bytes.len());
+
+    let mut entries = Vec::new();
+    let mut offset = 8;
+    while offset < bytes.len() {
+        let end = offset + length(offset + 4) as usize;
+        let image =
+            image::load_from_memory_with_format(&bytes[offset + 8..end], ImageFormat::Png).unwrap();
+        entries.push((
+            String::from_utf8(bytes[offset..offset + 4].to_vec()).unwrap(),
+            image.width(),
+        ));
+        offset = end;
+    }
+    assert_eq!(
+        entries,
+        [
+            (String::from("icp4"), 16),
+            (String::from("icp6"), 64),
+            (String::from("ic09"), 512),
+        ]
+    );
+}
+
+#[cfg(feature = "icns")]
+#[test]
+fn it_rejects_icns_entries_without_icns_sizes() {
+    use thumbnailer::write_icns;
+
+    for sizes in [
+        vec![ThumbnailSize::Custom {
+            width: 48,
+            height: 48,
+        }],
+        vec![ThumbnailSize::Custom {
+            width: 64,
+            height: 32,
+        }],
+    ] {
+        let result = write_icns(&square_thumbnails(sizes), &mut Cursor::new(Vec::new()));
+        assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+    }
+}
@@ -0,0 +1,157 @@
+use image::{ImageFormat, Rgba};
+use std::io::Cursor;
+use thumbnailer::{
+    create_thumbnails, FlipDirection, Rotation, Thumbnail, ThumbnailSize, ThumbnailerBuilder,
+};
+
+const ORIENTED_JPEGS: [&[u8]; 8] = [
+    include_bytes!("assets/orientation_1.jpg"),
+    include_bytes!("assets/orientation_2.jpg"),
+    include_bytes!("assets/orientation_3.jpg"),
+    include_bytes!("assets/orientation_4.jpg"),
+    include_bytes!("assets/orientation_5.jpg"),
+    include_bytes!("assets/orientation_6.jpg"),
+    include_bytes!("assets/orientation_7.jpg"),
+    include_bytes!("assets/orientation_8.jpg"),
+];
+const CORRUPT_EXIF_BYTES: &[u8] = include_bytes!("assets/corrupt_exif.jpg");
+
+fn is_red(pixel: &Rgba<u8>) -> bool {
+    pixel[0] > 200 && pixel[2] < 50
+}
+
+#[test]
+fn it_applies_all_exif_orientations() {
+    for (index, bytes) in ORIENTED_JPEGS.iter().enumerate() {
+        let thumb = create_thumbnails(Cursor::new(bytes), mime::IMAGE_JPEG, [ThumbnailSize::Small])
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert_eq!(thumb.size(), (128, 85), "orientation {}", index + 1);
+
+        let mut buf = Cursor::new(Vec::new());
+        thumb.write_png(&mut buf).unwrap();
+        let image = image::load_from_memory_with_format(buf.get_ref(), ImageFormat::Png)
+            .unwrap()
+            .into_rgba8();
+
+        assert!(is_red(image.get_pixel(10, 10)), "orientation {}", index + 1);
+        assert!(
+            !is_red(image.get_pixel(117, 10)),
+            "orientation {}",
+            index + 1
+        );
+        assert!(
+            !is_red(image.get_pixel(10, 75)),
+            "orientation {}",
+            index + 1
+        );
+        assert!(
+            !is_red(image.get_pixel(117, 75)),
+            "orientation {}",
+            index + 1
+        );
+    }
+}
+
+#[test]
+fn it_ignores_corrupt_exif_data() {
+    let thumb = create_thumbnails(
+        Cursor::new(CORRUPT_EXIF_BYTES),
+        mime::IMAGE_JPEG,
+        [ThumbnailSize::Small],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+
+    assert_eq!(thumb.size(), (128, 85));
+}
+
+/// Returns which corners (top left, top right, bottom left, bottom right) are red
+fn red_corners(thumb: &Thumbnail) -> [bool; 4] {
+    let image = thumb.as_image().to_rgba8();
+    let (width, height) = image.dimensions();
+
+    [
+        (10, 10),
+        (width - 11, 10),
+        (10, height - 11),
+        (width - 11, height - 11),
+    ]
+    .map(|(x, y)| is_red(image.get_pixel(x, y)))
+}
+
+#[test]
+fn it_rotates_and_flips_after_the_exif_orientation() {
+    let cases = [
+        (
+            Some(Rotation::Cw90),
+            None,
+            (85, 128),
+            [false, true, false, false],
+        ),
+        (
+            Some(Rotation::Cw180),
+            None,
+            (128, 85),
+            [false, false, false, true],
+        ),
+        (
+            Some(Rotation::Cw270),
+            None,
+            (85, 128),
+            [false, false, true, false],
+        ),
+        (
+            None,
+            Some(FlipDirection::Horizontal),
+            (128, 85),
+            [false, true, false, false],
+        ),
+        (
+            None,
+            Some(FlipDirection::Vertical),
+            (128, 85),
+            [false, false, true, false],
+        ),
+        (
+            Some(Rotation::Cw90),
+            Some(FlipDirection::Horizontal),
+            (85, 128),
+            [true, false, false, false],
+        ),
+    ];
+
+    for bytes in [ORIENTED_JPEGS[0], ORIENTED_JPEGS[5]] {
+        for (rotation, flip, size, corners) in cases {
+            let mut builder = ThumbnailerBuilder::new().size(ThumbnailSize::Small);
+            if let Some(rotation) = rotation {
+                builder = builder.rotate(rotation);
+            }
+            if let Some(flip) = flip {
+                builder = builder.flip(flip);
+            }
+            let thumb = builder
+                .run(Cursor::new(bytes), mime::IMAGE_JPEG)
+                .unwrap()
+                .pop()
+                .unwrap();
+
+            assert_eq!(thumb.size(), size, "{:?} {:?}", rotation, flip);
+            assert_eq!(red_corners(&thumb), corners, "{:?} {:?}", rotation, flip);
+        }
+    }
+}
+
+#[test]
+fn it_rotates_all_sizes_the_same() {
+    let thumbs = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Icon, ThumbnailSize::Small])
+        .rotate(Rotation::Cw90)
+        .run(Cursor::new(ORIENTED_JPEGS[0]), mime::IMAGE_JPEG)
+        .unwrap();
+
+    assert_eq!(thumbs[0].size(), (43, 64));
+    assert_eq!(thumbs[1].size(), (85, 128));
+}
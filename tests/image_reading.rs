@@ -6,8 +6,11 @@ use crate::ImageType::{Jpeg, Png, Webp};
 use mime::Mime;
 use std::io::Cursor;
 use std::str::FromStr;
-use thumbnailer::error::ThumbResult;
-use thumbnailer::{create_thumbnails, Thumbnail, ThumbnailSize};
+use thumbnailer::error::{ThumbError, ThumbResult};
+use thumbnailer::{
+    create_thumbnails, create_thumbnails_with_filter, create_thumbnails_with_meta, BaseImage,
+    FilterType, Thumbnail, ThumbnailSize,
+};
 
 enum ImageType {
     Png,
@@ -77,3 +80,138 @@ fn create_thumbnail(image_type: ImageType, size: ThumbnailSize) -> ThumbResult<V
         }
     }
 }
+
+#[test]
+fn it_creates_custom_thumbnails_for_png() {
+    let size = ThumbnailSize::Custom {
+        width: 320,
+        height: 180,
+    };
+    let thumb = create_thumbnail(Png, size).unwrap().pop().unwrap();
+    let (width, height) = thumb.size();
+    assert!(width <= 320 && height <= 180);
+    assert!(width == 320 || height == 180);
+}
+
+#[test]
+fn it_rejects_zero_sized_thumbnails() {
+    let size = ThumbnailSize::Custom {
+        width: 0,
+        height: 180,
+    };
+    assert!(matches!(
+        create_thumbnail(Png, size),
+        Err(ThumbError::InvalidSize(0, 180))
+    ));
+    assert!(ThumbnailSize::from_dimensions(128, 0).is_err());
+}
+
+#[test]
+fn it_snaps_dimensions_to_presets() {
+    assert_eq!(
+        ThumbnailSize::from_dimensions(256, 256).unwrap(),
+        ThumbnailSize::Medium
+    );
+    assert_eq!(
+        ThumbnailSize::from_dimensions(1024, 576).unwrap(),
+        ThumbnailSize::Custom {
+            width: 1024,
+            height: 576
+        }
+    );
+}
+
+#[test]
+fn it_creates_thumbnails_with_different_filters() {
+    for filter in [
+        FilterType::Nearest,
+        FilterType::Triangle,
+        FilterType::Lanczos3,
+    ] {
+        let thumbnails = create_thumbnails_with_filter(
+            Cursor::new(JPG_BYTES),
+            mime::IMAGE_JPEG,
+            [ThumbnailSize::Small, ThumbnailSize::Medium],
+            filter,
+        )
+        .unwrap();
+        assert_eq!(thumbnails.len(), 2);
+        assert_eq!(thumbnails[0].size(), (128, 95));
+    }
+}
+
+#[test]
+fn it_resizes_with_the_given_filter() {
+    let small = |filter| {
+        create_thumbnails_with_filter(
+            Cursor::new(PNG_BYTES),
+            mime::IMAGE_PNG,
+            [ThumbnailSize::Small],
+            filter,
+        )
+        .unwrap()
+        .remove(0)
+    };
+    let nearest = small(FilterType::Nearest);
+    let lanczos = small(FilterType::Lanczos3);
+
+    assert_eq!(nearest.size(), lanczos.size());
+    assert!(!nearest.pixel_eq(&lanczos));
+    assert!(nearest.pixel_eq(&small(FilterType::Nearest)));
+}
+
+#[test]
+fn it_returns_thumbnails_in_the_requested_order() {
+    let sizes = [
+        ThumbnailSize::Large,
+        ThumbnailSize::Icon,
+        ThumbnailSize::Custom {
+            width: 300,
+            height: 300,
+        },
+        ThumbnailSize::Custom {
+            width: 400,
+            height: 400,
+        },
+        ThumbnailSize::Small,
+    ];
+    let thumbnails = create_thumbnails(Cursor::new(PNG_BYTES), mime::IMAGE_PNG, sizes).unwrap();
+
+    assert_eq!(thumbnails.len(), sizes.len());
+    for (thumbnail, size) in thumbnails.iter().zip(sizes) {
+        assert_eq!(thumbnail.requested_size(), size);
+        assert_eq!(thumbnail.size().0, size.dimensions().0);
+    }
+}
+
+#[test]
+fn it_reuses_decoded_base_images() {
+    let base = BaseImage::decode(Cursor::new(PNG_BYTES), mime::IMAGE_PNG).unwrap();
+    assert_eq!(base.dimensions(), (792, 589));
+    assert_eq!(base.format(), Some(image::ImageFormat::Png));
+
+    let small = base.thumbnails([ThumbnailSize::Small]).unwrap();
+    let medium = base.thumbnails([ThumbnailSize::Medium]).unwrap();
+    assert_eq!(small[0].size(), (128, 95));
+    assert_eq!(medium[0].size(), (256, 190));
+}
+
+#[test]
+fn it_returns_the_source_info_with_thumbnails() {
+    let (info, thumbnails) = create_thumbnails_with_meta(
+        Cursor::new(JPG_BYTES),
+        mime::IMAGE_JPEG,
+        [ThumbnailSize::Small],
+    )
+    .unwrap();
+    assert_eq!(thumbnails.len(), 1);
+    assert_eq!((info.width, info.height), (792, 589));
+    assert_eq!(info.format, Some(image::ImageFormat::Jpeg));
+    assert_eq!(info.color_type, image::ColorType::Rgb8);
+    assert_eq!(info.duration, None);
+    assert_eq!(info.page_count, None);
+
+    let base = BaseImage::decode(Cursor::new(PNG_BYTES), mime::IMAGE_PNG).unwrap();
+    assert_eq!(base.info().format, Some(image::ImageFormat::Png));
+    assert_eq!((base.info().width, base.info().height), base.dimensions());
+}
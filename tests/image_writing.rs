@@ -1,8 +1,15 @@
 use mime::Mime;
 use std::io::Cursor;
 use std::str::FromStr;
-use thumbnailer::error::ThumbResult;
-use thumbnailer::{create_thumbnails, ThumbnailSize};
+use thumbnailer::error::{ThumbError, ThumbResult};
+use thumbnailer::image::codecs::png::CompressionType;
+use thumbnailer::image::error::ImageFormatHint;
+use thumbnailer::image::{
+    DynamicImage, GenericImage, GenericImageView, ImageFormat, Rgb, Rgba, RgbaImage,
+};
+use thumbnailer::{
+    create_thumbnails, JpegOptions, PngOptions, Thumbnail, ThumbnailSize, ThumbnailerBuilder,
+};
 
 const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
 const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
@@ -17,6 +24,7 @@ enum SourceFormat {
 enum TargetFormat {
     Png,
     Jpeg,
+    Webp,
 }
 
 #[test]
@@ -49,6 +57,305 @@ fn it_converts_jpeg_thumbnails_for_webp() {
     write_thumbnail(SourceFormat::Webp, TargetFormat::Jpeg).unwrap();
 }
 
+#[test]
+fn it_converts_webp_thumbnails_for_png() {
+    write_thumbnail(SourceFormat::Png, TargetFormat::Webp).unwrap();
+}
+
+#[test]
+fn it_converts_webp_thumbnails_for_jpeg() {
+    write_thumbnail(SourceFormat::Jpeg, TargetFormat::Webp).unwrap();
+}
+
+#[test]
+fn it_converts_webp_thumbnails_for_webp() {
+    write_thumbnail(SourceFormat::Webp, TargetFormat::Webp).unwrap();
+}
+
+#[test]
+fn it_preserves_alpha_when_writing_webp() {
+    let bytes = write_thumbnail(SourceFormat::Png, TargetFormat::Webp).unwrap();
+    let image = image::load_from_memory_with_format(&bytes, image::ImageFormat::WebP).unwrap();
+    assert!(image.color().has_alpha());
+
+    let image = image.into_rgba8();
+    assert!(image.pixels().any(|p| p[3] == 0));
+    assert!(image.pixels().any(|p| p[3] == 255));
+}
+
+#[cfg(feature = "libwebp")]
+#[test]
+fn it_writes_smaller_webp_with_lower_quality() {
+    let thumb = create_thumbnails(
+        Cursor::new(JPG_BYTES),
+        mime::IMAGE_JPEG,
+        [ThumbnailSize::Medium],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let mut low = Cursor::new(Vec::new());
+    thumb.clone().write_webp_with_quality(&mut low, 10).unwrap();
+    let mut high = Cursor::new(Vec::new());
+    thumb.write_webp_with_quality(&mut high, 100).unwrap();
+
+    assert!(low.into_inner().len() < high.into_inner().len());
+}
+
+#[test]
+fn it_writes_smaller_jpeg_with_lower_quality() {
+    let thumb = create_thumbnails(
+        Cursor::new(JPG_BYTES),
+        mime::IMAGE_JPEG,
+        [ThumbnailSize::Medium],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let mut low = Cursor::new(Vec::new());
+    thumb.clone().write_jpeg_with_quality(&mut low, 30).unwrap();
+    let mut high = Cursor::new(Vec::new());
+    thumb
+        .clone()
+        .write_jpeg_with_quality(&mut high, 95)
+        .unwrap();
+
+    assert!(low.into_inner().len() < high.into_inner().len());
+    assert!(matches!(
+        thumb.write_jpeg_with_quality(&mut Cursor::new(Vec::new()), 0),
+        Err(ThumbError::InvalidQuality(0))
+    ));
+}
+
+#[test]
+fn it_round_trips_modified_images() {
+    let reader = Cursor::new(PNG_BYTES);
+    let thumbnail = create_thumbnails(reader, mime::IMAGE_PNG, [ThumbnailSize::Small])
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(thumbnail.as_image().dimensions(), thumbnail.size());
+
+    let mut image = thumbnail.into_inner();
+    image.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+    let thumbnail = Thumbnail::from(image);
+    assert_eq!(thumbnail.as_image().get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    let written = thumbnailer::image::load_from_memory(buf.get_ref()).unwrap();
+    assert_eq!(written.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+}
+
+#[test]
+fn it_uses_the_image_dimensions_as_requested_size() {
+    let thumbnail = Thumbnail::from(DynamicImage::new_rgb8(64, 64));
+    assert_eq!(thumbnail.requested_size(), ThumbnailSize::Icon);
+
+    let thumbnail = Thumbnail::from(DynamicImage::new_rgb8(30, 20));
+    assert_eq!(
+        thumbnail.requested_size(),
+        ThumbnailSize::Custom {
+            width: 30,
+            height: 20
+        }
+    );
+}
+
+#[test]
+fn it_writes_thumbnails_in_runtime_formats() {
+    for format in [
+        ImageFormat::Png,
+        ImageFormat::Jpeg,
+        ImageFormat::WebP,
+        ImageFormat::Gif,
+        ImageFormat::Bmp,
+        ImageFormat::Tiff,
+        ImageFormat::Pnm,
+        ImageFormat::Farbfeld,
+    ] {
+        let mut buf = Cursor::new(Vec::new());
+        small_thumbnail().write_to(&mut buf, format).unwrap();
+        let written =
+            thumbnailer::image::load_from_memory_with_format(buf.get_ref(), format).unwrap();
+        assert_eq!(written.dimensions(), small_thumbnail().size());
+    }
+}
+
+#[test]
+fn it_writes_thumbnails_for_mimes() {
+    let mut buf = Cursor::new(Vec::new());
+    small_thumbnail()
+        .write_with_mime(&mut buf, &mime::IMAGE_JPEG)
+        .unwrap();
+    assert_eq!(
+        thumbnailer::image::guess_format(buf.get_ref()).unwrap(),
+        ImageFormat::Jpeg
+    );
+}
+
+#[test]
+fn it_rejects_unsupported_output_formats() {
+    let mut buf = Cursor::new(Vec::new());
+    let result = small_thumbnail().write_to(&mut buf, ImageFormat::Dds);
+    assert!(matches!(
+        result,
+        Err(ThumbError::UnsupportedOutputFormat(ImageFormatHint::Exact(
+            ImageFormat::Dds
+        )))
+    ));
+
+    let result = small_thumbnail().write_with_mime(&mut buf, &mime::TEXT_PLAIN);
+    assert!(matches!(result, Err(ThumbError::UnsupportedMime(_))));
+}
+
+#[test]
+fn it_blends_transparency_onto_the_matte_for_jpeg() {
+    let mut image = RgbaImage::from_pixel(64, 64, Rgba([0, 0, 0, 0]));
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        if (16..48).contains(&x) && (16..48).contains(&y) {
+            *pixel = Rgba([255, 0, 0, 128]);
+        }
+    }
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageFormat::Png).unwrap();
+
+    let create = |builder: ThumbnailerBuilder| {
+        builder
+            .size(ThumbnailSize::Icon)
+            .run(Cursor::new(png.get_ref()), mime::IMAGE_PNG)
+            .unwrap()
+            .pop()
+            .unwrap()
+    };
+    let read_jpeg = |buf: Cursor<Vec<u8>>| {
+        thumbnailer::image::load_from_memory(buf.get_ref())
+            .unwrap()
+            .into_rgb8()
+    };
+
+    let mut buf = Cursor::new(Vec::new());
+    create(ThumbnailerBuilder::new())
+        .write_jpeg(&mut buf)
+        .unwrap();
+    let written = read_jpeg(buf);
+    assert_close(written.get_pixel(0, 0).0, [255, 255, 255]);
+    assert_close(written.get_pixel(32, 32).0, [255, 128, 128]);
+
+    let mut buf = Cursor::new(Vec::new());
+    create(ThumbnailerBuilder::new().matte(Rgb([0, 0, 255])))
+        .write_jpeg(&mut buf)
+        .unwrap();
+    assert_close(read_jpeg(buf).get_pixel(0, 0).0, [0, 0, 255]);
+
+    let mut buf = Cursor::new(Vec::new());
+    create(ThumbnailerBuilder::new())
+        .write_jpeg_with_background(&mut buf, Rgb([0, 255, 0]))
+        .unwrap();
+    let written = read_jpeg(buf);
+    assert_close(written.get_pixel(0, 0).0, [0, 255, 0]);
+    assert_close(written.get_pixel(32, 32).0, [128, 127, 0]);
+}
+
+#[test]
+fn it_writes_progressive_jpeg() {
+    let count_scans = |options: JpegOptions| {
+        let mut buf = Cursor::new(Vec::new());
+        small_thumbnail()
+            .write_jpeg_with_options(&mut buf, options)
+            .unwrap();
+        image::load_from_memory_with_format(buf.get_ref(), ImageFormat::Jpeg).unwrap();
+        buf.get_ref()
+            .windows(2)
+            .filter(|marker| marker == &[0xFF, 0xDA])
+            .count()
+    };
+
+    assert_eq!(count_scans(JpegOptions::default()), 1);
+    let progressive = JpegOptions {
+        progressive: true,
+        ..JpegOptions::default()
+    };
+    assert!(count_scans(progressive) > 1);
+}
+
+#[test]
+fn it_writes_the_same_bytes_with_default_options() {
+    let mut png = Cursor::new(Vec::new());
+    small_thumbnail().write_png(&mut png).unwrap();
+    let mut png_with_options = Cursor::new(Vec::new());
+    small_thumbnail()
+        .write_png_with_options(&mut png_with_options, PngOptions::default())
+        .unwrap();
+    assert_eq!(png.get_ref(), png_with_options.get_ref());
+
+    let mut jpeg = Cursor::new(Vec::new());
+    small_thumbnail().write_jpeg(&mut jpeg).unwrap();
+    let mut jpeg_with_options = Cursor::new(Vec::new());
+    small_thumbnail()
+        .write_jpeg_with_options(&mut jpeg_with_options, JpegOptions::default())
+        .unwrap();
+    assert_eq!(jpeg.get_ref(), jpeg_with_options.get_ref());
+}
+
+#[test]
+fn it_writes_interlaced_png() {
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Custom {
+            width: 13,
+            height: 7,
+        })
+        .preserve_aspect(false)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap()
+        .pop()
+        .unwrap();
+    let expected = thumbnail.as_image().to_rgba8();
+
+    for compression in [CompressionType::Uncompressed, CompressionType::Best] {
+        let mut buf = Cursor::new(Vec::new());
+        thumbnail
+            .clone()
+            .write_png_with_options(
+                &mut buf,
+                PngOptions {
+                    interlaced: true,
+                    compression,
+                    palette: None,
+                },
+            )
+            .unwrap();
+        // the interlace method is the last byte of the header chunk
+        assert_eq!(buf.get_ref()[28], 1);
+        let written = image::load_from_memory_with_format(buf.get_ref(), ImageFormat::Png)
+            .unwrap()
+            .into_rgba8();
+        assert_eq!(written, expected);
+    }
+}
+
+fn assert_close(actual: [u8; 3], expected: [u8; 3]) {
+    for (a, e) in actual.iter().zip(expected) {
+        assert!(
+            (*a as i16 - e as i16).abs() <= 8,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+}
+
+fn small_thumbnail() -> Thumbnail {
+    create_thumbnails(
+        Cursor::new(PNG_BYTES),
+        mime::IMAGE_PNG,
+        [ThumbnailSize::Icon],
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
+
 fn write_thumbnail(
     source_format: SourceFormat,
     target_format: TargetFormat,
@@ -75,7 +382,184 @@ fn write_thumbnail(
     match target_format {
         TargetFormat::Png => thumb.write_png(&mut buf)?,
         TargetFormat::Jpeg => thumb.write_jpeg(&mut buf)?,
+        TargetFormat::Webp => thumb.write_webp(&mut buf)?,
     }
 
     Ok(buf.into_inner())
 }
+
+#[cfg(feature = "avif-encode")]
+fn write_avif(thumbnail: Thumbnail, alpha: bool) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    let options = thumbnailer::AvifOptions {
+        alpha,
+        ..Default::default()
+    };
+    thumbnail
+        .write_avif_with_options(&mut buf, options)
+        .unwrap();
+
+    buf.into_inner()
+}
+
+/// Returns the dimensions of the first image spatial extents property of an avif
+#[cfg(feature = "avif-encode")]
+fn avif_dimensions(avif: &[u8]) -> (u32, u32) {
+    let offset = avif.windows(4).position(|w| w == b"ispe").unwrap() + 8;
+    let read = |offset: usize| {
+        u32::from_be_bytes([
+            avif[offset],
+            avif[offset + 1],
+            avif[offset + 2],
+            avif[offset + 3],
+        ])
+    };
+
+    (read(offset), read(offset + 4))
+}
+
+#[test]
+#[cfg(feature = "avif-encode")]
+fn it_writes_avif() {
+    let thumbnail = small_thumbnail();
+    let size = thumbnail.size();
+    let avif = write_avif(thumbnail.clone(), true);
+
+    assert_eq!(
+        thumbnailer::image::guess_format(&avif).unwrap(),
+        ImageFormat::Avif
+    );
+    assert_eq!(avif_dimensions(&avif), size);
+
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_to(&mut buf, ImageFormat::Avif).unwrap();
+    assert_eq!(avif_dimensions(buf.get_ref()), size);
+
+    let mut buf = Cursor::new(Vec::new());
+    let options = thumbnailer::AvifOptions {
+        speed: 11,
+        ..Default::default()
+    };
+    let result = small_thumbnail().write_avif_with_options(&mut buf, options);
+    assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+}
+
+#[test]
+#[cfg(feature = "avif-encode")]
+fn it_preserves_alpha_when_writing_avif() {
+    let image = RgbaImage::from_fn(32, 16, |x, _| {
+        Rgba([255, 0, 0, if x < 16 { 0 } else { 255 }])
+    });
+    let thumbnail = Thumbnail::from(DynamicImage::ImageRgba8(image));
+    let alpha_marker = b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha";
+
+    let avif = write_avif(thumbnail.clone(), true);
+    assert!(avif.windows(alpha_marker.len()).any(|w| w == alpha_marker));
+
+    let avif = write_avif(thumbnail, false);
+    assert!(!avif.windows(alpha_marker.len()).any(|w| w == alpha_marker));
+}
+
+#[test]
+#[cfg(all(feature = "avif-encode", feature = "heif"))]
+fn it_round_trips_avif() {
+    let image = RgbaImage::from_fn(32, 16, |x, _| {
+        Rgba([255, 0, 0, if x < 16 { 0 } else { 255 }])
+    });
+    let avif = write_avif(Thumbnail::from(DynamicImage::ImageRgba8(image)), true);
+
+    let decoded = create_thumbnails(
+        Cursor::new(avif),
+        Mime::from_str("image/avif").unwrap(),
+        [ThumbnailSize::Small],
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+    .into_inner()
+    .into_rgba8();
+    assert_eq!(decoded.dimensions(), (32, 16));
+    assert!(decoded.get_pixel(4, 8)[3] < 16);
+    let opaque = decoded.get_pixel(24, 8);
+    assert!(opaque[3] > 240);
+    assert_close([opaque[0], opaque[1], opaque[2]], [255, 0, 0]);
+}
+
+/// Returns a large thumbnail of the photographic test image
+fn photo_thumbnail() -> Thumbnail {
+    create_thumbnails(
+        Cursor::new(JPG_BYTES),
+        mime::IMAGE_JPEG,
+        [ThumbnailSize::Large],
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
+
+#[test]
+fn it_encodes_thumbnails_to_bytes() {
+    let thumbnail = photo_thumbnail();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail
+        .clone()
+        .write_to(&mut buf, ImageFormat::WebP)
+        .unwrap();
+
+    assert_eq!(
+        thumbnail.encode(ImageFormat::WebP).unwrap(),
+        buf.into_inner()
+    );
+}
+
+#[cfg(feature = "libwebp")]
+#[test]
+fn it_encodes_thumbnails_within_a_size_limit() {
+    let thumbnail = photo_thumbnail();
+    for (format, limit) in [(ImageFormat::Jpeg, 12_000), (ImageFormat::WebP, 3_000)] {
+        let unbounded = thumbnail.clone().encode(format).unwrap();
+        assert!(unbounded.len() > limit, "{:?}", format);
+
+        let bounded = thumbnail.clone().encode_bounded(format, limit).unwrap();
+        assert!(bounded.len() <= limit, "{:?} {}", format, bounded.len());
+        // lowering the quality is enough so the dimensions are kept
+        let image = image::load_from_memory_with_format(&bounded, format).unwrap();
+        assert_eq!(image.dimensions(), thumbnail.size());
+    }
+}
+
+#[test]
+fn it_scales_thumbnails_down_to_fit_a_size_limit() {
+    const LIMIT: usize = 20_000;
+    let thumbnail = photo_thumbnail();
+    let unbounded = thumbnail.clone().encode(ImageFormat::Png).unwrap();
+    assert!(unbounded.len() > LIMIT);
+
+    let bounded = thumbnail
+        .clone()
+        .encode_bounded(ImageFormat::Png, LIMIT)
+        .unwrap();
+    assert!(bounded.len() <= LIMIT);
+    let image = image::load_from_memory_with_format(&bounded, ImageFormat::Png).unwrap();
+    let (width, height) = thumbnail.size();
+    assert!(image.width() < width && image.height() < height);
+
+    let bounded = thumbnail
+        .clone()
+        .encode_bounded(ImageFormat::Jpeg, 1_000)
+        .unwrap();
+    assert!(bounded.len() <= 1_000);
+}
+
+#[test]
+fn it_fails_if_thumbnails_dont_fit_a_size_limit() {
+    let result = photo_thumbnail().encode_bounded(ImageFormat::Jpeg, 10);
+
+    match result {
+        Err(ThumbError::OutputTooLarge { size, limit }) => {
+            assert!(size > 10);
+            assert_eq!(limit, 10);
+        }
+        result => panic!("unexpected result {:?}", result.map(|bytes| bytes.len())),
+    }
+}
@@ -0,0 +1,68 @@
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{
+    create_thumbnails, create_thumbnails_individual, ThumbnailSize, ThumbnailerBuilder,
+};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+const SIZES: [ThumbnailSize; 3] = [
+    ThumbnailSize::Icon,
+    ThumbnailSize::Custom {
+        width: 0,
+        height: 64,
+    },
+    ThumbnailSize::Medium,
+];
+
+#[test]
+fn it_keeps_the_sizes_that_succeed() {
+    let results =
+        create_thumbnails_individual(Cursor::new(PNG_BYTES), mime::IMAGE_PNG, SIZES).unwrap();
+
+    assert_eq!(results.len(), 3);
+    for ((size, result), expected) in results.iter().zip(SIZES) {
+        assert_eq!(*size, expected);
+        match expected {
+            ThumbnailSize::Custom { .. } => {
+                assert!(matches!(result, Err(ThumbError::InvalidSize(0, 64))))
+            }
+            _ => assert_eq!(result.as_ref().unwrap().requested_size(), expected),
+        }
+    }
+}
+
+#[test]
+fn it_keeps_the_order_when_resizing_in_parallel() {
+    let sizes = [
+        ThumbnailSize::Large,
+        ThumbnailSize::Icon,
+        ThumbnailSize::Medium,
+        ThumbnailSize::Small,
+    ];
+    let results = ThumbnailerBuilder::new()
+        .sizes(sizes)
+        .parallel(true)
+        .run_individual(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    let returned: Vec<ThumbnailSize> = results
+        .iter()
+        .map(|(_, thumbnail)| thumbnail.as_ref().unwrap().requested_size())
+        .collect();
+    assert_eq!(returned, sizes);
+}
+
+#[test]
+fn it_fails_all_sizes_for_decoding_errors() {
+    let result = create_thumbnails_individual(Cursor::new(b"not a png"), mime::IMAGE_PNG, SIZES);
+
+    assert!(matches!(result, Err(ThumbError::Decode { .. })));
+}
+
+#[test]
+fn it_keeps_failing_the_combined_result() {
+    let result = create_thumbnails(Cursor::new(PNG_BYTES), mime::IMAGE_PNG, SIZES);
+
+    assert!(matches!(result, Err(ThumbError::InvalidSize(0, 64))));
+}
@@ -0,0 +1,56 @@
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{create_thumbnails, ThumbnailSize, ThumbnailerBuilder};
+
+/// A png declaring 100000x100000 pixels without containing the pixel data
+const BOMB_BYTES: &[u8] = include_bytes!("assets/bomb.png");
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+#[test]
+fn it_rejects_decompression_bombs() {
+    let result = create_thumbnails(
+        Cursor::new(BOMB_BYTES),
+        mime::IMAGE_PNG,
+        [ThumbnailSize::Small],
+    );
+
+    assert!(matches!(
+        result,
+        Err(ThumbError::InputTooLarge {
+            width: 100000,
+            height: 100000,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn it_applies_configured_limits() {
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .max_input_pixels(Some(100))
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG);
+    assert!(matches!(
+        result,
+        Err(ThumbError::InputTooLarge { limit: 100, .. })
+    ));
+
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .max_input_dimension(Some(10))
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG);
+    assert!(matches!(
+        result,
+        Err(ThumbError::InputTooLarge { limit: 10, .. })
+    ));
+}
+
+#[test]
+fn it_allows_disabling_limits() {
+    ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .max_input_pixels(None)
+        .max_input_dimension(None)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+}
@@ -0,0 +1,111 @@
+//! Measures the memory used for decoding large jpeg images with a counting allocator.
+//! The file only contains a single test so that no other test allocates concurrently.
+#![cfg(feature = "jpeg-prescale")]
+
+use image::codecs::jpeg::JpegEncoder;
+use image::{Rgb, RgbImage};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::Cursor;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use thumbnailer::{BaseImage, ThumbnailSize, ThumbnailerBuilder};
+
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const WIDTH: u32 = 4800;
+const HEIGHT: u32 = 3200;
+
+/// Returns the jpeg of a large image with a red left and blue right half
+fn large_jpeg() -> Vec<u8> {
+    let image = RgbImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        let shade = (y * 255 / HEIGHT) as u8;
+        if x < WIDTH / 2 {
+            Rgb([255, shade, 0])
+        } else {
+            Rgb([0, shade, 255])
+        }
+    });
+    let mut buf = Vec::new();
+    JpegEncoder::new_with_quality(&mut buf, 90)
+        .encode_image(&image)
+        .unwrap();
+
+    buf
+}
+
+/// Decodes the image for thumbnails of the size and returns it with the peak memory
+/// allocated beyond what was allocated before
+fn decode_with_peak(bytes: &[u8], size: ThumbnailSize) -> (BaseImage, usize) {
+    let baseline = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let base = ThumbnailerBuilder::new()
+        .size(size)
+        .decode(Cursor::new(bytes), mime::IMAGE_JPEG)
+        .unwrap();
+    let peak = PEAK.load(Ordering::SeqCst) - baseline;
+
+    (base, peak)
+}
+
+#[test]
+fn it_decodes_large_jpegs_at_a_reduced_scale() {
+    let bytes = large_jpeg();
+    let full_size = (WIDTH * HEIGHT * 3) as usize;
+
+    let (base, peak) = decode_with_peak(&bytes, ThumbnailSize::Medium);
+    // the image is decoded at 1/8 of its dimensions
+    assert_eq!(base.dimensions(), (600, 400));
+    assert_eq!((base.info().width, base.info().height), (WIDTH, HEIGHT));
+    assert!(
+        peak < full_size / 10,
+        "peak of {} bytes for a full size of {} bytes",
+        peak,
+        full_size
+    );
+
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Medium)
+        .run_base(&base)
+        .unwrap()
+        .pop()
+        .unwrap()
+        .into_inner()
+        .into_rgb8();
+    assert_eq!(thumbnail.dimensions(), (256, 171));
+    let left = thumbnail.get_pixel(20, 85);
+    let right = thumbnail.get_pixel(235, 85);
+    assert!(left[0] > 240 && left[2] < 16, "{:?}", left);
+    assert!(right[0] < 16 && right[2] > 240, "{:?}", right);
+
+    // sizes close to the size of the image need the full resolution
+    let (base, peak) = decode_with_peak(
+        &bytes,
+        ThumbnailSize::Custom {
+            width: 4000,
+            height: 4000,
+        },
+    );
+    assert_eq!(base.dimensions(), (WIDTH, HEIGHT));
+    assert!(peak > full_size);
+}
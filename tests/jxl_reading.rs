@@ -0,0 +1,99 @@
+#![cfg(feature = "jxl")]
+
+use mime::Mime;
+use std::io::Cursor;
+use std::str::FromStr;
+use thumbnailer::image::RgbaImage;
+use thumbnailer::{create_thumbnails, create_thumbnails_guess, BaseImage, ThumbnailSize};
+
+const LOSSLESS_BYTES: &[u8] = include_bytes!("assets/lossless.jxl");
+const LOSSY_BYTES: &[u8] = include_bytes!("assets/lossy.jxl");
+const RGB16_BYTES: &[u8] = include_bytes!("assets/rgb16.jxl");
+const HDR_BYTES: &[u8] = include_bytes!("assets/hdr.jxl");
+const ANIMATED_BYTES: &[u8] = include_bytes!("assets/animated.jxl");
+const CONTAINER_BYTES: &[u8] = include_bytes!("assets/container.jxl");
+
+fn jxl_mime() -> Mime {
+    Mime::from_str("image/jxl").unwrap()
+}
+
+/// Returns the dimensions of the decoded image and a thumbnail of the same size
+fn decode(bytes: &[u8]) -> ((u32, u32), RgbaImage) {
+    let base = BaseImage::decode(Cursor::new(bytes), jxl_mime()).unwrap();
+    let (width, height) = base.dimensions();
+    let thumbnails = base
+        .thumbnails([ThumbnailSize::Custom { width, height }])
+        .unwrap();
+
+    ((width, height), thumbnails[0].as_image().to_rgba8())
+}
+
+fn assert_close(pixel: &[u8], expected: &[u8], tolerance: i32) {
+    for (value, expected) in pixel.iter().zip(expected) {
+        assert!(
+            (*value as i32 - *expected as i32).abs() <= tolerance,
+            "{:?} isn't close to {:?}",
+            pixel,
+            expected
+        );
+    }
+}
+
+#[test]
+fn it_reads_lossless_images_with_alpha() {
+    let (dimensions, image) = decode(LOSSLESS_BYTES);
+
+    assert_eq!(dimensions, (64, 48));
+    assert_eq!(image.get_pixel(10, 10).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(10, 40).0, [0, 255, 0, 128]);
+}
+
+#[test]
+fn it_reads_lossy_images() {
+    let thumbnails =
+        create_thumbnails(Cursor::new(LOSSY_BYTES), jxl_mime(), [ThumbnailSize::Icon]).unwrap();
+    let image = thumbnails[0].as_image().to_rgb8();
+    assert_eq!(image.dimensions(), (64, 43));
+
+    assert_close(&image.get_pixel(10, 20).0, &[230, 120, 30], 12);
+    assert_close(&image.get_pixel(54, 20).0, &[30, 60, 200], 12);
+}
+
+#[test]
+fn it_rounds_16_bit_images_to_8_bits() {
+    let (dimensions, image) = decode(RGB16_BYTES);
+
+    assert_eq!(dimensions, (40, 30));
+    assert_close(&image.get_pixel(20, 15).0, &[191, 64, 128, 255], 1);
+}
+
+#[test]
+fn it_tone_maps_hdr_images() {
+    // bands of 100, 1000 and 4000 nits, where the brighter two would clip to white without
+    // tone mapping
+    let (_, image) = decode(HDR_BYTES);
+    let bands: Vec<u8> = [16, 48, 80]
+        .iter()
+        .map(|x| image.get_pixel(*x, 16)[0])
+        .collect();
+
+    assert!(bands[0] < bands[1] && bands[1] < bands[2], "{:?}", bands);
+    assert!(bands[2] < 250, "{:?}", bands);
+}
+
+#[test]
+fn it_uses_the_first_frame_of_animations() {
+    let (dimensions, image) = decode(ANIMATED_BYTES);
+
+    assert_eq!(dimensions, (32, 32));
+    assert_eq!(image.get_pixel(16, 16).0, [255, 0, 0, 255]);
+}
+
+#[test]
+fn it_detects_codestreams_and_containers() {
+    for bytes in [LOSSLESS_BYTES, CONTAINER_BYTES] {
+        let thumbnails =
+            create_thumbnails_guess(Cursor::new(bytes), [ThumbnailSize::Icon]).unwrap();
+        assert_eq!(thumbnails[0].size(), (64, 48));
+    }
+}
@@ -0,0 +1,126 @@
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage, Rgba, RgbaImage};
+use std::io::Cursor;
+use thumbnailer::{Thumbnail, ThumbnailSize, ThumbnailerBuilder};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+const BLUE: Rgb<u8> = Rgb([0, 0, 255]);
+const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+fn solid(width: u32, height: u32) -> Thumbnail {
+    Thumbnail::from(DynamicImage::ImageRgb8(RgbImage::from_pixel(
+        width, height, BLUE,
+    )))
+}
+
+fn alpha(thumbnail: &Thumbnail, x: u32, y: u32) -> u8 {
+    thumbnail.as_image().get_pixel(x, y)[3]
+}
+
+#[test]
+fn it_masks_thumbnails_with_a_circle() {
+    let thumbnail = solid(64, 64).circle_mask();
+
+    assert_eq!(alpha(&thumbnail, 0, 0), 0);
+    assert_eq!(alpha(&thumbnail, 63, 63), 0);
+    assert_eq!(alpha(&thumbnail, 32, 32), 255);
+    assert_eq!(alpha(&thumbnail, 32, 1), 255);
+    // the edge is anti-aliased instead of a hard threshold
+    let image = thumbnail.as_image().to_rgba8();
+    assert!(image.pixels().any(|pixel| pixel[3] > 0 && pixel[3] < 255));
+    // the masked area is transparent but the color is kept
+    assert_eq!(image.get_pixel(32, 32).0, [0, 0, 255, 255]);
+}
+
+#[test]
+fn it_rounds_corners() {
+    let thumbnail = solid(64, 48).round_corners(8);
+
+    assert_eq!(alpha(&thumbnail, 0, 0), 0);
+    assert_eq!(alpha(&thumbnail, 63, 47), 0);
+    assert_eq!(alpha(&thumbnail, 8, 0), 255);
+    assert_eq!(alpha(&thumbnail, 0, 8), 255);
+    assert_eq!(alpha(&thumbnail, 32, 24), 255);
+    let partial = alpha(&thumbnail, 2, 2);
+    assert!(partial > 0 && partial < 255, "{}", partial);
+}
+
+#[test]
+fn it_clamps_large_radii() {
+    let rounded = solid(64, 64).round_corners(1000);
+    let circle = solid(64, 64).circle_mask();
+    assert_eq!(rounded.as_image(), circle.as_image());
+
+    // wide thumbnails become a stadium
+    let stadium = solid(96, 32).round_corners(1000);
+    assert_eq!(alpha(&stadium, 0, 0), 0);
+    assert_eq!(alpha(&stadium, 48, 0), 255);
+    assert_eq!(alpha(&stadium, 16, 16), 255);
+    assert_eq!(alpha(&stadium, 1, 16), 255);
+}
+
+#[test]
+fn it_draws_borders_along_the_mask() {
+    let thumbnail = solid(64, 64).round_corners(16).border(2, RED);
+    let image = thumbnail.as_image().to_rgba8();
+
+    assert_eq!(image.get_pixel(32, 0).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(32, 1).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(32, 2).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(0, 0)[3], 0);
+    // the border follows the curve of the corner
+    let corner = image.get_pixel(5, 5);
+    assert!(corner[0] > 128 && corner[3] > 128, "{:?}", corner);
+    assert_eq!(image.get_pixel(8, 8).0, [0, 0, 255, 255]);
+}
+
+#[test]
+fn it_draws_rectangular_borders_without_a_mask() {
+    let thumbnail = solid(32, 32).border(1, Rgba([255, 0, 0, 128]));
+    let image = thumbnail.as_image().to_rgba8();
+
+    // translucent borders are blended onto the image
+    let edge = image.get_pixel(0, 0);
+    assert_eq!(edge[3], 255);
+    assert!((edge[0] as i16 - 128).abs() <= 1 && (edge[2] as i16 - 127).abs() <= 1);
+    assert_eq!(image.get_pixel(1, 1).0, [0, 0, 255, 255]);
+}
+
+#[test]
+fn it_fills_masked_areas_with_the_matte_in_jpegs() {
+    let mut buf = Cursor::new(Vec::new());
+    solid(64, 64).circle_mask().write_jpeg(&mut buf).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap().to_rgb8();
+
+    let corner = image.get_pixel(0, 0);
+    assert!(corner.0.iter().all(|c| *c > 240), "{:?}", corner);
+    let center = image.get_pixel(32, 32);
+    assert!(center[2] > 240 && center[0] < 16, "{:?}", center);
+}
+
+#[test]
+fn it_masks_all_sizes_with_the_builder() {
+    let thumbnails = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Icon, ThumbnailSize::Small])
+        .resize_mode(thumbnailer::ResizeMode::Cover)
+        .circle_mask()
+        .border(2, RED)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    for thumbnail in thumbnails {
+        let image: RgbaImage = thumbnail.as_image().to_rgba8();
+        let (width, height) = image.dimensions();
+        assert_eq!(image.get_pixel(0, 0)[3], 0);
+        assert_eq!(image.get_pixel(width - 1, height - 1)[3], 0);
+        assert_eq!(image.get_pixel(width / 2, 1).0, [255, 0, 0, 255]);
+        // thumbnails created by the builder keep the shape for later borders
+        let outlined = thumbnail.border(1, Rgba([0, 255, 0, 255]));
+        let edge = outlined.as_image().get_pixel(width / 2, 0);
+        assert!(edge.0[..3] == [0, 255, 0] && edge[3] > 250, "{:?}", edge);
+        // the inner edge of the new border barely touches the pixels below it
+        let inside = outlined.as_image().get_pixel(width / 2, 1);
+        assert!(inside[0] > 250 && inside[1] < 5, "{:?}", inside);
+        assert_eq!(outlined.as_image().get_pixel(0, 0)[3], 0);
+    }
+}
@@ -0,0 +1,187 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::{BufReader, Cursor};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use thumbnailer::image::{DynamicImage, ImageFormat, Rgb, RgbImage, Rgba, RgbaImage};
+use thumbnailer::{
+    create_thumbnails, create_thumbnails_iter, create_thumbnails_shared, BaseImage, ResizeMode,
+    ThumbnailSize, ThumbnailerBuilder,
+};
+
+/// Counts the allocated bytes and the peak since the last reset
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// The measurements would include the allocations of tests running at the same time
+static SERIAL: Mutex<()> = Mutex::new(());
+
+/// Returns the result of the function and the most bytes it had allocated at once
+fn peak_allocation<T, F: FnOnce() -> T>(f: F) -> (T, usize) {
+    let start = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(start, Ordering::SeqCst);
+    let result = f();
+
+    (result, PEAK.load(Ordering::SeqCst).saturating_sub(start))
+}
+
+/// Returns a jpeg of noise that compresses badly, so that the input is large
+fn noisy_jpeg(width: u32, height: u32) -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    let image = RgbImage::from_fn(width, height, |_, _| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        let [r, g, b, _] = state.to_le_bytes();
+        Rgb([r, g, b])
+    });
+    let mut buf = Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(image)
+        .write_to(&mut buf, ImageFormat::Jpeg)
+        .unwrap();
+
+    buf.into_inner()
+}
+
+#[test]
+fn it_resizes_all_sizes_without_copying_the_source() {
+    let _serial = SERIAL.lock().unwrap();
+    let image = RgbaImage::from_pixel(3000, 2000, Rgba([255, 0, 0, 255]));
+    let source_size = image.as_raw().len();
+    let mut png = Cursor::new(Vec::new());
+    DynamicImage::ImageRgba8(image)
+        .write_to(&mut png, ImageFormat::Png)
+        .unwrap();
+    let base = BaseImage::decode(Cursor::new(png.into_inner()), mime::IMAGE_PNG).unwrap();
+    let builder = ThumbnailerBuilder::new()
+        .sizes([
+            ThumbnailSize::Icon,
+            ThumbnailSize::Small,
+            ThumbnailSize::Medium,
+            ThumbnailSize::Large,
+        ])
+        .resize_mode(ResizeMode::Cover)
+        .parallel(false);
+
+    let (thumbnails, peak) = peak_allocation(|| builder.run_base(&base).unwrap());
+
+    assert_eq!(thumbnails.len(), 4);
+    // resizing allocates an intermediate with the width of the source, and cropping a
+    // copy of the source for each size would allocate most of it on top of that
+    assert!(
+        peak < source_size,
+        "{} bytes allocated for a source of {} bytes",
+        peak,
+        source_size
+    );
+}
+
+#[test]
+fn it_decodes_shared_bytes_without_copying_them() {
+    let _serial = SERIAL.lock().unwrap();
+    let bytes = noisy_jpeg(1500, 1500);
+    let sizes = [ThumbnailSize::Icon, ThumbnailSize::Small];
+
+    let (buffered, buffered_peak) = peak_allocation(|| {
+        create_thumbnails(BufReader::new(Cursor::new(&bytes)), mime::IMAGE_JPEG, sizes).unwrap()
+    });
+    let (shared, shared_peak) =
+        peak_allocation(|| create_thumbnails_shared(&bytes, mime::IMAGE_JPEG, sizes).unwrap());
+
+    assert_eq!(buffered.len(), shared.len());
+    // reading through a buffer copies the whole input before decoding it
+    assert!(
+        shared_peak + bytes.len() / 2 < buffered_peak,
+        "{} bytes allocated with shared and {} with buffered input of {} bytes",
+        shared_peak,
+        buffered_peak,
+        bytes.len()
+    );
+}
+
+#[test]
+fn it_keeps_a_single_thumbnail_in_memory_when_iterating() {
+    let _serial = SERIAL.lock().unwrap();
+    let image = RgbaImage::from_pixel(2000, 2000, Rgba([0, 0, 255, 255]));
+    let mut png = Cursor::new(Vec::new());
+    DynamicImage::ImageRgba8(image)
+        .write_to(&mut png, ImageFormat::Png)
+        .unwrap();
+    let png = png.into_inner();
+    // resizing the largest size last keeps the smaller thumbnails in memory while the
+    // intermediate of the largest one is allocated
+    let sizes = [1200, 1400, 1600, 1800].map(|edge| ThumbnailSize::Custom {
+        width: edge,
+        height: edge,
+    });
+    // the bytes of all but the largest thumbnail
+    let smaller_sizes: usize = [1600usize, 1400, 1200]
+        .iter()
+        .map(|edge| edge * edge * 4)
+        .sum();
+
+    let (count, eager_peak) = peak_allocation(|| {
+        create_thumbnails(Cursor::new(&png), mime::IMAGE_PNG, sizes)
+            .unwrap()
+            .len()
+    });
+    let (encoded, lazy_peak) = peak_allocation(|| {
+        create_thumbnails_iter(Cursor::new(&png), mime::IMAGE_PNG, sizes)
+            .unwrap()
+            .map(|result| result.unwrap().1.size())
+            .collect::<Vec<_>>()
+    });
+
+    assert_eq!(encoded.len(), count);
+    assert!(
+        lazy_peak + smaller_sizes / 2 < eager_peak,
+        "{} bytes allocated when iterating and {} at once",
+        lazy_peak,
+        eager_peak
+    );
+}
+
+#[test]
+fn it_frees_the_source_when_downscaling_progressively() {
+    let _serial = SERIAL.lock().unwrap();
+    let image = RgbaImage::from_pixel(3000, 2000, Rgba([0, 255, 0, 255]));
+    let source_size = image.as_raw().len();
+    let mut png = Cursor::new(Vec::new());
+    DynamicImage::ImageRgba8(image)
+        .write_to(&mut png, ImageFormat::Png)
+        .unwrap();
+    let png = png.into_inner();
+    let builder = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Small, ThumbnailSize::Icon])
+        .progressive_downscale(true);
+
+    let start = ALLOCATED.load(Ordering::SeqCst);
+    let mut thumbnails = builder
+        .run_iter(Cursor::new(&png), mime::IMAGE_PNG)
+        .unwrap();
+    let decoded = ALLOCATED.load(Ordering::SeqCst) - start;
+    let first = thumbnails.next().unwrap().unwrap();
+    let downscaled = ALLOCATED.load(Ordering::SeqCst) - start;
+
+    assert!(decoded >= source_size, "{} bytes", decoded);
+    assert!(downscaled < source_size / 4, "{} bytes", downscaled);
+    assert_eq!(first.0, ThumbnailSize::Small);
+    assert_eq!(thumbnails.count(), 1);
+}
@@ -0,0 +1,45 @@
+use std::io::{Cursor, Seek, SeekFrom};
+use thumbnailer::error::ThumbError;
+use thumbnailer::{create_thumbnails_guess, ThumbnailSize, ThumbnailerBuilder};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+
+#[test]
+fn it_fails_for_mislabeled_images_without_sniffing() {
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_JPEG);
+
+    assert!(matches!(result, Err(ThumbError::Decode { .. })));
+}
+
+#[test]
+fn it_sniffs_mislabeled_images() {
+    let thumbnails = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .allow_sniffing(true)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_JPEG)
+        .unwrap();
+
+    assert_eq!(thumbnails.len(), 1);
+}
+
+#[test]
+fn it_guesses_octet_streams() {
+    create_thumbnails_guess(Cursor::new(PNG_BYTES), [ThumbnailSize::Small]).unwrap();
+    create_thumbnails_guess(Cursor::new(JPG_BYTES), [ThumbnailSize::Small]).unwrap();
+
+    let result = create_thumbnails_guess(Cursor::new(b"not an image"), [ThumbnailSize::Small]);
+    assert!(matches!(result, Err(ThumbError::UnsupportedMime(_))));
+}
+
+#[test]
+fn it_sniffs_from_the_current_position() {
+    let mut bytes = b"garbage".to_vec();
+    bytes.extend_from_slice(PNG_BYTES);
+    let mut reader = Cursor::new(bytes);
+    reader.seek(SeekFrom::Start(7)).unwrap();
+
+    create_thumbnails_guess(reader, [ThumbnailSize::Small]).unwrap();
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "office")]
+
+mod common;
+
+use common::{center_pixel, create_thumbnail};
+use thumbnailer::error::ThumbError;
+use thumbnailer::image::Rgb;
+
+const DOCX_MIME: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+const ODT_MIME: &str = "application/vnd.oasis.opendocument.text";
+
+const DOCX_BYTES: &[u8] = include_bytes!("assets/preview.docx");
+const DOCX_NO_PREVIEW_BYTES: &[u8] = include_bytes!("assets/no_preview.docx");
+const DOCX_WMF_PREVIEW_BYTES: &[u8] = include_bytes!("assets/wmf_preview.docx");
+const ODT_BYTES: &[u8] = include_bytes!("assets/preview.odt");
+const ODT_NO_PREVIEW_BYTES: &[u8] = include_bytes!("assets/no_preview.odt");
+
+#[test]
+fn it_reads_embedded_previews_of_docx() {
+    let thumbnail = create_thumbnail(DOCX_BYTES, DOCX_MIME).unwrap();
+
+    assert_eq!(thumbnail.size(), (128, 96));
+    let pixel = center_pixel(&thumbnail);
+    assert!(
+        pixel[2] > 240 && pixel[0] < 16 && pixel[1] < 16,
+        "{:?}",
+        pixel
+    );
+}
+
+#[test]
+fn it_reads_embedded_previews_of_odt() {
+    let thumbnail = create_thumbnail(ODT_BYTES, ODT_MIME).unwrap();
+
+    assert_eq!(thumbnail.size(), (96, 128));
+    assert_eq!(center_pixel(&thumbnail), Rgb([0, 255, 0]));
+}
+
+#[test]
+fn it_returns_no_embedded_image_without_previews() {
+    for (bytes, mime) in [
+        (DOCX_NO_PREVIEW_BYTES, DOCX_MIME),
+        (DOCX_WMF_PREVIEW_BYTES, DOCX_MIME),
+        (ODT_NO_PREVIEW_BYTES, ODT_MIME),
+    ] {
+        let result = create_thumbnail(bytes, mime);
+        assert!(
+            matches!(result, Err(ThumbError::NoEmbeddedImage)),
+            "{:?}",
+            result.err()
+        );
+    }
+}
+
+#[test]
+fn it_fails_to_decode_corrupt_archives() {
+    let truncated = &DOCX_BYTES[..DOCX_BYTES.len() / 2];
+
+    for bytes in [truncated, b"not a zip archive".as_slice()] {
+        let result = create_thumbnail(bytes, DOCX_MIME);
+        assert!(
+            matches!(result, Err(ThumbError::Decode { .. })),
+            "{:?}",
+            result.err()
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::image::{ColorType, DynamicImage, Rgb, RgbImage, Rgba, RgbaImage};
+use thumbnailer::{OverlayPosition, Thumbnail, ThumbnailSize, ThumbnailerBuilder};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+fn white_thumbnail(width: u32, height: u32) -> Thumbnail {
+    Thumbnail::from(DynamicImage::ImageRgb8(RgbImage::from_pixel(
+        width,
+        height,
+        Rgb([255, 255, 255]),
+    )))
+}
+
+fn black_overlay(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255])))
+}
+
+fn pixel(thumbnail: &Thumbnail, x: u32, y: u32) -> Rgb<u8> {
+    *thumbnail.as_image().to_rgb8().get_pixel(x, y)
+}
+
+#[test]
+fn it_places_overlays_in_the_corners_with_a_margin() {
+    let mut thumbnail = white_thumbnail(20, 10);
+    thumbnail.overlay_with_margin(&black_overlay(4, 2), OverlayPosition::BottomRight, 1.0, 2);
+
+    assert_eq!(thumbnail.as_image().color(), ColorType::Rgb8);
+    assert_eq!(pixel(&thumbnail, 14, 6), Rgb([0, 0, 0]));
+    assert_eq!(pixel(&thumbnail, 17, 7), Rgb([0, 0, 0]));
+    assert_eq!(pixel(&thumbnail, 13, 6), Rgb([255, 255, 255]));
+    assert_eq!(pixel(&thumbnail, 18, 8), Rgb([255, 255, 255]));
+
+    let mut thumbnail = white_thumbnail(20, 10);
+    thumbnail.overlay(&black_overlay(4, 2), OverlayPosition::TopLeft, 1.0);
+    assert_eq!(pixel(&thumbnail, 0, 0), Rgb([0, 0, 0]));
+    assert_eq!(pixel(&thumbnail, 4, 0), Rgb([255, 255, 255]));
+}
+
+#[test]
+fn it_blends_with_the_opacity_and_alpha_channel() {
+    let mut thumbnail = white_thumbnail(10, 10);
+    thumbnail.overlay(&black_overlay(10, 10), OverlayPosition::Center, 0.5);
+    assert_eq!(pixel(&thumbnail, 5, 5), Rgb([128, 128, 128]));
+
+    let translucent = DynamicImage::ImageRgba8(RgbaImage::from_pixel(10, 10, Rgba([0, 0, 0, 128])));
+    let mut thumbnail = white_thumbnail(10, 10);
+    thumbnail.overlay(&translucent, OverlayPosition::Center, 0.5);
+    assert_eq!(pixel(&thumbnail, 5, 5), Rgb([191, 191, 191]));
+}
+
+#[test]
+fn it_scales_down_large_overlays() {
+    let mut thumbnail = white_thumbnail(10, 10);
+    thumbnail.overlay_with_margin(&black_overlay(100, 50), OverlayPosition::TopLeft, 1.0, 1);
+
+    assert_eq!(thumbnail.size(), (10, 10));
+    assert_eq!(pixel(&thumbnail, 1, 1), Rgb([0, 0, 0]));
+    assert_eq!(pixel(&thumbnail, 8, 4), Rgb([0, 0, 0]));
+    assert_eq!(pixel(&thumbnail, 0, 0), Rgb([255, 255, 255]));
+    assert_eq!(pixel(&thumbnail, 5, 6), Rgb([255, 255, 255]));
+}
+
+#[test]
+fn it_cuts_off_overlays_at_absolute_positions() {
+    let mut thumbnail = white_thumbnail(10, 10);
+    thumbnail.overlay(&black_overlay(4, 4), OverlayPosition::Absolute(8, 8), 1.0);
+    assert_eq!(pixel(&thumbnail, 9, 9), Rgb([0, 0, 0]));
+    assert_eq!(pixel(&thumbnail, 7, 7), Rgb([255, 255, 255]));
+
+    thumbnail.overlay(
+        &black_overlay(4, 4),
+        OverlayPosition::Absolute(u32::MAX, 0),
+        1.0,
+    );
+    assert_eq!(thumbnail.size(), (10, 10));
+}
+
+#[test]
+fn it_applies_the_overlay_to_every_size() {
+    let thumbnails = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Small, ThumbnailSize::Medium])
+        .overlay(black_overlay(16, 16), OverlayPosition::BottomRight, 1.0)
+        .overlay_margin(4)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    for thumbnail in &thumbnails {
+        let (width, height) = thumbnail.size();
+        let image = thumbnail.as_image().to_rgba8();
+        assert_eq!(
+            image.get_pixel(width - 5, height - 5),
+            &Rgba([0, 0, 0, 255])
+        );
+        assert_eq!(
+            image.get_pixel(width - 20, height - 20),
+            &Rgba([0, 0, 0, 255])
+        );
+    }
+
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .overlay(black_overlay(16, 16), OverlayPosition::Center, 1.5)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG);
+    assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+}
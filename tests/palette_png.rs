@@ -0,0 +1,237 @@
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::image::{DynamicImage, Rgba, RgbaImage};
+use thumbnailer::{
+    create_thumbnails, Dithering, PaletteOptions, PngOptions, Thumbnail, ThumbnailSize,
+    ThumbnailerBuilder,
+};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+const SCAN_G4_TIFF_BYTES: &[u8] = include_bytes!("assets/scan_g4.tiff");
+
+fn icon(bytes: &[u8], mime: mime::Mime) -> Thumbnail {
+    create_thumbnails(Cursor::new(bytes), mime, [ThumbnailSize::Icon])
+        .unwrap()
+        .remove(0)
+}
+
+fn write(thumbnail: &Thumbnail, palette: Option<PaletteOptions>) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail
+        .clone()
+        .write_png_with_options(
+            &mut buf,
+            PngOptions {
+                palette,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    buf.into_inner()
+}
+
+/// Returns the header information of the png
+fn png_info(bytes: &[u8]) -> (png::ColorType, png::BitDepth, usize, Option<Vec<u8>>) {
+    let reader = png::Decoder::new(Cursor::new(bytes)).read_info().unwrap();
+    let info = reader.info();
+
+    (
+        info.color_type,
+        info.bit_depth,
+        info.palette.as_ref().map_or(0, |palette| palette.len() / 3),
+        info.trns.as_ref().map(|trns| trns.to_vec()),
+    )
+}
+
+fn decode(bytes: &[u8]) -> RgbaImage {
+    thumbnailer::image::load_from_memory(bytes)
+        .unwrap()
+        .into_rgba8()
+}
+
+/// Returns the mean absolute difference of the color channels weighted by their alpha,
+/// since the color of transparent pixels isn't kept
+fn mean_difference(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    let premultiplied = |pixel: &Rgba<u8>, c: usize| pixel[c] as i64 * pixel[3] as i64 / 255;
+    let total: u64 = a
+        .pixels()
+        .zip(b.pixels())
+        .flat_map(|(a, b)| {
+            (0..3).map(move |c| (premultiplied(a, c) - premultiplied(b, c)).unsigned_abs())
+        })
+        .sum();
+
+    total as f64 / (a.width() * a.height() * 3) as f64
+}
+
+#[test]
+fn it_writes_smaller_indexed_pngs() {
+    for (bytes, mime) in [(PNG_BYTES, mime::IMAGE_PNG), (JPG_BYTES, mime::IMAGE_JPEG)] {
+        let thumbnail = icon(bytes, mime);
+        let rgba = write(&thumbnail, None);
+        let indexed = write(&thumbnail, Some(PaletteOptions::default()));
+        let fewer_colors = PaletteOptions {
+            max_colors: 64,
+            ..Default::default()
+        };
+        let small = write(&thumbnail, Some(fewer_colors));
+
+        assert!(
+            indexed.len() * 3 < rgba.len() * 2,
+            "{} {}",
+            indexed.len(),
+            rgba.len()
+        );
+        assert!(
+            small.len() * 2 < rgba.len(),
+            "{} {}",
+            small.len(),
+            rgba.len()
+        );
+        let (color_type, bit_depth, colors, _) = png_info(&indexed);
+        assert_eq!(color_type, png::ColorType::Indexed);
+        assert_eq!(bit_depth, png::BitDepth::Eight);
+        assert!(colors <= 256);
+
+        let difference = mean_difference(&decode(&indexed), &decode(&rgba));
+        assert!(difference < 8.0, "{}", difference);
+    }
+}
+
+#[test]
+fn it_limits_the_number_of_colors() {
+    let thumbnail = icon(JPG_BYTES, mime::IMAGE_JPEG);
+
+    for (max_colors, bit_depth) in [(16, png::BitDepth::Four), (4, png::BitDepth::Two)] {
+        for dithering in [Dithering::None, Dithering::FloydSteinberg] {
+            let options = PaletteOptions {
+                max_colors,
+                dithering,
+                ..Default::default()
+            };
+            let indexed = write(&thumbnail, Some(options));
+            let (_, depth, colors, _) = png_info(&indexed);
+
+            assert!(colors <= max_colors as usize, "{}", colors);
+            assert_eq!(depth, bit_depth);
+            assert_eq!(decode(&indexed).dimensions(), thumbnail.size());
+        }
+    }
+}
+
+#[test]
+fn it_keeps_the_exact_colors_of_images_with_few_colors() {
+    let image = RgbaImage::from_fn(30, 20, |x, y| match (x / 10 + y / 10) % 3 {
+        0 => Rgba([255, 0, 0, 255]),
+        1 => Rgba([0, 0, 255, 128]),
+        _ => Rgba([0, 0, 0, 0]),
+    });
+    let thumbnail = Thumbnail::from(DynamicImage::ImageRgba8(image.clone()));
+    let options = PaletteOptions {
+        max_colors: 3,
+        ..Default::default()
+    };
+    let indexed = write(&thumbnail, Some(options));
+
+    let (_, bit_depth, colors, trns) = png_info(&indexed);
+    assert_eq!(bit_depth, png::BitDepth::Two);
+    assert_eq!(colors, 3);
+    let mut trns = trns.unwrap();
+    trns.sort_unstable();
+    assert_eq!(trns, [0, 128]);
+    assert_eq!(decode(&indexed), image);
+}
+
+#[test]
+fn it_writes_the_transparency_of_masked_thumbnails() {
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Icon)
+        .circle_mask()
+        .run(Cursor::new(JPG_BYTES), mime::IMAGE_JPEG)
+        .unwrap()
+        .remove(0);
+    let source = thumbnail.as_image().to_rgba8();
+    let (width, height) = thumbnail.size();
+    let center = (width / 2, height / 2);
+
+    let indexed = decode(&write(&thumbnail, Some(PaletteOptions::default())));
+    assert_eq!(indexed.get_pixel(0, 0)[3], 0);
+    assert_eq!(indexed.get_pixel(center.0, center.1)[3], 255);
+    // the antialiased edge keeps some of its alpha levels
+    let edges = indexed
+        .pixels()
+        .filter(|pixel| pixel[3] > 0 && pixel[3] < 255)
+        .count();
+    assert!(edges > 0);
+
+    let binary = PaletteOptions {
+        binary_alpha: true,
+        alpha_threshold: 127,
+        ..Default::default()
+    };
+    let indexed = decode(&write(&thumbnail, Some(binary)));
+    for (written, source) in indexed.pixels().zip(source.pixels()) {
+        let expected = if source[3] <= 127 { 0 } else { 255 };
+        assert_eq!(written[3], expected);
+    }
+}
+
+#[test]
+fn it_writes_interlaced_indexed_pngs() {
+    let thumbnail = icon(PNG_BYTES, mime::IMAGE_PNG);
+    let palette = Some(PaletteOptions::default());
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail
+        .clone()
+        .write_png_with_options(
+            &mut buf,
+            PngOptions {
+                interlaced: true,
+                palette,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    // the interlace method is the last byte of the header chunk
+    assert_eq!(buf.get_ref()[28], 1);
+    assert_eq!(decode(buf.get_ref()), decode(&write(&thumbnail, palette)));
+}
+
+#[test]
+fn it_writes_the_resolution_of_indexed_pngs() {
+    let thumbnail = icon(SCAN_G4_TIFF_BYTES, "image/tiff".parse().unwrap());
+    assert!(thumbnail.dpi().is_some());
+    let pixel_dims = |bytes: Vec<u8>| {
+        let reader = png::Decoder::new(Cursor::new(bytes)).read_info().unwrap();
+        let dims = reader.info().pixel_dims.unwrap();
+        (dims.xppu, dims.yppu)
+    };
+
+    assert_eq!(
+        pixel_dims(write(&thumbnail, Some(PaletteOptions::default()))),
+        pixel_dims(write(&thumbnail, None))
+    );
+}
+
+#[test]
+fn it_rejects_invalid_palette_sizes() {
+    let thumbnail = icon(PNG_BYTES, mime::IMAGE_PNG);
+
+    for max_colors in [0, 1, 257] {
+        let options = PngOptions {
+            palette: Some(PaletteOptions {
+                max_colors,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let result = thumbnail
+            .clone()
+            .write_png_with_options(&mut Cursor::new(Vec::new()), options);
+
+        assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+    }
+}
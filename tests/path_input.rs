@@ -0,0 +1,77 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use tempfile::TempDir;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{create_thumbnails_from_bytes, create_thumbnails_from_path, ThumbnailSize};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+/// Writes the content to a file with the given name in a temporary directory
+fn temp_file(name: &str, content: &[u8]) -> (TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(name);
+    fs::write(&path, content).unwrap();
+
+    (dir, path)
+}
+
+#[test]
+fn it_creates_thumbnails_from_bytes() {
+    let thumbnails =
+        create_thumbnails_from_bytes(PNG_BYTES, mime::IMAGE_PNG, [ThumbnailSize::Small]).unwrap();
+
+    assert_eq!(thumbnails.len(), 1);
+}
+
+#[test]
+fn it_creates_thumbnails_from_paths() {
+    let thumbnails = create_thumbnails_from_path(
+        "tests/assets/test.png",
+        [ThumbnailSize::Icon, ThumbnailSize::Small],
+    )
+    .unwrap();
+
+    assert_eq!(thumbnails.len(), 2);
+}
+
+#[test]
+fn it_ignores_the_case_of_extensions() {
+    let (_dir, path) = temp_file("TEST.PNG", PNG_BYTES);
+
+    assert!(create_thumbnails_from_path(path, [ThumbnailSize::Small]).is_ok());
+}
+
+#[test]
+fn it_sniffs_files_with_unknown_or_wrong_extensions() {
+    for name in ["image.unknown", "image", "image.jpg"] {
+        let (_dir, path) = temp_file(name, PNG_BYTES);
+
+        assert!(
+            create_thumbnails_from_path(path, [ThumbnailSize::Small]).is_ok(),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn it_rejects_unknown_content_with_unknown_extensions() {
+    let (_dir, path) = temp_file("data.unknown", &[0, 1, 2, 3, 4, 5, 6, 7]);
+    let result = create_thumbnails_from_path(path, [ThumbnailSize::Small]);
+
+    assert!(matches!(result, Err(ThumbError::UnsupportedMime(_))));
+}
+
+#[test]
+fn it_includes_the_path_in_io_errors() {
+    let result = create_thumbnails_from_path("tests/assets/missing.png", [ThumbnailSize::Small]);
+
+    match result {
+        Err(ThumbError::Io(e)) => {
+            assert_eq!(e.kind(), ErrorKind::NotFound);
+            assert!(e.to_string().contains("tests/assets/missing.png"), "{}", e);
+        }
+        other => panic!("unexpected result {:?}", other.map(|t| t.len())),
+    }
+}
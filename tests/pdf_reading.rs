@@ -0,0 +1,50 @@
+#![cfg(feature = "pdf")]
+
+use image::ImageFormat;
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{create_thumbnails, BaseImage, ThumbnailSize};
+
+const MULTIPAGE_PDF_BYTES: &[u8] = include_bytes!("assets/multipage.pdf");
+const ENCRYPTED_PDF_BYTES: &[u8] = include_bytes!("assets/encrypted.pdf");
+
+#[test]
+fn it_renders_the_first_page_of_pdfs() {
+    let thumbnails = create_thumbnails(
+        Cursor::new(MULTIPAGE_PDF_BYTES),
+        mime::APPLICATION_PDF,
+        [ThumbnailSize::Small, ThumbnailSize::Larger],
+    )
+    .unwrap();
+    assert_eq!(thumbnails[0].size(), (128, 64));
+    assert_eq!(thumbnails[1].size(), (1024, 512));
+
+    let mut buf = Cursor::new(Vec::new());
+    thumbnails[0].clone().write_png(&mut buf).unwrap();
+    let image = image::load_from_memory_with_format(buf.get_ref(), ImageFormat::Png)
+        .unwrap()
+        .into_rgb8();
+    let pixel = image.get_pixel(64, 32);
+    assert!(pixel[0] > 200 && pixel[1] < 50 && pixel[2] < 50);
+}
+
+#[test]
+fn it_reports_the_page_count_of_pdfs() {
+    let base = BaseImage::decode(Cursor::new(MULTIPAGE_PDF_BYTES), mime::APPLICATION_PDF).unwrap();
+    let info = base.info();
+
+    assert_eq!(info.page_count, Some(3));
+    assert_eq!(info.format, None);
+    assert_eq!(info.duration, None);
+}
+
+#[test]
+fn it_fails_for_encrypted_pdfs() {
+    let result = create_thumbnails(
+        Cursor::new(ENCRYPTED_PDF_BYTES),
+        mime::APPLICATION_PDF,
+        [ThumbnailSize::Small],
+    );
+
+    assert!(matches!(result, Err(ThumbError::Decode { .. })));
+}
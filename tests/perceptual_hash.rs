@@ -0,0 +1,95 @@
+use std::io::Cursor;
+use thumbnailer::image::ImageFormat;
+use thumbnailer::{create_thumbnails, HashAlg, ImageHash, Thumbnail, ThumbnailSize};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+const OTHER_BYTES: &[u8] = include_bytes!("assets/orientation_1.jpg");
+
+const ALGS: [HashAlg; 3] = [HashAlg::Average, HashAlg::Difference, HashAlg::Perceptual];
+
+fn thumbnails(bytes: &[u8], mime: mime::Mime) -> Vec<Thumbnail> {
+    create_thumbnails(
+        Cursor::new(bytes),
+        mime,
+        [ThumbnailSize::Small, ThumbnailSize::Large],
+    )
+    .unwrap()
+}
+
+/// Returns a copy of the thumbnail that has been written and read as a jpeg
+fn recompressed(thumbnail: &Thumbnail) -> Thumbnail {
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail
+        .clone()
+        .write_jpeg_with_quality(&mut buf, 75)
+        .unwrap();
+    let image =
+        thumbnailer::image::load_from_memory_with_format(buf.get_ref(), ImageFormat::Jpeg).unwrap();
+
+    Thumbnail::from(image)
+}
+
+#[test]
+fn it_hashes_independently_of_the_thumbnail_size() {
+    let thumbnails = thumbnails(PNG_BYTES, mime::IMAGE_PNG);
+
+    for alg in ALGS {
+        assert_eq!(
+            thumbnails[0].perceptual_hash(alg),
+            thumbnails[1].perceptual_hash(alg),
+            "{:?}",
+            alg
+        );
+    }
+}
+
+#[test]
+fn it_keeps_recompressed_copies_close() {
+    let thumbnail = thumbnails(PNG_BYTES, mime::IMAGE_PNG).remove(1);
+    let copy = recompressed(&thumbnail);
+
+    for alg in ALGS {
+        let distance = thumbnail
+            .perceptual_hash(alg)
+            .hamming_distance(&copy.perceptual_hash(alg));
+        assert!(distance <= 2, "{:?} {}", alg, distance);
+    }
+}
+
+#[test]
+fn it_keeps_different_images_far_apart() {
+    let thumbnail = thumbnails(PNG_BYTES, mime::IMAGE_PNG).remove(1);
+    let other = thumbnails(OTHER_BYTES, mime::IMAGE_JPEG).remove(1);
+
+    for alg in ALGS {
+        let distance = thumbnail
+            .perceptual_hash(alg)
+            .hamming_distance(&other.perceptual_hash(alg));
+        assert!(distance >= 16, "{:?} {}", alg, distance);
+    }
+}
+
+#[test]
+fn it_round_trips_hashes_through_base64() {
+    let thumbnail = thumbnails(PNG_BYTES, mime::IMAGE_PNG).remove(0);
+
+    for alg in ALGS {
+        let hash = thumbnail.perceptual_hash(alg);
+        let encoded = hash.to_base64();
+
+        assert_eq!(encoded.len(), 12);
+        assert_eq!(hash.to_string(), encoded);
+        assert_eq!(ImageHash::from_base64(&encoded).unwrap(), hash);
+        assert_eq!(ImageHash::from_base64(&encoded).unwrap().alg(), alg);
+    }
+}
+
+#[test]
+fn it_rejects_invalid_base64_hashes() {
+    // too short, invalid characters and an unknown algorithm
+    for encoded in ["", "AAAA", "AAAAAAAAAAA!", "/AAAAAAAAAAA"] {
+        let error = ImageHash::from_base64(encoded).unwrap_err();
+
+        assert!(error.to_string().contains(&format!("\"{}\"", encoded)));
+    }
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "blurhash")]
+
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::image::{DynamicImage, Rgba, RgbaImage};
+use thumbnailer::{create_thumbnails, Thumbnail, ThumbnailSize};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+fn gradient_thumbnail(size: u32) -> Thumbnail {
+    let image = RgbaImage::from_fn(size, size, |x, y| {
+        Rgba([(x * 255 / size) as u8, (y * 255 / size) as u8, 128, 255])
+    });
+
+    Thumbnail::from(DynamicImage::ImageRgba8(image))
+}
+
+#[test]
+fn it_creates_blurhashes() {
+    // computed with the reference implementation
+    let hash = gradient_thumbnail(32).blurhash(4, 3).unwrap();
+    assert_eq!(hash, "LxG[[y2swxX8l}WDjte;gJfjfQfj");
+
+    let hash = gradient_thumbnail(32).blurhash(1, 1).unwrap();
+    assert_eq!(hash.len(), 6);
+}
+
+#[test]
+fn it_creates_blurhashes_for_large_thumbnails() {
+    let thumbnail = create_thumbnails(
+        Cursor::new(PNG_BYTES),
+        mime::IMAGE_PNG,
+        [ThumbnailSize::Larger],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let hash = thumbnail.blurhash(9, 9).unwrap();
+
+    assert_eq!(hash.len(), 4 + 2 * 9 * 9);
+    assert_eq!(thumbnail.blurhash(9, 9).unwrap(), hash);
+}
+
+#[test]
+fn it_rejects_invalid_blurhash_components() {
+    let thumbnail = gradient_thumbnail(8);
+
+    for (x, y) in [(0, 3), (4, 0), (10, 3), (4, 10)] {
+        assert!(matches!(
+            thumbnail.blurhash(x, y),
+            Err(ThumbError::InvalidOptions(_))
+        ));
+    }
+}
+
+#[test]
+fn it_creates_thumbhashes() {
+    let small = gradient_thumbnail(32).thumbhash();
+    assert!(small.len() >= 5);
+    assert_eq!(gradient_thumbnail(32).thumbhash(), small);
+
+    // the hash is computed from a downscaled copy so it stays small for large images
+    let large = gradient_thumbnail(1024).thumbhash();
+    assert!(large.len() <= 25);
+}
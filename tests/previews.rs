@@ -0,0 +1,235 @@
+use image::{GenericImageView, ImageFormat};
+use std::fs;
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{create_preview, create_preview_with_info, PreviewInfo, PreviewSource};
+
+/// Creates a webp preview of the content and checks that it has the reported dimensions
+fn preview_bytes(bytes: &[u8], mime: &str, max_dimension: u32) -> PreviewInfo {
+    let (preview, info) = create_preview_with_info(
+        Cursor::new(bytes),
+        mime.parse().unwrap(),
+        max_dimension,
+        ImageFormat::WebP,
+    )
+    .unwrap_or_else(|e| panic!("preview of {} failed: {}", mime, e));
+    let image = image::load_from_memory_with_format(&preview, ImageFormat::WebP).unwrap();
+
+    assert_eq!(image.dimensions(), info.dimensions, "{}", mime);
+    assert!(
+        info.dimensions.0.max(info.dimensions.1) <= max_dimension,
+        "{:?}",
+        info
+    );
+    info
+}
+
+/// Creates a preview of the asset like [preview_bytes]
+fn preview(name: &str, mime: &str) -> PreviewInfo {
+    let bytes = fs::read(format!("tests/assets/{}", name)).unwrap();
+
+    preview_bytes(&bytes, mime, 512)
+}
+
+fn assert_source(assets: &[(&str, &str)], source: PreviewSource) {
+    for (name, mime) in assets {
+        let info = preview(name, mime);
+        assert_eq!(info.source, source, "{}", name);
+        assert_eq!(info.mime.essence_str(), *mime, "{}", name);
+        assert!(!info.sniffed, "{}", name);
+    }
+}
+
+#[test]
+fn it_creates_previews_of_images() {
+    assert_source(
+        &[
+            ("test.png", "image/png"),
+            ("test.jpg", "image/jpeg"),
+            ("test.webp", "image/webp"),
+            ("rgba16.png", "image/png"),
+            ("cmyk_plain.jpg", "image/jpeg"),
+            ("orientation_6.jpg", "image/jpeg"),
+            ("animated.gif", "image/gif"),
+            ("animated.webp", "image/webp"),
+        ],
+        PreviewSource::Image,
+    );
+}
+
+#[test]
+fn it_limits_the_longer_edge() {
+    let large = preview("test.png", "image/png");
+    let (width, height) = (large.info.width, large.info.height);
+    assert!(width.max(height) > 256);
+
+    let bytes = fs::read("tests/assets/test.png").unwrap();
+    let small = preview_bytes(&bytes, "image/png", 256);
+    assert_eq!(small.dimensions.0.max(small.dimensions.1), 256);
+}
+
+#[test]
+fn it_does_not_upscale_small_sources() {
+    let bytes = fs::read("tests/assets/test.png").unwrap();
+    let info = preview_bytes(&bytes, "image/png", 4096);
+
+    assert_eq!(info.dimensions, (info.info.width, info.info.height));
+}
+
+#[test]
+fn it_falls_back_to_the_detected_media_type() {
+    let bytes = fs::read("tests/assets/test.jpg").unwrap();
+
+    for declared in ["image/png", "application/octet-stream"] {
+        let info = preview_bytes(&bytes, declared, 512);
+        assert!(info.sniffed, "{}", declared);
+        assert_eq!(info.mime, mime::IMAGE_JPEG);
+        assert_eq!(info.source, PreviewSource::Image);
+    }
+}
+
+#[test]
+fn it_encodes_previews_in_the_requested_format() {
+    let bytes = fs::read("tests/assets/test.png").unwrap();
+
+    for format in [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::WebP] {
+        let preview = create_preview(Cursor::new(&bytes), mime::IMAGE_PNG, 128, format).unwrap();
+        assert_eq!(image::guess_format(&preview).unwrap(), format);
+    }
+}
+
+#[test]
+fn it_fails_for_unreadable_content() {
+    let result = create_preview(
+        Cursor::new(b"neither an image nor anything else".to_vec()),
+        mime::IMAGE_PNG,
+        512,
+        ImageFormat::WebP,
+    );
+
+    assert!(result.unwrap_err().is_corrupt());
+}
+
+#[test]
+fn it_rejects_a_maximum_dimension_of_zero() {
+    let bytes = fs::read("tests/assets/test.png").unwrap();
+    let result = create_preview(Cursor::new(bytes), mime::IMAGE_PNG, 0, ImageFormat::WebP);
+
+    assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+}
+
+#[cfg(feature = "raw")]
+#[test]
+fn it_uses_the_embedded_preview_of_raw_files() {
+    assert_source(
+        &[("preview.nef", "image/x-nikon-nef")],
+        PreviewSource::Embedded,
+    );
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn it_creates_previews_of_svg_images() {
+    assert_source(
+        &[
+            ("sized.svg", "image/svg+xml"),
+            ("viewbox.svg", "image/svg+xml"),
+        ],
+        PreviewSource::Image,
+    );
+}
+
+#[cfg(feature = "heif")]
+#[test]
+fn it_creates_previews_of_avif_images() {
+    assert_source(&[("gradient.avif", "image/avif")], PreviewSource::Image);
+}
+
+#[cfg(feature = "jxl")]
+#[test]
+fn it_creates_previews_of_jpeg_xl_images() {
+    assert_source(
+        &[
+            ("lossless.jxl", "image/jxl"),
+            ("lossy.jxl", "image/jxl"),
+            ("animated.jxl", "image/jxl"),
+        ],
+        PreviewSource::Image,
+    );
+}
+
+#[cfg(feature = "pdf")]
+#[test]
+fn it_renders_the_first_page_of_documents() {
+    assert_source(
+        &[("multipage.pdf", "application/pdf")],
+        PreviewSource::FirstPage,
+    );
+}
+
+#[cfg(feature = "office")]
+#[test]
+fn it_uses_the_embedded_preview_of_office_documents() {
+    assert_source(
+        &[
+            (
+                "preview.docx",
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            ),
+            ("preview.odt", "application/vnd.oasis.opendocument.text"),
+        ],
+        PreviewSource::Embedded,
+    );
+}
+
+#[cfg(feature = "ebook")]
+#[test]
+fn it_uses_the_cover_of_ebooks() {
+    assert_source(
+        &[
+            ("cover.epub", "application/epub+zip"),
+            ("pages.cbz", "application/vnd.comicbook+zip"),
+        ],
+        PreviewSource::Embedded,
+    );
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn it_uses_the_first_image_of_archives() {
+    assert_source(
+        &[
+            ("images.zip", "application/zip"),
+            ("images.tar", "application/x-tar"),
+            ("images.tar.gz", "application/gzip"),
+        ],
+        PreviewSource::Embedded,
+    );
+}
+
+#[cfg(feature = "audio")]
+#[test]
+fn it_uses_the_cover_art_of_audio_files() {
+    assert_source(
+        &[
+            ("cover.mp3", "audio/mpeg"),
+            ("cover.flac", "audio/flac"),
+            ("cover.m4a", "audio/mp4"),
+        ],
+        PreviewSource::Embedded,
+    );
+}
+
+#[cfg(feature = "text")]
+#[test]
+fn it_renders_text_files() {
+    let info = preview_bytes(b"fn main() {}\n", "text/plain", 512);
+
+    assert_eq!(info.source, PreviewSource::Text);
+}
+
+#[cfg(feature = "ffmpeg")]
+#[test]
+fn it_uses_a_frame_of_videos() {
+    assert_source(&[("test.mp4", "video/mp4")], PreviewSource::VideoFrame);
+}
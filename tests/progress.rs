@@ -0,0 +1,123 @@
+use image::ImageFormat;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use thumbnailer::error::ThumbError;
+use thumbnailer::{Progress, Stage, ThumbnailSize, ThumbnailerBuilder};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+const SIZES: [ThumbnailSize; 4] = [
+    ThumbnailSize::Icon,
+    ThumbnailSize::Small,
+    ThumbnailSize::Medium,
+    ThumbnailSize::Large,
+];
+
+#[test]
+fn it_reports_the_progress_of_all_stages() {
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let collected = reports.clone();
+    let thumbnails = ThumbnailerBuilder::new()
+        .sizes(SIZES)
+        .on_progress(move |progress: Progress| collected.lock().unwrap().push(progress))
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert_eq!(thumbnails.len(), 4);
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 5);
+    assert_eq!(reports[0].stage, Stage::Decoding);
+
+    let mut indices: Vec<usize> = reports[1..]
+        .iter()
+        .map(|progress| match progress.stage {
+            Stage::Resizing { size, index, total } => {
+                assert_eq!(total, 4);
+                assert_eq!(size, SIZES[index]);
+                index
+            }
+            stage => panic!("unexpected stage {:?}", stage),
+        })
+        .collect();
+    indices.sort_unstable();
+    assert_eq!(indices, [0, 1, 2, 3]);
+
+    let fractions: Vec<f32> = reports[1..].iter().filter_map(|p| p.fraction).collect();
+    assert_eq!(fractions, [0.25, 0.5, 0.75, 1.0]);
+}
+
+#[test]
+fn it_cancels_between_sizes() {
+    let token = Arc::new(AtomicBool::new(false));
+    let resized = Arc::new(Mutex::new(0));
+    let (cancel, count) = (token.clone(), resized.clone());
+    let result = ThumbnailerBuilder::new()
+        .sizes(SIZES)
+        .parallel(false)
+        .cancel_token(token)
+        .on_progress(move |progress| {
+            if let Stage::Resizing { .. } = progress.stage {
+                *count.lock().unwrap() += 1;
+                cancel.store(true, Ordering::Relaxed);
+            }
+        })
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG);
+
+    assert!(matches!(result, Err(ThumbError::Cancelled)));
+    assert_eq!(*resized.lock().unwrap(), 1);
+}
+
+#[test]
+fn it_cancels_before_decoding() {
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .cancel_token(Arc::new(AtomicBool::new(true)))
+        .on_progress(|progress| panic!("unexpected progress {:?}", progress))
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG);
+
+    assert!(matches!(result, Err(ThumbError::Cancelled)));
+}
+
+#[test]
+fn it_reports_encoding_before_each_thumbnail() {
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let collected = reports.clone();
+    let encoded = ThumbnailerBuilder::new()
+        .sizes(SIZES)
+        .on_progress(move |progress: Progress| collected.lock().unwrap().push(progress))
+        .run_encoded(Cursor::new(PNG_BYTES), mime::IMAGE_PNG, ImageFormat::Png)
+        .unwrap();
+
+    assert_eq!(encoded.len(), 4);
+    for bytes in &encoded {
+        assert_eq!(image::guess_format(bytes).unwrap(), ImageFormat::Png);
+    }
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 9);
+    let encoding: Vec<Option<f32>> = reports[5..]
+        .iter()
+        .map(|progress| {
+            assert_eq!(progress.stage, Stage::Encoding);
+            progress.fraction
+        })
+        .collect();
+    assert_eq!(encoding, [Some(0.0), Some(0.25), Some(0.5), Some(0.75)]);
+}
+
+#[test]
+fn it_cancels_between_encodings() {
+    let token = Arc::new(AtomicBool::new(false));
+    let cancel = token.clone();
+    let result = ThumbnailerBuilder::new()
+        .sizes(SIZES)
+        .cancel_token(token)
+        .on_progress(move |progress| {
+            if progress.stage == Stage::Encoding {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        })
+        .run_encoded(Cursor::new(PNG_BYTES), mime::IMAGE_PNG, ImageFormat::Png);
+
+    assert!(matches!(result, Err(ThumbError::Cancelled)));
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "raw")]
+
+use mime::Mime;
+use std::io::Cursor;
+use std::str::FromStr;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{create_thumbnails, BaseImage, ThumbnailSize};
+
+const NEF_BYTES: &[u8] = include_bytes!("assets/preview.nef");
+const DNG_NO_PREVIEW_BYTES: &[u8] = include_bytes!("assets/no_preview.dng");
+
+#[test]
+fn it_uses_the_largest_embedded_preview() {
+    let base = BaseImage::decode(
+        Cursor::new(NEF_BYTES),
+        Mime::from_str("image/x-nikon-nef").unwrap(),
+    )
+    .unwrap();
+    // the 64x32 preview is rotated by the orientation of the raw image
+    assert_eq!(base.dimensions(), (32, 64));
+
+    let thumbnails = base.thumbnails([ThumbnailSize::Small]).unwrap();
+    let image = thumbnails[0].as_image().to_rgb8();
+    let pixel = image.get_pixel(image.width() / 2, image.height() / 2);
+    assert!(pixel[0] < 30 && pixel[1] < 30 && pixel[2] > 220);
+}
+
+#[test]
+fn it_fails_for_raw_files_without_preview() {
+    let result = create_thumbnails(
+        Cursor::new(DNG_NO_PREVIEW_BYTES),
+        Mime::from_str("image/x-adobe-dng").unwrap(),
+        [ThumbnailSize::Small],
+    );
+
+    assert!(matches!(result, Err(ThumbError::NoEmbeddedImage)));
+}
+
+#[test]
+fn it_fails_for_raw_files_without_tiff_structure() {
+    let result = create_thumbnails(
+        Cursor::new(b"not a raw file".to_vec()),
+        Mime::from_str("image/x-canon-cr2").unwrap(),
+        [ThumbnailSize::Small],
+    );
+
+    assert!(matches!(result, Err(ThumbError::Decode { .. })));
+}
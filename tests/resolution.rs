@@ -0,0 +1,228 @@
+use image::codecs::jpeg::{JpegEncoder, PixelDensity};
+use image::{DynamicImage, ImageFormat, RgbImage};
+use std::io::Cursor;
+use thumbnailer::{
+    create_thumbnails_from_bytes, create_thumbnails_with_meta, ResizeMode, ThumbnailSize,
+    ThumbnailerBuilder,
+};
+
+/// Returns a 400x200 source image
+fn source() -> RgbImage {
+    RgbImage::from_fn(400, 200, |x, y| image::Rgb([x as u8, y as u8, 128]))
+}
+
+/// Encodes the source as png with the given resolution
+fn png_with_dpi(dpi: Option<f32>) -> Vec<u8> {
+    let image = source();
+    let mut buf = Vec::new();
+    let mut encoder = png::Encoder::new(&mut buf, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_pixel_dims(dpi.map(|dpi| png::PixelDimensions {
+        xppu: (dpi / 0.0254).round() as u32,
+        yppu: (dpi / 0.0254).round() as u32,
+        unit: png::Unit::Meter,
+    }));
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(image.as_raw()).unwrap();
+    writer.finish().unwrap();
+
+    buf
+}
+
+/// Encodes the source as jpeg with the given resolution
+fn jpeg_with_dpi(dpi: u16) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let mut encoder = JpegEncoder::new_with_quality(&mut buf, 90);
+    encoder.set_pixel_density(PixelDensity::dpi(dpi));
+    DynamicImage::ImageRgb8(source())
+        .write_with_encoder(encoder)
+        .unwrap();
+
+    buf
+}
+
+/// Encodes a small uncompressed grayscale tiff with a resolution in pixels per centimeter
+fn tiff_with_dots_per_cm(dots: u32) -> Vec<u8> {
+    let (width, height) = (40u32, 20u32);
+    let entries: &[(u16, u16, u32)] = &[
+        (256, 4, width),
+        (257, 4, height),
+        (258, 3, 8),
+        (259, 3, 1),
+        (262, 3, 1),
+        (273, 4, 0),
+        (277, 3, 1),
+        (278, 4, height),
+        (279, 4, width * height),
+        (282, 5, 0),
+        (283, 5, 0),
+        (296, 3, 3),
+    ];
+    let ifd_size = 2 + entries.len() as u32 * 12 + 4;
+    let rational_offset = 8 + ifd_size;
+    let data_offset = rational_offset + 8;
+
+    let mut buf = b"II*\0".to_vec();
+    buf.extend(8u32.to_le_bytes());
+    buf.extend((entries.len() as u16).to_le_bytes());
+    for &(tag, kind, value) in entries {
+        let value = match tag {
+            273 => data_offset,
+            282 | 283 => rational_offset,
+            _ => value,
+        };
+        buf.extend(tag.to_le_bytes());
+        buf.extend(kind.to_le_bytes());
+        buf.extend(1u32.to_le_bytes());
+        if kind == 3 {
+            buf.extend((value as u16).to_le_bytes());
+            buf.extend([0, 0]);
+        } else {
+            buf.extend(value.to_le_bytes());
+        }
+    }
+    buf.extend(0u32.to_le_bytes());
+    buf.extend(dots.to_le_bytes());
+    buf.extend(1u32.to_le_bytes());
+    buf.extend(vec![200u8; (width * height) as usize]);
+
+    buf
+}
+
+/// Returns the resolution of the pHYs chunk of a png image
+fn png_pixel_dims(bytes: &[u8]) -> Option<png::PixelDimensions> {
+    let reader = png::Decoder::new(Cursor::new(bytes)).read_info().unwrap();
+    reader.info().pixel_dims
+}
+
+/// Returns the units and density of the JFIF segment of a jpeg image
+fn jfif_density(bytes: &[u8]) -> (u8, u16, u16) {
+    assert_eq!(&bytes[6..11], b"JFIF\0");
+    (
+        bytes[13],
+        u16::from_be_bytes([bytes[14], bytes[15]]),
+        u16::from_be_bytes([bytes[16], bytes[17]]),
+    )
+}
+
+fn assert_dpi(dpi: Option<(f32, f32)>, expected: f32) {
+    let (x, y) = dpi.expect("the thumbnail has no resolution");
+    assert!((x - expected).abs() < 0.5, "{} != {}", x, expected);
+    assert!((y - expected).abs() < 0.5, "{} != {}", y, expected);
+}
+
+#[test]
+fn it_reads_the_resolution_of_sources() {
+    let (info, _) = create_thumbnails_with_meta(
+        Cursor::new(png_with_dpi(Some(300.0))),
+        mime::IMAGE_PNG,
+        [ThumbnailSize::Small],
+    )
+    .unwrap();
+    assert_dpi(info.dpi, 300.0);
+
+    let (info, _) = create_thumbnails_with_meta(
+        Cursor::new(jpeg_with_dpi(150)),
+        mime::IMAGE_JPEG,
+        [ThumbnailSize::Small],
+    )
+    .unwrap();
+    assert_dpi(info.dpi, 150.0);
+
+    let (info, _) = create_thumbnails_with_meta(
+        Cursor::new(tiff_with_dots_per_cm(100)),
+        "image/tiff".parse().unwrap(),
+        [ThumbnailSize::Small],
+    )
+    .unwrap();
+    assert_dpi(info.dpi, 254.0);
+}
+
+#[test]
+fn it_scales_the_resolution_with_the_thumbnail() {
+    let thumbnail = create_thumbnails_from_bytes(
+        &png_with_dpi(Some(300.0)),
+        mime::IMAGE_PNG,
+        [ThumbnailSize::Small],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+
+    assert_eq!(thumbnail.size(), (128, 64));
+    assert_dpi(thumbnail.dpi(), 96.0);
+}
+
+#[test]
+fn it_scales_the_resolution_of_cropped_thumbnails() {
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Custom {
+            width: 100,
+            height: 100,
+        })
+        .resize_mode(ResizeMode::Cover)
+        .run(Cursor::new(png_with_dpi(Some(300.0))), mime::IMAGE_PNG)
+        .unwrap()
+        .pop()
+        .unwrap();
+
+    // the content is scaled by half to cover the height
+    assert_dpi(thumbnail.dpi(), 150.0);
+}
+
+#[test]
+fn it_writes_the_resolution_to_png() {
+    let thumbnail = create_thumbnails_from_bytes(
+        &png_with_dpi(Some(300.0)),
+        mime::IMAGE_PNG,
+        [ThumbnailSize::Small],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+
+    let dims = png_pixel_dims(&thumbnail.encode(ImageFormat::Png).unwrap()).unwrap();
+
+    assert_eq!(dims.unit, png::Unit::Meter);
+    assert_eq!(dims.xppu, 3780);
+    assert_eq!(dims.yppu, 3780);
+}
+
+#[test]
+fn it_writes_the_resolution_to_jpeg() {
+    let thumbnail = create_thumbnails_from_bytes(
+        &jpeg_with_dpi(300),
+        mime::IMAGE_JPEG,
+        [ThumbnailSize::Small],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+
+    let encoded = thumbnail.clone().encode(ImageFormat::Jpeg).unwrap();
+    assert_eq!(jfif_density(&encoded), (1, 96, 96));
+
+    let deterministic = thumbnail
+        .deterministic(true)
+        .encode(ImageFormat::Jpeg)
+        .unwrap();
+    assert_eq!(jfif_density(&deterministic), (1, 96, 96));
+}
+
+#[test]
+fn it_omits_unknown_resolutions() {
+    let thumbnail =
+        create_thumbnails_from_bytes(&png_with_dpi(None), mime::IMAGE_PNG, [ThumbnailSize::Small])
+            .unwrap()
+            .pop()
+            .unwrap();
+
+    assert_eq!(thumbnail.dpi(), None);
+    assert!(png_pixel_dims(&thumbnail.clone().encode(ImageFormat::Png).unwrap()).is_none());
+    // a density without a unit only describes the aspect ratio of the pixels
+    assert_eq!(
+        jfif_density(&thumbnail.encode(ImageFormat::Jpeg).unwrap()).0,
+        0
+    );
+}
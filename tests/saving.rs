@@ -0,0 +1,142 @@
+use image::error::ImageFormatHint;
+use image::{DynamicImage, ImageFormat};
+use std::fs;
+use std::path::Path;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{create_thumbnails_from_bytes, SaveOptions, Thumbnail, ThumbnailSize};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+fn thumbnail() -> Thumbnail {
+    create_thumbnails_from_bytes(PNG_BYTES, mime::IMAGE_PNG, [ThumbnailSize::Small])
+        .unwrap()
+        .pop()
+        .unwrap()
+}
+
+/// Returns the names of all files in the directory
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+
+    names
+}
+
+#[test]
+fn it_saves_in_the_format_of_the_extension() {
+    let dir = tempfile::tempdir().unwrap();
+    let thumbnail = thumbnail();
+
+    for (name, format) in [
+        ("thumb.png", ImageFormat::Png),
+        ("thumb.JPG", ImageFormat::Jpeg),
+        ("thumb.webp", ImageFormat::WebP),
+        ("thumb.bmp", ImageFormat::Bmp),
+    ] {
+        let path = dir.path().join(name);
+        thumbnail.save(&path).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(image::guess_format(&bytes).unwrap(), format, "{}", name);
+        let image = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((image.width(), image.height()), thumbnail.size());
+    }
+    assert_eq!(
+        file_names(dir.path()),
+        ["thumb.JPG", "thumb.bmp", "thumb.png", "thumb.webp"]
+    );
+}
+
+#[test]
+fn it_saves_in_explicit_formats() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("thumb");
+    thumbnail()
+        .save_with_format(&path, ImageFormat::Jpeg)
+        .unwrap();
+
+    assert_eq!(
+        image::guess_format(&fs::read(&path).unwrap()).unwrap(),
+        ImageFormat::Jpeg
+    );
+}
+
+#[test]
+fn it_replaces_existing_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("thumb.png");
+    fs::write(&path, b"old content").unwrap();
+    thumbnail().save(&path).unwrap();
+
+    assert_eq!(
+        image::guess_format(&fs::read(&path).unwrap()).unwrap(),
+        ImageFormat::Png
+    );
+    assert_eq!(file_names(dir.path()), ["thumb.png"]);
+}
+
+#[test]
+fn it_rejects_unknown_extensions() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let result = thumbnail().save(dir.path().join("thumb.unknown"));
+    assert!(matches!(
+        &result,
+        Err(ThumbError::UnsupportedOutputFormat(ImageFormatHint::PathExtension(extension)))
+            if extension.as_os_str() == "unknown"
+    ));
+    let result = thumbnail().save(dir.path().join("thumb"));
+    assert!(matches!(
+        result,
+        Err(ThumbError::UnsupportedOutputFormat(
+            ImageFormatHint::Unknown
+        ))
+    ));
+    assert!(result.unwrap_err().is_unsupported());
+    let result = thumbnail().save(dir.path().join("thumb.dds"));
+    assert!(matches!(
+        result,
+        Err(ThumbError::UnsupportedOutputFormat(ImageFormatHint::Exact(
+            ImageFormat::Dds
+        )))
+    ));
+    assert!(file_names(dir.path()).is_empty());
+}
+
+#[test]
+fn it_creates_parent_directories_if_enabled() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache").join("small").join("thumb.png");
+
+    assert!(matches!(thumbnail().save(&path), Err(ThumbError::Io(_))));
+    thumbnail()
+        .save_with_options(
+            &path,
+            SaveOptions {
+                create_dirs: true,
+                ..SaveOptions::default()
+            },
+        )
+        .unwrap();
+
+    assert!(path.is_file());
+}
+
+#[test]
+fn it_leaves_no_partial_files_when_writing_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("thumb.jpg");
+    // jpeg dimensions are limited to 65535 pixels
+    let thumbnail = Thumbnail::from(DynamicImage::new_rgb8(70_000, 1)).deterministic(true);
+
+    assert!(thumbnail.save(&path).is_err());
+    assert!(file_names(dir.path()).is_empty());
+
+    fs::write(&path, b"old content").unwrap();
+    assert!(thumbnail.save(&path).is_err());
+    assert_eq!(fs::read(&path).unwrap(), b"old content");
+    assert_eq!(file_names(dir.path()), ["thumb.jpg"]);
+}
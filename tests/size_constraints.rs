@@ -0,0 +1,133 @@
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{SizeConstraint, ThumbnailSize, ThumbnailerBuilder};
+
+/// Returns the png of an image with the given dimensions
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let image = RgbImage::from_fn(width, height, |x, _| Rgb([(x % 256) as u8, 0, 0]));
+    let mut buf = Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(image)
+        .write_to(&mut buf, ImageFormat::Png)
+        .unwrap();
+
+    buf.into_inner()
+}
+
+/// Returns the dimensions of the thumbnails created with the builder
+fn thumbnail_sizes(builder: ThumbnailerBuilder, width: u32, height: u32) -> Vec<(u32, u32)> {
+    builder
+        .run(Cursor::new(png(width, height)), mime::IMAGE_PNG)
+        .unwrap()
+        .iter()
+        .map(|thumbnail| thumbnail.size())
+        .collect()
+}
+
+#[test]
+fn it_creates_thumbnails_with_edge_constraints() {
+    // both edge constraints result in the same dimensions for these sources
+    let builder = ThumbnailerBuilder::new()
+        .sizes([
+            SizeConstraint::LongestEdge(150),
+            SizeConstraint::ShortestEdge(50),
+            SizeConstraint::BoundingBox(60, 60),
+        ])
+        .deduplicate_sizes(false);
+
+    assert_eq!(
+        thumbnail_sizes(builder.clone(), 300, 100),
+        [(150, 50), (150, 50), (60, 20)]
+    );
+    assert_eq!(
+        thumbnail_sizes(builder, 100, 300),
+        [(50, 150), (50, 150), (20, 60)]
+    );
+}
+
+#[test]
+fn it_limits_the_longer_edge_of_shortest_edge_constraints() {
+    let builder = ThumbnailerBuilder::new().size(SizeConstraint::ShortestEdge(128));
+    assert_eq!(thumbnail_sizes(builder.clone(), 10000, 3), [(4096, 128)]);
+    assert_eq!(
+        thumbnail_sizes(builder.max_constrained_dimension(1000), 3, 10000),
+        [(128, 1000)]
+    );
+
+    let builder = ThumbnailerBuilder::new().size(SizeConstraint::LongestEdge(128));
+    assert_eq!(thumbnail_sizes(builder, 10000, 3), [(128, 1)]);
+}
+
+#[test]
+fn it_returns_the_requested_constraint() {
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(SizeConstraint::LongestEdge(150))
+        .run(Cursor::new(png(300, 100)), mime::IMAGE_PNG)
+        .unwrap()
+        .pop()
+        .unwrap();
+
+    assert_eq!(
+        thumbnail.requested_size(),
+        ThumbnailSize::Constrained(SizeConstraint::LongestEdge(150))
+    );
+}
+
+#[test]
+fn it_converts_sizes_and_constraints() {
+    assert_eq!(
+        SizeConstraint::from(ThumbnailSize::Small),
+        SizeConstraint::BoundingBox(128, 128)
+    );
+    assert_eq!(
+        ThumbnailSize::from(SizeConstraint::BoundingBox(64, 64)),
+        ThumbnailSize::Icon
+    );
+    assert_eq!(
+        ThumbnailSize::from(SizeConstraint::BoundingBox(10, 20)),
+        ThumbnailSize::Custom {
+            width: 10,
+            height: 20
+        }
+    );
+    assert_eq!(
+        SizeConstraint::from(ThumbnailSize::from(SizeConstraint::ShortestEdge(5))),
+        SizeConstraint::ShortestEdge(5)
+    );
+}
+
+#[test]
+fn it_parses_and_displays_edge_constraints() {
+    for (input, constraint) in [
+        ("longest:300", SizeConstraint::LongestEdge(300)),
+        ("Shortest: 128", SizeConstraint::ShortestEdge(128)),
+    ] {
+        let size: ThumbnailSize = input.parse().unwrap();
+        assert_eq!(size, ThumbnailSize::Constrained(constraint));
+        assert_eq!(size.to_string().parse::<ThumbnailSize>().unwrap(), size);
+    }
+    assert_eq!(
+        ThumbnailSize::Constrained(SizeConstraint::ShortestEdge(128)).to_string(),
+        "shortest:128"
+    );
+
+    for input in ["longest:0", "widest:10", "longest:", "shortest:1x2"] {
+        assert!(input.parse::<ThumbnailSize>().is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn it_rejects_invalid_constraints() {
+    for builder in [
+        ThumbnailerBuilder::new().size(SizeConstraint::LongestEdge(0)),
+        ThumbnailerBuilder::new()
+            .size(SizeConstraint::ShortestEdge(10))
+            .max_constrained_dimension(0),
+    ] {
+        let result = builder.run(Cursor::new(png(30, 10)), mime::IMAGE_PNG);
+        assert!(matches!(
+            result,
+            Err(ThumbError::InvalidSize(..) | ThumbError::InvalidOptions(_))
+        ));
+    }
+}
@@ -0,0 +1,305 @@
+use std::io::Cursor;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use thumbnailer::image::Rgba;
+use thumbnailer::{
+    create_thumbnails, ResizeMode, Stage, Thumbnail, ThumbnailSize, Thumbnailer, ThumbnailerBuilder,
+};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+const PNG_DIMENSIONS: (u32, u32) = (792, 589);
+
+const POOL: [ThumbnailSize; 7] = [
+    ThumbnailSize::Icon,
+    ThumbnailSize::Small,
+    ThumbnailSize::Medium,
+    ThumbnailSize::Custom {
+        width: 128,
+        height: 128,
+    },
+    ThumbnailSize::Custom {
+        width: 200,
+        height: 80,
+    },
+    // fits the source into the same dimensions as the medium size
+    ThumbnailSize::Custom {
+        width: 256,
+        height: 400,
+    },
+    ThumbnailSize::Constrained(thumbnailer::SizeConstraint::BoundingBox(64, 64)),
+];
+
+/// Returns a list of sizes from the pool with duplicates in an order that depends on the seed
+fn shuffled_sizes(seed: u64) -> Vec<ThumbnailSize> {
+    let mut state = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    let mut next = move |bound: usize| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as usize % bound
+    };
+    let len = 1 + next(10);
+
+    (0..len).map(|_| POOL[next(POOL.len())]).collect()
+}
+
+/// Returns the dimensions of the thumbnail of the fixture that fits into the size
+fn fitted_dimensions(size: &ThumbnailSize) -> (u32, u32) {
+    let (width, height) = PNG_DIMENSIONS;
+    let (max_width, max_height) = size.dimensions();
+    let ratio = (max_width as f64 / width as f64).min(max_height as f64 / height as f64);
+    let scale = |dimension: u32| (dimension as f64 * ratio).round() as u32;
+
+    (scale(width), scale(height))
+}
+
+/// Returns the sizes without those whose thumbnails have the dimensions of an earlier one
+fn first_occurrences(sizes: &[ThumbnailSize]) -> Vec<ThumbnailSize> {
+    let mut unique: Vec<ThumbnailSize> = Vec::new();
+    for size in sizes {
+        if !unique
+            .iter()
+            .any(|u| fitted_dimensions(u) == fitted_dimensions(size))
+        {
+            unique.push(*size);
+        }
+    }
+    unique
+}
+
+/// Returns the builder with the sizes and a counter of the resized sizes
+fn counting_builder(sizes: &[ThumbnailSize]) -> (ThumbnailerBuilder, Arc<AtomicUsize>) {
+    let resized = Arc::new(AtomicUsize::new(0));
+    let counter = resized.clone();
+    let builder = ThumbnailerBuilder::new()
+        .sizes(sizes.iter().copied())
+        .on_progress(move |progress| {
+            if let Stage::Resizing { .. } = progress.stage {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+    (builder, resized)
+}
+
+fn requested_sizes(thumbnails: &[Thumbnail]) -> Vec<ThumbnailSize> {
+    thumbnails.iter().map(Thumbnail::requested_size).collect()
+}
+
+#[test]
+fn it_skips_duplicate_sizes() {
+    let sizes = [
+        ThumbnailSize::Small,
+        ThumbnailSize::Small,
+        ThumbnailSize::Medium,
+    ];
+    let (builder, resized) = counting_builder(&sizes);
+    let thumbnails = builder
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert_eq!(
+        requested_sizes(&thumbnails),
+        [ThumbnailSize::Small, ThumbnailSize::Medium]
+    );
+    assert_eq!(resized.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn it_skips_sizes_with_the_same_dimensions() {
+    let sizes = [
+        ThumbnailSize::Custom {
+            width: 128,
+            height: 128,
+        },
+        ThumbnailSize::Small,
+    ];
+    let thumbnails = ThumbnailerBuilder::new()
+        .sizes(sizes)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert_eq!(requested_sizes(&thumbnails), sizes[..1]);
+}
+
+#[test]
+fn it_skips_boxes_that_fit_the_source_into_the_same_dimensions() {
+    let sizes = [
+        ThumbnailSize::Custom {
+            width: 100,
+            height: 100,
+        },
+        ThumbnailSize::Custom {
+            width: 100,
+            height: 200,
+        },
+    ];
+    let (builder, resized) = counting_builder(&sizes);
+    let results = builder
+        .run_individual(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+    assert_eq!(resized.load(Ordering::SeqCst), 1);
+
+    for ((size, result), expected) in results.into_iter().zip(sizes) {
+        assert_eq!(size, expected);
+        assert_eq!(result.unwrap().size(), (100, 74));
+    }
+}
+
+#[test]
+fn it_keeps_boxes_that_pad_or_cover_to_different_dimensions() {
+    let sizes = [
+        ThumbnailSize::Custom {
+            width: 100,
+            height: 100,
+        },
+        ThumbnailSize::Custom {
+            width: 100,
+            height: 200,
+        },
+    ];
+    for mode in [
+        ResizeMode::Cover,
+        ResizeMode::Pad {
+            color: Rgba([0, 0, 0, 0]),
+        },
+    ] {
+        let thumbnails = ThumbnailerBuilder::new()
+            .sizes(sizes)
+            .resize_mode(mode)
+            .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+            .unwrap();
+
+        let dimensions: Vec<(u32, u32)> = thumbnails.iter().map(Thumbnail::size).collect();
+        assert_eq!(dimensions, [(100, 100), (100, 200)]);
+    }
+}
+
+#[test]
+fn it_returns_a_thumbnail_for_every_size_from_the_free_functions() {
+    let sizes = [
+        ThumbnailSize::Small,
+        ThumbnailSize::Icon,
+        ThumbnailSize::Small,
+    ];
+    let thumbnails = create_thumbnails(Cursor::new(PNG_BYTES), mime::IMAGE_PNG, sizes).unwrap();
+    assert_eq!(requested_sizes(&thumbnails), sizes);
+    assert!(thumbnails[2].pixel_eq(&thumbnails[0]));
+
+    let thumbnails = Thumbnailer::default()
+        .create(Cursor::new(PNG_BYTES), mime::IMAGE_PNG, sizes)
+        .unwrap();
+    assert_eq!(requested_sizes(&thumbnails), sizes);
+}
+
+#[test]
+fn it_keeps_duplicates_if_requested() {
+    let sizes = [
+        ThumbnailSize::Small,
+        ThumbnailSize::Small,
+        ThumbnailSize::Medium,
+    ];
+    let (builder, resized) = counting_builder(&sizes);
+    let thumbnails = builder
+        .deduplicate_sizes(false)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert_eq!(requested_sizes(&thumbnails), sizes);
+    assert_eq!(resized.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn it_returns_a_result_for_every_added_size() {
+    let sizes = [
+        ThumbnailSize::Medium,
+        ThumbnailSize::Small,
+        ThumbnailSize::Custom {
+            width: 256,
+            height: 256,
+        },
+        ThumbnailSize::Small,
+    ];
+    let (builder, resized) = counting_builder(&sizes);
+    let results = builder
+        .run_individual(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+    assert_eq!(resized.load(Ordering::SeqCst), 2);
+    assert_eq!(results.len(), sizes.len());
+
+    let thumbnails: Vec<Thumbnail> = results
+        .into_iter()
+        .zip(sizes)
+        .map(|((size, result), expected)| {
+            assert_eq!(size, expected);
+            let thumbnail = result.unwrap();
+            assert_eq!(thumbnail.requested_size(), expected);
+            thumbnail
+        })
+        .collect();
+    assert!(thumbnails[2].pixel_eq(&thumbnails[0]));
+    assert!(thumbnails[3].pixel_eq(&thumbnails[1]));
+}
+
+#[test]
+fn it_keeps_the_order_of_shuffled_and_duplicated_sizes() {
+    for seed in 0..12 {
+        let sizes = shuffled_sizes(seed);
+        let unique = first_occurrences(&sizes);
+        let (builder, resized) = counting_builder(&sizes);
+        let thumbnails = builder
+            .parallel(seed % 2 == 0)
+            .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+            .unwrap();
+
+        assert_eq!(requested_sizes(&thumbnails), unique, "{:?}", sizes);
+        assert_eq!(resized.load(Ordering::SeqCst), unique.len(), "{:?}", sizes);
+    }
+}
+
+#[test]
+fn it_maps_shuffled_and_duplicated_sizes_to_their_thumbnails() {
+    for seed in 0..12 {
+        let sizes = shuffled_sizes(seed);
+        let unique = first_occurrences(&sizes);
+        let (builder, resized) = counting_builder(&sizes);
+        let results = builder
+            .run_individual(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+            .unwrap();
+        let thumbnails = ThumbnailerBuilder::new()
+            .sizes(unique.iter().copied())
+            .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+            .unwrap();
+        assert_eq!(resized.load(Ordering::SeqCst), unique.len(), "{:?}", sizes);
+
+        assert_eq!(results.len(), sizes.len());
+        for ((size, result), expected) in results.iter().zip(&sizes) {
+            assert_eq!(size, expected);
+            let position = unique
+                .iter()
+                .position(|u| fitted_dimensions(u) == fitted_dimensions(expected))
+                .unwrap();
+            assert!(result.as_ref().unwrap().pixel_eq(&thumbnails[position]));
+        }
+    }
+}
+
+#[test]
+fn it_skips_duplicate_sizes_while_iterating() {
+    let sizes = [
+        ThumbnailSize::Small,
+        ThumbnailSize::Icon,
+        ThumbnailSize::Small,
+    ];
+    let thumbnails: Vec<ThumbnailSize> = ThumbnailerBuilder::new()
+        .sizes(sizes)
+        .largest_first(false)
+        .run_iter(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap()
+        .map(|result| result.unwrap().0)
+        .collect();
+
+    assert_eq!(thumbnails, sizes[..2]);
+}
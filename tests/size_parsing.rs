@@ -0,0 +1,196 @@
+use std::str::FromStr;
+use thumbnailer::ThumbnailSize;
+
+#[test]
+fn it_lists_all_presets() {
+    let dimensions: Vec<(u32, u32)> = ThumbnailSize::all()
+        .iter()
+        .map(ThumbnailSize::dimensions)
+        .collect();
+
+    assert_eq!(
+        dimensions,
+        [
+            (64, 64),
+            (128, 128),
+            (256, 256),
+            (512, 512),
+            (960, 960),
+            (1024, 1024),
+            (2048, 2048)
+        ]
+    );
+}
+
+#[test]
+fn it_round_trips_presets_through_strings() {
+    for preset in ThumbnailSize::all() {
+        let name = preset.to_string();
+
+        assert_eq!(ThumbnailSize::from_str(&name).unwrap(), *preset);
+        assert_eq!(
+            ThumbnailSize::from_str(&name.to_uppercase()).unwrap(),
+            *preset
+        );
+    }
+}
+
+#[test]
+fn it_parses_custom_sizes() {
+    let custom = ThumbnailSize::Custom {
+        width: 320,
+        height: 240,
+    };
+
+    assert_eq!(custom.to_string(), "320x240");
+    assert_eq!(ThumbnailSize::from_str("320x240").unwrap(), custom);
+    assert_eq!(ThumbnailSize::from_str(" 320 X 240 ").unwrap(), custom);
+    assert_eq!(
+        ThumbnailSize::from_str("300").unwrap(),
+        ThumbnailSize::Custom {
+            width: 300,
+            height: 300
+        }
+    );
+    // dimensions of presets return the preset
+    assert_eq!(
+        ThumbnailSize::from_str("256").unwrap(),
+        ThumbnailSize::Medium
+    );
+    assert_eq!(
+        ThumbnailSize::from_str("64x64").unwrap(),
+        ThumbnailSize::Icon
+    );
+}
+
+#[test]
+fn it_rejects_invalid_sizes() {
+    for input in [
+        "",
+        "huge",
+        "0",
+        "320x0",
+        "320x",
+        "x240",
+        "-5",
+        "320x240x2",
+        "medium@",
+        "medium@0x",
+        "medium@2",
+        "@2x",
+        "medium@2x@2x",
+        "xlarge@40x",
+    ] {
+        let error = ThumbnailSize::from_str(input).unwrap_err();
+
+        assert!(error.to_string().contains(&format!("\"{}\"", input)));
+    }
+}
+
+#[test]
+fn it_lists_the_accepted_forms_in_errors() {
+    let message = ThumbnailSize::from_str("huge").unwrap_err().to_string();
+
+    for preset in ThumbnailSize::all() {
+        assert!(message.contains(&format!(" {},", preset)), "{}", message);
+    }
+    for (form, example) in [
+        ("WIDTHxHEIGHT", "320x240"),
+        ("longest:EDGE", "longest:300"),
+        ("shortest:EDGE", "shortest:300"),
+        ("NAME@2x", "icon@2x"),
+    ] {
+        assert!(message.contains(form), "{}", message);
+        assert!(ThumbnailSize::from_str(example).is_ok(), "{}", example);
+    }
+}
+
+#[test]
+fn it_multiplies_scaled_sizes() {
+    let scaled = ThumbnailSize::Medium.scaled(2).unwrap();
+
+    assert_eq!(scaled.dimensions(), (512, 512));
+    assert_eq!(scaled.scale_factor(), 2);
+    assert_eq!(scaled.unscaled(), ThumbnailSize::Medium);
+    // a scaled size is distinct from the preset with the same dimensions
+    assert_ne!(scaled, ThumbnailSize::Large);
+    assert_eq!(scaled.scaled(3).unwrap().dimensions(), (1536, 1536));
+    assert_eq!(ThumbnailSize::Icon.scaled(1).unwrap(), ThumbnailSize::Icon);
+
+    let custom = ThumbnailSize::Custom {
+        width: 320,
+        height: 240,
+    };
+    assert_eq!(custom.scaled(3).unwrap().dimensions(), (960, 720));
+    assert_eq!(
+        ThumbnailSize::from_str("longest:100@2x")
+            .unwrap()
+            .dimensions(),
+        (200, 200)
+    );
+}
+
+#[test]
+fn it_round_trips_scaled_sizes_through_strings() {
+    for input in ["medium@2x", "icon@3x", "320x240@2x", "shortest:128@4x"] {
+        let size = ThumbnailSize::from_str(input).unwrap();
+
+        assert_eq!(size.to_string(), input);
+    }
+    assert_eq!(
+        ThumbnailSize::from_str(" Medium@2X ").unwrap(),
+        ThumbnailSize::Medium.scaled(2).unwrap()
+    );
+    assert_eq!(
+        ThumbnailSize::from_str("medium@1x").unwrap(),
+        ThumbnailSize::Medium
+    );
+}
+
+#[test]
+fn it_rejects_scaled_sizes_that_overflow() {
+    assert!(ThumbnailSize::Medium.scaled(0).is_err());
+    assert!(ThumbnailSize::XLarge.scaled(32).is_err());
+    assert!(ThumbnailSize::Icon.scaled(u32::MAX).is_err());
+    let scaled = ThumbnailSize::Icon.scaled(1 << 16).unwrap_err();
+    assert!(scaled.to_string().contains("icon"), "{}", scaled);
+    // the factors of scaled sizes are multiplied with overflow checks as well
+    let large = ThumbnailSize::Icon.scaled(1000).unwrap();
+    assert!(large.scaled(u32::MAX).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn it_round_trips_sizes_through_serde() {
+    let mut sizes = ThumbnailSize::all().to_vec();
+    sizes.push(ThumbnailSize::Custom {
+        width: 320,
+        height: 240,
+    });
+
+    let json = serde_json::to_string(&sizes).unwrap();
+    assert_eq!(
+        json,
+        r#"["icon","small","medium","large","preview","larger","xlarge","320x240"]"#
+    );
+    assert_eq!(
+        serde_json::from_str::<Vec<ThumbnailSize>>(&json).unwrap(),
+        sizes
+    );
+    for size in &sizes {
+        let json = serde_json::to_string(size).unwrap();
+        assert_eq!(serde_json::from_str::<ThumbnailSize>(&json).unwrap(), *size);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn it_deserializes_numbers_and_rejects_invalid_sizes() {
+    assert_eq!(
+        serde_json::from_str::<ThumbnailSize>("512").unwrap(),
+        ThumbnailSize::Large
+    );
+    for json in [r#""huge""#, "0", "-1", "true"] {
+        assert!(serde_json::from_str::<ThumbnailSize>(json).is_err());
+    }
+}
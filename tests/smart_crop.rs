@@ -0,0 +1,117 @@
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use thumbnailer::{CropStrategy, ResizeMode, ThumbnailSize, ThumbnailerBuilder};
+
+const BACKGROUND: Rgb<u8> = Rgb([128, 128, 128]);
+
+/// Returns a png of a flat image with a colorful checkerboard in the top left corner
+fn subject_in_top_left(width: u32, height: u32) -> Vec<u8> {
+    let image = RgbImage::from_fn(width, height, |x, y| {
+        if x < 60 && y < 60 {
+            if (x / 5 + y / 5) % 2 == 0 {
+                Rgb([255, 0, 0])
+            } else {
+                Rgb([0, 0, 255])
+            }
+        } else {
+            BACKGROUND
+        }
+    });
+    let mut buf = Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(image)
+        .write_to(&mut buf, ImageFormat::Png)
+        .unwrap();
+
+    buf.into_inner()
+}
+
+/// Returns the fraction of pixels of the cover thumbnail that differ from the background
+fn subject_fraction(source: &[u8], strategy: CropStrategy) -> f32 {
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Icon)
+        .resize_mode(ResizeMode::Cover)
+        .crop_strategy(strategy)
+        .run(Cursor::new(source), mime::IMAGE_PNG)
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(thumbnail.size(), (64, 64));
+    let image = thumbnail.into_inner().into_rgb8();
+    let subject = image
+        .pixels()
+        .filter(|pixel| {
+            pixel
+                .0
+                .iter()
+                .zip(BACKGROUND.0)
+                .any(|(a, b)| a.abs_diff(b) > 8)
+        })
+        .count();
+
+    subject as f32 / (64 * 64) as f32
+}
+
+#[test]
+fn it_keeps_the_subject_with_attention_crops() {
+    for (width, height) in [(300, 100), (100, 300)] {
+        let source = subject_in_top_left(width, height);
+
+        // the center of the image is empty
+        assert_eq!(subject_fraction(&source, CropStrategy::Center), 0.0);
+        assert!(subject_fraction(&source, CropStrategy::Attention) > 0.3);
+    }
+}
+
+#[test]
+fn it_crops_around_fixed_focal_points() {
+    let source = subject_in_top_left(300, 100);
+
+    assert!(subject_fraction(&source, CropStrategy::Fixed(0.1, 0.5)) > 0.3);
+    assert_eq!(
+        subject_fraction(&source, CropStrategy::Fixed(0.5, 0.5)),
+        0.0
+    );
+    assert_eq!(
+        subject_fraction(&source, CropStrategy::Fixed(1.0, 0.0)),
+        0.0
+    );
+}
+
+#[test]
+fn it_keeps_the_center_of_flat_images_with_attention_crops() {
+    let image = RgbImage::from_fn(300, 100, |x, _| {
+        if (140..160).contains(&x) {
+            Rgb([0, 0, 0])
+        } else {
+            BACKGROUND
+        }
+    });
+    let mut buf = Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(image)
+        .write_to(&mut buf, ImageFormat::Png)
+        .unwrap();
+
+    let center = subject_fraction(buf.get_ref(), CropStrategy::Center);
+    assert_eq!(
+        subject_fraction(buf.get_ref(), CropStrategy::Attention),
+        center
+    );
+}
+
+#[test]
+fn it_rejects_focal_points_outside_of_the_image() {
+    for strategy in [
+        CropStrategy::Fixed(1.5, 0.5),
+        CropStrategy::Fixed(0.5, -0.1),
+        CropStrategy::Fixed(f32::NAN, 0.5),
+    ] {
+        let result = ThumbnailerBuilder::new()
+            .size(ThumbnailSize::Icon)
+            .resize_mode(ResizeMode::Cover)
+            .crop_strategy(strategy)
+            .run(Cursor::new(subject_in_top_left(300, 100)), mime::IMAGE_PNG);
+
+        assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+    }
+}
@@ -0,0 +1,116 @@
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use thumbnailer::error::ThumbError;
+use thumbnailer::{SpriteCell, SpriteSheet, Thumbnail};
+
+/// Returns a thumbnail of the given size and color
+fn solid(width: u32, height: u32, color: [u8; 4]) -> Thumbnail {
+    Thumbnail::from(DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+        width,
+        height,
+        Rgba(color),
+    )))
+}
+
+#[test]
+fn it_packs_cells_row_by_row() {
+    let sheets = SpriteSheet::new(40, 30, 3)
+        .padding(2)
+        .extend((0..5).map(|i| solid(40, 30, [i * 40, 0, 0, 255])))
+        .finish()
+        .unwrap();
+
+    assert_eq!(sheets.len(), 1);
+    let (sheet, cells) = &sheets[0];
+    assert_eq!(sheet.size(), (124, 62));
+    assert_eq!(
+        cells[4],
+        SpriteCell {
+            index: 4,
+            x: 42,
+            y: 32,
+            width: 40,
+            height: 30,
+        }
+    );
+    let image = sheet.as_image();
+    assert_eq!(image.get_pixel(43, 33), Rgba([160, 0, 0, 255]));
+    // the padding and the empty last cell have the background color
+    assert_eq!(image.get_pixel(41, 10), Rgba([0, 0, 0, 0]));
+    assert_eq!(image.get_pixel(100, 40), Rgba([0, 0, 0, 0]));
+}
+
+#[test]
+fn it_scales_down_large_images() {
+    let sheets = SpriteSheet::new(40, 40, 2)
+        .background(Rgba([255, 255, 255, 255]))
+        .push(solid(200, 100, [255, 0, 0, 255]))
+        .push(solid(10, 10, [0, 255, 0, 255]))
+        .finish()
+        .unwrap();
+
+    let (sheet, cells) = &sheets[0];
+    assert_eq!(
+        cells[0],
+        SpriteCell {
+            index: 0,
+            x: 0,
+            y: 10,
+            width: 40,
+            height: 20,
+        }
+    );
+    // small images aren't scaled up and are centered in their cell
+    assert_eq!(
+        cells[1],
+        SpriteCell {
+            index: 1,
+            x: 55,
+            y: 15,
+            width: 10,
+            height: 10,
+        }
+    );
+    assert_eq!(
+        sheet.as_image().get_pixel(20, 5),
+        Rgba([255, 255, 255, 255])
+    );
+}
+
+#[test]
+fn it_starts_new_sheets_at_the_maximum_height() {
+    let sheets = SpriteSheet::new(10, 10, 2)
+        .padding(1)
+        .max_height(25)
+        .extend((0..9).map(|_| solid(10, 10, [0, 0, 255, 255])))
+        .finish()
+        .unwrap();
+
+    let sizes: Vec<_> = sheets.iter().map(|(sheet, _)| sheet.size()).collect();
+    assert_eq!(sizes, vec![(21, 21), (21, 21), (21, 10)]);
+    let (_, cells) = &sheets[2];
+    assert_eq!(cells.len(), 1);
+    assert_eq!((cells[0].index, cells[0].x, cells[0].y), (8, 0, 0));
+}
+
+#[test]
+fn it_returns_no_sheets_without_images() {
+    let sheets = SpriteSheet::new(10, 10, 2).finish().unwrap();
+
+    assert!(sheets.is_empty());
+}
+
+#[test]
+fn it_rejects_invalid_layouts() {
+    let zero_columns = SpriteSheet::new(10, 10, 0)
+        .push(solid(10, 10, [0, 0, 0, 255]))
+        .finish();
+    let too_low = SpriteSheet::new(10, 10, 2)
+        .max_height(5)
+        .push(solid(10, 10, [0, 0, 0, 255]))
+        .finish();
+    let empty_cells = SpriteSheet::new(0, 10, 2).finish();
+
+    assert!(matches!(zero_columns, Err(ThumbError::InvalidOptions(_))));
+    assert!(matches!(too_low, Err(ThumbError::InvalidOptions(_))));
+    assert!(matches!(empty_cells, Err(ThumbError::InvalidSize(0, 10))));
+}
@@ -0,0 +1,235 @@
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+use std::convert::TryInto;
+use std::time::Duration;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{Storyboard, StoryboardOptions, Thumbnail, ThumbnailSize};
+
+#[cfg(feature = "ffmpeg")]
+use std::io::Cursor;
+#[cfg(feature = "ffmpeg")]
+use thumbnailer::generate_storyboard;
+
+#[cfg(feature = "ffmpeg")]
+const VIDEO_BYTES: &[u8] = include_bytes!("assets/test.mp4");
+
+/// A cue that has been parsed from a WebVTT file
+#[derive(Debug, PartialEq)]
+struct ParsedCue {
+    start: Duration,
+    end: Duration,
+    url: String,
+    xywh: [u32; 4],
+}
+
+fn parse_vtt(vtt: &str) -> Vec<ParsedCue> {
+    let mut blocks = vtt.split("\n\n");
+    assert_eq!(blocks.next(), Some("WEBVTT"));
+
+    blocks
+        .map(|block| {
+            let mut lines = block.lines();
+            let timings = lines.next().unwrap();
+            let payload = lines.next().unwrap();
+            assert_eq!(lines.next(), None, "{:?}", block);
+            let (start, end) = timings.split_once(" --> ").unwrap();
+            let (url, fragment) = payload.split_once("#xywh=").unwrap();
+            let xywh: Vec<u32> = fragment.split(',').map(|n| n.parse().unwrap()).collect();
+
+            ParsedCue {
+                start: parse_timestamp(start),
+                end: parse_timestamp(end),
+                url: url.to_string(),
+                xywh: xywh.try_into().unwrap(),
+            }
+        })
+        .collect()
+}
+
+fn parse_timestamp(timestamp: &str) -> Duration {
+    let (time, millis) = timestamp.split_once('.').unwrap();
+    assert_eq!(millis.len(), 3, "{}", timestamp);
+    let parts: Vec<u64> = time.split(':').map(|n| n.parse().unwrap()).collect();
+    let [hours, minutes, seconds]: [u64; 3] = parts.try_into().unwrap();
+    assert!(minutes < 60 && seconds < 60, "{}", timestamp);
+
+    Duration::from_millis(
+        ((hours * 60 + minutes) * 60 + seconds) * 1000 + millis.parse::<u64>().unwrap(),
+    )
+}
+
+fn write_vtt(storyboard: &Storyboard, pattern: &str) -> Vec<ParsedCue> {
+    let mut buf = Vec::new();
+    storyboard.write_vtt(&mut buf, pattern).unwrap();
+
+    parse_vtt(&String::from_utf8(buf).unwrap())
+}
+
+/// Returns frames of 320x180 pixels with a different shade of red each
+fn frames(count: u8) -> Vec<Thumbnail> {
+    (0..count)
+        .map(|i| {
+            Thumbnail::from(DynamicImage::ImageRgb8(RgbImage::from_pixel(
+                320,
+                180,
+                Rgb([i * 10, 0, 0]),
+            )))
+        })
+        .collect()
+}
+
+fn options(interval: Duration) -> StoryboardOptions {
+    StoryboardOptions {
+        interval,
+        cell_size: ThumbnailSize::Custom {
+            width: 80,
+            height: 60,
+        },
+        columns: 4,
+        max_height: Some(130),
+        max_frames: 100,
+    }
+}
+
+#[test]
+fn it_writes_cues_for_the_packed_cells() {
+    let storyboard = Storyboard::from_frames(
+        frames(25),
+        Some(Duration::from_millis(49_500)),
+        options(Duration::from_secs(2)),
+    )
+    .unwrap();
+    let cues = write_vtt(&storyboard, "thumbs-{}.jpg");
+
+    // two rows of four cells fit onto each sheet
+    assert_eq!(storyboard.sheets().len(), 4);
+    assert_eq!(cues.len(), 25);
+    assert_eq!(storyboard.cues().len(), 25);
+    for (i, (parsed, cue)) in cues.iter().zip(storyboard.cues()).enumerate() {
+        let cell = cue.cell;
+        assert_eq!(cell.index, i);
+        assert_eq!(parsed.xywh, [cell.x, cell.y, cell.width, cell.height]);
+        assert_eq!(parsed.url, format!("thumbs-{}.jpg", cue.sheet));
+        assert_eq!((parsed.start, parsed.end), (cue.start, cue.end));
+        assert_eq!(parsed.start, Duration::from_secs(2 * i as u64));
+        assert!(parsed.end > parsed.start);
+
+        // the fragment points at the frame inside the sheet
+        let sheet = storyboard.sheets()[cue.sheet].as_image();
+        let center = sheet.get_pixel(cell.x + cell.width / 2, cell.y + cell.height / 2);
+        assert_eq!(center.0, [i as u8 * 10, 0, 0, 255]);
+        assert!(cell.x + cell.width <= sheet.width() && cell.y + cell.height <= sheet.height());
+    }
+    // the cues follow each other without gaps and the last one ends with the video
+    for pair in cues.windows(2) {
+        assert_eq!(pair[0].end, pair[1].start);
+    }
+    assert_eq!(cues[24].end, Duration::from_millis(49_500));
+    assert_eq!(cues[9].url, "thumbs-1.jpg");
+    assert_eq!(cues[9].xywh, [80, 7, 80, 45]);
+}
+
+#[test]
+fn it_writes_spec_compliant_timestamps() {
+    let storyboard = Storyboard::from_frames(
+        frames(2),
+        Some(Duration::from_millis(2 * 3_600_000 + 1_234)),
+        options(Duration::from_secs(3_600)),
+    )
+    .unwrap();
+    let mut buf = Vec::new();
+    storyboard.write_vtt(&mut buf, "thumbs.jpg").unwrap();
+
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        "WEBVTT\n\
+         \n\
+         00:00:00.000 --> 01:00:00.000\n\
+         thumbs.jpg#xywh=0,7,80,45\n\
+         \n\
+         01:00:00.000 --> 02:00:01.234\n\
+         thumbs.jpg#xywh=80,7,80,45\n"
+    );
+}
+
+#[test]
+fn it_creates_one_cue_for_short_videos() {
+    let storyboard = Storyboard::from_frames(
+        frames(1),
+        Some(Duration::from_millis(3_250)),
+        options(Duration::from_secs(10)),
+    )
+    .unwrap();
+    let cues = write_vtt(&storyboard, "thumbs.jpg");
+
+    assert_eq!(cues.len(), 1);
+    assert_eq!(cues[0].start, Duration::ZERO);
+    assert_eq!(cues[0].end, Duration::from_millis(3_250));
+}
+
+#[test]
+fn it_ends_cues_after_the_interval_without_a_duration() {
+    let storyboard =
+        Storyboard::from_frames(frames(2), None, options(Duration::from_secs(5))).unwrap();
+    let cues = write_vtt(&storyboard, "thumbs.jpg");
+
+    assert_eq!(cues[1].start, Duration::from_secs(5));
+    assert_eq!(cues[1].end, Duration::from_secs(10));
+}
+
+#[test]
+fn it_rejects_intervals_below_a_millisecond() {
+    let result = Storyboard::from_frames(frames(1), None, options(Duration::from_micros(500)));
+
+    assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+}
+
+#[cfg(feature = "ffmpeg")]
+#[test]
+fn it_generates_storyboards_of_videos() {
+    let storyboard = generate_storyboard(
+        Cursor::new(VIDEO_BYTES),
+        "video/mp4".parse().unwrap(),
+        options(Duration::from_secs(1)),
+    )
+    .unwrap();
+    let cues = write_vtt(&storyboard, "thumbs-{}.jpg");
+
+    assert!(!cues.is_empty());
+    assert_eq!(cues.len(), storyboard.cues().len());
+    for (parsed, cue) in cues.iter().zip(storyboard.cues()) {
+        let cell = cue.cell;
+        assert_eq!(parsed.xywh, [cell.x, cell.y, cell.width, cell.height]);
+        assert!(parsed.end > parsed.start);
+    }
+}
+
+#[cfg(feature = "ffmpeg")]
+#[test]
+fn it_rejects_videos_with_more_frames_than_the_limit() {
+    let result = generate_storyboard(
+        Cursor::new(VIDEO_BYTES),
+        "video/mp4".parse().unwrap(),
+        StoryboardOptions {
+            max_frames: 2,
+            ..options(Duration::from_millis(100))
+        },
+    );
+
+    assert!(
+        matches!(result, Err(ThumbError::TooManyFrames { count, limit: 2 }) if count > 2),
+        "{:?}",
+        result.map(|storyboard| storyboard.cues().len())
+    );
+}
+
+#[cfg(feature = "ffmpeg")]
+#[test]
+fn it_only_generates_storyboards_of_videos() {
+    let result = generate_storyboard(
+        Cursor::new(include_bytes!("assets/test.png")),
+        mime::IMAGE_PNG,
+        options(Duration::from_secs(1)),
+    );
+
+    assert!(matches!(result, Err(ThumbError::UnsupportedMime(_))));
+}
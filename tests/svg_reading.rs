@@ -0,0 +1,48 @@
+#![cfg(feature = "svg")]
+
+use image::{ImageFormat, Rgb};
+use std::io::Cursor;
+use thumbnailer::{create_thumbnails, Thumbnail, ThumbnailSize};
+
+const SIZED_SVG_BYTES: &[u8] = include_bytes!("assets/sized.svg");
+const VIEWBOX_SVG_BYTES: &[u8] = include_bytes!("assets/viewbox.svg");
+const EMBEDDED_SVG_BYTES: &[u8] = include_bytes!("assets/embedded.svg");
+
+fn create_thumbnail(bytes: &[u8], size: ThumbnailSize) -> Thumbnail {
+    create_thumbnails(Cursor::new(bytes), mime::IMAGE_SVG, [size])
+        .unwrap()
+        .pop()
+        .unwrap()
+}
+
+fn center_pixel(thumbnail: Thumbnail) -> Rgb<u8> {
+    let (width, height) = thumbnail.size();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    let image = image::load_from_memory_with_format(buf.get_ref(), ImageFormat::Png)
+        .unwrap()
+        .into_rgb8();
+
+    *image.get_pixel(width / 2, height / 2)
+}
+
+#[test]
+fn it_renders_svgs_with_dimensions() {
+    let thumbnail = create_thumbnail(SIZED_SVG_BYTES, ThumbnailSize::Larger);
+    assert_eq!(thumbnail.size(), (1024, 512));
+    assert_eq!(center_pixel(thumbnail), Rgb([255, 0, 0]));
+}
+
+#[test]
+fn it_renders_svgs_with_only_a_viewbox() {
+    let thumbnail = create_thumbnail(VIEWBOX_SVG_BYTES, ThumbnailSize::Medium);
+    assert_eq!(thumbnail.size(), (128, 256));
+    assert_eq!(center_pixel(thumbnail), Rgb([0, 0, 255]));
+}
+
+#[test]
+fn it_renders_svgs_with_embedded_images() {
+    let thumbnail = create_thumbnail(EMBEDDED_SVG_BYTES, ThumbnailSize::Small);
+    assert_eq!(thumbnail.size(), (128, 128));
+    assert_eq!(center_pixel(thumbnail), Rgb([0, 255, 0]));
+}
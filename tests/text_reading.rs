@@ -0,0 +1,154 @@
+#![cfg(feature = "text")]
+
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::image::RgbImage;
+use thumbnailer::{Thumbnail, ThumbnailSize, ThumbnailerBuilder};
+
+const TEXT: &str = "fn main() {\n\tprintln!(\"Hello, world!\");\n}\n";
+
+fn create_thumbnail(builder: ThumbnailerBuilder, bytes: &[u8], mime: &str) -> Thumbnail {
+    builder
+        .run(Cursor::new(bytes.to_vec()), mime.parse().unwrap())
+        .unwrap()
+        .pop()
+        .unwrap()
+}
+
+fn small_thumbnail(bytes: &[u8]) -> RgbImage {
+    let builder = ThumbnailerBuilder::new().size(ThumbnailSize::Small);
+
+    create_thumbnail(builder, bytes, "text/plain")
+        .as_image()
+        .to_rgb8()
+}
+
+/// Returns the number of pixels that are darker than the page
+fn ink(image: &RgbImage) -> usize {
+    image.pixels().filter(|pixel| pixel[0] < 200).count()
+}
+
+/// Returns the number of bands of rows with ink separated by empty rows
+fn text_lines(image: &RgbImage) -> usize {
+    let rows: Vec<bool> = (0..image.height())
+        .map(|y| (0..image.width()).any(|x| image.get_pixel(x, y)[0] < 200))
+        .collect();
+
+    rows.windows(2).filter(|w| !w[0] && w[1]).count()
+}
+
+/// Returns the lowest row with ink
+fn last_row_with_ink(image: &RgbImage) -> u32 {
+    image
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel[0] < 200)
+        .map(|(_, y, _)| y)
+        .max()
+        .unwrap()
+}
+
+#[test]
+fn it_renders_text_onto_a_portrait_page() {
+    let image = small_thumbnail(TEXT.as_bytes());
+
+    assert_eq!(image.dimensions(), (91, 128));
+    assert!(ink(&image) > 50);
+    assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255]);
+    assert_eq!(image.get_pixel(90, 127).0, [255, 255, 255]);
+}
+
+#[test]
+fn it_renders_empty_files_as_blank_pages() {
+    let image = small_thumbnail(b"");
+
+    assert_eq!(image.dimensions(), (91, 128));
+    assert_eq!(ink(&image), 0);
+}
+
+#[test]
+fn it_renders_invalid_utf8_and_long_lines() {
+    let mut bytes = b"caf\xe9 \xff\xfe\n".to_vec();
+    bytes.extend(std::iter::repeat_n(b'#', 10_000));
+    let image = small_thumbnail(&bytes);
+
+    assert!(ink(&image) > 0);
+    // long lines are truncated at the margin
+    for y in 0..image.height() {
+        assert_eq!(image.get_pixel(image.width() - 1, y).0, [255, 255, 255]);
+    }
+}
+
+#[test]
+fn it_shows_fewer_lines_on_small_pages() {
+    let text = "line\n".repeat(100);
+    let builder = ThumbnailerBuilder::new().size(ThumbnailSize::Icon);
+    let icon = create_thumbnail(builder, text.as_bytes(), "text/plain")
+        .as_image()
+        .to_rgb8();
+    let small = small_thumbnail(text.as_bytes());
+
+    // both pages are filled down to the bottom margin with readable lines
+    assert!(last_row_with_ink(&icon) > 48);
+    assert!(last_row_with_ink(&small) > 100);
+    let builder = ThumbnailerBuilder::new().size(ThumbnailSize::Medium);
+    let medium = create_thumbnail(builder, text.as_bytes(), "text/plain")
+        .as_image()
+        .to_rgb8();
+    assert_eq!(text_lines(&medium), 20);
+    assert_eq!(text_lines(&small), 16);
+    assert_eq!(text_lines(&icon), 8);
+}
+
+#[test]
+fn it_limits_the_number_of_lines() {
+    let text = "line\n".repeat(100);
+    let builder = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .text_lines(5)
+        .text_page_aspect(1.0);
+    let image = create_thumbnail(builder, text.as_bytes(), "text/x-rust")
+        .as_image()
+        .to_rgb8();
+
+    assert_eq!(image.dimensions(), (128, 128));
+    // five lines fill the page
+    assert_eq!(text_lines(&image), 5);
+    assert!(last_row_with_ink(&image) > 100);
+}
+
+#[test]
+fn it_renders_json_documents() {
+    let builder = ThumbnailerBuilder::new().size(ThumbnailSize::Small);
+    let thumbnail = create_thumbnail(builder, br#"{"key": "value"}"#, "application/json");
+
+    assert!(ink(&thumbnail.as_image().to_rgb8()) > 0);
+}
+
+#[test]
+fn it_limits_the_page_size() {
+    let builder = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Custom {
+            width: 1_000_000,
+            height: 1_000_000,
+        })
+        .no_upscale(true)
+        .text_page_aspect(1000.0);
+    let thumbnail = create_thumbnail(builder, TEXT.as_bytes(), "text/plain");
+
+    assert_eq!(thumbnail.size(), (8192, 8));
+}
+
+#[test]
+fn it_rejects_invalid_text_options() {
+    for builder in [
+        ThumbnailerBuilder::new().text_lines(0),
+        ThumbnailerBuilder::new().text_page_aspect(0.0),
+        ThumbnailerBuilder::new().text_page_aspect(f32::NAN),
+    ] {
+        let result = builder
+            .size(ThumbnailSize::Small)
+            .run(Cursor::new(TEXT), mime::TEXT_PLAIN);
+
+        assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+    }
+}
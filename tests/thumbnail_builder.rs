@@ -0,0 +1,226 @@
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::image::{GenericImageView, Rgba};
+use thumbnailer::{FilterType, ResizeMode, ThumbnailSize, ThumbnailerBuilder};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+#[test]
+fn it_creates_thumbnails_with_the_builder() {
+    let thumbnails = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .size(ThumbnailSize::Medium)
+        .filter(FilterType::Triangle)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert_eq!(thumbnails.len(), 2);
+    assert_eq!(thumbnails[0].size(), (128, 95));
+    assert_eq!(thumbnails[1].size(), (256, 190));
+}
+
+#[test]
+fn it_stretches_thumbnails_without_preserving_aspect() {
+    let thumbnails = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Small, ThumbnailSize::Medium])
+        .preserve_aspect(false)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert_eq!(thumbnails[0].size(), (128, 128));
+    assert_eq!(thumbnails[1].size(), (256, 256));
+}
+
+#[test]
+fn it_validates_sizes_before_decoding() {
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Custom {
+            width: 10,
+            height: 0,
+        })
+        .run(Cursor::new(Vec::new()), mime::IMAGE_PNG);
+
+    assert!(matches!(result, Err(ThumbError::InvalidSize(10, 0))));
+}
+
+#[test]
+fn it_crops_thumbnails_in_cover_mode() {
+    let thumbnails = ThumbnailerBuilder::new()
+        .sizes([
+            ThumbnailSize::Small,
+            ThumbnailSize::Custom {
+                width: 320,
+                height: 90,
+            },
+            ThumbnailSize::Custom {
+                width: 1,
+                height: 1000,
+            },
+        ])
+        .resize_mode(ResizeMode::Cover)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert_eq!(thumbnails[0].size(), (128, 128));
+    assert_eq!(thumbnails[1].size(), (320, 90));
+    assert_eq!(thumbnails[2].size(), (1, 1000));
+}
+
+#[test]
+fn it_stretches_thumbnails_in_exact_mode() {
+    let thumbnails = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Custom {
+            width: 2000,
+            height: 10,
+        })
+        .resize_mode(ResizeMode::Exact)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert_eq!(thumbnails[0].size(), (2000, 10));
+}
+
+#[test]
+fn it_rejects_conflicting_resize_options() {
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .resize_mode(ResizeMode::Cover)
+        .preserve_aspect(false)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG);
+
+    assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+}
+
+#[test]
+fn it_does_not_upscale_small_images() {
+    let thumbnails = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Small, ThumbnailSize::Larger])
+        .resize_mode(ResizeMode::Cover)
+        .no_upscale(true)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert_eq!(thumbnails[0].size(), (128, 128));
+    assert_eq!(thumbnails[1].size(), (792, 589));
+    assert_eq!(thumbnails[1].requested_size(), ThumbnailSize::Larger);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn it_creates_the_same_thumbnails_with_any_parallelism() {
+    let sizes = [ThumbnailSize::Icon, ThumbnailSize::Small];
+    let pool = std::sync::Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap(),
+    );
+    let builders = [
+        ThumbnailerBuilder::new().sizes(sizes),
+        ThumbnailerBuilder::new().sizes(sizes).parallel(false),
+        ThumbnailerBuilder::new().sizes(sizes).thread_pool(pool),
+    ];
+    let results: Vec<Vec<_>> = builders
+        .iter()
+        .map(|builder| {
+            builder
+                .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+                .unwrap()
+                .into_iter()
+                .map(|thumbnail| thumbnail.into_inner().into_bytes())
+                .collect()
+        })
+        .collect();
+
+    assert_eq!(results[0], results[1]);
+    assert_eq!(results[0], results[2]);
+}
+
+#[test]
+fn it_sharpens_thumbnails() {
+    let create = |builder: ThumbnailerBuilder| {
+        builder
+            .size(ThumbnailSize::Small)
+            .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+            .unwrap()
+            .pop()
+            .unwrap()
+            .into_inner()
+            .into_rgb8()
+    };
+    let unsharpened = create(ThumbnailerBuilder::new());
+    let sharpened = create(ThumbnailerBuilder::new().sharpen(1.0, 1.0, 0));
+    let unchanged = create(ThumbnailerBuilder::new().sharpen(0.0, 1.0, 0));
+
+    assert_eq!(unsharpened.dimensions(), sharpened.dimensions());
+    assert_ne!(unsharpened, sharpened);
+    assert_eq!(unsharpened, unchanged);
+
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .sharpen(1.0, -1.0, 0)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG);
+    assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+}
+
+#[test]
+fn it_pads_thumbnails_to_the_exact_size() {
+    let color = Rgba([0, 0, 0, 0]);
+    for (width, height) in [(256, 256), (255, 101), (100, 300)] {
+        let thumbnail = ThumbnailerBuilder::new()
+            .size(ThumbnailSize::Custom { width, height })
+            .resize_mode(ResizeMode::Pad { color })
+            .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert_eq!(thumbnail.size(), (width, height));
+
+        let image = thumbnail.as_image();
+        let corners = [(0, 0), (width - 1, height - 1)];
+        // none of the boxes has the aspect ratio of the source
+        assert!(corners
+            .iter()
+            .all(|(x, y)| image.get_pixel(*x, *y) == color));
+        let center = image.get_pixel(width / 2, height / 2);
+        assert_eq!(center[3], 255);
+    }
+}
+
+#[test]
+fn it_pads_small_images_without_upscaling() {
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Larger)
+        .resize_mode(ResizeMode::Pad {
+            color: Rgba([255, 255, 255, 255]),
+        })
+        .no_upscale(true)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap()
+        .pop()
+        .unwrap();
+
+    assert_eq!(thumbnail.size(), (1024, 1024));
+    assert_eq!(
+        thumbnail.as_image().get_pixel(0, 0),
+        Rgba([255, 255, 255, 255])
+    );
+}
+
+#[test]
+fn it_keeps_the_scale_of_requested_sizes() {
+    let scaled = ThumbnailSize::Icon.scaled(2).unwrap();
+    let thumbnails = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Icon, scaled])
+        .resize_mode(ResizeMode::Cover)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert_eq!(thumbnails[0].size(), (64, 64));
+    assert_eq!(thumbnails[1].size(), (128, 128));
+    assert_eq!(thumbnails[1].requested_size(), scaled);
+    assert_eq!(
+        format!("thumb_{}.png", thumbnails[1].requested_size()),
+        "thumb_icon@2x.png"
+    );
+}
@@ -0,0 +1,176 @@
+use mime::Mime;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{
+    create_thumbnails_iter, Stage, Thumbnail, ThumbnailSize, ThumbnailerBuilder, TrimMode,
+};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+
+const SIZES: [ThumbnailSize; 4] = [
+    ThumbnailSize::Small,
+    ThumbnailSize::Large,
+    ThumbnailSize::Icon,
+    ThumbnailSize::Medium,
+];
+
+fn collect(
+    builder: ThumbnailerBuilder,
+    bytes: &[u8],
+    mime: Mime,
+) -> Vec<(ThumbnailSize, Thumbnail)> {
+    builder
+        .run_iter(Cursor::new(bytes), mime)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+#[test]
+fn it_produces_the_largest_size_first() {
+    let thumbnails = create_thumbnails_iter(Cursor::new(PNG_BYTES), mime::IMAGE_PNG, SIZES)
+        .unwrap()
+        .map(|result| result.unwrap().0)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        thumbnails,
+        [
+            ThumbnailSize::Large,
+            ThumbnailSize::Medium,
+            ThumbnailSize::Small,
+            ThumbnailSize::Icon
+        ]
+    );
+}
+
+#[test]
+fn it_keeps_the_order_of_the_sizes_if_requested() {
+    let builder = ThumbnailerBuilder::new().sizes(SIZES).largest_first(false);
+    let sizes: Vec<ThumbnailSize> = collect(builder, PNG_BYTES, mime::IMAGE_PNG)
+        .into_iter()
+        .map(|(size, _)| size)
+        .collect();
+
+    assert_eq!(sizes, SIZES);
+}
+
+#[test]
+fn it_creates_the_same_thumbnails_as_the_eager_api() {
+    let builder = ThumbnailerBuilder::new()
+        .sizes(SIZES)
+        .trim(TrimMode::Transparent)
+        .round_corners(8);
+    let eager = builder
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    for (size, thumbnail) in collect(builder, PNG_BYTES, mime::IMAGE_PNG) {
+        let index = SIZES.iter().position(|s| *s == size).unwrap();
+        assert!(thumbnail.pixel_eq(&eager[index]), "{}", size);
+        assert_eq!(thumbnail.requested_size(), size);
+        assert_eq!(thumbnail.dpi(), eager[index].dpi());
+    }
+}
+
+#[test]
+fn it_resizes_each_size_when_it_is_requested() {
+    let resized = Arc::new(AtomicUsize::new(0));
+    let decoded = Arc::new(AtomicUsize::new(0));
+    let (resized_count, decoded_count) = (resized.clone(), decoded.clone());
+    let builder =
+        ThumbnailerBuilder::new()
+            .sizes(SIZES)
+            .on_progress(move |progress| match progress.stage {
+                Stage::Decoding => {
+                    decoded_count.fetch_add(1, Ordering::SeqCst);
+                }
+                Stage::Resizing { .. } => {
+                    resized_count.fetch_add(1, Ordering::SeqCst);
+                }
+                _ => {}
+            });
+
+    let mut thumbnails = builder
+        .run_iter(Cursor::new(JPG_BYTES), mime::IMAGE_JPEG)
+        .unwrap();
+    assert_eq!(decoded.load(Ordering::SeqCst), 1);
+    assert_eq!(resized.load(Ordering::SeqCst), 0);
+    assert_eq!(thumbnails.size_hint(), (0, Some(4)));
+
+    for expected in 1..=SIZES.len() {
+        thumbnails.next().unwrap().unwrap();
+        assert_eq!(resized.load(Ordering::SeqCst), expected);
+        assert_eq!(thumbnails.remaining_sizes().count(), SIZES.len() - expected);
+    }
+    assert!(thumbnails.next().is_none());
+    assert_eq!(decoded.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn it_downscales_the_source_progressively() {
+    let sizes = [
+        ThumbnailSize::Icon,
+        ThumbnailSize::Small,
+        ThumbnailSize::Large,
+    ];
+    let direct = collect(
+        ThumbnailerBuilder::new().sizes(sizes),
+        JPG_BYTES,
+        mime::IMAGE_JPEG,
+    );
+    let progressive = collect(
+        ThumbnailerBuilder::new()
+            .sizes(sizes)
+            .progressive_downscale(true),
+        JPG_BYTES,
+        mime::IMAGE_JPEG,
+    );
+
+    for ((size, direct), (progressive_size, progressive)) in direct.iter().zip(&progressive) {
+        assert_eq!(size, progressive_size);
+        assert_eq!(direct.size(), progressive.size(), "{}", size);
+        let similarity = direct.similarity(progressive);
+        assert!(similarity > 0.95, "{} {}", size, similarity);
+    }
+}
+
+#[test]
+fn it_only_fails_the_items_of_invalid_sizes() {
+    let results: Vec<_> = ThumbnailerBuilder::new()
+        .sizes([
+            ThumbnailSize::Small,
+            ThumbnailSize::Custom {
+                width: 0,
+                height: 10,
+            },
+        ])
+        .run_iter(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap()
+        .collect();
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap().0, ThumbnailSize::Small);
+    assert!(matches!(results[1], Err(ThumbError::InvalidSize(0, 10))));
+}
+
+#[test]
+fn it_ends_after_cancellation() {
+    let token = Arc::new(AtomicBool::new(false));
+    let mut thumbnails = ThumbnailerBuilder::new()
+        .sizes(SIZES)
+        .cancel_token(token.clone())
+        .run_iter(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert!(thumbnails.next().unwrap().is_ok());
+    token.store(true, Ordering::SeqCst);
+    assert!(matches!(
+        thumbnails.next(),
+        Some(Err(ThumbError::Cancelled))
+    ));
+    assert!(thumbnails.next().is_none());
+}
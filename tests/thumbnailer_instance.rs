@@ -0,0 +1,113 @@
+use image::GenericImageView;
+use std::io::Cursor;
+use std::sync::Arc;
+use std::thread;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{FilterType, ThumbnailSize, Thumbnailer};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+#[test]
+fn it_is_shareable_between_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Thumbnailer>();
+
+    let thumbnailer = Arc::new(Thumbnailer::builder().filter(FilterType::Triangle).build());
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let thumbnailer = thumbnailer.clone();
+            thread::spawn(move || {
+                thumbnailer
+                    .create(
+                        Cursor::new(PNG_BYTES),
+                        mime::IMAGE_PNG,
+                        [ThumbnailSize::Small],
+                    )
+                    .unwrap()
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        let thumbnails = handle.join().unwrap();
+        assert_eq!(thumbnails.len(), 1);
+        assert_eq!(thumbnails[0].as_image().dimensions(), (128, 95));
+    }
+}
+
+#[test]
+fn it_uses_the_sizes_of_each_call() {
+    let thumbnailer = Thumbnailer::builder().size(ThumbnailSize::Large).build();
+
+    let small = thumbnailer
+        .create(
+            Cursor::new(PNG_BYTES),
+            mime::IMAGE_PNG,
+            [ThumbnailSize::Small],
+        )
+        .unwrap();
+    let both = thumbnailer
+        .create(
+            Cursor::new(PNG_BYTES),
+            mime::IMAGE_PNG,
+            [ThumbnailSize::Icon, ThumbnailSize::Small],
+        )
+        .unwrap();
+
+    assert_eq!(small.len(), 1);
+    assert_eq!(small[0].requested_size(), ThumbnailSize::Small);
+    assert_eq!(both.len(), 2);
+    assert_eq!(both[0].requested_size(), ThumbnailSize::Icon);
+}
+
+#[test]
+fn it_applies_the_configured_limits() {
+    let thumbnailer = Thumbnailer::builder().max_input_pixels(Some(100)).build();
+
+    let result = thumbnailer.create(
+        Cursor::new(PNG_BYTES),
+        mime::IMAGE_PNG,
+        [ThumbnailSize::Small],
+    );
+
+    assert!(matches!(result, Err(ThumbError::InputTooLarge { .. })));
+}
+
+#[test]
+fn it_reports_supported_mimes() {
+    let thumbnailer = Thumbnailer::default();
+
+    assert!(thumbnailer.supports_mime(&mime::IMAGE_PNG));
+    assert!(!thumbnailer.supports_mime(&"application/x-unknown".parse().unwrap()));
+}
+
+#[test]
+fn it_returns_unsupported_mimes() {
+    let thumbnailer = Thumbnailer::default();
+
+    let result = thumbnailer.create(
+        Cursor::new(PNG_BYTES),
+        "application/x-unknown".parse().unwrap(),
+        [ThumbnailSize::Small],
+    );
+
+    assert!(matches!(result, Err(ThumbError::UnsupportedMime(_))));
+}
+
+#[test]
+fn it_reuses_decoded_images() {
+    let thumbnailer = Thumbnailer::default();
+    let base = thumbnailer
+        .decode(
+            Cursor::new(PNG_BYTES),
+            mime::IMAGE_PNG,
+            [ThumbnailSize::Small],
+        )
+        .unwrap();
+
+    let thumbnails = thumbnailer
+        .create_from_base(&base, [ThumbnailSize::Icon, ThumbnailSize::Small])
+        .unwrap();
+
+    assert_eq!(thumbnails.len(), 2);
+}
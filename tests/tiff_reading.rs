@@ -0,0 +1,98 @@
+use image::Rgb;
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{BaseImage, ThumbnailSize, ThumbnailerBuilder};
+
+const PAGES_TIFF_BYTES: &[u8] = include_bytes!("assets/pages.tiff");
+const BIGTIFF_BYTES: &[u8] = include_bytes!("assets/bigtiff.tiff");
+const SCAN_G4_TIFF_BYTES: &[u8] = include_bytes!("assets/scan_g4.tiff");
+
+fn tiff_mime() -> mime::Mime {
+    "image/tiff".parse().unwrap()
+}
+
+/// Decodes the page and returns its dimensions with the color of its center
+fn page(bytes: &[u8], index: usize) -> ((u32, u32), Rgb<u8>) {
+    let base = ThumbnailerBuilder::new()
+        .page(index)
+        .decode(Cursor::new(bytes), tiff_mime())
+        .unwrap();
+    let dimensions = base.dimensions();
+    let thumbnail = base
+        .thumbnails([ThumbnailSize::Custom {
+            width: dimensions.0,
+            height: dimensions.1,
+        }])
+        .unwrap()
+        .remove(0);
+    let image = thumbnail.as_image().to_rgb8();
+
+    (
+        dimensions,
+        *image.get_pixel(dimensions.0 / 2, dimensions.1 / 2),
+    )
+}
+
+#[test]
+fn it_reports_the_page_count_of_tiffs() {
+    let base = BaseImage::decode(Cursor::new(PAGES_TIFF_BYTES), tiff_mime()).unwrap();
+    let info = base.info();
+
+    assert_eq!(info.page_count, Some(3));
+    assert_eq!((info.width, info.height), (64, 48));
+    assert_eq!(info.format, Some(image::ImageFormat::Tiff));
+}
+
+#[test]
+fn it_selects_the_page_of_tiffs() {
+    assert_eq!(page(PAGES_TIFF_BYTES, 0), ((64, 48), Rgb([255, 0, 0])));
+    assert_eq!(page(PAGES_TIFF_BYTES, 1), ((48, 64), Rgb([0, 255, 0])));
+    assert_eq!(page(PAGES_TIFF_BYTES, 2), ((32, 32), Rgb([128, 128, 128])));
+}
+
+#[test]
+fn it_fails_for_pages_past_the_end() {
+    let result = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Small])
+        .page(3)
+        .run(Cursor::new(PAGES_TIFF_BYTES), tiff_mime());
+
+    let error = result.unwrap_err();
+    assert!(matches!(
+        error,
+        ThumbError::PageOutOfRange { page: 3, count: 3 }
+    ));
+    assert!(error.to_string().contains("has 3 pages"), "{}", error);
+}
+
+#[test]
+fn it_reads_the_pages_of_bigtiffs() {
+    let base = BaseImage::decode(Cursor::new(BIGTIFF_BYTES), tiff_mime()).unwrap();
+    assert_eq!(base.info().page_count, Some(2));
+
+    assert_eq!(page(BIGTIFF_BYTES, 0), ((40, 30), Rgb([0, 0, 255])));
+    assert_eq!(page(BIGTIFF_BYTES, 1), ((30, 40), Rgb([255, 255, 0])));
+}
+
+#[test]
+fn it_reads_fax_compressed_scans_as_grayscale() {
+    let base = BaseImage::decode(Cursor::new(SCAN_G4_TIFF_BYTES), tiff_mime()).unwrap();
+    let info = base.info();
+    assert_eq!((info.width, info.height), (200, 100));
+    assert_eq!(info.color_type, image::ColorType::L8);
+    assert_eq!(info.dpi, Some((200.0, 200.0)));
+    assert_eq!(info.page_count, Some(1));
+
+    let thumbnail = base
+        .thumbnails([ThumbnailSize::Custom {
+            width: 200,
+            height: 100,
+        }])
+        .unwrap()
+        .remove(0);
+    let image = thumbnail.as_image().to_luma8();
+    assert_eq!(image.get_pixel(50, 40)[0], 0);
+    assert_eq!(image.get_pixel(10, 10)[0], 255);
+    assert_eq!(image.get_pixel(150, 10)[0], 0);
+    assert_eq!(image.get_pixel(150, 15)[0], 255);
+}
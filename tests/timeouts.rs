@@ -0,0 +1,145 @@
+use image::{DynamicImage, RgbImage};
+use mime::Mime;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use thumbnailer::error::{ThumbError, ThumbResult};
+use thumbnailer::{FormatHandler, ReadSeek, Stage, ThumbnailSize, ThumbnailerBuilder};
+
+const GIF_BYTES: &[u8] = include_bytes!("assets/animated.gif");
+
+/// Takes the given time to decode an image
+struct SlowHandler {
+    mimes: Vec<Mime>,
+    delay: Duration,
+}
+
+impl SlowHandler {
+    fn new(delay: Duration) -> Self {
+        Self {
+            mimes: vec![test_mime()],
+            delay,
+        }
+    }
+}
+
+impl FormatHandler for SlowHandler {
+    fn supported_mimes(&self) -> &[Mime] {
+        &self.mimes
+    }
+
+    fn get_base_image(&self, _reader: &mut dyn ReadSeek) -> ThumbResult<DynamicImage> {
+        thread::sleep(self.delay);
+
+        Ok(DynamicImage::ImageRgb8(RgbImage::new(256, 256)))
+    }
+}
+
+fn test_mime() -> Mime {
+    "application/x-test".parse().unwrap()
+}
+
+#[test]
+fn it_times_out_slow_handlers() {
+    let builder = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Icon)
+        .register_handler(SlowHandler::new(Duration::from_secs(2)))
+        .timeout(Duration::from_millis(100));
+
+    let start = Instant::now();
+    let result = builder.run(Cursor::new(Vec::new()), test_mime());
+
+    assert!(matches!(result, Err(ThumbError::Timeout)));
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn it_stops_the_background_work_after_timing_out() {
+    let resized = Arc::new(AtomicUsize::new(0));
+    let counter = resized.clone();
+    let builder = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Icon; 4])
+        .register_handler(SlowHandler::new(Duration::from_millis(300)))
+        .timeout(Duration::from_millis(50))
+        .on_progress(move |progress| {
+            if let Stage::Resizing { .. } = progress.stage {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+    let result = builder.run(Cursor::new(Vec::new()), test_mime());
+    assert!(matches!(result, Err(ThumbError::Timeout)));
+
+    // the worker finishes decoding but stops before resizing
+    thread::sleep(Duration::from_millis(600));
+    assert_eq!(resized.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn it_returns_results_within_the_timeout() {
+    let thumbnails = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Icon)
+        .register_handler(SlowHandler::new(Duration::from_millis(10)))
+        .timeout(Duration::from_secs(10))
+        .run(Cursor::new(Vec::new()), test_mime())
+        .unwrap();
+
+    assert_eq!(thumbnails.len(), 1);
+    assert_eq!(thumbnails[0].size(), (64, 64));
+}
+
+#[test]
+fn it_times_out_animations_with_a_zero_timeout() {
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Icon)
+        .timeout(Duration::ZERO)
+        .run_animated(Cursor::new(GIF_BYTES), mime::IMAGE_GIF);
+
+    assert!(matches!(result, Err(ThumbError::Timeout)));
+}
+
+#[cfg(all(unix, feature = "ffmpeg"))]
+#[test]
+fn it_kills_ffmpeg_after_timing_out() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    // ffmpeg and ffprobe are replaced by a script that hangs like on a corrupt video
+    let dir = tempfile::tempdir().unwrap();
+    let pids = dir.path().join("pids");
+    let script = format!(
+        "#!/bin/sh\n\
+         case \"$*\" in *-version*) exit 0;; esac\n\
+         echo $$ >> '{}'\n\
+         exec sleep 30\n",
+        pids.display()
+    );
+    for program in ["ffmpeg", "ffprobe"] {
+        let path = dir.path().join(program);
+        fs::write(&path, &script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let mut paths = vec![dir.path().to_path_buf()];
+    paths.extend(std::env::split_paths(&path));
+    std::env::set_var("PATH", std::env::join_paths(paths).unwrap());
+
+    let start = Instant::now();
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Icon)
+        .timeout(Duration::from_millis(500))
+        .run(Cursor::new(vec![0u8; 64]), "video/mp4".parse().unwrap());
+    assert!(matches!(result, Err(ThumbError::Timeout)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // the worker kills the process once the deadline passes
+    thread::sleep(Duration::from_millis(300));
+    let pids = fs::read_to_string(pids).unwrap();
+    assert!(!pids.is_empty());
+    for pid in pids.lines() {
+        assert!(!Path::new(&format!("/proc/{}", pid)).exists(), "{}", pid);
+    }
+}
@@ -0,0 +1,156 @@
+#![cfg(feature = "tracing")]
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use thumbnailer::image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use thumbnailer::{ThumbnailSize, ThumbnailerBuilder};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// The name and the fields of a recorded span
+#[derive(Debug, Default)]
+struct RecordedSpan {
+    name: &'static str,
+    fields: HashMap<&'static str, String>,
+}
+
+impl Visit for RecordedSpan {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.fields.insert(field.name(), format!("{:?}", value));
+    }
+}
+
+/// Records all spans with their fields and the messages of events
+#[derive(Clone, Default)]
+struct RecordingSubscriber {
+    spans: Arc<Mutex<Vec<RecordedSpan>>>,
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingSubscriber {
+    fn spans(&self, name: &str) -> Vec<HashMap<&'static str, String>> {
+        self.spans
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|span| span.name == name)
+            .map(|span| span.fields.clone())
+            .collect()
+    }
+}
+
+impl Subscriber for RecordingSubscriber {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut recorded = RecordedSpan {
+            name: span.metadata().name(),
+            ..RecordedSpan::default()
+        };
+        span.record(&mut recorded);
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(recorded);
+
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut spans = self.spans.lock().unwrap();
+        values.record(&mut spans[span.into_u64() as usize - 1]);
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut recorded = RecordedSpan::default();
+        event.record(&mut recorded);
+        if let Some(message) = recorded.fields.remove("message") {
+            self.events.lock().unwrap().push(message);
+        }
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+fn png(image: DynamicImage) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    image.write_to(&mut buf, ImageFormat::Png).unwrap();
+
+    buf.into_inner()
+}
+
+#[test]
+fn it_records_spans_of_decoding_resizing_and_encoding() {
+    let bytes = png(DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+        400,
+        300,
+        Rgba([255, 0, 0, 255]),
+    )));
+    let subscriber = RecordingSubscriber::default();
+
+    tracing::subscriber::with_default(subscriber.clone(), || {
+        let thumbnails = ThumbnailerBuilder::new()
+            .sizes([ThumbnailSize::Icon, ThumbnailSize::Small])
+            .parallel(false)
+            .run(Cursor::new(&bytes), mime::IMAGE_PNG)
+            .unwrap();
+        for thumbnail in thumbnails {
+            thumbnail.encode(ImageFormat::Png).unwrap();
+        }
+    });
+
+    let decode = subscriber.spans("decode");
+    assert_eq!(decode.len(), 1);
+    assert_eq!(decode[0]["mime"], "image/png");
+    assert_eq!(decode[0]["format"], "\"png\"");
+    assert_eq!(decode[0]["source_width"], "400");
+    assert_eq!(decode[0]["source_height"], "300");
+    assert!(decode[0].contains_key("elapsed_ms"));
+
+    let resize = subscriber.spans("resize");
+    assert_eq!(resize.len(), 2);
+    assert_eq!(resize[0]["size"], "icon");
+    assert_eq!(resize[0]["width"], "64");
+    assert_eq!(resize[1]["size"], "small");
+    assert_eq!(resize[1]["filter"], "Lanczos3");
+    assert!(resize.iter().all(|span| span.contains_key("elapsed_ms")));
+
+    let encode = subscriber.spans("encode");
+    assert_eq!(encode.len(), 2);
+    assert!(encode.iter().all(|span| span["format"] == "\"png\""));
+    assert!(encode
+        .iter()
+        .all(|span| span["bytes"].parse::<u64>().unwrap() > 0));
+}
+
+#[test]
+fn it_records_events_of_fallbacks() {
+    let bytes = png(DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+        10,
+        10,
+        Rgba([0, 0, 255, 255]),
+    )));
+    let subscriber = RecordingSubscriber::default();
+
+    tracing::subscriber::with_default(subscriber.clone(), || {
+        ThumbnailerBuilder::new()
+            .size(ThumbnailSize::Icon)
+            .allow_sniffing(true)
+            .run(Cursor::new(&bytes), mime::APPLICATION_OCTET_STREAM)
+            .unwrap();
+    });
+
+    let events = subscriber.events.lock().unwrap();
+    assert!(
+        events.iter().any(|message| message.contains("sniffed")),
+        "{:?}",
+        *events
+    );
+}
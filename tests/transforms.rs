@@ -0,0 +1,75 @@
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::image::{ColorType, DynamicImage, Rgb, RgbImage};
+use thumbnailer::{Thumbnail, ThumbnailSize, ThumbnailerBuilder};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+fn gradient_thumbnail() -> Thumbnail {
+    let image = RgbImage::from_fn(10, 6, |x, y| Rgb([x as u8 * 20, y as u8 * 40, 100]));
+
+    Thumbnail::from(DynamicImage::ImageRgb8(image))
+}
+
+#[test]
+fn it_pixelates_thumbnails_in_blocks() {
+    let image = gradient_thumbnail().pixelate(4).into_inner().into_rgb8();
+
+    assert_eq!(image.dimensions(), (10, 6));
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let block = image.get_pixel(x / 4 * 4, y / 4 * 4);
+        assert_eq!(pixel, block, "{} {}", x, y);
+    }
+    assert_ne!(image.get_pixel(0, 0), image.get_pixel(4, 0));
+    assert_ne!(image.get_pixel(8, 0), image.get_pixel(4, 0));
+}
+
+#[test]
+fn it_clamps_the_pixelate_block_size() {
+    let original = gradient_thumbnail().into_inner();
+    assert_eq!(gradient_thumbnail().pixelate(0).into_inner(), original);
+    assert_eq!(gradient_thumbnail().pixelate(1).into_inner(), original);
+
+    let image = gradient_thumbnail().pixelate(1000).into_inner().into_rgb8();
+    assert_eq!(image.dimensions(), (10, 6));
+    assert!(image.pixels().all(|pixel| pixel == image.get_pixel(0, 0)));
+}
+
+#[test]
+fn it_chains_transforms() {
+    let image = gradient_thumbnail()
+        .grayscale()
+        .brightness(300)
+        .into_inner();
+    assert_eq!(image.color(), ColorType::L8);
+    assert!(image.into_luma8().pixels().all(|pixel| pixel[0] == 255));
+
+    let original = gradient_thumbnail().into_inner();
+    let blurred = gradient_thumbnail().blur(2.0).into_inner();
+    assert_ne!(blurred, original);
+    assert_eq!(gradient_thumbnail().blur(-1.0).into_inner(), original);
+    assert_eq!(gradient_thumbnail().blur(f32::NAN).into_inner(), original);
+}
+
+#[test]
+fn it_applies_transforms_to_every_size() {
+    let create = |builder: ThumbnailerBuilder| {
+        builder
+            .sizes([ThumbnailSize::Small, ThumbnailSize::Medium])
+            .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+            .unwrap()
+    };
+    let plain = create(ThumbnailerBuilder::new());
+    let transformed = create(ThumbnailerBuilder::new().pixelate(8).grayscale());
+
+    for (plain, transformed) in plain.into_iter().zip(transformed) {
+        let expected = plain.pixelate(8).grayscale();
+        assert_eq!(transformed.as_image(), expected.as_image());
+    }
+
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .blur(-1.0)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG);
+    assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+}
@@ -0,0 +1,223 @@
+use std::io::Cursor;
+use thumbnailer::image::{
+    DynamicImage, GenericImageView, ImageFormat, Rgb, RgbImage, Rgba, RgbaImage,
+};
+use thumbnailer::{Thumbnail, ThumbnailSize, ThumbnailerBuilder, TrimMode};
+
+const GIF_BYTES: &[u8] = include_bytes!("assets/animated.gif");
+
+const TRANSPARENT: Rgba<u8> = Rgba([0, 0, 0, 0]);
+const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+fn png(image: DynamicImage) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    image.write_to(&mut buf, ImageFormat::Png).unwrap();
+
+    buf.into_inner()
+}
+
+/// Returns a png of a 100x100 icon padded by 400 transparent pixels on each side
+fn padded_icon() -> Vec<u8> {
+    let image = RgbaImage::from_fn(900, 900, |x, y| {
+        if (400..500).contains(&x) && (400..500).contains(&y) {
+            RED
+        } else {
+            TRANSPARENT
+        }
+    });
+
+    png(DynamicImage::ImageRgba8(image))
+}
+
+fn icon_thumbnail(bytes: &[u8], trim: Option<TrimMode>) -> Thumbnail {
+    let mut builder = ThumbnailerBuilder::new().size(ThumbnailSize::Icon);
+    if let Some(trim) = trim {
+        builder = builder.trim(trim);
+    }
+
+    builder
+        .run(Cursor::new(bytes), mime::IMAGE_PNG)
+        .unwrap()
+        .pop()
+        .unwrap()
+}
+
+fn opaque_pixels(thumbnail: &Thumbnail) -> usize {
+    thumbnail
+        .as_image()
+        .to_rgba8()
+        .pixels()
+        .filter(|pixel| pixel[3] > 128)
+        .count()
+}
+
+#[test]
+fn it_trims_transparent_borders() {
+    let bytes = padded_icon();
+    let untrimmed = icon_thumbnail(&bytes, None);
+    let trimmed = icon_thumbnail(&bytes, Some(TrimMode::Transparent));
+
+    // the icon covers 1/81 of the untrimmed thumbnail and all of the trimmed one
+    assert_eq!(untrimmed.size(), (64, 64));
+    assert!(
+        opaque_pixels(&untrimmed) < 64,
+        "{}",
+        opaque_pixels(&untrimmed)
+    );
+    assert_eq!(trimmed.size(), (64, 64));
+    assert_eq!(opaque_pixels(&trimmed), 64 * 64);
+    assert_eq!(trimmed.as_image().get_pixel(0, 0), RED);
+}
+
+#[test]
+fn it_trims_borders_within_the_tolerance() {
+    // a 200x100 subject on a noisy white background
+    let image = RgbImage::from_fn(800, 800, |x, y| {
+        if (300..500).contains(&x) && (350..450).contains(&y) {
+            Rgb([0, 0, 255])
+        } else {
+            let noise = ((x * 7 + y * 13) % 6) as u8;
+            Rgb([255 - noise, 255 - noise, 255])
+        }
+    });
+    let bytes = png(DynamicImage::ImageRgb8(image));
+    let white = Rgba([255, 255, 255, 255]);
+
+    let trimmed = icon_thumbnail(
+        &bytes,
+        Some(TrimMode::Color {
+            color: white,
+            tolerance: 8,
+        }),
+    );
+    assert_eq!(trimmed.size(), (64, 32));
+
+    // the noise exceeds a lower tolerance, so nothing is trimmed
+    let strict = icon_thumbnail(
+        &bytes,
+        Some(TrimMode::Color {
+            color: white,
+            tolerance: 2,
+        }),
+    );
+    assert_eq!(strict.size(), (64, 64));
+}
+
+#[test]
+fn it_picks_the_border_automatically() {
+    let bytes = padded_icon();
+    assert_eq!(
+        opaque_pixels(&icon_thumbnail(&bytes, Some(TrimMode::Auto))),
+        64 * 64
+    );
+
+    // images without alpha use the color of the top left pixel
+    let image = RgbImage::from_fn(300, 300, |x, y| {
+        if (100..250).contains(&x) && (100..150).contains(&y) {
+            Rgb([255, 0, 0])
+        } else {
+            Rgb([20, 200, 20])
+        }
+    });
+    let trimmed = icon_thumbnail(&png(DynamicImage::ImageRgb8(image)), Some(TrimMode::Auto));
+    assert_eq!(trimmed.size(), (64, 21));
+}
+
+#[test]
+fn it_keeps_thin_content_of_large_images() {
+    // a single line near the edge of an image that is scanned on a downsampled copy
+    let image = RgbaImage::from_fn(2000, 1000, |x, y| {
+        if x == 10 && (400..600).contains(&y) || (990..1010).contains(&x) && y == 500 {
+            RED
+        } else {
+            TRANSPARENT
+        }
+    });
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Custom {
+            width: 1000,
+            height: 1000,
+        })
+        .no_upscale(true)
+        .trim(TrimMode::Transparent)
+        .run(
+            Cursor::new(png(DynamicImage::ImageRgba8(image))),
+            mime::IMAGE_PNG,
+        )
+        .unwrap()
+        .pop()
+        .unwrap();
+
+    assert_eq!(thumbnail.size(), (1000, 200));
+}
+
+#[test]
+fn it_keeps_faint_hairlines_of_large_images() {
+    // the line is lost when the image is averaged down before searching for the content
+    let image = RgbImage::from_fn(2000, 2000, |x, y| {
+        if y == 100 && (250..1750).contains(&x) {
+            Rgb([240, 240, 240])
+        } else if (998..1002).contains(&x) && (998..1002).contains(&y) {
+            Rgb([0, 0, 0])
+        } else {
+            Rgb([255, 255, 255])
+        }
+    });
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Custom {
+            width: 2000,
+            height: 2000,
+        })
+        .no_upscale(true)
+        .trim(TrimMode::Auto)
+        .run(
+            Cursor::new(png(DynamicImage::ImageRgb8(image))),
+            mime::IMAGE_PNG,
+        )
+        .unwrap()
+        .pop()
+        .unwrap();
+
+    assert_eq!(thumbnail.size(), (1500, 902));
+    let image = thumbnail.as_image().to_rgb8();
+    assert_eq!(image.get_pixel(0, 0), &Rgb([240, 240, 240]));
+    assert_eq!(image.get_pixel(749, 899), &Rgb([0, 0, 0]));
+}
+
+#[test]
+fn it_trims_fully_transparent_images_to_a_pixel() {
+    let image = RgbaImage::from_pixel(600, 400, TRANSPARENT);
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Icon)
+        .no_upscale(true)
+        .trim(TrimMode::Transparent)
+        .run(
+            Cursor::new(png(DynamicImage::ImageRgba8(image))),
+            mime::IMAGE_PNG,
+        )
+        .unwrap()
+        .pop()
+        .unwrap();
+
+    assert_eq!(thumbnail.size(), (1, 1));
+    assert_eq!(thumbnail.as_image().get_pixel(0, 0)[3], 0);
+}
+
+#[test]
+fn it_trims_all_frames_of_animations_alike() {
+    let run = |builder: ThumbnailerBuilder| {
+        builder
+            .size(ThumbnailSize::Icon)
+            .run_animated(Cursor::new(GIF_BYTES), mime::IMAGE_GIF)
+            .unwrap()
+            .pop()
+            .unwrap()
+    };
+    let untrimmed = run(ThumbnailerBuilder::new());
+    let trimmed = run(ThumbnailerBuilder::new().trim(TrimMode::Auto));
+
+    // all frames are cropped to the same bounds, so they keep a common size
+    assert_eq!(trimmed.frame_count(), untrimmed.frame_count());
+    let (width, height) = trimmed.size();
+    assert!(width <= 64 && height <= 64 && width > 0 && height > 0);
+}
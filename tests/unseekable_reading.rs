@@ -0,0 +1,78 @@
+use std::io::{BufRead, Cursor, Read};
+use std::str::FromStr;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{create_thumbnails_unseekable, ThumbnailSize, ThumbnailerBuilder};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+const GIF_BYTES: &[u8] = include_bytes!("assets/animated.gif");
+const WEBP_BYTES: &[u8] = include_bytes!("assets/test.webp");
+
+/// A reader that only implements [Read] and [BufRead]
+struct Unseekable<R>(R);
+
+impl<R: Read> Read for Unseekable<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<R: BufRead> BufRead for Unseekable<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.0.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.0.consume(amount)
+    }
+}
+
+fn create_thumbnail(bytes: &[u8], mime: mime::Mime) {
+    let reader = Unseekable(Cursor::new(bytes));
+    let thumbnails = create_thumbnails_unseekable(reader, mime, [ThumbnailSize::Small]).unwrap();
+    assert_eq!(thumbnails.len(), 1);
+}
+
+#[test]
+fn it_creates_thumbnails_for_unseekable_png() {
+    create_thumbnail(PNG_BYTES, mime::IMAGE_PNG);
+}
+
+#[test]
+fn it_creates_thumbnails_for_unseekable_jpeg() {
+    create_thumbnail(JPG_BYTES, mime::IMAGE_JPEG);
+}
+
+#[test]
+fn it_creates_thumbnails_for_unseekable_gif() {
+    create_thumbnail(GIF_BYTES, mime::IMAGE_GIF);
+}
+
+#[test]
+fn it_requires_seek_for_other_types() {
+    let reader = Unseekable(Cursor::new(WEBP_BYTES));
+    let mime = mime::Mime::from_str("image/webp").unwrap();
+    let result = create_thumbnails_unseekable(reader, mime, [ThumbnailSize::Small]);
+    assert!(matches!(result, Err(ThumbError::SeekRequired(_))));
+}
+
+#[test]
+fn it_sniffs_unseekable_readers() {
+    let thumbnails = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .allow_sniffing(true)
+        .run_unseekable(Unseekable(Cursor::new(PNG_BYTES)), mime::IMAGE_PNG)
+        .unwrap();
+    assert_eq!(thumbnails.len(), 1);
+
+    // the content can't be decoded again with the sniffed type
+    let result = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Small)
+        .allow_sniffing(true)
+        .run_unseekable(Unseekable(Cursor::new(PNG_BYTES)), mime::IMAGE_JPEG);
+    assert!(
+        matches!(result, Err(ThumbError::Decode { .. })),
+        "{:?}",
+        result
+    );
+}
@@ -1,12 +1,17 @@
-extern crate core;
+#![cfg(feature = "ffmpeg")]
 
 use mime::Mime;
 use std::io::Cursor;
 use std::str::FromStr;
+use std::time::Duration;
 use thumbnailer::error::ThumbError;
-use thumbnailer::{create_thumbnails, ThumbnailSize};
+use thumbnailer::{
+    create_thumbnails, create_video_thumbnails, ThumbnailSize, ThumbnailerBuilder,
+    VideoFrameSelector,
+};
 
 const VIDEO_BYTES: &[u8] = include_bytes!("assets/test.mp4");
+const FRAMES_BYTES: &[u8] = include_bytes!("assets/frames.avi");
 
 #[test]
 fn it_creates_thumbnails_for_mp4() {
@@ -24,7 +29,7 @@ fn it_creates_thumbnails_for_mp4() {
     match result {
         Ok(_) => {}
         Err(e) => match e {
-            ThumbError::Unsupported(_) => {
+            ThumbError::UnsupportedMime(_) => {
                 panic!("unsupported format, is ffmpeg installed?");
             }
             e => {
@@ -33,3 +38,114 @@ fn it_creates_thumbnails_for_mp4() {
         },
     }
 }
+
+/// Returns the color of the center of the frame that the selector picks from the fixture
+/// whose first, middle and last second are red, green and blue
+fn frame_color(selector: VideoFrameSelector) -> [u8; 3] {
+    let thumbnail = ThumbnailerBuilder::new()
+        .size(ThumbnailSize::Icon)
+        .video_frame(selector)
+        .run(
+            Cursor::new(FRAMES_BYTES),
+            Mime::from_str("video/x-msvideo").unwrap(),
+        )
+        .unwrap_or_else(|e| panic!("failed to select frame {:?}: {}", selector, e))
+        .remove(0);
+    let image = thumbnail.as_image().to_rgb8();
+
+    image.get_pixel(image.width() / 2, image.height() / 2).0
+}
+
+/// Returns whether the color is close to the expected one
+fn is_close(color: [u8; 3], expected: [u8; 3]) -> bool {
+    color
+        .iter()
+        .zip(expected.iter())
+        .all(|(c, e)| (*c as i32 - *e as i32).abs() < 16)
+}
+
+#[test]
+fn it_selects_video_frames() {
+    const RED: [u8; 3] = [255, 0, 0];
+    const GREEN: [u8; 3] = [0, 255, 0];
+    const BLUE: [u8; 3] = [0, 0, 255];
+    let selectors = [
+        (VideoFrameSelector::Frame(0), RED),
+        (VideoFrameSelector::Timestamp(Duration::ZERO), RED),
+        (VideoFrameSelector::Frame(15), GREEN),
+        (VideoFrameSelector::Percentage(0.5), GREEN),
+        (
+            VideoFrameSelector::Timestamp(Duration::from_millis(1500)),
+            GREEN,
+        ),
+        (VideoFrameSelector::Percentage(1.0), BLUE),
+        (VideoFrameSelector::Frame(u64::MAX), BLUE),
+        (
+            VideoFrameSelector::Timestamp(Duration::from_secs(60 * 60 * 24)),
+            BLUE,
+        ),
+    ];
+
+    for (selector, expected) in selectors {
+        let color = frame_color(selector);
+        assert!(is_close(color, expected), "{:?}: {:?}", selector, color);
+    }
+    // the default frame at 10% of the duration is in the first second
+    let color = frame_color(VideoFrameSelector::default());
+    assert!(is_close(color, RED), "{:?}", color);
+}
+
+#[test]
+fn it_creates_thumbnails_of_multiple_frames() {
+    // out of order, beyond the end and repeated selectors
+    let selectors = [
+        VideoFrameSelector::Percentage(0.9),
+        VideoFrameSelector::Timestamp(Duration::ZERO),
+        VideoFrameSelector::Timestamp(Duration::from_secs(60 * 60 * 24)),
+        VideoFrameSelector::Frame(1),
+        VideoFrameSelector::Percentage(0.9),
+    ];
+    let results = create_video_thumbnails(
+        Cursor::new(VIDEO_BYTES),
+        Mime::from_str("video/mp4").unwrap(),
+        &selectors,
+        [ThumbnailSize::Icon, ThumbnailSize::Small],
+    )
+    .unwrap();
+
+    assert_eq!(results.len(), selectors.len());
+    for ((selector, thumbnails), expected) in results.iter().zip(selectors) {
+        assert_eq!(*selector, expected);
+        assert_eq!(thumbnails.len(), 2);
+        assert_eq!(thumbnails[0].requested_size(), ThumbnailSize::Icon);
+        assert_eq!(thumbnails[1].requested_size(), ThumbnailSize::Small);
+    }
+    assert_eq!(
+        results[0].1[0].as_image().as_bytes(),
+        results[4].1[0].as_image().as_bytes()
+    );
+}
+
+#[test]
+fn it_rejects_invalid_frame_selectors() {
+    let result = create_video_thumbnails(
+        Cursor::new(VIDEO_BYTES),
+        Mime::from_str("video/mp4").unwrap(),
+        &[VideoFrameSelector::Percentage(f32::NAN)],
+        [ThumbnailSize::Icon],
+    );
+
+    assert!(matches!(result, Err(ThumbError::InvalidOptions(_))));
+}
+
+#[test]
+fn it_only_extracts_multiple_frames_of_videos() {
+    let result = create_video_thumbnails(
+        Cursor::new(VIDEO_BYTES),
+        mime::IMAGE_PNG,
+        &[VideoFrameSelector::Frame(0)],
+        [ThumbnailSize::Icon],
+    );
+
+    assert!(matches!(result, Err(ThumbError::UnsupportedMime(_))));
+}
@@ -0,0 +1,51 @@
+//! Runs with `wasm-bindgen-test-runner` as the runner of the wasm32-unknown-unknown target:
+//! `cargo test --target wasm32-unknown-unknown --no-default-features --test wasm`
+
+#![cfg(target_arch = "wasm32")]
+
+use std::io::Cursor;
+use thumbnailer::image::{load_from_memory_with_format, GenericImageView, ImageFormat};
+use thumbnailer::{create_thumbnails, ThumbnailSize, ThumbnailerBuilder};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+
+/// Creates thumbnails of the content and checks that every output format decodes again
+fn assert_round_trip(bytes: &[u8], mime: mime::Mime) {
+    let sizes = [ThumbnailSize::Small, ThumbnailSize::Medium];
+    let thumbnails = create_thumbnails(Cursor::new(bytes), mime, sizes).unwrap();
+    assert_eq!(thumbnails.len(), 2);
+
+    for thumbnail in thumbnails {
+        let size = thumbnail.size();
+        for format in [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::WebP] {
+            let mut buf = Cursor::new(Vec::new());
+            thumbnail.clone().write_to(&mut buf, format).unwrap();
+
+            let image = load_from_memory_with_format(buf.get_ref(), format).unwrap();
+            assert_eq!(image.dimensions(), size, "{:?}", format);
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+fn it_creates_thumbnails_of_png_images() {
+    assert_round_trip(PNG_BYTES, mime::IMAGE_PNG);
+}
+
+#[wasm_bindgen_test]
+fn it_creates_thumbnails_of_jpeg_images() {
+    assert_round_trip(JPG_BYTES, mime::IMAGE_JPEG);
+}
+
+#[wasm_bindgen_test]
+fn it_resizes_multiple_sizes_without_threads() {
+    let thumbnails = ThumbnailerBuilder::new()
+        .sizes([ThumbnailSize::Icon, ThumbnailSize::Large])
+        .parallel(true)
+        .run(Cursor::new(PNG_BYTES), mime::IMAGE_PNG)
+        .unwrap();
+
+    assert!(thumbnails[0].size().0 < thumbnails[1].size().0);
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "xdg")]
+
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use thumbnailer::xdg::{file_uri, XdgCache, XdgSize};
+use thumbnailer::{create_thumbnails, Thumbnail, ThumbnailSize};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+fn create_thumbnail(size: ThumbnailSize) -> Thumbnail {
+    create_thumbnails(Cursor::new(PNG_BYTES), mime::IMAGE_PNG, [size])
+        .unwrap()
+        .pop()
+        .unwrap()
+}
+
+#[test]
+fn it_stores_thumbnails_for_uris() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = XdgCache::with_root(dir.path());
+    let uri = "file:///home/jens/photos/me.png";
+    let path = cache
+        .store_for_uri(
+            uri,
+            1000,
+            create_thumbnail(ThumbnailSize::Medium),
+            XdgSize::Normal,
+        )
+        .unwrap();
+
+    assert_eq!(
+        path,
+        dir.path()
+            .join("normal")
+            .join("c6ee772d9e49320e97ec29a7eb5b1697.png")
+    );
+    let image = image::open(&path).unwrap();
+    assert!(image.width() <= 128 && image.height() <= 128);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let decoder = png::Decoder::new(BufReader::new(File::open(&path).unwrap()));
+    let reader = decoder.read_info().unwrap();
+    let chunks = &reader.info().uncompressed_latin1_text;
+    assert!(chunks
+        .iter()
+        .any(|c| c.keyword == "Thumb::URI" && c.text == uri));
+    assert!(chunks
+        .iter()
+        .any(|c| c.keyword == "Thumb::MTime" && c.text == "1000"));
+}
+
+#[test]
+fn it_checks_the_mtime_on_lookup() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = XdgCache::with_root(dir.path());
+    let uri = "file:///tmp/test.png";
+    let path = cache
+        .store_for_uri(
+            uri,
+            42,
+            create_thumbnail(ThumbnailSize::Small),
+            XdgSize::Large,
+        )
+        .unwrap();
+
+    assert_eq!(cache.lookup_for_uri(uri, 42, XdgSize::Large), Some(path));
+    assert_eq!(cache.lookup_for_uri(uri, 43, XdgSize::Large), None);
+    assert_eq!(cache.lookup_for_uri(uri, 42, XdgSize::Normal), None);
+}
+
+#[test]
+fn it_stores_thumbnails_for_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = XdgCache::with_root(dir.path().join("thumbnails"));
+    let file = dir.path().join("my image.png");
+    std::fs::write(&file, PNG_BYTES).unwrap();
+
+    assert!(file_uri(&file).unwrap().ends_with("/my%20image.png"));
+    let path = cache
+        .store_for_file(
+            &file,
+            create_thumbnail(ThumbnailSize::Small),
+            XdgSize::Normal,
+        )
+        .unwrap();
+    assert_eq!(cache.lookup_for_file(&file, XdgSize::Normal), Some(path));
+}